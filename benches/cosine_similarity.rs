@@ -0,0 +1,26 @@
+//! Benchmark of the optimized cosine similarity against the scalar reference
+//!
+//! Run with `cargo bench --bench cosine_similarity`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use indexer::vector_search::{cosine_similarity, cosine_similarity_scalar};
+use std::hint::black_box;
+
+/// Dimension of OpenAI's text-embedding-ada-002 vectors
+const DIMENSION: usize = 1536;
+
+/// Deterministic, non-trivial vector so both versions do the same work every run
+fn vector(seed: usize) -> Vec<f32> {
+    (0..DIMENSION).map(|i| ((i * 31 + seed * 17) % 97) as f32 / 97.0 - 0.5).collect()
+}
+
+fn bench_cosine_similarity(c: &mut Criterion) {
+    let (a, b) = (vector(1), vector(2));
+    let mut group = c.benchmark_group("cosine_similarity_1536");
+    group.bench_function("scalar", |bench| bench.iter(|| cosine_similarity_scalar(black_box(&a), black_box(&b))));
+    group.bench_function("chunked", |bench| bench.iter(|| cosine_similarity(black_box(&a), black_box(&b))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_cosine_similarity);
+criterion_main!(benches);
@@ -19,19 +19,122 @@ pub struct CodeEntity {
     pub parent_class: Option<String>,
     pub bases: Option<Vec<String>>,
     pub value_repr: Option<String>,
+    /// Source text of the entity, possibly truncated when stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
-pub fn extract_code_info(file_path: &Path, base_dir: &Path) -> Vec<CodeEntity> {
-    let content = match fs::read_to_string(file_path) {
-        Ok(c) => c,
-        Err(_) => return vec![],
+/// Default byte limit for a stored entity's source snippet
+pub const DEFAULT_SOURCE_MAX_BYTES: usize = 4096;
+
+/// Cut a source snippet down to at most `max_bytes`, on a char boundary, and note
+/// how much was dropped
+pub fn truncate_source(source: &str, max_bytes: usize) -> String {
+    if source.len() <= max_bytes {
+        return source.to_string();
+    }
+    let mut end = max_bytes;
+    while !source.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n... [truncated {} bytes]", &source[..end], source.len() - end)
+}
+
+/// Source text of the 1-based, inclusive line range `start..=end`
+///
+/// The result has no trailing line terminator. A `start` of 0 is read as 1, an `end`
+/// past the last line stops at the last line, and a range that is empty or starts
+/// past the end of `src` yields `""`.
+pub fn slice_lines(src: &str, start: usize, end: usize) -> &str {
+    let start = start.max(1);
+    if end < start {
+        return "";
+    }
+    let mut begin = None;
+    let mut offset = 0;
+    for (number, line) in (1..).zip(src.split_inclusive('\n')) {
+        if number == start {
+            begin = Some(offset);
+        }
+        offset += line.len();
+        if number == end {
+            break;
+        }
+    }
+    let Some(begin) = begin else {
+        return "";
     };
+    let slice = &src[begin..offset];
+    let slice = slice.strip_suffix('\n').unwrap_or(slice);
+    slice.strip_suffix('\r').unwrap_or(slice)
+}
 
+/// Read and parse a python file, recording its path relative to `base_dir`
+///
+/// # Returns
+/// * `Result<Vec<CodeEntity>, ParseDiagnostic>` - The file's entities, or where it
+///   failed to parse; a file that cannot be read is reported at line 0, column 0
+pub fn extract_code_info(file_path: &Path, base_dir: &Path) -> Result<Vec<CodeEntity>, ParseDiagnostic> {
     let rel_path = file_path.strip_prefix(base_dir).unwrap_or(file_path).to_string_lossy().to_string();
+    let content = fs::read_to_string(file_path).map_err(|e| ParseDiagnostic {
+        file: rel_path.clone(),
+        line: 0,
+        col: 0,
+        message: format!("could not read file: {}", e),
+    })?;
+    parse_code_info(&content, &rel_path)
+}
+
+/// Location and message of a python source file that failed to parse
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ParseDiagnostic {
+    pub file: String,
+    /// 1-based line of the parser error
+    pub line: usize,
+    /// 1-based column (in characters) of the parser error
+    pub col: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.file, self.line, self.col, self.message)
+    }
+}
+
+/// Convert a byte offset into a 1-based (line, column) pair
+fn offset_to_line_col(src: &str, offset: usize) -> (usize, usize) {
+    let mut idx = offset.min(src.len());
+    while !src.is_char_boundary(idx) {
+        idx -= 1;
+    }
+    let before = &src[..idx];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let col = before[line_start..].chars().count() + 1;
+    (line, col)
+}
+
+/// Extract entities from python source already in memory (e.g. read from an archive),
+/// recording `rel_path` as each entity's file path. Unparseable source yields no entities.
+pub fn extract_code_info_from_source(content: &str, rel_path: &str) -> Vec<CodeEntity> {
+    parse_code_info(content, rel_path).unwrap_or_default()
+}
+
+/// Like `extract_code_info_from_source`, but reports where parsing failed
+pub fn parse_code_info(content: &str, rel_path: &str) -> Result<Vec<CodeEntity>, ParseDiagnostic> {
     let mut entities = Vec::new();
-    let ast = match Suite::parse(&content, "<embedded>") {
+    let ast = match Suite::parse(content, rel_path) {
         Ok(a) => a,
-        Err(_) => return vec![],
+        Err(e) => {
+            let (line, col) = offset_to_line_col(content, e.offset.to_usize());
+            return Err(ParseDiagnostic {
+                file: rel_path.to_string(),
+                line,
+                col,
+                message: e.error.to_string(),
+            });
+        }
     };
 
 
@@ -72,27 +175,60 @@ pub fn extract_code_info(file_path: &Path, base_dir: &Path) -> Vec<CodeEntity> {
     fn textsize_to_line(src: &str, pos: rustpython_parser::ast::TextSize) -> usize {
     // TextSize is a byte offset; count newlines up to that offset
     let idx = pos.to_usize();
-    src[..idx].lines().count() + 1 // 1-based line number
+    src.as_bytes()[..idx].iter().filter(|&&b| b == b'\n').count() + 1 // 1-based line number
+}
+
+/// Source text covered by an AST node's range
+fn source_slice(src: &str, range: rustpython_parser::text_size::TextRange) -> Option<String> {
+    src.get(range.start().to_usize()..range.end().to_usize()).map(str::to_string)
+}
+
+/// Names bound by an assignment target, unpacking tuples, lists, and starred names
+///
+/// Attribute and subscript targets (`obj.attr = ...`, `d[k] = ...`) bind no new
+/// name and are skipped.
+fn bound_names(target: &Expr, names: &mut Vec<String>) {
+    match target {
+        Expr::Name(name) => names.push(name.id.to_string()),
+        Expr::Tuple(tuple) => tuple.elts.iter().for_each(|elt| bound_names(elt, names)),
+        Expr::List(list) => list.elts.iter().for_each(|elt| bound_names(elt, names)),
+        Expr::Starred(starred) => bound_names(&starred.value, names),
+        _ => {}
+    }
+}
+
+/// Function entity for a `def` or `async def`, a method when inside `parent_class`
+fn function_entity(
+    name: &str,
+    args: &Arguments,
+    body: &[Stmt],
+    range: rustpython_parser::text_size::TextRange,
+    rel_path: &str,
+    parent_class: Option<&str>,
+    src: &str,
+) -> CodeEntity {
+    CodeEntity {
+        entity_type: if parent_class.is_some() { "method" } else { "function" }.to_string(),
+        file_path: rel_path.to_string(),
+        name: name.to_string(),
+        signature: Some(get_signature(name, args)),
+        docstring: get_docstring(body),
+        line_start: textsize_to_line(src, range.start()),
+        line_end: textsize_to_line(src, range.end()),
+        parent_class: parent_class.map(|s| s.to_string()),
+        bases: None,
+        value_repr: None,
+        source: source_slice(src, range),
+    }
 }
 
 fn walk(node: &Stmt, rel_path: &str, entities: &mut Vec<CodeEntity>, parent_class: Option<&str>, src: &str) {
         match node {
             Stmt::FunctionDef(def) => {
-                let line_start = textsize_to_line(src, def.range.start());
-                let line_end = textsize_to_line(src, def.range.end());
-                let docstring = get_docstring(&def.body);
-                entities.push(CodeEntity {
-                    entity_type: if parent_class.is_some() { "method" } else { "function" }.to_string(),
-                    file_path: rel_path.to_string(),
-                    name: def.name.to_string(),
-                    signature: Some(get_signature(&def.name, &def.args)),
-                    docstring,
-                    line_start,
-                    line_end,
-                    parent_class: parent_class.map(|s| s.to_string()),
-                    bases: None,
-                    value_repr: None,
-                });
+                entities.push(function_entity(&def.name, &def.args, &def.body, def.range, rel_path, parent_class, src));
+            }
+            Stmt::AsyncFunctionDef(def) => {
+                entities.push(function_entity(&def.name, &def.args, &def.body, def.range, rel_path, parent_class, src));
             }
             Stmt::ClassDef(def) => {
                 let line_start = textsize_to_line(src, def.range.start());
@@ -110,43 +246,43 @@ fn walk(node: &Stmt, rel_path: &str, entities: &mut Vec<CodeEntity>, parent_clas
                     parent_class: None,
                     bases: Some(base_names),
                     value_repr: None,
+                    source: source_slice(src, def.range),
                 });
                 for stmt in &def.body {
                     walk(stmt, rel_path, entities, Some(&def.name), src);
                 }
             }
             Stmt::Assign(assign) => {
-                // Only top-level or class-level
+                // Only top-level or class-level; `x = y = 0` has one target per name
+                let mut names = Vec::new();
                 for target in &assign.targets {
-                    if let Expr::Name(boxed_id) = target {
-                        entities.push(CodeEntity {
-                            entity_type: "variable".to_string(),
-                            file_path: rel_path.to_string(),
-                            name: boxed_id.id.to_string(),
-                            signature: None,
-                            docstring: None,
-                            line_start: 1,
-                            line_end: 1,
-                            parent_class: parent_class.map(|s| s.to_string()),
-                            bases: None,
-                            value_repr: Some(format!("{:?}", assign.value)),
-                        });
-                    }
+                    bound_names(target, &mut names);
+                }
+                let line_start = textsize_to_line(src, assign.range.start());
+                let line_end = textsize_to_line(src, assign.range.end());
+                for name in names {
+                    entities.push(CodeEntity {
+                        entity_type: "variable".to_string(),
+                        file_path: rel_path.to_string(),
+                        name,
+                        signature: None,
+                        docstring: None,
+                        line_start,
+                        line_end,
+                        parent_class: parent_class.map(|s| s.to_string()),
+                        bases: None,
+                        value_repr: Some(format!("{:?}", assign.value)),
+                        source: source_slice(src, assign.range),
+                    });
                 }
             }
             _ => {}
         }
-        // Recurse into children
-        if let Stmt::ClassDef(def) = node {
-            for stmt in &def.body {
-                walk(stmt, rel_path, entities, parent_class, src);
-            }
-        }
     }
     for stmt in &ast {
-        walk(stmt, &rel_path, &mut entities, None, &content);
+        walk(stmt, rel_path, &mut entities, None, content);
     }
-    entities
+    Ok(entities)
 }
 
 #[cfg(test)]
@@ -162,9 +298,122 @@ mod tests {
         let code = "class Bar:\n    def foo(self):\n        pass\n";
         let mut file = File::create(&file_path).unwrap();
         write!(file, "{}", code).unwrap();
-        let entities = extract_code_info(&file_path, dir.path());
+        let entities = extract_code_info(&file_path, dir.path()).unwrap();
         assert!(entities.iter().any(|e| e.name == "Bar" && e.line_start > 0));
         assert!(entities.iter().any(|e| e.name == "foo" && e.line_start > 0));
     }
+
+    #[test]
+    fn test_extract_code_info_reports_syntax_errors() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("broken.py");
+        std::fs::write(&file_path, "def broken(:\n    pass\n").unwrap();
+        let diagnostic = extract_code_info(&file_path, dir.path()).unwrap_err();
+        assert_eq!(diagnostic.file, "broken.py");
+        assert!(diagnostic.line >= 1);
+
+        let missing = extract_code_info(&dir.path().join("missing.py"), dir.path()).unwrap_err();
+        assert_eq!((missing.line, missing.col), (0, 0));
+        assert!(missing.message.contains("could not read file"));
+    }
+
+    #[test]
+    fn test_extract_code_info_from_source() {
+        let code = "def foo():\n    pass\n";
+        let entities = extract_code_info_from_source(code, "pkg/foo.py");
+        assert!(entities.iter().any(|e| e.name == "foo" && e.file_path == "pkg/foo.py"));
+    }
+
+    #[test]
+    fn test_parse_code_info_reports_error_location() {
+        let code = "def ok():\n    pass\n\ndef broken(:\n    pass\n";
+        let diagnostic = parse_code_info(code, "pkg/broken.py").unwrap_err();
+        assert_eq!(diagnostic.file, "pkg/broken.py");
+        assert!((4..=5).contains(&diagnostic.line), "unexpected line: {}", diagnostic);
+        assert!(diagnostic.col >= 1);
+        assert!(!diagnostic.message.is_empty());
+        assert!(extract_code_info_from_source(code, "pkg/broken.py").is_empty());
+    }
+
+    #[test]
+    fn test_entities_capture_source() {
+        let code = "MAX = 3\n\nclass Bar:\n    def foo(self):\n        return MAX\n";
+        let entities = extract_code_info_from_source(code, "bar.py");
+        let foo = entities.iter().find(|e| e.name == "foo").unwrap();
+        assert_eq!(foo.source.as_deref(), Some("def foo(self):\n        return MAX"));
+        let max = entities.iter().find(|e| e.name == "MAX").unwrap();
+        assert_eq!(max.source.as_deref(), Some("MAX = 3"));
+        let bar = entities.iter().find(|e| e.name == "Bar").unwrap();
+        assert!(bar.source.as_deref().unwrap().starts_with("class Bar:"));
+    }
+
+    #[test]
+    fn test_unpacking_assignment_binds_each_name() {
+        let entities = extract_code_info_from_source("a, b = f()\n", "vars.py");
+        let names: Vec<&str> = entities.iter().filter(|e| e.entity_type == "variable").map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+
+        let entities = extract_code_info_from_source("first, [second, *rest] = items\n", "vars.py");
+        let names: Vec<&str> = entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["first", "second", "rest"]);
+    }
+
+    #[test]
+    fn test_chained_assignment_binds_every_target() {
+        let entities = extract_code_info_from_source("x = y = 1\n", "vars.py");
+        let names: Vec<&str> = entities.iter().filter(|e| e.entity_type == "variable").map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["x", "y"]);
+
+        // Attribute and subscript targets bind no name of their own
+        let entities = extract_code_info_from_source("obj.attr = cache[key] = total = 0\n", "vars.py");
+        let names: Vec<&str> = entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["total"]);
+    }
+
+    #[test]
+    fn test_entities_carry_their_line_ranges() {
+        let code = "import os\n\nclass Bar:\n    def foo(self):\n        return 1\n\nTIMEOUT = 30\nPATHS = [\n    \"a\",\n]\n";
+        let entities = extract_code_info_from_source(code, "settings.py");
+        let lines = |name: &str| entities.iter().find(|e| e.name == name).map(|e| (e.line_start, e.line_end)).unwrap();
+        assert_eq!(lines("Bar"), (3, 5));
+        assert_eq!(lines("foo"), (4, 5));
+        assert_eq!(lines("TIMEOUT"), (7, 7));
+        assert_eq!(lines("PATHS"), (8, 10));
+    }
+
+    #[test]
+    fn test_truncate_source() {
+        assert_eq!(truncate_source("def f(): pass", 100), "def f(): pass");
+        assert_eq!(truncate_source("def f(): pass", 5), "def f\n... [truncated 8 bytes]");
+        // Never splits a multi-byte character
+        assert_eq!(truncate_source("é", 1), "\n... [truncated 2 bytes]");
+    }
+
+    #[test]
+    fn test_slice_lines() {
+        let src = "def f():\n    return 1\n\nx = 2\n";
+        // Single line
+        assert_eq!(slice_lines(src, 2, 2), "    return 1");
+        // Multi-line, keeping blank lines inside the range
+        assert_eq!(slice_lines(src, 1, 3), "def f():\n    return 1\n");
+        assert_eq!(slice_lines(src, 1, 4), "def f():\n    return 1\n\nx = 2");
+        // Out of bounds
+        assert_eq!(slice_lines(src, 4, 100), "x = 2");
+        assert_eq!(slice_lines(src, 0, 1), "def f():");
+        assert_eq!(slice_lines(src, 5, 6), "");
+        assert_eq!(slice_lines(src, 3, 2), "");
+        assert_eq!(slice_lines("", 1, 1), "");
+        // CRLF line endings and a last line without a terminator
+        assert_eq!(slice_lines("a\r\nb\r\nc", 2, 3), "b\r\nc");
+        assert_eq!(slice_lines("a\r\nb\r\nc", 1, 1), "a");
+    }
+
+    #[test]
+    fn test_offset_to_line_col() {
+        let src = "ab\ncd\nef";
+        assert_eq!(offset_to_line_col(src, 0), (1, 1));
+        assert_eq!(offset_to_line_col(src, 4), (2, 2));
+        assert_eq!(offset_to_line_col(src, 100), (3, 3));
+    }
 }
 
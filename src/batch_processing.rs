@@ -2,9 +2,11 @@
 //! - Processes multiple texts in batch
 //! - Handles errors gracefully
 //! - Provides progress updates via callback
+//! - Embeds extracted entities concurrently through an `AsyncEmbedder`
 
-use crate::embedder::Embedder;
+use crate::embedder::{AsyncEmbedder, Embedder};
 use crate::extract_entities;
+use futures::stream::{self, StreamExt};
 use log::{info, warn};
 
 /// Process a batch of texts, extract entities, and generate embeddings with progress tracking
@@ -74,6 +76,110 @@ where
     Ok((all_embeddings, errors))
 }
 
+/// Embed already-extracted entities with at most `concurrency` requests in flight
+/// 
+/// Failed embeddings do not stop the batch: they are collected as errors and the
+/// remaining entities are still embedded. Results keep the input order.
+/// 
+/// # Arguments
+/// * `entities` - `(entity_id, entity_text, entity_type)` tuples to embed
+/// * `embedder` - Any `AsyncEmbedder`, including every blocking `Embedder`
+/// * `concurrency` - Maximum number of embedding requests in flight (at least 1)
+/// * `progress_callback` - Called with `(done, total)` after every entity
+/// 
+/// # Returns
+/// A tuple of:
+/// * A vector of `(entity_id, entity_type, embedding)` for every embedded entity
+/// * A vector of errors, one per entity that failed to embed
+pub async fn batch_process_entities_async<A, F>(
+    entities: Vec<(String, String, String)>,
+    embedder: &A,
+    concurrency: usize,
+    mut progress_callback: F,
+) -> (Vec<(String, String, Vec<f32>)>, Vec<String>)
+where
+    A: AsyncEmbedder + ?Sized,
+    F: FnMut(usize, usize),
+{
+    let total = entities.len();
+    let mut embeddings = Vec::with_capacity(total);
+    let mut errors = Vec::new();
+    let mut done = 0;
+    
+    let mut results = stream::iter(entities)
+        .map(|(entity_id, entity_text, entity_type)| async move {
+            let result = embedder.embed_async(&entity_text).await;
+            (entity_id, entity_type, result)
+        })
+        .buffered(concurrency.max(1));
+    
+    while let Some((entity_id, entity_type, result)) = results.next().await {
+        match result {
+            Ok(embedding) => embeddings.push((entity_id, entity_type, embedding)),
+            Err(e) => {
+                warn!("Failed to embed {}: {}", entity_id, e);
+                errors.push(format!("{}: {}", entity_id, e));
+            }
+        }
+        done += 1;
+        progress_callback(done, total);
+    }
+    
+    (embeddings, errors)
+}
+
+/// Embed already-extracted entities with one `embed_batch` call
+/// 
+/// Providers with a batch API send one request (or one per sub-batch) instead of
+/// one per entity. If the batch call fails, or returns the wrong number of vectors,
+/// the entities are embedded one at a time with `batch_process_entities_async`, so a
+/// single bad input only loses its own entity.
+/// 
+/// # Arguments
+/// * `entities` - `(entity_id, entity_text, entity_type)` tuples to embed
+/// * `embedder` - Embedder whose `embed_batch` is used
+/// * `concurrency` - Maximum number of requests in flight when falling back to
+///   embedding one entity at a time
+/// * `progress_callback` - Called with `(done, total)` as entities are embedded
+/// 
+/// # Returns
+/// A tuple of:
+/// * A vector of `(entity_id, entity_type, embedding)` for every embedded entity
+/// * A vector of errors, one per entity that failed to embed
+pub async fn batch_embed_entities<E, F>(
+    entities: Vec<(String, String, String)>,
+    embedder: &E,
+    concurrency: usize,
+    mut progress_callback: F,
+) -> (Vec<(String, String, Vec<f32>)>, Vec<String>)
+where
+    E: Embedder + ?Sized,
+    F: FnMut(usize, usize),
+{
+    if entities.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let texts: Vec<String> = entities.iter().map(|(_, entity_text, _)| entity_text.clone()).collect();
+    match embedder.embed_batch(&texts) {
+        Ok(embeddings) if embeddings.len() == entities.len() => {
+            progress_callback(entities.len(), entities.len());
+            let embedded = entities.into_iter()
+                .zip(embeddings)
+                .map(|((entity_id, _, entity_type), embedding)| (entity_id, entity_type, embedding))
+                .collect();
+            (embedded, Vec::new())
+        }
+        Ok(embeddings) => {
+            warn!("Batch of {} entities returned {} embeddings, embedding one at a time", entities.len(), embeddings.len());
+            batch_process_entities_async(entities, embedder, concurrency, progress_callback).await
+        }
+        Err(e) => {
+            warn!("Batch of {} entities failed, embedding one at a time: {}", entities.len(), e);
+            batch_process_entities_async(entities, embedder, concurrency, progress_callback).await
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,4 +222,87 @@ mod tests {
         assert!(errors.is_empty(), "Expected no errors, but got: {:?}", errors);
         assert_eq!(progress_counter.load(std::sync::atomic::Ordering::SeqCst), 1);
     }
+    
+    /// Embedder that fails for any input mentioning "broken"
+    struct FlakyEmbedder;
+    
+    impl Embedder for FlakyEmbedder {
+        fn embed(&self, _input: &str) -> Vec<f32> {
+            vec![0.5, 0.5, 0.5]
+        }
+        
+        fn try_embed(&self, input: &str) -> Result<Vec<f32>, crate::error::IndexerError> {
+            if input.contains("broken") {
+                Err(crate::error::IndexerError::Embed("provider unavailable".to_string()))
+            } else {
+                Ok(self.embed(input))
+            }
+        }
+    }
+    
+    #[test]
+    fn test_batch_process_async_collects_errors_and_keeps_order() {
+        let entities: Vec<(String, String, String)> = ["first", "broken", "third"].iter()
+            .map(|name| (format!("fn:a.py:{}", name), format!("fn {}", name), "function".to_string()))
+            .collect();
+        let mut progress = Vec::new();
+        
+        let (embeddings, errors) = futures::executor::block_on(batch_process_entities_async(
+            entities,
+            &FlakyEmbedder,
+            2,
+            |done, total| progress.push((done, total)),
+        ));
+        
+        let ids: Vec<&str> = embeddings.iter().map(|(id, _, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["fn:a.py:first", "fn:a.py:third"]);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("fn:a.py:broken"), "unexpected error: {}", errors[0]);
+        assert_eq!(progress, vec![(1, 3), (2, 3), (3, 3)]);
+    }
+    
+    /// Embedder that counts its `embed_batch` calls
+    #[derive(Default)]
+    struct BatchCountingEmbedder {
+        batches: std::sync::atomic::AtomicUsize,
+    }
+    
+    impl Embedder for BatchCountingEmbedder {
+        fn embed(&self, input: &str) -> Vec<f32> {
+            MockEmbedder.embed(input)
+        }
+        
+        fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, crate::error::IndexerError> {
+            self.batches.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(inputs.iter().map(|input| self.embed(input)).collect())
+        }
+    }
+    
+    #[test]
+    fn test_batch_embed_entities_sends_one_batch() {
+        let entities: Vec<(String, String, String)> = ["first", "second", "third"].iter()
+            .map(|name| (format!("fn:a.py:{}", name), format!("fn {}", name), "function".to_string()))
+            .collect();
+        let embedder = BatchCountingEmbedder::default();
+        
+        let (embeddings, errors) = futures::executor::block_on(batch_embed_entities(entities, &embedder, 2, |_, _| {}));
+        
+        assert_eq!(embedder.batches.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let ids: Vec<&str> = embeddings.iter().map(|(id, _, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["fn:a.py:first", "fn:a.py:second", "fn:a.py:third"]);
+        assert!(errors.is_empty());
+    }
+    
+    #[test]
+    fn test_batch_embed_entities_falls_back_to_single_requests() {
+        let entities: Vec<(String, String, String)> = ["first", "broken", "third"].iter()
+            .map(|name| (format!("fn:a.py:{}", name), format!("fn {}", name), "function".to_string()))
+            .collect();
+        
+        let (embeddings, errors) = futures::executor::block_on(batch_embed_entities(entities, &FlakyEmbedder, 2, |_, _| {}));
+        
+        let ids: Vec<&str> = embeddings.iter().map(|(id, _, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["fn:a.py:first", "fn:a.py:third"]);
+        assert_eq!(errors.len(), 1);
+    }
 }
@@ -1,6 +1,10 @@
 //! cli argument parsing for code_indexer_rust
 
-use clap::{Parser, Subcommand};
+use crate::output_format::OutputFormat;
+use crate::vectorize::Granularity;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -11,16 +15,53 @@ use clap::{Parser, Subcommand};
 pub struct CliArgs {
     #[command(subcommand)]
     pub command: Commands,
+    /// only log warnings and errors, whatever the config's log_level
+    #[arg(short = 'q', long = "quiet", conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// log debug messages, whatever the config's log_level
+    #[arg(short = 'v', long = "verbose")]
+    pub verbose: bool,
+}
+
+impl CliArgs {
+    /// Log level `--quiet` or `--verbose` asks for, taking precedence over the config
+    pub fn log_level_override(&self) -> Option<log::LevelFilter> {
+        if self.quiet {
+            Some(log::LevelFilter::Warn)
+        } else if self.verbose {
+            Some(log::LevelFilter::Debug)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
+    /// print a shell completion script to stdout
+    Completions {
+        /// shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
     /// index code in a directory
     Remember {
         #[arg(long = "name", alias = "project-name")]
         name: String,
         #[arg(default_value = ".")]
         path: String,
+        /// print the location and message of every file that fails to parse
+        #[arg(long = "show-parse-errors")]
+        show_parse_errors: bool,
+        /// skip source files larger than this many bytes (default: config `max_file_bytes` or 1 MiB)
+        #[arg(long = "max-file-bytes")]
+        max_file_bytes: Option<u64>,
+        /// skip test files (by default test_*.py, *_test.py, and anything under tests/; see config `test_file_patterns`)
+        #[arg(long = "exclude-tests", overrides_with = "include_tests")]
+        exclude_tests: bool,
+        /// index test files too (the default)
+        #[arg(long = "include-tests", overrides_with = "exclude_tests")]
+        include_tests: bool,
     },
     /// update specific files in memory
     Refresh {
@@ -28,26 +69,112 @@ pub enum Commands {
         name: String,
         files: String,
     },
+    /// keep the index live by re-indexing files as they change
+    Watch {
+        #[arg(long = "name", alias = "project-name")]
+        name: String,
+        #[arg(default_value = ".")]
+        path: String,
+    },
     /// search for code in memory
     Recall {
         #[arg(long)]
         entity: Option<String>,
+        /// print each entity's line range; combine with --format json for [{name, line_start, line_end}]
         #[arg(long = "show-lines")]
         show_lines: bool,
+        /// print each entity's stored source snippet
+        #[arg(long = "show-source")]
+        show_source: bool,
+        /// output format: human (table), json, jsonl, or markdown; with --show-lines, formats the line ranges
+        #[arg(long = "format")]
+        format: Option<OutputFormat>,
+        /// return at most this many entities; 0 or unset means no limit
         #[arg(long)]
         max: Option<usize>,
+        /// only list entities of files modified since a date (YYYY-MM-DD) or within a duration (e.g. 7d, 12h)
+        #[arg(long = "since", value_parser = parse_since)]
+        since: Option<i64>,
         #[arg(long = "name", alias = "project-name")]
         project_name: String,
+        /// write the results to this file instead of stdout, creating parent directories
+        #[arg(long = "output")]
+        output: Option<PathBuf>,
+    },
+    /// find entities by name in the keyword indexes, without embeddings
+    Search {
+        #[arg(long = "name", alias = "project-name")]
+        name: String,
+        /// name fragment to look for, matched case-insensitively
+        term: String,
+        /// restrict results to one entity type (e.g. function, class)
+        #[arg(long = "entity-type")]
+        entity_type: Option<String>,
+        /// match the words of the term against docstrings instead of names
+        #[arg(long = "docstrings")]
+        docstrings: bool,
+        /// output format: human, json, jsonl, or markdown
+        #[arg(long = "format", default_value = "human")]
+        format: OutputFormat,
     },
-    /// check what's in memory
+    /// check what's in memory: indexed files and entity counts by type
     Status {
         #[arg(long = "name", alias = "project-name")]
         name: String,
+        /// output format: human, json, jsonl, or markdown
+        #[arg(long = "format", default_value = "human")]
+        format: OutputFormat,
+    },
+    /// show file, entity, and vector counts for a project
+    Stats {
+        #[arg(long = "name", alias = "project-name")]
+        name: String,
     },
+    /// list the projects indexed in Redis
+    Projects,
     /// clear indexed data
     Forget {
         #[arg(long = "name", alias = "project-name")]
         name: String,
+        /// leave the project's vector embeddings in place
+        #[arg(long = "keep-vectors")]
+        keep_vectors: bool,
+        /// output format: human, json, jsonl, or markdown
+        #[arg(long = "format", default_value = "human")]
+        format: OutputFormat,
+    },
+    /// remove the entities and vectors of indexed files that no longer exist on disk
+    Prune {
+        #[arg(long = "name", alias = "project-name")]
+        name: String,
+        /// project directory the indexed files are relative to
+        #[arg(long = "path")]
+        path: String,
+    },
+    /// export a project's files, entities, and vectors to a newline-delimited JSON file
+    Export {
+        #[arg(long = "name", alias = "project-name")]
+        name: String,
+        #[arg(long = "out")]
+        out: PathBuf,
+    },
+    /// import a project previously written by export
+    Import {
+        #[arg(long = "name", alias = "project-name")]
+        name: String,
+        #[arg(long = "file")]
+        file: PathBuf,
+    },
+    /// compare a project's index with an export file or another key prefix
+    Diff {
+        #[arg(long = "name", alias = "project-name")]
+        name: String,
+        /// export file written by `export`, or a key prefix such as `code_index:old`
+        #[arg(long = "against")]
+        against: String,
+        /// output format: human, json, jsonl, or markdown
+        #[arg(long = "format", default_value = "human")]
+        format: OutputFormat,
     },
     /// batch-generate embeddings for code entities
     Vectorize {
@@ -65,21 +192,202 @@ pub enum Commands {
         dry_run: bool,
         #[arg(long = "verbose")]
         verbose: bool,
+        /// also embed TODO/FIXME/XXX comments as `todo` entities
+        #[arg(long = "index-todos")]
+        index_todos: bool,
+        /// print the location and message of every file that fails to parse
+        #[arg(long = "show-parse-errors")]
+        show_parse_errors: bool,
+        /// report new vs. already-embedded entities and the estimated cost, without embedding or writing
+        #[arg(long = "plan")]
+        plan: bool,
+        /// number of files to embed in parallel
+        #[arg(long = "concurrency", default_value_t = 4)]
+        concurrency: usize,
+        /// skip source files larger than this many bytes (default: config `max_file_bytes` or 1 MiB)
+        #[arg(long = "max-file-bytes")]
+        max_file_bytes: Option<u64>,
+        /// embed the entities `remember` stored in Redis instead of re-parsing the files under --path
+        #[arg(long = "from-index")]
+        from_index: bool,
+        /// comma-separated entity types to embed (e.g. function,class); default: all
+        #[arg(long = "entity-types", value_delimiter = ',')]
+        entity_types: Option<Vec<String>>,
+        /// comma-separated entity types to leave out
+        #[arg(long = "exclude-entity-types", value_delimiter = ',')]
+        exclude_entity_types: Option<Vec<String>>,
+        /// embedding dimension to build the vector store with, for models the embedder
+        /// does not know; checked against the first embedding
+        #[arg(long = "dimension")]
+        dimension: Option<usize>,
+        /// only embed entities that do not have a vector in the store yet
+        #[arg(long = "only-missing")]
+        only_missing: bool,
+        /// stop starting new files after this many seconds, keeping what was stored
+        #[arg(long = "timeout")]
+        timeout: Option<u64>,
+        /// embedding model for the openai or hf provider (default: the provider's configured model)
+        #[arg(long = "model")]
+        model: Option<String>,
+        /// stop after embedding this many entities, for a quick check of keys and store
+        #[arg(long = "limit")]
+        limit: Option<usize>,
+        /// also write every embedding to this JSONL file; with --dry-run, only write the file
+        #[arg(long = "emit")]
+        emit: Option<String>,
+        /// embed each entity, or each whole file as one `file` entity identified by its relative path
+        #[arg(long = "granularity", value_enum, default_value_t = Granularity::Entity)]
+        granularity: Granularity,
+        /// delete the stored vectors first when they have another dimension than this run's model
+        #[arg(long = "force-reembed")]
+        force_reembed: bool,
+        /// L2-normalize every embedding before storing it (or set `normalize_embeddings: true` in the config)
+        #[arg(long = "normalize-embeddings")]
+        normalize_embeddings: bool,
+    },
+    /// re-embed a project with a new embedding model and swap it in
+    Remodel {
+        #[arg(long = "name", alias = "project-name")]
+        name: String,
+        #[arg(long = "path")]
+        path: String,
+        #[arg(long = "provider")]
+        provider: Option<String>,
+        #[arg(long = "model")]
+        model: String,
+        /// L2-normalize every embedding before storing it (or set `normalize_embeddings: true` in the config)
+        #[arg(long = "normalize-embeddings")]
+        normalize_embeddings: bool,
     },
     /// recall/query vector embeddings for code/project
     VectorRecall {
-        #[arg(long = "name", alias = "project-name")]
+        /// project to search, or a comma-separated list of projects to search together
+        #[arg(long = "name", alias = "project-name", required_unless_present = "all", default_value = "")]
         name: String,
+        /// search every indexed project, merging the results by score
+        #[arg(long = "all", conflicts_with = "name")]
+        all: bool,
         #[arg(long = "query")]
         query: String,
         #[arg(long = "provider")]
         provider: Option<String>,
         #[arg(long = "db")]
         db: Option<String>,
-        #[arg(long = "top-k")]
+        /// number of results to return, at least 1 (default: 5; capped at config `max_top_k`, default 100)
+        #[arg(long = "top-k", value_parser = parse_top_k)]
         top_k: Option<usize>,
-        #[arg(long = "json")]
+        /// shorthand for --format json
+        #[arg(long = "json", conflicts_with = "format")]
         json: bool,
+        /// output format: human, color, json, jsonl, or markdown (default: results grouped by file, colored on a terminal)
+        #[arg(long = "format")]
+        format: Option<OutputFormat>,
+        /// restrict results to one entity type (e.g. function, class, todo)
+        #[arg(long = "type", alias = "entity-type")]
+        entity_type: Option<String>,
+        /// comma-separated entity types to leave out of the results
+        #[arg(long = "exclude-type", value_delimiter = ',')]
+        exclude_types: Option<Vec<String>>,
+        /// restrict results to files matching this path or glob (e.g. "src/**/*.py")
+        #[arg(long = "file")]
+        file: Option<String>,
+        /// leave out results scoring below this similarity (default: 0.0)
+        #[arg(long = "min-score")]
+        min_score: Option<f32>,
+        /// skip this many ranked results (for paging with --top-k)
+        #[arg(long = "offset")]
+        offset: Option<usize>,
+        /// comma-separated metadata fields to return (id is always included)
+        #[arg(long = "fields", value_delimiter = ',')]
+        fields: Option<Vec<String>>,
+        /// include each result's embedding vector in json or jsonl output
+        #[arg(long = "with-vectors")]
+        with_vectors: bool,
+        /// print the distribution of scores over every entity instead of the top results
+        #[arg(long)]
+        histogram: bool,
+        /// write the results to this file instead of stdout, creating parent directories
+        #[arg(long = "output")]
+        output: Option<PathBuf>,
+        /// embedding model for the openai or hf provider; must match the model the project was vectorized with
+        #[arg(long = "model")]
+        model: Option<String>,
     },
 
 }
+
+/// Parse a `--top-k` value, rejecting 0 instead of silently returning no results
+fn parse_top_k(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(top_k) => Ok(top_k),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parse a `--since` value into the Unix time it stands for
+fn parse_since(value: &str) -> Result<i64, String> {
+    since_cutoff(value, chrono::Utc::now().timestamp())
+}
+
+/// Unix time a `--since` value stands for at `now`
+///
+/// Accepts a date (`2024-01-01`, midnight UTC), an RFC 3339 timestamp, or a duration
+/// back from `now`: a number followed by `s`, `m`, `h`, `d`, or `w` (e.g. `7d`).
+pub fn since_cutoff(value: &str, now: i64) -> Result<i64, String> {
+    let value = value.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp());
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(time.timestamp());
+    }
+    let unit_secs = match value.chars().last() {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 60 * 60,
+        Some('d') => 24 * 60 * 60,
+        Some('w') => 7 * 24 * 60 * 60,
+        _ => return Err(format!("expected a date like 2024-01-01 or a duration like 7d, got `{}`", value)),
+    };
+    let amount: i64 = value[..value.len() - 1]
+        .parse()
+        .map_err(|_| format!("expected a date like 2024-01-01 or a duration like 7d, got `{}`", value))?;
+    amount.checked_mul(unit_secs)
+        .and_then(|secs| now.checked_sub(secs))
+        .ok_or_else(|| format!("duration `{}` is too large", value))
+}
+
+/// Write a completion script for `shell` covering every subcommand and flag
+pub fn write_completions(shell: Shell, out: &mut dyn std::io::Write) {
+    let mut command = CliArgs::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+impl Commands {
+    /// Project directory the command works on, used to find its `.indexer.yaml`
+    pub fn project_path(&self) -> Option<&str> {
+        match self {
+            Commands::Remember { path, .. }
+            | Commands::Watch { path, .. }
+            | Commands::Vectorize { path, .. }
+            | Commands::Remodel { path, .. }
+            | Commands::Prune { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+    
+    /// Whether the command reads or writes Redis, so main only connects when it must
+    ///
+    /// `vectorize --db memory` needs Redis only to read `--from-index` entities.
+    /// `vector-recall --db memory` is rejected before it would search anything.
+    pub fn uses_redis(&self) -> bool {
+        match self {
+            Commands::Completions { .. } => false,
+            Commands::Vectorize { db, from_index, .. } => *from_index || db.as_deref() != Some("memory"),
+            Commands::VectorRecall { db, .. } => db.as_deref() != Some("memory"),
+            _ => true,
+        }
+    }
+}
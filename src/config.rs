@@ -1,10 +1,46 @@
 //! configuration module for indexer
-//! loads ~/.indexer/config.yaml using serde_yaml
+//! loads ~/.indexer/config.yaml using serde_yaml, optionally merged with a
+//! project's .indexer.yaml
 
+use crate::embedder::RetryPolicy;
 use serde::Deserialize;
 use std::fs;
+use std::path::Path;
 use thiserror::Error;
 
+/// Per-project config file, looked up in the project directory
+pub const PROJECT_CONFIG_FILE: &str = ".indexer.yaml";
+
+/// Environment variable overriding `redis_url`
+pub const ENV_REDIS_URL: &str = "INDEXER_REDIS_URL";
+
+/// Environment variable overriding `log_level`
+pub const ENV_LOG_LEVEL: &str = "INDEXER_LOG_LEVEL";
+
+/// Environment variables overriding the `api_key` of a configured provider
+const PROVIDER_KEY_VARS: &[(&str, &str)] = &[("openai", "OPENAI_API_KEY"), ("hf", "HF_API_KEY")];
+
+/// Top-level keys `AppConfig` reads; `unknown_keys` reports any others
+const APP_CONFIG_KEYS: &[&str] = &[
+    "redis_url", "log_level", "log_format", "global_defaults", "providers", "vector_dbs",
+    "search_threads", "search_chunk_size", "embed_max_retries", "embed_retry_base_delay_ms",
+    "source_max_bytes", "max_file_bytes", "redis_pool_size", "extensions", "max_top_k",
+    "vector_encoding", "embed_model_load_max_wait_secs", "dedup_vectors", "normalize_embeddings",
+    "redis_connect_timeout_secs", "redis_command_timeout_secs", "test_file_patterns",
+];
+
+/// Keys read from `global_defaults`
+const GLOBAL_DEFAULTS_KEYS: &[&str] = &["provider", "db"];
+
+/// Keys read from each entry of `providers`
+const PROVIDER_CONFIG_KEYS: &[&str] = &[
+    "api_key", "model", "dimension", "base_url", "embeddings_path", "auth_header",
+    "max_batch_items", "max_batch_tokens",
+];
+
+/// Keys read from each entry of `vector_dbs`
+const VECTOR_DB_CONFIG_KEYS: &[&str] = &["url", "key_prefix"];
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,13 +49,131 @@ mod tests {
         let gd = GlobalDefaults { provider: "prov".to_string(), db: "db".to_string() };
         assert_eq!(gd.provider(), "prov");
         assert_eq!(gd.db(), "db");
-        let pc = ProviderConfig { api_key: "key".to_string(), model: "mod".to_string() };
+        let pc = ProviderConfig { api_key: "key".to_string(), model: "mod".to_string(), dimension: None, base_url: None, embeddings_path: None, auth_header: None, max_batch_items: None, max_batch_tokens: None };
         assert_eq!(pc.api_key(), "key");
         assert_eq!(pc.model(), "mod");
         let vdb = VectorDbConfig { url: "url".to_string(), key_prefix: "kp".to_string() };
         assert_eq!(vdb.url(), "url");
         assert_eq!(vdb.key_prefix(), "kp");
     }
+
+    #[test]
+    fn test_vector_db_url() {
+        let mut vdbs = HashMap::new();
+        vdbs.insert("qdrant".to_string(), VectorDbConfig { url: "http://qdrant:6334".to_string(), key_prefix: "kp".to_string() });
+        let config = AppConfig { vector_dbs: Some(vdbs), ..AppConfig::default() };
+        assert_eq!(config.vector_db_url("qdrant"), Some("http://qdrant:6334"));
+        assert_eq!(config.vector_db_url("redis"), None);
+        assert_eq!(AppConfig::default().vector_db_url("qdrant"), None);
+    }
+
+    #[test]
+    fn test_provider_dimension() {
+        let mut providers = HashMap::new();
+        providers.insert("hf".to_string(), ProviderConfig { api_key: "key".to_string(), model: "mod".to_string(), dimension: Some(384), base_url: None, embeddings_path: None, auth_header: None, max_batch_items: None, max_batch_tokens: None });
+        let config = AppConfig { providers: Some(providers), ..AppConfig::default() };
+        assert_eq!(config.provider_dimension("hf"), Some(384));
+        assert_eq!(config.provider_dimension("openai"), None);
+    }
+
+    #[test]
+    fn test_merge_keeps_global_provider_fields_a_project_does_not_set() {
+        let global: AppConfig = serde_yaml::from_str("providers:\n  openai:\n    api_key: global-key\n    model: text-embedding-3-small\n    max_batch_items: 64\n  hf:\n    api_key: hf-key\n    model: m\n").unwrap();
+        let project: AppConfig = serde_yaml::from_str("providers:\n  openai:\n    model: text-embedding-3-large\n").unwrap();
+        let merged = global.merge(project);
+        let openai = merged.provider("openai").unwrap();
+        assert_eq!(openai.model(), "text-embedding-3-large");
+        assert_eq!(openai.api_key(), "global-key");
+        assert_eq!(openai.max_batch_items, Some(64));
+        assert_eq!(merged.provider("hf").unwrap().api_key(), "hf-key");
+    }
+
+    #[test]
+    fn test_redis_pool_size() {
+        assert_eq!(AppConfig::default().redis_pool_size(), crate::redis_ops::DEFAULT_REDIS_POOL_SIZE);
+        let merged = AppConfig::default().merge(AppConfig { redis_pool_size: Some(8), ..AppConfig::default() });
+        assert_eq!(merged.redis_pool_size(), 8);
+    }
+
+    #[test]
+    fn test_redis_timeouts() {
+        let defaults = AppConfig::default().redis_timeouts();
+        assert_eq!(defaults.connect, crate::redis_ops::DEFAULT_REDIS_CONNECT_TIMEOUT);
+        assert_eq!(defaults.command, std::time::Duration::ZERO);
+        let merged = AppConfig::default().merge(AppConfig { redis_command_timeout_secs: Some(2), ..AppConfig::default() });
+        assert_eq!(merged.redis_timeouts().command, std::time::Duration::from_secs(2));
+        assert_eq!(merged.redis_timeouts().connect, crate::redis_ops::DEFAULT_REDIS_CONNECT_TIMEOUT);
+    }
+
+    #[test]
+    fn test_source_extensions() {
+        assert_eq!(AppConfig::default().source_extensions(), vec!["py"]);
+        let extensions = ["py", ".pyi", "", "*.rs", "rs"].iter().map(|e| e.to_string()).collect();
+        let config = AppConfig { extensions: Some(extensions), ..AppConfig::default() };
+        assert_eq!(config.source_extensions(), vec!["py", "pyi", "rs"]);
+    }
+
+    #[test]
+    fn test_test_file_patterns() {
+        assert_eq!(AppConfig::default().test_file_patterns(), vec!["**/test_*.py", "**/*_test.py", "**/tests/**"]);
+        let config = AppConfig { test_file_patterns: Some(vec!["**/spec_*.py".to_string()]), ..AppConfig::default() };
+        assert_eq!(config.test_file_patterns(), vec!["**/spec_*.py"]);
+    }
+
+    #[test]
+    fn test_top_k_limit() {
+        assert_eq!(AppConfig::default().top_k_limit(), crate::vector_search::DEFAULT_MAX_TOP_K);
+        assert_eq!(AppConfig { max_top_k: Some(20), ..AppConfig::default() }.top_k_limit(), 20);
+        assert_eq!(AppConfig { max_top_k: Some(0), ..AppConfig::default() }.top_k_limit(), 1);
+    }
+
+    #[test]
+    fn test_unknown_keys_lists_misspelled_keys_at_every_level() {
+        let yaml: serde_yaml::Value = serde_yaml::from_str(
+            "redis_ur: redis://x\nlog_level: info\nglobal_defaults:\n  provider: openai\n  db: redis\n  dbs: qdrant\nproviders:\n  openai:\n    api_key: k\n    modle: m\nvector_dbs:\n  redis:\n    url: u\n    key_prefix: p\n",
+        ).unwrap();
+        assert_eq!(unknown_keys(&yaml), vec!["redis_ur", "global_defaults.dbs", "providers.openai.modle"]);
+        assert!(unknown_keys(&serde_yaml::Value::Null).is_empty());
+    }
+
+    #[test]
+    fn test_dedup_vectors_defaults_off() {
+        assert!(!AppConfig::default().dedup_vectors());
+        let project = AppConfig { dedup_vectors: Some(true), ..AppConfig::default() };
+        assert!(AppConfig::default().merge(project).dedup_vectors());
+    }
+
+    #[test]
+    fn test_normalize_embeddings_defaults_off() {
+        assert!(!AppConfig::default().normalize_embeddings());
+        let project = AppConfig { normalize_embeddings: Some(true), ..AppConfig::default() };
+        assert!(AppConfig::default().merge(project).normalize_embeddings());
+    }
+
+    #[test]
+    fn test_vector_encoding() {
+        use crate::vector_store::VectorEncoding;
+        assert_eq!(AppConfig::default().vector_encoding().unwrap(), VectorEncoding::Json);
+        let config = AppConfig { vector_encoding: Some("binary".to_string()), ..AppConfig::default() };
+        assert_eq!(config.vector_encoding().unwrap(), VectorEncoding::Binary);
+        assert!(AppConfig { vector_encoding: Some("gzip".to_string()), ..AppConfig::default() }.vector_encoding().is_err());
+    }
+
+    #[test]
+    fn test_embed_retry_policy_overrides() {
+        let config = AppConfig { embed_max_retries: Some(2), embed_retry_base_delay_ms: Some(50), ..AppConfig::default() };
+        let policy = config.embed_retry_policy();
+        assert_eq!(policy.max_retries, 2);
+        assert_eq!(policy.base_delay, std::time::Duration::from_millis(50));
+        assert_eq!(AppConfig::default().embed_retry_policy(), RetryPolicy::default());
+    }
+
+    #[test]
+    fn test_embed_model_load_max_wait_override() {
+        let config = AppConfig { embed_model_load_max_wait_secs: Some(5), ..AppConfig::default() };
+        assert_eq!(config.embed_model_load_max_wait(), std::time::Duration::from_secs(5));
+        assert_eq!(AppConfig::default().embed_model_load_max_wait(), crate::embedder::DEFAULT_HF_MAX_LOAD_WAIT);
+    }
 }
 
 
@@ -44,8 +198,29 @@ impl GlobalDefaults {
 
 #[derive(Debug, Deserialize)]
 pub struct ProviderConfig {
+    /// may be left out of a project file to keep the global key
+    #[serde(default)]
     pub api_key: String,
+    #[serde(default)]
     pub model: String,
+    /// embedding length of `model`, for providers that cannot report it up front
+    #[serde(default)]
+    pub dimension: Option<usize>,
+    /// base URL of an OpenAI-compatible API (e.g. Azure OpenAI or a proxy)
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// embeddings path under `base_url` (default: /embeddings)
+    #[serde(default)]
+    pub embeddings_path: Option<String>,
+    /// how the API key is sent: bearer (default) or api-key
+    #[serde(default)]
+    pub auth_header: Option<String>,
+    /// most inputs per batched embeddings request
+    #[serde(default)]
+    pub max_batch_items: Option<usize>,
+    /// approximate token budget per batched embeddings request (chars / 4)
+    #[serde(default)]
+    pub max_batch_tokens: Option<usize>,
 }
 
 impl ProviderConfig {
@@ -55,6 +230,20 @@ impl ProviderConfig {
     pub fn model(&self) -> &str {
         &self.model
     }
+
+    /// Layer `over` on top of `self`: every field set in `over` wins
+    pub fn merge(self, over: ProviderConfig) -> ProviderConfig {
+        ProviderConfig {
+            api_key: if over.api_key.is_empty() { self.api_key } else { over.api_key },
+            model: if over.model.is_empty() { self.model } else { over.model },
+            dimension: over.dimension.or(self.dimension),
+            base_url: over.base_url.or(self.base_url),
+            embeddings_path: over.embeddings_path.or(self.embeddings_path),
+            auth_header: over.auth_header.or(self.auth_header),
+            max_batch_items: over.max_batch_items.or(self.max_batch_items),
+            max_batch_tokens: over.max_batch_tokens.or(self.max_batch_tokens),
+        }
+    }
 }
 
 
@@ -78,9 +267,44 @@ impl VectorDbConfig {
 pub struct AppConfig {
     pub redis_url: Option<String>,
     pub log_level: Option<String>,
+    /// log line format: "text" (default) or "json" for one JSON object per line
+    pub log_format: Option<String>,
     pub global_defaults: Option<GlobalDefaults>,
     pub providers: Option<HashMap<String, ProviderConfig>>,
     pub vector_dbs: Option<HashMap<String, VectorDbConfig>>,
+    /// worker threads for the similarity scan (default: available parallelism)
+    pub search_threads: Option<usize>,
+    /// candidates scored per chunk in the similarity scan (default: split evenly across threads)
+    pub search_chunk_size: Option<usize>,
+    /// retries for rate-limited or failed embedding requests (default: 5)
+    pub embed_max_retries: Option<u32>,
+    /// delay before the first embedding retry in milliseconds, doubled per retry (default: 500)
+    pub embed_retry_base_delay_ms: Option<u64>,
+    /// longest wait in seconds for a HuggingFace model that is still loading (default: 60)
+    pub embed_model_load_max_wait_secs: Option<u64>,
+    /// byte limit for each entity's stored source snippet (default: 4096)
+    pub source_max_bytes: Option<usize>,
+    /// source files larger than this many bytes are skipped (default: 1 MiB)
+    pub max_file_bytes: Option<u64>,
+    /// connections in each Redis vector store's pool (default: 4)
+    pub redis_pool_size: Option<usize>,
+    /// seconds to wait for a Redis connection before giving up (default: 5)
+    pub redis_connect_timeout_secs: Option<u64>,
+    /// seconds to wait for a reply to any one Redis command; 0 waits forever (default: 0)
+    pub redis_command_timeout_secs: Option<u64>,
+    /// source file extensions to index, without the leading dot (default: ["py"])
+    pub extensions: Option<Vec<String>>,
+    /// globs over project-relative paths that `remember --exclude-tests` skips
+    /// (default: ["**/test_*.py", "**/*_test.py", "**/tests/**"])
+    pub test_file_patterns: Option<Vec<String>>,
+    /// largest --top-k vector-recall accepts; larger requests are capped (default: 100)
+    pub max_top_k: Option<usize>,
+    /// how the Redis vector store writes vectors: "json" (default) or "binary" (little-endian f32, about 3x smaller)
+    pub vector_encoding: Option<String>,
+    /// store one vector per distinct embedding text in Redis, shared by identical entities (default: false)
+    pub dedup_vectors: Option<bool>,
+    /// L2-normalize embeddings before storing them, for vectorize and remodel (default: false)
+    pub normalize_embeddings: Option<bool>,
 }
 
 impl Default for AppConfig {
@@ -88,67 +312,354 @@ impl Default for AppConfig {
         Self {
             redis_url: Some("redis://127.0.0.1:6379/0".to_string()),
             log_level: Some("info".to_string()),
+            log_format: None,
             global_defaults: None,
             providers: None,
             vector_dbs: None,
+            search_threads: None,
+            search_chunk_size: None,
+            embed_max_retries: None,
+            embed_retry_base_delay_ms: None,
+            embed_model_load_max_wait_secs: None,
+            source_max_bytes: None,
+            max_file_bytes: None,
+            redis_pool_size: None,
+            redis_connect_timeout_secs: None,
+            redis_command_timeout_secs: None,
+            extensions: None,
+            test_file_patterns: None,
+            max_top_k: None,
+            vector_encoding: None,
+            dedup_vectors: None,
+            normalize_embeddings: None,
         }
     }
 }
 
 
 impl AppConfig {
+    /// Retry policy for embedding requests, with configured values over the defaults
+    pub fn embed_retry_policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        RetryPolicy {
+            max_retries: self.embed_max_retries.unwrap_or(default.max_retries),
+            base_delay: self.embed_retry_base_delay_ms
+                .map(std::time::Duration::from_millis)
+                .unwrap_or(default.base_delay),
+            ..default
+        }
+    }
+
+    /// Longest wait for a HuggingFace model that is still loading
+    pub fn embed_model_load_max_wait(&self) -> std::time::Duration {
+        self.embed_model_load_max_wait_secs
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(crate::embedder::DEFAULT_HF_MAX_LOAD_WAIT)
+    }
+
+    /// Settings of a configured provider (e.g. `providers.openai`)
+    pub fn provider(&self, name: &str) -> Option<&ProviderConfig> {
+        self.providers.as_ref()?.get(name)
+    }
+
+    /// Embedding dimension configured for a provider (e.g. `providers.hf.dimension`)
+    pub fn provider_dimension(&self, name: &str) -> Option<usize> {
+        self.provider(name)?.dimension
+    }
+
+    /// URL of a configured vector DB (e.g. `vector_dbs.qdrant.url`)
+    pub fn vector_db_url(&self, name: &str) -> Option<&str> {
+        self.vector_dbs.as_ref()?.get(name).map(VectorDbConfig::url)
+    }
+
+    /// Byte limit for stored source snippets, with the configured value over the default
+    pub fn source_byte_limit(&self) -> usize {
+        self.source_max_bytes.unwrap_or(crate::ast_parser::DEFAULT_SOURCE_MAX_BYTES)
+    }
+
+    /// Redis connection pool size, with the configured value over the default
+    pub fn redis_pool_size(&self) -> usize {
+        self.redis_pool_size.unwrap_or(crate::redis_ops::DEFAULT_REDIS_POOL_SIZE)
+    }
+
+    /// Redis connect and per-command timeouts, with the configured values over the defaults
+    pub fn redis_timeouts(&self) -> crate::redis_ops::RedisTimeouts {
+        let defaults = crate::redis_ops::RedisTimeouts::default();
+        crate::redis_ops::RedisTimeouts {
+            connect: self.redis_connect_timeout_secs.map(std::time::Duration::from_secs).unwrap_or(defaults.connect),
+            command: self.redis_command_timeout_secs.map(std::time::Duration::from_secs).unwrap_or(defaults.command),
+        }
+    }
+
+    /// Largest number of recall results, with the configured value over the default
+    pub fn top_k_limit(&self) -> usize {
+        self.max_top_k.unwrap_or(crate::vector_search::DEFAULT_MAX_TOP_K).max(1)
+    }
+
+    /// Encoding for vectors written to Redis, `Json` unless configured
+    ///
+    /// # Returns
+    /// * `Result<VectorEncoding, IndexerError>` - The encoding, or `Config` for an unknown name
+    pub fn vector_encoding(&self) -> Result<crate::vector_store::VectorEncoding, crate::error::IndexerError> {
+        self.vector_encoding.as_deref().map_or(Ok(Default::default()), str::parse)
+    }
+
+    /// Whether identical embedding texts share one stored vector; off unless configured
+    pub fn dedup_vectors(&self) -> bool {
+        self.dedup_vectors.unwrap_or(false)
+    }
+
+    /// Whether embeddings are L2-normalized before storage; off unless configured
+    pub fn normalize_embeddings(&self) -> bool {
+        self.normalize_embeddings.unwrap_or(false)
+    }
+
+    /// Size limit for source files, with the configured value over the default
+    pub fn file_byte_limit(&self) -> u64 {
+        self.max_file_bytes.unwrap_or(crate::file_processing::DEFAULT_MAX_FILE_BYTES)
+    }
+
+    /// Test file patterns for `--exclude-tests`, with the configured list over the default
+    pub fn test_file_patterns(&self) -> Vec<String> {
+        match self.test_file_patterns {
+            Some(ref patterns) => patterns.clone(),
+            None => crate::file_processing::DEFAULT_TEST_FILE_PATTERNS.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    /// Source file extensions to index, with the configured list over the default
+    ///
+    /// A leading dot is dropped; empty entries and entries that are not a plain
+    /// extension (e.g. `"*.py"` or `"src/py"`) are skipped.
+    pub fn source_extensions(&self) -> Vec<String> {
+        match self.extensions {
+            Some(ref extensions) => extensions.iter()
+                .map(|e| e.trim().trim_start_matches('.'))
+                .filter(|e| {
+                    let valid = !e.is_empty() && e.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                    if !valid {
+                        log::warn!("Ignoring invalid source extension {:?}", e);
+                    }
+                    valid
+                })
+                .map(str::to_string)
+                .collect(),
+            None => crate::file_processing::default_source_extensions(),
+        }
+    }
+
+    /// Load `~/.indexer/config.yaml` (see `global_config_path` for the fallbacks
+    /// without `$HOME`) over the built-in defaults, then apply environment overrides
     pub fn load() -> Result<Self, ConfigError> {
-        
-        let home = std::env::var("HOME").ok().map(std::path::PathBuf::from);
-    log::debug!("$HOME in loader = {:?}", std::env::var("HOME"));
-        let mut config_path = home.ok_or(ConfigError::HomeDirNotFound)?;
-        config_path.push(".indexer");
-        config_path.push("config.yaml");
-        if config_path.exists() {
-            let contents = fs::read_to_string(&config_path)?;
-            match serde_yaml::from_str::<AppConfig>(&contents) {
-                Ok(yaml) => {
-                        // Access all config fields to avoid dead code warnings
-                        if let Some(ref gd) = yaml.global_defaults {
-                            log::debug!("Loaded global_defaults: provider={}, db={}", gd.provider, gd.db);
-                            // Call getters to ensure they are used
-                            let _ = gd.provider();
-                            let _ = gd.db();
-                        }
-                        if let Some(ref providers) = yaml.providers {
-                            for (k, v) in providers {
-                                 log::debug!("Provider {}: model={}", k, v.model);
-                                // Call getters to ensure they are used
-                                let _ = v.api_key();
-                                let _ = v.model();
-                            }
-                        }
-                        if let Some(ref vdbs) = yaml.vector_dbs {
-                            for (k, v) in vdbs {
-                                log::debug!("VectorDb {}: url={}, key_prefix={}", k, v.url, v.key_prefix);
-                                // Call getters to ensure they are used
-                                let _ = v.url();
-                                let _ = v.key_prefix();
-                            }
-                        }
-                        let default = AppConfig::default();
-                    Ok(AppConfig {
-                        redis_url: yaml.redis_url.or(default.redis_url),
-                        log_level: yaml.log_level.or(default.log_level),
-                        global_defaults: yaml.global_defaults.or(default.global_defaults),
-                        providers: yaml.providers.or(default.providers),
-                        vector_dbs: yaml.vector_dbs.or(default.vector_dbs),
-                    })
-                },
-                Err(e) => {
-                    log::debug!("YAML parse error: {}", e);
-                    Err(ConfigError::Yaml(e))
-                },
+        Ok(Self::load_global()?.apply_env_overrides())
+    }
+
+    /// Load the global config, layer `.indexer.yaml` from `project_dir` over it, then
+    /// apply environment overrides
+    ///
+    /// Precedence is environment > project file > global file > defaults.
+    pub fn load_merged(project_dir: &Path) -> Result<Self, ConfigError> {
+        let global = Self::load_global()?;
+        let project_path = project_dir.join(PROJECT_CONFIG_FILE);
+        let merged = match Self::read_file(&project_path)? {
+            Some(project) => {
+                log::debug!("Merging project config from {}", project_path.display());
+                global.merge(project)
+            }
+            None => global,
+        };
+        Ok(merged.apply_env_overrides())
+    }
+
+    /// The global config file over the built-in defaults
+    fn load_global() -> Result<Self, ConfigError> {
+        let config_path = Self::global_config_path()?;
+        log::debug!("Global config path = {}", config_path.display());
+        let default = AppConfig::default();
+        Ok(match Self::read_file(&config_path)? {
+            Some(global) => default.merge(global),
+            None => default,
+        })
+    }
+
+    /// `~/.indexer/config.yaml`, or when `$HOME` is unset (as in many containers and
+    /// CI jobs) the first existing `$XDG_CONFIG_HOME/.indexer/config.yaml` or
+    /// `./.indexer/config.yaml`
+    ///
+    /// # Returns
+    /// * `Result<PathBuf, ConfigError>` - The path to read, or `HomeDirNotFound` when
+    ///   `$HOME` is unset and neither fallback file exists
+    fn global_config_path() -> Result<std::path::PathBuf, ConfigError> {
+        let config_file = |dir: std::path::PathBuf| dir.join(".indexer").join("config.yaml");
+        if let Ok(home) = std::env::var("HOME") {
+            return Ok(config_file(home.into()));
+        }
+        let fallbacks = [
+            std::env::var("XDG_CONFIG_HOME").ok().map(std::path::PathBuf::from),
+            std::env::current_dir().ok(),
+        ];
+        fallbacks.into_iter()
+            .flatten()
+            .map(config_file)
+            .find(|path| path.exists())
+            .ok_or(ConfigError::HomeDirNotFound)
+    }
+
+    /// Override fields from `INDEXER_REDIS_URL`, `INDEXER_LOG_LEVEL`, and the provider
+    /// API key variables (`OPENAI_API_KEY` for `openai`, `HF_API_KEY` for `hf`)
+    fn apply_env_overrides(mut self) -> Self {
+        if let Ok(url) = std::env::var(ENV_REDIS_URL) {
+            log::debug!("{} overrides redis_url", ENV_REDIS_URL);
+            self.redis_url = Some(url);
+        }
+        if let Ok(level) = std::env::var(ENV_LOG_LEVEL) {
+            log::debug!("{} overrides log_level", ENV_LOG_LEVEL);
+            self.log_level = Some(level);
+        }
+        if let Some(ref mut providers) = self.providers {
+            for (provider, var) in PROVIDER_KEY_VARS {
+                if let (Some(config), Ok(key)) = (providers.get_mut(*provider), std::env::var(var)) {
+                    log::debug!("{} overrides the {} api_key", var, provider);
+                    config.api_key = key;
+                }
+            }
+        }
+        self
+    }
+
+    /// Parse a config file, or `None` if it does not exist
+    ///
+    /// Keys no config field reads are an `UnknownKeys` error rather than ignored.
+    fn read_file(config_path: &Path) -> Result<Option<Self>, ConfigError> {
+        if !config_path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(config_path)?;
+        let value: serde_yaml::Value = serde_yaml::from_str(&contents)?;
+        let unknown = unknown_keys(&value);
+        if !unknown.is_empty() {
+            log::debug!("Unknown keys in {}: {:?}", config_path.display(), unknown);
+            return Err(ConfigError::UnknownKeys { file: config_path.to_path_buf(), keys: unknown });
+        }
+        match serde_yaml::from_str::<AppConfig>(&contents) {
+            Ok(yaml) => {
+                // Access all config fields to avoid dead code warnings
+                if let Some(ref gd) = yaml.global_defaults {
+                    log::debug!("Loaded global_defaults: provider={}, db={}", gd.provider, gd.db);
+                    // Call getters to ensure they are used
+                    let _ = gd.provider();
+                    let _ = gd.db();
+                }
+                if let Some(ref providers) = yaml.providers {
+                    for (k, v) in providers {
+                         log::debug!("Provider {}: model={}", k, v.model);
+                        // Call getters to ensure they are used
+                        let _ = v.api_key();
+                        let _ = v.model();
+                    }
+                }
+                if let Some(ref vdbs) = yaml.vector_dbs {
+                    for (k, v) in vdbs {
+                        log::debug!("VectorDb {}: url={}, key_prefix={}", k, v.url, v.key_prefix);
+                        // Call getters to ensure they are used
+                        let _ = v.url();
+                        let _ = v.key_prefix();
+                    }
+                }
+                Ok(Some(yaml))
+            },
+            Err(e) => {
+                log::debug!("YAML parse error in {}: {}", config_path.display(), e);
+                Err(ConfigError::Yaml(e))
+            },
+        }
+    }
+
+    /// Layer `over` on top of `self`: every field set in `over` wins
+    pub fn merge(self, over: AppConfig) -> AppConfig {
+        AppConfig {
+            redis_url: over.redis_url.or(self.redis_url),
+            log_level: over.log_level.or(self.log_level),
+            log_format: over.log_format.or(self.log_format),
+            global_defaults: over.global_defaults.or(self.global_defaults),
+            providers: merge_providers(self.providers, over.providers),
+            vector_dbs: over.vector_dbs.or(self.vector_dbs),
+            search_threads: over.search_threads.or(self.search_threads),
+            search_chunk_size: over.search_chunk_size.or(self.search_chunk_size),
+            embed_max_retries: over.embed_max_retries.or(self.embed_max_retries),
+            embed_retry_base_delay_ms: over.embed_retry_base_delay_ms.or(self.embed_retry_base_delay_ms),
+            embed_model_load_max_wait_secs: over.embed_model_load_max_wait_secs.or(self.embed_model_load_max_wait_secs),
+            source_max_bytes: over.source_max_bytes.or(self.source_max_bytes),
+            max_file_bytes: over.max_file_bytes.or(self.max_file_bytes),
+            redis_pool_size: over.redis_pool_size.or(self.redis_pool_size),
+            redis_connect_timeout_secs: over.redis_connect_timeout_secs.or(self.redis_connect_timeout_secs),
+            redis_command_timeout_secs: over.redis_command_timeout_secs.or(self.redis_command_timeout_secs),
+            extensions: over.extensions.or(self.extensions),
+            test_file_patterns: over.test_file_patterns.or(self.test_file_patterns),
+            max_top_k: over.max_top_k.or(self.max_top_k),
+            vector_encoding: over.vector_encoding.or(self.vector_encoding),
+            dedup_vectors: over.dedup_vectors.or(self.dedup_vectors),
+            normalize_embeddings: over.normalize_embeddings.or(self.normalize_embeddings),
+        }
+    }
+}
+
+/// Merge `over` into `base` provider by provider, so a project file can change one
+/// field of a provider without repeating the rest
+fn merge_providers(
+    base: Option<HashMap<String, ProviderConfig>>,
+    over: Option<HashMap<String, ProviderConfig>>,
+) -> Option<HashMap<String, ProviderConfig>> {
+    let (mut merged, over) = match (base, over) {
+        (Some(base), Some(over)) => (base, over),
+        (base, over) => return over.or(base),
+    };
+    for (name, provider) in over {
+        let provider = match merged.remove(&name) {
+            Some(base) => base.merge(provider),
+            None => provider,
+        };
+        merged.insert(name, provider);
+    }
+    Some(merged)
+}
+
+/// Dotted paths of the keys in a parsed config file that no config field reads
+///
+/// `serde_yaml` ignores unknown fields, so without this a typo like `redis_ur:`
+/// would silently leave `redis_url` at its default.
+pub fn unknown_keys(yaml: &serde_yaml::Value) -> Vec<String> {
+    fn check(value: &serde_yaml::Value, known: &[&str], path: &str, unknown: &mut Vec<String>) {
+        let Some(map) = value.as_mapping() else { return };
+        for (key, value) in map {
+            let key = key.as_str().map(str::to_string).unwrap_or_else(|| format!("{:?}", key));
+            let key_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+            if !known.contains(&key.as_str()) {
+                unknown.push(key_path);
+                continue;
+            }
+            let nested = match (path, key.as_str()) {
+                ("", "global_defaults") => Some((GLOBAL_DEFAULTS_KEYS, false)),
+                ("", "providers") => Some((PROVIDER_CONFIG_KEYS, true)),
+                ("", "vector_dbs") => Some((VECTOR_DB_CONFIG_KEYS, true)),
+                _ => None,
+            };
+            match nested {
+                Some((keys, false)) => check(value, keys, &key_path, unknown),
+                // Named entries: any name is allowed, their fields are checked
+                Some((keys, true)) => {
+                    for (name, entry) in value.as_mapping().into_iter().flatten() {
+                        let name = name.as_str().map(str::to_string).unwrap_or_else(|| format!("{:?}", name));
+                        check(entry, keys, &format!("{}.{}", key_path, name), unknown);
+                    }
+                }
+                None => {}
             }
-        } else {
-            Ok(AppConfig::default())
         }
     }
+    let mut unknown = Vec::new();
+    check(yaml, APP_CONFIG_KEYS, "", &mut unknown);
+    unknown
 }
 
 #[derive(Debug, Error)]
@@ -159,4 +670,6 @@ pub enum ConfigError {
     Io(#[from] std::io::Error),
     #[error("yaml parse error: {0}")]
     Yaml(#[from] serde_yaml::Error),
+    #[error("unknown config keys in {}: {}", .file.display(), .keys.join(", "))]
+    UnknownKeys { file: std::path::PathBuf, keys: Vec<String> },
 }
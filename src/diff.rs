@@ -0,0 +1,286 @@
+//! Implementation of the diff command
+//!
+//! Compares the entities of two snapshots of the same project, either the live index
+//! against an `export` file or against a second key prefix. Entities are keyed on
+//! `(entity_type, file_path, name)`; entities present in both snapshots are checked for
+//! line-range changes, and a removed and an added entity of the same type starting on
+//! the same line of the same file are reported as a rename.
+
+use crate::ast_parser::CodeEntity;
+use crate::error::IndexerError;
+use crate::output_format::{format_json_lines, OutputFormat};
+use crate::redis_ops::{query_code_entity, DumpRecord, DumpValue, ENTITY_TYPES};
+use fred::prelude::Client;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// An entity as reported in a diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntityRef {
+    pub entity_type: String,
+    pub name: String,
+    pub line_start: usize,
+    pub line_end: usize,
+}
+
+impl From<&CodeEntity> for EntityRef {
+    fn from(entity: &CodeEntity) -> Self {
+        Self {
+            entity_type: entity.entity_type.clone(),
+            name: entity.name.clone(),
+            line_start: entity.line_start,
+            line_end: entity.line_end,
+        }
+    }
+}
+
+/// An entity whose name changed while its type, file, and start line stayed put
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Renamed {
+    pub entity_type: String,
+    pub from: String,
+    pub to: String,
+    pub line_start: usize,
+}
+
+/// An entity present in both snapshots whose line range changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LineChange {
+    pub entity_type: String,
+    pub name: String,
+    pub old_lines: (usize, usize),
+    pub new_lines: (usize, usize),
+}
+
+/// Changes to the entities of one file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FileDiff {
+    pub file: String,
+    pub added: Vec<EntityRef>,
+    pub removed: Vec<EntityRef>,
+    pub renamed: Vec<Renamed>,
+    pub moved: Vec<LineChange>,
+}
+
+impl FileDiff {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// Compare the entities of a baseline snapshot with the current ones
+///
+/// # Arguments
+/// * `baseline` - Entities of the older snapshot
+/// * `current` - Entities of the newer snapshot
+///
+/// # Returns
+/// * `Vec<FileDiff>` - Changes grouped by file, sorted by file; unchanged files are omitted
+pub fn diff_entities(baseline: &[CodeEntity], current: &[CodeEntity]) -> Vec<FileDiff> {
+    fn key(entity: &CodeEntity) -> (&str, &str, &str) {
+        (&entity.entity_type, &entity.file_path, &entity.name)
+    }
+    let old: HashMap<_, &CodeEntity> = baseline.iter().map(|e| (key(e), e)).collect();
+    let new: HashMap<_, &CodeEntity> = current.iter().map(|e| (key(e), e)).collect();
+
+    let mut files: BTreeMap<String, FileDiff> = BTreeMap::new();
+
+    for entity in current {
+        match old.get(&key(entity)) {
+            None => file_diff(&mut files, &entity.file_path).added.push(entity.into()),
+            Some(before) if (before.line_start, before.line_end) != (entity.line_start, entity.line_end) => {
+                file_diff(&mut files, &entity.file_path).moved.push(LineChange {
+                    entity_type: entity.entity_type.clone(),
+                    name: entity.name.clone(),
+                    old_lines: (before.line_start, before.line_end),
+                    new_lines: (entity.line_start, entity.line_end),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for entity in baseline {
+        if !new.contains_key(&key(entity)) {
+            file_diff(&mut files, &entity.file_path).removed.push(entity.into());
+        }
+    }
+
+    let mut diffs: Vec<FileDiff> = files.into_values().collect();
+    for diff in &mut diffs {
+        pair_renames(diff);
+        diff.added.sort_by(|a, b| (a.line_start, &a.name).cmp(&(b.line_start, &b.name)));
+        diff.removed.sort_by(|a, b| (a.line_start, &a.name).cmp(&(b.line_start, &b.name)));
+        diff.moved.sort_by(|a, b| (a.new_lines, &a.name).cmp(&(b.new_lines, &b.name)));
+    }
+    diffs.retain(|diff| !diff.is_empty());
+    diffs
+}
+
+/// The diff entry for `path`, created on first use
+fn file_diff<'a>(files: &'a mut BTreeMap<String, FileDiff>, path: &str) -> &'a mut FileDiff {
+    files.entry(path.to_string()).or_insert_with(|| FileDiff { file: path.to_string(), ..FileDiff::default() })
+}
+
+/// Turn removed/added pairs of the same type and start line into renames
+fn pair_renames(diff: &mut FileDiff) {
+    let mut removed = Vec::new();
+    for old in std::mem::take(&mut diff.removed) {
+        let partner = diff.added.iter()
+            .position(|new| new.entity_type == old.entity_type && new.line_start == old.line_start);
+        match partner {
+            Some(i) => {
+                let new = diff.added.remove(i);
+                diff.renamed.push(Renamed {
+                    entity_type: old.entity_type,
+                    from: old.name,
+                    to: new.name,
+                    line_start: new.line_start,
+                });
+            }
+            None => removed.push(old),
+        }
+    }
+    diff.removed = removed;
+    diff.renamed.sort_by(|a, b| (a.line_start, &a.to).cmp(&(b.line_start, &b.to)));
+}
+
+/// Extract the entities recorded in an `export` dump
+pub fn entities_from_dump(records: &[DumpRecord]) -> Result<Vec<CodeEntity>, IndexerError> {
+    let mut entities = Vec::new();
+    for entity_type in ENTITY_TYPES {
+        let type_key = format!("{}s", entity_type);
+        let Some(record) = records.iter().find(|r| r.key == type_key) else {
+            continue;
+        };
+        if let DumpValue::Hash(ref values) = record.value {
+            for json in values.values() {
+                entities.push(serde_json::from_str(json)?);
+            }
+        }
+    }
+    Ok(entities)
+}
+
+/// Load every entity stored under a project's key prefix
+pub async fn load_entities(redis: &Client, key_prefix: &str) -> Result<Vec<CodeEntity>, IndexerError> {
+    let mut entities = Vec::new();
+    for entity_type in ENTITY_TYPES {
+        entities.extend(query_code_entity(redis, key_prefix, entity_type, None).await?);
+    }
+    Ok(entities)
+}
+
+/// Render a diff for the terminal or as JSON
+pub fn format_diff(diffs: &[FileDiff], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(diffs).unwrap_or_else(|e| {
+            log::error!("Failed to serialize diff to JSON: {}", e);
+            String::from("[]")
+        }),
+        OutputFormat::JsonLines => format_json_lines(diffs),
+        OutputFormat::Human | OutputFormat::HumanColored | OutputFormat::Markdown => {
+            if diffs.is_empty() {
+                return "No entity changes.".to_string();
+            }
+            let mut lines = Vec::new();
+            for diff in diffs {
+                lines.push(diff.file.clone());
+                for e in &diff.added {
+                    lines.push(format!("  + {} {} ({}-{})", e.entity_type, e.name, e.line_start, e.line_end));
+                }
+                for e in &diff.removed {
+                    lines.push(format!("  - {} {} ({}-{})", e.entity_type, e.name, e.line_start, e.line_end));
+                }
+                for r in &diff.renamed {
+                    lines.push(format!("  ~ {} {} -> {} (line {})", r.entity_type, r.from, r.to, r.line_start));
+                }
+                for m in &diff.moved {
+                    lines.push(format!(
+                        "  > {} {} {}-{} -> {}-{}",
+                        m.entity_type, m.name, m.old_lines.0, m.old_lines.1, m.new_lines.0, m.new_lines.1
+                    ));
+                }
+            }
+            lines.join("\n")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(entity_type: &str, file_path: &str, name: &str, line_start: usize, line_end: usize) -> CodeEntity {
+        CodeEntity {
+            entity_type: entity_type.to_string(),
+            file_path: file_path.to_string(),
+            name: name.to_string(),
+            signature: None,
+            docstring: None,
+            line_start,
+            line_end,
+            parent_class: None,
+            bases: None,
+            value_repr: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_entities_groups_changes_by_file() {
+        let baseline = vec![
+            entity("function", "a.py", "keep", 1, 3),
+            entity("function", "a.py", "shift", 5, 7),
+            entity("function", "a.py", "old_name", 9, 10),
+            entity("class", "b.py", "Gone", 1, 4),
+        ];
+        let current = vec![
+            entity("function", "a.py", "keep", 1, 3),
+            entity("function", "a.py", "shift", 6, 9),
+            entity("function", "a.py", "new_name", 9, 10),
+            entity("function", "c.py", "fresh", 1, 2),
+        ];
+
+        let diffs = diff_entities(&baseline, &current);
+        let files: Vec<&str> = diffs.iter().map(|d| d.file.as_str()).collect();
+        assert_eq!(files, vec!["a.py", "b.py", "c.py"]);
+
+        let a = &diffs[0];
+        assert!(a.added.is_empty() && a.removed.is_empty());
+        assert_eq!(a.renamed, vec![Renamed {
+            entity_type: "function".to_string(),
+            from: "old_name".to_string(),
+            to: "new_name".to_string(),
+            line_start: 9,
+        }]);
+        assert_eq!(a.moved.len(), 1);
+        assert_eq!((a.moved[0].old_lines, a.moved[0].new_lines), ((5, 7), (6, 9)));
+
+        assert_eq!(diffs[1].removed[0].name, "Gone");
+        assert_eq!(diffs[2].added[0].name, "fresh");
+    }
+
+    #[test]
+    fn test_diff_of_identical_snapshots_is_empty() {
+        let entities = vec![entity("function", "a.py", "keep", 1, 3)];
+        let diffs = diff_entities(&entities, &entities);
+        assert!(diffs.is_empty());
+        assert_eq!(format_diff(&diffs, OutputFormat::Human), "No entity changes.");
+        assert_eq!(format_diff(&diffs, OutputFormat::Json), "[]");
+    }
+
+    #[test]
+    fn test_entities_from_dump() {
+        let foo = entity("function", "a.py", "foo", 1, 2);
+        let records = vec![
+            DumpRecord { key: "file_index".to_string(), value: DumpValue::Set(vec!["a.py".to_string()]) },
+            DumpRecord {
+                key: "functions".to_string(),
+                value: DumpValue::Hash([("foo".to_string(), serde_json::to_string(&foo).unwrap())].into_iter().collect()),
+            },
+        ];
+        let entities = entities_from_dump(&records).unwrap();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].name, "foo");
+    }
+}
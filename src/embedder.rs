@@ -1,11 +1,336 @@
 //! Embedder trait and mock/test implementation
 
+use crate::config::ProviderConfig;
+use crate::error::IndexerError;
+use futures::future::LocalBoxFuture;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
 pub trait Embedder {
     fn embed(&self, input: &str) -> Vec<f32>;
+
+    /// Embed `input`, surfacing provider failures instead of an empty vector
+    ///
+    /// Defaults to wrapping `embed`; providers that can fail should override it.
+    fn try_embed(&self, input: &str) -> Result<Vec<f32>, IndexerError> {
+        Ok(self.embed(input))
+    }
+
+    /// Embed every input, returning the embeddings in input order
+    ///
+    /// Defaults to one `try_embed` call per input; providers with a batch API
+    /// should override it to send fewer requests.
+    fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, IndexerError> {
+        inputs.iter().map(|input| self.try_embed(input)).collect()
+    }
+
+    /// Length of the vectors this embedder produces
+    ///
+    /// Defaults to embedding a probe string; providers with a known model dimension
+    /// should override this to avoid the extra request.
+    fn dimension(&self) -> usize {
+        self.embed("dimension probe").len()
+    }
+
+    /// Length of the vectors this embedder produces, surfacing a failed probe
+    ///
+    /// Defaults to `dimension`; providers that probe the model should override it so
+    /// a failed request is an error instead of a dimension of 0.
+    fn try_dimension(&self) -> Result<usize, IndexerError> {
+        Ok(self.dimension())
+    }
+
+    /// Name of the model behind this embedder, recorded with a project's vectors
+    fn model_name(&self) -> &str {
+        "unknown"
+    }
+}
+
+/// Borrowed and boxed embedders, such as the provider main picks at runtime, can be
+/// wrapped (e.g. in `NormalizingEmbedder`) like owned ones
+impl<E: Embedder + ?Sized> Embedder for &E {
+    fn embed(&self, input: &str) -> Vec<f32> {
+        (**self).embed(input)
+    }
+
+    fn try_embed(&self, input: &str) -> Result<Vec<f32>, IndexerError> {
+        (**self).try_embed(input)
+    }
+
+    fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, IndexerError> {
+        (**self).embed_batch(inputs)
+    }
+
+    fn dimension(&self) -> usize {
+        (**self).dimension()
+    }
+
+    fn try_dimension(&self) -> Result<usize, IndexerError> {
+        (**self).try_dimension()
+    }
+
+    fn model_name(&self) -> &str {
+        (**self).model_name()
+    }
+}
+
+impl<E: Embedder + ?Sized> Embedder for Box<E> {
+    fn embed(&self, input: &str) -> Vec<f32> {
+        (**self).embed(input)
+    }
+
+    fn try_embed(&self, input: &str) -> Result<Vec<f32>, IndexerError> {
+        (**self).try_embed(input)
+    }
+
+    fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, IndexerError> {
+        (**self).embed_batch(inputs)
+    }
+
+    fn dimension(&self) -> usize {
+        (**self).dimension()
+    }
+
+    fn try_dimension(&self) -> Result<usize, IndexerError> {
+        (**self).try_dimension()
+    }
+
+    fn model_name(&self) -> &str {
+        (**self).model_name()
+    }
+}
+
+/// Embedder whose requests can be awaited, so several can be in flight at once
+///
+/// Every `Embedder` is an `AsyncEmbedder` whose future completes on the first poll;
+/// providers with a non-blocking client can implement this directly to get real
+/// overlap from `batch_process_entities_async`.
+pub trait AsyncEmbedder {
+    fn embed_async<'a>(&'a self, input: &'a str) -> LocalBoxFuture<'a, Result<Vec<f32>, IndexerError>>;
+}
+
+impl<E: Embedder + ?Sized> AsyncEmbedder for E {
+    fn embed_async<'a>(&'a self, input: &'a str) -> LocalBoxFuture<'a, Result<Vec<f32>, IndexerError>> {
+        Box::pin(async move { self.try_embed(input) })
+    }
+}
+
+/// Scale `vector` to unit L2 length; zero vectors are returned unchanged
+pub fn l2_normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|x| x / norm).collect()
+}
+
+/// Embedder wrapper that L2-normalizes every vector of the inner embedder
+///
+/// Unit-length vectors make the dot product equal to cosine similarity.
+pub struct NormalizingEmbedder<E> {
+    inner: E,
+}
+
+impl<E: Embedder> NormalizingEmbedder<E> {
+    pub fn new(inner: E) -> Self {
+        Self { inner }
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: Embedder> Embedder for NormalizingEmbedder<E> {
+    fn embed(&self, input: &str) -> Vec<f32> {
+        l2_normalize(&self.inner.embed(input))
+    }
+
+    fn try_embed(&self, input: &str) -> Result<Vec<f32>, IndexerError> {
+        self.inner.try_embed(input).map(|vector| l2_normalize(&vector))
+    }
+
+    fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, IndexerError> {
+        self.inner.embed_batch(inputs).map(|vectors| vectors.iter().map(|vector| l2_normalize(vector)).collect())
+    }
+
+    fn dimension(&self) -> usize {
+        self.inner.dimension()
+    }
+
+    fn try_dimension(&self) -> Result<usize, IndexerError> {
+        self.inner.try_dimension()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+/// Retry settings for transient embedding API failures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt before giving up
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on every further retry
+    pub base_delay: Duration,
+    /// Upper bound for any single delay
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (0-based): exponential backoff capped at
+    /// `max_delay`, with jitter drawn from the upper half of the window
+    pub fn delay_for(&self, retry: u32) -> Duration {
+        let window = self.base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let half = window / 2;
+        let jitter = random_u64() % (half.as_millis() as u64 + 1);
+        half + Duration::from_millis(jitter)
+    }
+}
+
+/// Cheap random number for jitter, seeded per call by the std hasher
+fn random_u64() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new().build_hasher().finish()
+}
+
+/// Longest any one embedding request may take
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Timeout for the next request: `REQUEST_TIMEOUT`, cut to what is left before `deadline`
+///
+/// # Returns
+/// * `Result<Duration, IndexerError>` - The timeout, or `Embed` once the deadline has passed
+fn request_timeout(deadline: Option<Instant>) -> Result<Duration, IndexerError> {
+    let Some(deadline) = deadline else {
+        return Ok(REQUEST_TIMEOUT);
+    };
+    match deadline.checked_duration_since(Instant::now()) {
+        Some(left) if !left.is_zero() => Ok(left.min(REQUEST_TIMEOUT)),
+        _ => Err(IndexerError::Embed("deadline passed before the request was sent".to_string())),
+    }
+}
+
+/// Sleep `delay` before a retry, or fail if that would run past `deadline`
+fn wait_to_retry(delay: Duration, deadline: Option<Instant>) -> Result<(), IndexerError> {
+    if deadline.is_some_and(|deadline| Instant::now() + delay >= deadline) {
+        return Err(IndexerError::Embed(format!("a retry in {:?} would run past the deadline", delay)));
+    }
+    std::thread::sleep(delay);
+    Ok(())
+}
+
+/// Whether an HTTP status is worth retrying: rate limits and server-side failures
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// OpenAI embeddings endpoint
+pub const OPENAI_EMBEDDINGS_URL: &str = "https://api.openai.com/v1/embeddings";
+
+/// Path appended to a custom OpenAI base URL when no other path is configured
+pub const OPENAI_EMBEDDINGS_PATH: &str = "/embeddings";
+
+/// How the OpenAI embedder sends its API key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthHeader {
+    /// `Authorization: Bearer <key>`, used by OpenAI and most compatible gateways
+    #[default]
+    Bearer,
+    /// `api-key: <key>`, used by Azure OpenAI
+    ApiKey,
+}
+
+impl std::str::FromStr for AuthHeader {
+    type Err = IndexerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "bearer" => Ok(AuthHeader::Bearer),
+            "api-key" | "api_key" | "azure" => Ok(AuthHeader::ApiKey),
+            other => Err(IndexerError::Config(format!("Unknown OpenAI auth header {:?}; expected bearer or api-key", other))),
+        }
+    }
+}
+
+/// Model used when none is configured
+pub const DEFAULT_OPENAI_MODEL: &str = "text-embedding-ada-002";
+
+#[derive(serde::Deserialize)]
+struct OpenAIEmbeddingResponse {
+    data: Vec<OpenAIEmbeddingData>,
+}
+
+#[derive(serde::Deserialize)]
+struct OpenAIEmbeddingData {
+    embedding: Vec<f32>,
+    /// Position of the input this embedding belongs to
+    #[serde(default)]
+    index: usize,
+}
+
+/// Most inputs OpenAI accepts in one embeddings request
+pub const DEFAULT_MAX_BATCH_ITEMS: usize = 2048;
+
+/// Approximate token budget for one embeddings request
+pub const DEFAULT_MAX_BATCH_TOKENS: usize = 300_000;
+
+/// Rough token count of `input`, at four characters per token
+pub fn estimate_tokens(input: &str) -> usize {
+    input.chars().count().div_ceil(4).max(1)
+}
+
+/// Split `inputs` into consecutive ranges of at most `max_items` inputs and about
+/// `max_tokens` estimated tokens each
+///
+/// An input over the token budget on its own still gets a batch of one, so the
+/// provider can report it rather than it being dropped.
+pub fn split_batches(inputs: &[String], max_items: usize, max_tokens: usize) -> Vec<std::ops::Range<usize>> {
+    let max_items = max_items.max(1);
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut tokens = 0;
+    for (i, input) in inputs.iter().enumerate() {
+        let cost = estimate_tokens(input);
+        if i > start && (i - start >= max_items || tokens + cost > max_tokens) {
+            batches.push(start..i);
+            start = i;
+            tokens = 0;
+        }
+        tokens += cost;
+    }
+    if start < inputs.len() {
+        batches.push(start..inputs.len());
+    }
+    batches
 }
 
 pub struct OpenAIEmbedder {
     api_key: String,
+    model: String,
+    endpoint: String,
+    auth: AuthHeader,
+    retry: RetryPolicy,
+    max_batch_items: usize,
+    max_batch_tokens: usize,
+    /// No request is sent, or waited on, past this
+    deadline: Option<Instant>,
+    agent: ureq::Agent,
+    /// Dimension probed from a model without a known one
+    dimension: OnceLock<usize>,
 }
 
 impl OpenAIEmbedder {
@@ -13,26 +338,293 @@ impl OpenAIEmbedder {
         &self.api_key
     }
 
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Override the embeddings endpoint (e.g. for a proxy or a test server)
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Send requests to an OpenAI-compatible API at `base_url`, at `path` under it
+    /// (default `/embeddings`)
+    ///
+    /// For Azure OpenAI, `base_url` is the resource URL and `path` names the
+    /// deployment, e.g. `/openai/deployments/<name>/embeddings?api-version=2024-02-01`.
+    pub fn with_base_url(self, base_url: &str, path: Option<&str>) -> Self {
+        let path = path.unwrap_or(OPENAI_EMBEDDINGS_PATH);
+        let endpoint = format!("{}/{}", base_url.trim_end_matches('/'), path.trim_start_matches('/'));
+        self.with_endpoint(&endpoint)
+    }
+
+    /// Embed with `model` instead of the one given at construction; a dimension
+    /// probed for the old model is dropped
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self.dimension = OnceLock::new();
+        self
+    }
+
+    pub fn with_auth_header(mut self, auth: AuthHeader) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Apply the endpoint settings from `OPENAI_BASE_URL`, `OPENAI_EMBEDDINGS_PATH`,
+    /// and `OPENAI_AUTH_HEADER`, falling back to the `openai` provider config, and
+    /// the provider's batch limits
+    ///
+    /// # Returns
+    /// * `Result<Self, IndexerError>` - The embedder, or `Config` for an unknown auth header
+    pub fn with_endpoint_config(self, provider: Option<&ProviderConfig>) -> Result<Self, IndexerError> {
+        let setting = |var: &str, configured: Option<&String>| std::env::var(var).ok().or_else(|| configured.cloned());
+        let base_url = setting("OPENAI_BASE_URL", provider.and_then(|p| p.base_url.as_ref()));
+        let path = setting("OPENAI_EMBEDDINGS_PATH", provider.and_then(|p| p.embeddings_path.as_ref()));
+        let auth = setting("OPENAI_AUTH_HEADER", provider.and_then(|p| p.auth_header.as_ref()));
+
+        let mut embedder = self;
+        if let Some(provider) = provider {
+            embedder = embedder.with_batch_limits(
+                provider.max_batch_items.unwrap_or(DEFAULT_MAX_BATCH_ITEMS),
+                provider.max_batch_tokens.unwrap_or(DEFAULT_MAX_BATCH_TOKENS),
+            );
+        }
+        if let Some(base_url) = base_url {
+            embedder = embedder.with_base_url(&base_url, path.as_deref());
+        } else if path.is_some() {
+            log::warn!("Ignoring OpenAI embeddings path {:?} without a base URL", path);
+        }
+        if let Some(auth) = auth {
+            embedder = embedder.with_auth_header(auth.parse()?);
+        }
+        Ok(embedder)
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Cut requests and retries short at `deadline`, failing the embedding instead
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Cap each `embed_batch` request at `max_items` inputs and about `max_tokens`
+    /// estimated tokens
+    pub fn with_batch_limits(mut self, max_items: usize, max_tokens: usize) -> Self {
+        self.max_batch_items = max_items.max(1);
+        self.max_batch_tokens = max_tokens.max(1);
+        self
+    }
 }
 
 impl OpenAIEmbedder {
-    pub fn new_from_env() -> Result<Self, &'static str> {
+    /// Create an embedder for `model` authenticated with `api_key`
+    pub fn new(api_key: &str, model: &str) -> Result<Self, IndexerError> {
+        if api_key.trim().is_empty() {
+            return Err(IndexerError::Embed("OpenAI API key is empty".to_string()));
+        }
+        Ok(Self {
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            endpoint: OPENAI_EMBEDDINGS_URL.to_string(),
+            auth: AuthHeader::default(),
+            retry: RetryPolicy::default(),
+            max_batch_items: DEFAULT_MAX_BATCH_ITEMS,
+            max_batch_tokens: DEFAULT_MAX_BATCH_TOKENS,
+            deadline: None,
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            dimension: OnceLock::new(),
+        })
+    }
+
+    /// Create an embedder from `OPENAI_API_KEY`, with the endpoint settings of
+    /// `with_endpoint_config`
+    pub fn new_from_env() -> Result<Self, IndexerError> {
         match std::env::var("OPENAI_API_KEY") {
-            Ok(key) => Ok(Self { api_key: key }),
-            Err(_) => Err("OPENAI_API_KEY not set"),
+            Ok(key) => Self::new(&key, DEFAULT_OPENAI_MODEL)?.with_endpoint_config(None),
+            Err(_) => Err(IndexerError::Embed("OPENAI_API_KEY not set".to_string())),
+        }
+    }
+
+    /// POST `input` (a string or an array of strings) to the embeddings endpoint,
+    /// retrying rate-limited and transient failures per the retry policy
+    ///
+    /// # Returns
+    /// * `Result<Vec<Vec<f32>>, IndexerError>` - The embeddings ordered by input index
+    fn request_embeddings(&self, input: serde_json::Value) -> Result<Vec<Vec<f32>>, IndexerError> {
+        let body = serde_json::json!({ "model": self.model, "input": input });
+        let mut retries = 0;
+        loop {
+            let request = self.agent.post(&self.endpoint).timeout(request_timeout(self.deadline)?);
+            let request = match self.auth {
+                AuthHeader::Bearer => request.set("Authorization", &format!("Bearer {}", self.api_key)),
+                AuthHeader::ApiKey => request.set("api-key", &self.api_key),
+            };
+            let response = request.send_json(&body);
+            match response {
+                Ok(response) => {
+                    let parsed: OpenAIEmbeddingResponse = response.into_json()
+                        .map_err(|e| IndexerError::Embed(format!("Invalid OpenAI response: {}", e)))?;
+                    if retries > 0 {
+                        log::info!("OpenAI embedding succeeded after {} retries", retries);
+                    }
+                    let mut data = parsed.data;
+                    data.sort_by_key(|d| d.index);
+                    return Ok(data.into_iter().map(|d| d.embedding).collect());
+                }
+                Err(ureq::Error::Status(status, response)) if is_retryable_status(status) => {
+                    if retries >= self.retry.max_retries {
+                        log::error!("OpenAI embedding failed with HTTP {} after {} retries", status, retries);
+                        return Err(IndexerError::RetriesExhausted {
+                            attempts: retries + 1,
+                            last_error: format!("HTTP {}", status),
+                        });
+                    }
+                    // Honour Retry-After when the server sends one
+                    let delay = response.header("retry-after")
+                        .and_then(|secs| secs.trim().parse::<u64>().ok())
+                        .map(|secs| Duration::from_secs(secs).min(self.retry.max_delay))
+                        .unwrap_or_else(|| self.retry.delay_for(retries));
+                    retries += 1;
+                    log::warn!("OpenAI returned HTTP {}, retry {}/{} in {:?}", status, retries, self.retry.max_retries, delay);
+                    wait_to_retry(delay, self.deadline)?;
+                }
+                Err(ureq::Error::Status(status, response)) => {
+                    let message = response.into_string().unwrap_or_default();
+                    return Err(IndexerError::Embed(format!("OpenAI returned HTTP {}: {}", status, message)));
+                }
+                Err(ureq::Error::Transport(e)) => {
+                    return Err(IndexerError::Embed(format!("OpenAI request failed: {}", e)));
+                }
+            }
         }
     }
 }
 
 impl Embedder for OpenAIEmbedder {
     fn embed(&self, input: &str) -> Vec<f32> {
+        // An empty vector is rejected by the vectorize safe-mode guard
+        self.try_embed(input).unwrap_or_else(|e| {
+            log::error!("OpenAI embedding failed: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Embed `input`, retrying rate-limited and transient failures per the retry policy
+    ///
+    /// # Returns
+    /// * `Result<Vec<f32>, IndexerError>` - The embedding, `RetriesExhausted` once every
+    ///   retry failed, or `Embed` for a non-retryable failure
+    fn try_embed(&self, input: &str) -> Result<Vec<f32>, IndexerError> {
         log::info!("embedding input with OpenAI: {}", input);
-        vec![1.0, 2.0, 3.0] // dummy
+        self.request_embeddings(serde_json::json!(input))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| IndexerError::Embed("OpenAI response contained no embedding".to_string()))
+    }
+
+    /// Embed `inputs` in sub-batches within the configured item and token limits,
+    /// one request per sub-batch, concatenating the results in input order
+    fn embed_batch(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, IndexerError> {
+        let mut embeddings = Vec::with_capacity(inputs.len());
+        for range in split_batches(inputs, self.max_batch_items, self.max_batch_tokens) {
+            let batch = &inputs[range];
+            log::info!("embedding batch of {} inputs with OpenAI", batch.len());
+            let batch_embeddings = self.request_embeddings(serde_json::json!(batch))?;
+            if batch_embeddings.len() != batch.len() {
+                return Err(IndexerError::Embed(format!(
+                    "OpenAI returned {} embeddings for {} inputs",
+                    batch_embeddings.len(),
+                    batch.len()
+                )));
+            }
+            embeddings.extend(batch_embeddings);
+        }
+        Ok(embeddings)
+    }
+
+    fn dimension(&self) -> usize {
+        self.try_dimension().unwrap_or_else(|e| {
+            log::error!("Could not determine the dimension of {}: {}", self.model, e);
+            0
+        })
     }
+
+    /// The known dimension of OpenAI's models, else one probe embedding, made once
+    fn try_dimension(&self) -> Result<usize, IndexerError> {
+        match self.model.as_str() {
+            "text-embedding-ada-002" | "text-embedding-3-small" => return Ok(1536),
+            "text-embedding-3-large" => return Ok(3072),
+            _ => {}
+        }
+        if let Some(&dimension) = self.dimension.get() {
+            return Ok(dimension);
+        }
+        let probed = self.try_embed("dimension probe")?.len();
+        if probed == 0 {
+            return Err(IndexerError::Embed(format!("{} returned an empty embedding for the dimension probe", self.model)));
+        }
+        Ok(*self.dimension.get_or_init(|| probed))
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+}
+
+/// Model to embed with: the `--model` flag, else the provider's configured model,
+/// else `None` for the embedder's default
+pub fn select_model<'a>(flag: Option<&'a str>, provider: Option<&'a ProviderConfig>) -> Option<&'a str> {
+    flag.or_else(|| provider.map(|p| p.model()).filter(|model| !model.trim().is_empty()))
+}
+
+/// HuggingFace model used when none is configured
+pub const DEFAULT_HF_MODEL: &str = "sentence-transformers/all-MiniLM-L6-v2";
+
+/// HuggingFace feature-extraction endpoint; the model name is appended
+pub const HF_INFERENCE_URL: &str = "https://api-inference.huggingface.co/pipeline/feature-extraction";
+
+/// Longest single wait for a HuggingFace model to finish loading
+pub const DEFAULT_HF_MAX_LOAD_WAIT: Duration = Duration::from_secs(60);
+
+/// Body HuggingFace sends with a 503 while a model is cold-starting
+#[derive(serde::Deserialize)]
+struct HFLoadingResponse {
+    #[serde(default)]
+    error: String,
+    /// Seconds until the model is expected to be ready
+    estimated_time: Option<f64>,
+}
+
+/// A feature-extraction embedding: one pooled vector, or a single row of them
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum HFEmbeddingResponse {
+    Pooled(Vec<f32>),
+    Rows(Vec<Vec<f32>>),
 }
 
 pub struct HFEmbedder {
     api_key: String,
+    model: String,
+    endpoint: String,
+    retry: RetryPolicy,
+    /// Cap on the wait a 503 `estimated_time` asks for
+    max_load_wait: Duration,
+    /// No request is sent, or waited on, past this
+    deadline: Option<Instant>,
+    agent: ureq::Agent,
+    /// Configured dimension, or the length of the first non-empty embedding
+    dimension: OnceLock<usize>,
 }
 
 impl HFEmbedder {
@@ -40,20 +632,191 @@ impl HFEmbedder {
         &self.api_key
     }
 
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Use a known model dimension instead of learning it from the first embedding
+    pub fn with_dimension(self, dimension: usize) -> Self {
+        Self { dimension: OnceLock::from(dimension), ..self }
+    }
+
+    /// Embed with `model` instead of the one given at construction; a dimension
+    /// learned or configured for the old model is dropped
+    pub fn with_model(self, model: &str) -> Self {
+        Self { model: model.to_string(), dimension: OnceLock::new(), ..self }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Send requests to `endpoint` (e.g. a proxy or a test server); the model name
+    /// is appended as the last path segment
+    pub fn with_endpoint(self, endpoint: &str) -> Self {
+        Self { endpoint: endpoint.trim_end_matches('/').to_string(), ..self }
+    }
+
+    pub fn with_retry_policy(self, retry: RetryPolicy) -> Self {
+        Self { retry, ..self }
+    }
+
+    /// Wait at most `max_load_wait` each time the model reports it is still loading
+    pub fn with_max_load_wait(self, max_load_wait: Duration) -> Self {
+        Self { max_load_wait, ..self }
+    }
+
+    /// Cut requests, retries, and model-load waits short at `deadline`, failing the
+    /// embedding instead
+    pub fn with_deadline(self, deadline: Option<Instant>) -> Self {
+        Self { deadline, ..self }
+    }
+
+    /// POST `input` to the model's feature-extraction endpoint
+    ///
+    /// A 503 with an `estimated_time` means the model is cold-starting: the embedder
+    /// waits that long, capped at `max_load_wait`, and tries again. Rate limits and
+    /// other transient failures back off per the retry policy. Both count against
+    /// `max_retries`.
+    fn request_embedding(&self, input: &str) -> Result<Vec<f32>, IndexerError> {
+        let url = format!("{}/{}", self.endpoint, self.model);
+        let body = serde_json::json!({ "inputs": input });
+        let mut retries = 0;
+        loop {
+            let response = self.agent.post(&url)
+                .timeout(request_timeout(self.deadline)?)
+                .set("Authorization", &format!("Bearer {}", self.api_key))
+                .send_json(&body);
+            match response {
+                Ok(response) => {
+                    let parsed: HFEmbeddingResponse = response.into_json()
+                        .map_err(|e| IndexerError::Embed(format!("Invalid HuggingFace response: {}", e)))?;
+                    if retries > 0 {
+                        log::info!("HuggingFace embedding succeeded after {} retries", retries);
+                    }
+                    return match parsed {
+                        HFEmbeddingResponse::Pooled(embedding) => Ok(embedding),
+                        HFEmbeddingResponse::Rows(mut rows) if rows.len() == 1 => Ok(rows.remove(0)),
+                        HFEmbeddingResponse::Rows(rows) => Err(IndexerError::Embed(format!(
+                            "HuggingFace returned {} vectors for one input; use a sentence-embedding model",
+                            rows.len()
+                        ))),
+                    };
+                }
+                Err(ureq::Error::Status(status, response)) if is_retryable_status(status) => {
+                    let loading = (status == 503)
+                        .then(|| response.into_json::<HFLoadingResponse>().ok())
+                        .flatten();
+                    if retries >= self.retry.max_retries {
+                        log::error!("HuggingFace embedding failed with HTTP {} after {} retries", status, retries);
+                        let last_error = match loading {
+                            Some(loading) if !loading.error.is_empty() => format!("HTTP {}: {}", status, loading.error),
+                            _ => format!("HTTP {}", status),
+                        };
+                        return Err(IndexerError::RetriesExhausted { attempts: retries + 1, last_error });
+                    }
+                    retries += 1;
+                    match loading.and_then(|loading| loading.estimated_time) {
+                        Some(estimated) => {
+                            let wait = Duration::try_from_secs_f64(estimated).unwrap_or_default().min(self.max_load_wait);
+                            log::info!("HuggingFace model {} is loading (estimated {:.1}s), retry {}/{} in {:?}", self.model, estimated, retries, self.retry.max_retries, wait);
+                            wait_to_retry(wait, self.deadline)?;
+                        }
+                        None => {
+                            let delay = self.retry.delay_for(retries - 1);
+                            log::warn!("HuggingFace returned HTTP {}, retry {}/{} in {:?}", status, retries, self.retry.max_retries, delay);
+                            wait_to_retry(delay, self.deadline)?;
+                        }
+                    }
+                }
+                Err(ureq::Error::Status(status, response)) => {
+                    let message = response.into_string().unwrap_or_default();
+                    return Err(IndexerError::Embed(format!("HuggingFace returned HTTP {}: {}", status, message)));
+                }
+                Err(ureq::Error::Transport(e)) => {
+                    return Err(IndexerError::Embed(format!("HuggingFace request failed: {}", e)));
+                }
+            }
+        }
+    }
 }
 
 impl HFEmbedder {
-    pub fn new_from_env() -> Result<Self, &'static str> {
+    /// Create an embedder for `model` authenticated with `api_key`
+    pub fn new(api_key: &str, model: &str) -> Result<Self, IndexerError> {
+        if api_key.trim().is_empty() {
+            return Err(IndexerError::Embed("HuggingFace API key is empty".to_string()));
+        }
+        Ok(Self {
+            api_key: api_key.to_string(),
+            model: model.to_string(),
+            endpoint: HF_INFERENCE_URL.to_string(),
+            retry: RetryPolicy::default(),
+            max_load_wait: DEFAULT_HF_MAX_LOAD_WAIT,
+            deadline: None,
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+            dimension: OnceLock::new(),
+        })
+    }
+
+    pub fn new_from_env() -> Result<Self, IndexerError> {
         match std::env::var("HF_API_KEY") {
-            Ok(key) => Ok(Self { api_key: key }),
-            Err(_) => Err("HF_API_KEY not set"),
+            Ok(key) => Self::new(&key, DEFAULT_HF_MODEL),
+            Err(_) => Err(IndexerError::Embed("HF_API_KEY not set".to_string())),
         }
     }
 }
 
 impl Embedder for HFEmbedder {
-    fn embed(&self, _input: &str) -> Vec<f32> {
-        vec![1.0, 2.0, 3.0] // dummy
+    fn embed(&self, input: &str) -> Vec<f32> {
+        // An empty vector is rejected by the vectorize safe-mode guard
+        self.try_embed(input).unwrap_or_else(|e| {
+            log::error!("HuggingFace embedding failed: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// Embed `input`, waiting out model cold starts and retrying transient failures
+    ///
+    /// # Returns
+    /// * `Result<Vec<f32>, IndexerError>` - The embedding, `RetriesExhausted` once every
+    ///   retry failed, or `Embed` for a non-retryable failure
+    fn try_embed(&self, input: &str) -> Result<Vec<f32>, IndexerError> {
+        log::info!("embedding input with HuggingFace: {}", input);
+        let embedding = self.request_embedding(input)?;
+        if !embedding.is_empty() {
+            let expected = *self.dimension.get_or_init(|| embedding.len());
+            if expected != embedding.len() {
+                log::warn!("HuggingFace returned a {}-dimensional embedding, expected {}", embedding.len(), expected);
+            }
+        }
+        Ok(embedding)
+    }
+
+    /// The configured dimension, else the one learned from the first embedding,
+    /// probing the model if nothing has been embedded yet
+    fn dimension(&self) -> usize {
+        if let Some(&dimension) = self.dimension.get() {
+            return dimension;
+        }
+        let probed = self.embed("dimension probe").len();
+        self.dimension.get().copied().unwrap_or(probed)
+    }
+
+    /// Like `dimension`, with a failed or empty probe as an error
+    fn try_dimension(&self) -> Result<usize, IndexerError> {
+        if let Some(&dimension) = self.dimension.get() {
+            return Ok(dimension);
+        }
+        let probed = self.try_embed("dimension probe")?.len();
+        if probed == 0 {
+            return Err(IndexerError::Embed(format!("{} returned an empty embedding for the dimension probe", self.model)));
+        }
+        Ok(self.dimension.get().copied().unwrap_or(probed))
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
     }
 }
 
@@ -61,13 +824,66 @@ impl Embedder for HFEmbedder {
 mod tests {
     #[test]
     fn test_openai_api_key_getter() {
-        let embedder = OpenAIEmbedder { api_key: "testkey".to_string() };
+        let embedder = OpenAIEmbedder::new("testkey", DEFAULT_OPENAI_MODEL).unwrap();
         assert_eq!(embedder.api_key(), "testkey");
+        assert_eq!(embedder.model(), DEFAULT_OPENAI_MODEL);
+        assert_eq!(embedder.dimension(), 1536);
+        assert!(OpenAIEmbedder::new("  ", DEFAULT_OPENAI_MODEL).is_err());
+    }
+
+    #[test]
+    fn test_retry_delay_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        };
+        for _ in 0..20 {
+            let first = policy.delay_for(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100), "{:?}", first);
+            let third = policy.delay_for(2);
+            assert!(third >= Duration::from_millis(200) && third <= Duration::from_millis(400), "{:?}", third);
+            let capped = policy.delay_for(10);
+            assert!(capped >= Duration::from_millis(500) && capped <= Duration::from_millis(1000), "{:?}", capped);
+        }
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(500));
+        assert!(is_retryable_status(503));
+        assert!(!is_retryable_status(400));
+        assert!(!is_retryable_status(401));
     }
+    #[test]
+    fn test_normalizing_embedder_returns_unit_vectors() {
+        assert_eq!(l2_normalize(&[3.0, 4.0]), vec![0.6, 0.8]);
+        assert_eq!(l2_normalize(&[0.0, 0.0]), vec![0.0, 0.0]);
+
+        let embedder = NormalizingEmbedder::new(MockEmbedder);
+        let vector = embedder.embed("doc");
+        let magnitude = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((magnitude - 1.0).abs() < 1e-6, "{}", magnitude);
+        assert_eq!(embedder.dimension(), 3);
+    }
+
     #[test]
     fn test_hf_api_key_getter() {
-        let embedder = HFEmbedder { api_key: "testkey2".to_string() };
+        let embedder = HFEmbedder::new("testkey2", DEFAULT_HF_MODEL).unwrap();
         assert_eq!(embedder.api_key(), "testkey2");
+        assert_eq!(embedder.model(), DEFAULT_HF_MODEL);
+        assert!(HFEmbedder::new("  ", DEFAULT_HF_MODEL).is_err());
+    }
+
+    #[test]
+    fn test_hf_configured_dimension_skips_probe() {
+        // A configured dimension wins without embedding anything
+        let embedder = HFEmbedder::new("testkey2", DEFAULT_HF_MODEL).unwrap()
+            .with_endpoint("http://127.0.0.1:9/unreachable")
+            .with_dimension(384);
+        assert_eq!(embedder.dimension(), 384);
+        assert_eq!(embedder.endpoint(), "http://127.0.0.1:9/unreachable");
     }
 
     use super::*;
@@ -76,6 +892,7 @@ mod tests {
         let embedder = MockEmbedder;
         let vec = embedder.embed("foo");
         assert_eq!(vec, vec![0.0, 1.0, 2.0]);
+        assert_eq!(embedder.dimension(), 3);
     }
 }
 
@@ -105,4 +922,8 @@ impl Embedder for MockEmbedder {
             vec![0.0, 1.0, 2.0]
         }
     }
+
+    fn model_name(&self) -> &str {
+        "mock"
+    }
 }
@@ -0,0 +1,162 @@
+//! JSONL export of embeddings for `vectorize --emit`
+//!
+//! `EmbeddingEmitter` is a `VectorStore` that writes every embedding it is given as
+//! one JSON object per line, then passes it on to an inner store. Without an inner
+//! store nothing is stored, which turns vectorize into an embedding exporter.
+
+use crate::error::IndexerError;
+use crate::vector_store::{EntityDetails, UpsertItem, VectorStore};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Vector store that writes embeddings as JSONL, optionally storing them in `inner` too
+///
+/// Each line is `{"id": ..., "type": ..., "file": ..., "embedding": [...]}`. An
+/// embedding is written only once the inner store has accepted it, so a batch that
+/// fails and is retried one entity at a time emits each entity once.
+pub struct EmbeddingEmitter<'a, V: VectorStore + ?Sized> {
+    inner: Option<&'a V>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    emitted: Mutex<usize>,
+}
+
+impl<'a, V: VectorStore + ?Sized> EmbeddingEmitter<'a, V> {
+    /// Emit to `writer`, storing in `inner` as well when given
+    pub fn new(writer: Box<dyn Write + Send>, inner: Option<&'a V>) -> Self {
+        Self { inner, writer: Mutex::new(writer), emitted: Mutex::new(0) }
+    }
+
+    /// Emit to a new file at `path`, replacing any existing one
+    pub fn create(path: &Path, inner: Option<&'a V>) -> Result<Self, IndexerError> {
+        let file = File::create(path)
+            .map_err(|e| IndexerError::Io(std::io::Error::new(e.kind(), format!("Failed to create {}: {}", path.display(), e))))?;
+        Ok(Self::new(Box::new(BufWriter::new(file)), inner))
+    }
+
+    /// Flush the output, returning the number of embeddings emitted
+    pub fn finish(self) -> Result<usize, IndexerError> {
+        let mut writer = self.writer.into_inner()
+            .map_err(|e| IndexerError::Store(format!("Emitter lock poisoned: {}", e)))?;
+        writer.flush()?;
+        self.emitted.into_inner()
+            .map_err(|e| IndexerError::Store(format!("Emitter lock poisoned: {}", e)))
+    }
+
+    /// Write one embedding as a JSON line
+    fn emit(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+        let line = serde_json::json!({
+            "id": entity_id,
+            "type": entity_type.unwrap_or("unknown"),
+            "file": file.unwrap_or("unknown"),
+            "embedding": embedding,
+        });
+        let mut writer = self.writer.lock()
+            .map_err(|e| IndexerError::Store(format!("Emitter lock poisoned: {}", e)))?;
+        serde_json::to_writer(&mut *writer, &line)?;
+        writer.write_all(b"\n")?;
+        *self.emitted.lock()
+            .map_err(|e| IndexerError::Store(format!("Emitter lock poisoned: {}", e)))? += 1;
+        Ok(())
+    }
+
+    /// The inner store, or a `Store` error naming `what` when emitting only
+    fn inner(&self, what: &str) -> Result<&'a V, IndexerError> {
+        self.inner.ok_or_else(|| IndexerError::Store(format!("{} is not available when only emitting embeddings", what)))
+    }
+}
+
+impl<V: VectorStore + ?Sized> VectorStore for EmbeddingEmitter<'_, V> {
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+        self.upsert_embedding_with_details(entity_id, embedding, file, entity_type, &EntityDetails::default())
+    }
+
+    fn upsert_embedding_with_details(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>, details: &EntityDetails) -> Result<(), IndexerError> {
+        if let Some(inner) = self.inner {
+            inner.upsert_embedding_with_details(entity_id, embedding, file, entity_type, details)?;
+        }
+        self.emit(entity_id, embedding, file, entity_type)
+    }
+
+    fn upsert_batch(&self, items: &[UpsertItem]) -> Result<(), IndexerError> {
+        if let Some(inner) = self.inner {
+            inner.upsert_batch(items)?;
+        }
+        for (entity_id, embedding, file, entity_type, _) in items {
+            self.emit(entity_id, embedding, file.as_deref(), entity_type.as_deref())?;
+        }
+        Ok(())
+    }
+
+    fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+        self.inner.map(|inner| inner.similarity_search(query, top_k)).unwrap_or_default()
+    }
+
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+        self.inner.map_or(Ok(Vec::new()), |inner| inner.get_all_entity_ids())
+    }
+
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+        self.inner("get_entity_vector")?.get_entity_vector(entity_id)
+    }
+
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
+        self.inner("get_entity_metadata")?.get_entity_metadata(entity_id)
+    }
+
+    fn count(&self) -> Result<usize, IndexerError> {
+        self.inner.map_or(Ok(0), |inner| inner.count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedder::MockEmbedder;
+    use crate::vector_store::InMemoryVectorStore;
+    use crate::vectorize::{process_directory_with_diagnostics, VectorizeOptions};
+    use std::io::BufRead;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_emit_writes_one_line_per_entity() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("app.py"), "def load():\n    pass\n\nclass Job:\n    def run(self):\n        pass\n").unwrap();
+        let out = dir.path().join("embeddings.jsonl");
+
+        let emitter = EmbeddingEmitter::<InMemoryVectorStore>::create(&out, None).unwrap();
+        let report = process_directory_with_diagnostics(dir.path(), &MockEmbedder, &emitter, &VectorizeOptions { concurrency: 1, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        assert!(report.processed >= 3, "load, Job, and Job.run should be embedded");
+        assert_eq!(emitter.finish().unwrap(), report.processed);
+
+        let lines: Vec<serde_json::Value> = std::io::BufReader::new(File::open(&out).unwrap())
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).unwrap())
+            .collect();
+        assert_eq!(lines.len(), report.processed);
+        for line in &lines {
+            let embedding = line["embedding"].as_array().expect("embedding should be an array");
+            assert_eq!(embedding.len(), 3, "MockEmbedder vectors have 3 dimensions");
+            assert!(embedding.iter().all(|v| v.is_f64()));
+            assert!(line["file"].as_str().is_some_and(|f| f.ends_with("app.py")));
+        }
+        let ids: Vec<&str> = lines.iter().filter_map(|line| line["id"].as_str()).collect();
+        assert!(ids.contains(&"fn:app.py:load") && ids.contains(&"class:app.py:Job"), "{:?}", ids);
+    }
+
+    #[test]
+    fn test_emit_also_stores_in_inner_store() {
+        let store = InMemoryVectorStore::new();
+        let emitter = EmbeddingEmitter::new(Box::new(Vec::new()), Some(&store));
+        emitter.upsert_embedding("fn:a.py:f", &[1.0, 0.0, 0.0], Some("a.py"), Some("function")).unwrap();
+        assert_eq!(emitter.finish().unwrap(), 1);
+        assert_eq!(store.count().unwrap(), 1);
+
+        let emit_only = EmbeddingEmitter::<InMemoryVectorStore>::new(Box::new(Vec::new()), None);
+        emit_only.upsert_embedding("fn:a.py:f", &[1.0, 0.0, 0.0], Some("a.py"), Some("function")).unwrap();
+        assert!(emit_only.get_all_entity_ids().unwrap().is_empty());
+        assert!(emit_only.get_entity_vector("fn:a.py:f").is_err());
+    }
+}
@@ -1,14 +1,85 @@
 //! error module for indexer
 
+use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::config::ConfigError;
+
 #[derive(Debug, Error)]
-pub enum AppError {
+pub enum IndexerError {
+    #[error("redis error: {0}")]
+    Redis(String),
+    #[error("embedding error: {0}")]
+    Embed(String),
+    #[error("configuration error: {0}")]
+    Config(String),
+    #[error("parse error: {0}")]
+    Parse(String),
+    #[error("vector store error: {0}")]
+    Store(String),
+    #[error("watch error: {0}")]
+    Watch(String),
+    #[error("gave up after {attempts} attempts: {last_error}")]
+    RetriesExhausted { attempts: u32, last_error: String },
+    #[error("timed out after {secs}s with {stored} entities stored")]
+    TimedOut { secs: u64, stored: usize },
+    #[error("path does not exist: {}", .0.display())]
+    PathNotFound(PathBuf),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
-    #[error("yaml configuration error: {0}")]
-    YamlConfig(#[from] serde_yaml::Error),
-    #[error("redis error: {0}")]
-    Redis(#[from] fred::error::Error),
+}
+
+impl From<fred::error::Error> for IndexerError {
+    fn from(e: fred::error::Error) -> Self {
+        IndexerError::Redis(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for IndexerError {
+    fn from(e: serde_json::Error) -> Self {
+        IndexerError::Parse(e.to_string())
+    }
+}
+
+impl From<serde_yaml::Error> for IndexerError {
+    fn from(e: serde_yaml::Error) -> Self {
+        IndexerError::Config(e.to_string())
+    }
+}
+
+impl From<ConfigError> for IndexerError {
+    fn from(e: ConfigError) -> Self {
+        match e {
+            ConfigError::Io(e) => IndexerError::Io(e),
+            other => IndexerError::Config(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_not_found_message() {
+        let err = IndexerError::PathNotFound(PathBuf::from("/no/such/project"));
+        assert_eq!(err.to_string(), "path does not exist: /no/such/project");
+    }
+
+    #[test]
+    fn test_config_error_conversion() {
+        let err: IndexerError = ConfigError::HomeDirNotFound.into();
+        assert!(matches!(err, IndexerError::Config(_)));
+
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        let err: IndexerError = ConfigError::Io(io).into();
+        assert!(matches!(err, IndexerError::Io(_)));
+    }
 
+    #[test]
+    fn test_json_error_conversion() {
+        let parse_err = serde_json::from_str::<Vec<f32>>("not json").unwrap_err();
+        let err: IndexerError = parse_err.into();
+        assert!(matches!(err, IndexerError::Parse(_)));
+    }
 }
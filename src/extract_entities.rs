@@ -56,23 +56,27 @@ fn extract_entities_from_rust(text: &str) -> Vec<String> {
     entities
 }
 
+/// Extract a sync or async function's name and docstring, then its body
+fn extract_function(name: &str, body: &[Stmt], entities: &mut Vec<String>) {
+    // Extract function name
+    entities.push(format!("fn {}", name));
+    
+    // Extract docstring if present
+    if let Some(docstring) = get_docstring(body) {
+        entities.push(format!("doc {}: {}", name, docstring));
+    }
+    
+    // Recursively extract from function body
+    for stmt in body {
+        extract_entities_from_stmt(stmt, entities);
+    }
+}
+
 /// Extract entities from a statement recursively
 fn extract_entities_from_stmt(stmt: &Stmt, entities: &mut Vec<String>) {
     match stmt {
-        Stmt::FunctionDef(def) => {
-            // Extract function name
-            entities.push(format!("fn {}", def.name));
-            
-            // Extract docstring if present
-            if let Some(docstring) = get_docstring(&def.body) {
-                entities.push(format!("doc {}: {}", def.name, docstring));
-            }
-            
-            // Recursively extract from function body
-            for stmt in &def.body {
-                extract_entities_from_stmt(stmt, entities);
-            }
-        },
+        Stmt::FunctionDef(def) => extract_function(&def.name, &def.body, entities),
+        Stmt::AsyncFunctionDef(def) => extract_function(&def.name, &def.body, entities),
         Stmt::ClassDef(def) => {
             // Extract class name
             entities.push(format!("class {}", def.name));
@@ -88,10 +92,12 @@ fn extract_entities_from_stmt(stmt: &Stmt, entities: &mut Vec<String>) {
             }
         },
         Stmt::Assign(assign) => {
-            // Extract variable assignments at module level
+            // Extract variable assignments at module level; a name bound to a
+            // lambda is a function
+            let kind = if matches!(*assign.value, Expr::Lambda(_)) { "fn" } else { "var" };
             for target in &assign.targets {
                 if let Expr::Name(boxed_id) = target {
-                    entities.push(format!("var {}", boxed_id.id));
+                    entities.push(format!("{} {}", kind, boxed_id.id));
                 }
             }
         },
@@ -137,6 +143,22 @@ mod tests {
         assert!(entities.contains(&"doc foo: This is a docstring".to_string()));
     }
     
+    #[test]
+    fn test_extract_entities_async_function() {
+        let text = "async def fetch():\n    \"\"\"Fetch a page\"\"\"\n    async def parse():\n        pass\n\nclass Client:\n    async def close(self):\n        pass\n";
+        let entities = extract_entities(text);
+        assert!(entities.contains(&"fn fetch".to_string()));
+        assert!(entities.contains(&"doc fetch: Fetch a page".to_string()));
+        assert!(entities.contains(&"fn parse".to_string()));
+        assert!(entities.contains(&"fn close".to_string()));
+    }
+    
+    #[test]
+    fn test_extract_entities_lambda_assignment() {
+        let entities = extract_entities("square = lambda x: x * x\nlimit = 10\n");
+        assert_eq!(entities, vec!["fn square".to_string(), "var limit".to_string()]);
+    }
+    
     #[test]
     fn test_extract_entities_empty() {
         let text = "# just a comment";
@@ -1,18 +1,170 @@
 //! file collection and filtering logic for indexer
 //! - traverses directories
 //! - respects ignore patterns
+//! - streams python sources out of .tar, .tar.gz/.tgz and .zip archives
+//! - skips empty, oversized, and binary files before they are read
+//! - optionally skips test files by naming pattern
 
+use crate::error::IndexerError;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use ignore::{WalkBuilder, DirEntry};
-use std::path::{Path, PathBuf};
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
 
 const SKIP_DIRS: &[&str] = &[".logs", ".venv", ".git", "__pycache__", "node_modules", "build", "dist"];
 
-pub fn collect_python_files(app_dir: &Path, specific_files: Option<&[String]>) -> Vec<PathBuf> {
+/// Default size limit for source files read into memory (1 MiB)
+pub const DEFAULT_MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Source file extensions indexed when the config does not list any
+pub const DEFAULT_SOURCE_EXTENSIONS: &[&str] = &["py"];
+
+/// `DEFAULT_SOURCE_EXTENSIONS` as the owned list the walkers take
+pub fn default_source_extensions() -> Vec<String> {
+    DEFAULT_SOURCE_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+/// Paths, relative to the project root, that `--exclude-tests` treats as test files
+pub const DEFAULT_TEST_FILE_PATTERNS: &[&str] = &["**/test_*.py", "**/*_test.py", "**/tests/**"];
+
+/// Leading bytes searched for a NUL when sniffing for binary content
+const BINARY_SNIFF_BYTES: u64 = 8192;
+
+/// Why a source file was left out of indexing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Empty,
+    TooLarge { size: u64, limit: u64 },
+    Binary,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::Empty => write!(f, "file is empty"),
+            SkipReason::TooLarge { size, limit } => write!(f, "file is {} bytes, over the {} byte limit", size, limit),
+            SkipReason::Binary => write!(f, "file looks binary"),
+        }
+    }
+}
+
+/// Check a file before reading it: it must be non-empty, at most `max_bytes`
+/// long, and free of NUL bytes in its first 8 KiB
+///
+/// # Returns
+/// * `io::Result<Option<SkipReason>>` - `None` if the file should be indexed
+pub fn check_source_file(path: &Path, max_bytes: u64) -> io::Result<Option<SkipReason>> {
+    let size = path.metadata()?.len();
+    if size == 0 {
+        return Ok(Some(SkipReason::Empty));
+    }
+    if size > max_bytes {
+        return Ok(Some(SkipReason::TooLarge { size, limit: max_bytes }));
+    }
+    let mut head = Vec::new();
+    File::open(path)?.take(BINARY_SNIFF_BYTES).read_to_end(&mut head)?;
+    if head.contains(&0) {
+        return Ok(Some(SkipReason::Binary));
+    }
+    Ok(None)
+}
+
+/// A file's modification time as seconds since the unix epoch
+///
+/// Times before the epoch come out negative.
+pub fn modified_unix_secs(meta: &Metadata) -> io::Result<i64> {
+    let modified = meta.modified()?;
+    Ok(match modified.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    })
+}
+
+/// Keep the files that pass `check_source_file`, logging each one left out
+pub fn filter_source_files(files: Vec<PathBuf>, max_bytes: u64) -> Vec<PathBuf> {
+    files.into_iter()
+        .filter(|path| match check_source_file(path, max_bytes) {
+            Ok(None) => true,
+            Ok(Some(reason)) => {
+                log::info!("Skipping {}: {}", path.display(), reason);
+                false
+            }
+            Err(e) => {
+                log::warn!("Skipping unreadable file {}: {}", path.display(), e);
+                false
+            }
+        })
+        .collect()
+}
+
+/// Matches test files by globs over their path relative to the project root
+#[derive(Debug, Clone)]
+pub struct TestFileFilter {
+    patterns: GlobSet,
+}
+
+impl TestFileFilter {
+    /// Compile `patterns`, e.g. `**/test_*.py`; `*` does not cross a `/`
+    pub fn new(patterns: &[String]) -> Result<Self, IndexerError> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| IndexerError::Parse(format!("Invalid test file pattern {}: {}", pattern, e)))?;
+            builder.add(glob);
+        }
+        let patterns = builder.build()
+            .map_err(|e| IndexerError::Parse(format!("Invalid test file patterns: {}", e)))?;
+        Ok(Self { patterns })
+    }
+
+    /// Whether `rel_path` (relative to the project root) names a test file
+    pub fn is_test_file(&self, rel_path: &Path) -> bool {
+        self.patterns.is_match(rel_path)
+    }
+}
+
+/// Leave out the files under `root` that `filter` takes for test files, logging each one
+pub fn exclude_test_files(files: Vec<PathBuf>, root: &Path, filter: &TestFileFilter) -> Vec<PathBuf> {
+    files.into_iter()
+        .filter(|path| {
+            let is_test = filter.is_test_file(path.strip_prefix(root).unwrap_or(path));
+            if is_test {
+                log::info!("Skipping test file {}", path.display());
+            }
+            !is_test
+        })
+        .collect()
+}
+
+/// Whether `path` has one of `extensions` (given without the leading dot)
+pub fn has_source_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| e == ext))
+}
+
+/// Collect the python files under `app_dir` (or the given `specific_files`), leaving
+/// out empty, binary, and larger-than-`max_file_bytes` files
+pub fn collect_python_files(app_dir: &Path, specific_files: Option<&[String]>, max_file_bytes: u64) -> Vec<PathBuf> {
+    collect_source_files(app_dir, specific_files, max_file_bytes, &default_source_extensions())
+}
+
+/// Collect the files with one of `extensions` under `app_dir` (or the given
+/// `specific_files`), leaving out empty, binary, and larger-than-`max_file_bytes` files
+///
+/// `specific_files` are paths relative to `app_dir`, such as `pkg/mod.py`; a bare file
+/// name only matches a file directly in `app_dir`. Missing files are left out.
+pub fn collect_source_files(app_dir: &Path, specific_files: Option<&[String]>, max_file_bytes: u64, extensions: &[String]) -> Vec<PathBuf> {
     if let Some(files) = specific_files {
-        return files.iter()
+        let files = files.iter()
             .map(|f| app_dir.join(f))
-            .filter(|p| p.exists() && p.is_file() && p.extension().map(|e| e == "py").unwrap_or(false))
+            .filter(|p| p.exists() && p.is_file() && has_source_extension(p, extensions))
             .collect();
+        return filter_source_files(files, max_file_bytes);
     }
     let mut files = Vec::new();
     let walker = WalkBuilder::new(app_dir)
@@ -23,12 +175,29 @@ pub fn collect_python_files(app_dir: &Path, specific_files: Option<&[String]>) -
         .build();
     for entry in walker {
         if let Ok(entry) = entry {
-            if entry.path().extension().map(|e| e == "py").unwrap_or(false) {
+            if has_source_extension(entry.path(), extensions) {
                 files.push(entry.into_path());
             }
         }
     }
-    files
+    filter_source_files(files, max_file_bytes)
+}
+
+/// `collect_source_files` for async callers, walking the tree on tokio's blocking pool
+pub async fn collect_source_files_async(
+    app_dir: &Path,
+    specific_files: Option<&[String]>,
+    max_file_bytes: u64,
+    extensions: &[String],
+) -> io::Result<Vec<PathBuf>> {
+    let app_dir = app_dir.to_path_buf();
+    let specific_files = specific_files.map(<[String]>::to_vec);
+    let extensions = extensions.to_vec();
+    tokio::task::spawn_blocking(move || {
+        collect_source_files(&app_dir, specific_files.as_deref(), max_file_bytes, &extensions)
+    })
+    .await
+    .map_err(io::Error::other)
 }
 
 fn should_skip(entry: &DirEntry) -> bool {
@@ -39,3 +208,100 @@ fn should_skip(entry: &DirEntry) -> bool {
         false
     }
 }
+
+/// Whether any directory component of `path` is one the indexer skips
+pub fn is_skipped_path(path: &Path) -> bool {
+    path.components().any(|c| match c {
+        Component::Normal(name) => name.to_str().map(|n| SKIP_DIRS.contains(&n)).unwrap_or(false),
+        _ => false,
+    })
+}
+
+/// Supported source archive formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+/// Detect an archive from its file name, or `None` for directories and plain files
+pub fn archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(ArchiveKind::TarGz)
+    } else if name.ends_with(".tar") {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else {
+        None
+    }
+}
+
+/// Stream the sources with one of `extensions` out of an archive without extracting it to disk
+///
+/// Returns `(rel_path, content)` pairs where `rel_path` is the in-archive path.
+pub fn collect_archive_source_files(archive_path: &Path, extensions: &[String]) -> io::Result<Vec<(String, String)>> {
+    let kind = archive_kind(archive_path).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("not a supported archive: {}", archive_path.display()))
+    })?;
+    let file = File::open(archive_path)?;
+    match kind {
+        ArchiveKind::Tar => read_tar_source_files(file, extensions),
+        ArchiveKind::TarGz => read_tar_source_files(flate2::read::GzDecoder::new(file), extensions),
+        ArchiveKind::Zip => read_zip_source_files(file, extensions),
+    }
+}
+
+/// Read the sources with one of `extensions` from an uncompressed tar stream
+pub fn read_tar_source_files<R: Read>(reader: R, extensions: &[String]) -> io::Result<Vec<(String, String)>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut sources = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let rel_path = entry.path()?.to_string_lossy().to_string();
+        if !is_archived_source_file(&rel_path, extensions) {
+            continue;
+        }
+        let mut content = String::new();
+        match entry.read_to_string(&mut content) {
+            Ok(_) => sources.push((rel_path, content)),
+            Err(e) => log::warn!("Skipping unreadable archive entry {}: {}", rel_path, e),
+        }
+    }
+    Ok(sources)
+}
+
+/// Read the sources with one of `extensions` from a zip archive
+pub fn read_zip_source_files<R: Read + Seek>(reader: R, extensions: &[String]) -> io::Result<Vec<(String, String)>> {
+    let mut archive = zip::ZipArchive::new(reader)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut sources = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if !entry.is_file() {
+            continue;
+        }
+        let rel_path = entry.name().to_string();
+        if !is_archived_source_file(&rel_path, extensions) {
+            continue;
+        }
+        let mut content = String::new();
+        match entry.read_to_string(&mut content) {
+            Ok(_) => sources.push((rel_path, content)),
+            Err(e) => log::warn!("Skipping unreadable archive entry {}: {}", rel_path, e),
+        }
+    }
+    Ok(sources)
+}
+
+/// Apply the same extension and skip-dir rules used for directory walks to an in-archive path
+fn is_archived_source_file(rel_path: &str, extensions: &[String]) -> bool {
+    let path = Path::new(rel_path);
+    has_source_extension(path, extensions) && !is_skipped_path(path)
+}
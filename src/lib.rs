@@ -1,6 +1,7 @@
 pub mod config;
 pub mod embedder;
 pub mod vector_store;
+pub mod qdrant_store;
 pub mod output_format;
 pub mod extract_entities;
 pub use extract_entities::extract_entities;
@@ -11,8 +12,15 @@ pub mod redis_ops;
 pub mod file_processing;
 pub mod ast_parser;
 pub mod batch_processing;
-pub use batch_processing::batch_process_entities;
+pub use batch_processing::{batch_process_entities, batch_process_entities_async};
 pub mod vector_search;
-pub use vector_search::{search_vectors, SearchResult, SearchOptions};
+pub use vector_search::{hybrid_search, search_vectors, SearchResult, SearchOptions};
 pub mod vectorize;
+pub mod emit;
 pub use vectorize::vectorize_command;
+pub mod diff;
+pub mod remember;
+pub mod prune;
+pub mod remodel;
+pub use remodel::remodel_project;
+pub mod watch;
@@ -5,27 +5,98 @@ use fern::Dispatch;
 use log::LevelFilter;
 use std::io;
 
-pub fn setup_logging(config: &AppConfig) -> Result<(), io::Error> {
-    let log_level = config.log_level.as_deref().unwrap_or("info");
-    let level_filter = match log_level {
+/// Render a log record as a single-line JSON object for log aggregators
+fn json_log_line(timestamp: &str, level: log::Level, target: &str, message: &str) -> String {
+    serde_json::json!({
+        "timestamp": timestamp,
+        "level": level.to_string(),
+        "target": target,
+        "message": message,
+    })
+    .to_string()
+}
+
+/// Level to log at: `level_override` (from `--quiet`/`--verbose`) if given, else the
+/// config's `log_level`
+pub fn level_filter(config: &AppConfig, level_override: Option<LevelFilter>) -> LevelFilter {
+    if let Some(level) = level_override {
+        return level;
+    }
+    match config.log_level.as_deref().unwrap_or("info") {
         "debug" => LevelFilter::Debug,
         "warn" => LevelFilter::Warn,
         "error" => LevelFilter::Error,
         "trace" => LevelFilter::Trace,
         _ => LevelFilter::Info,
-    };
+    }
+}
+
+/// Logger formatting and filtering records per the config, not yet attached to an output
+fn dispatch(config: &AppConfig, level_override: Option<LevelFilter>) -> Dispatch {
+    let level_filter = level_filter(config, level_override);
+    let json = config.log_format.as_deref().is_some_and(|f| f.eq_ignore_ascii_case("json"));
 
     Dispatch::new()
-        .format(|out, message, record| {
-            out.finish(format_args!(
-                "{} [{}] {}",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                record.level(),
-                message
-            ))
+        .format(move |out, message, record| {
+            if json {
+                let timestamp = chrono::Local::now().to_rfc3339();
+                let line = json_log_line(&timestamp, record.level(), record.target(), &message.to_string());
+                out.finish(format_args!("{}", line))
+            } else {
+                out.finish(format_args!(
+                    "{} [{}] {}",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    record.level(),
+                    message
+                ))
+            }
         })
         .level(level_filter)
+}
+
+pub fn setup_logging(config: &AppConfig, level_override: Option<LevelFilter>) -> Result<(), io::Error> {
+    dispatch(config, level_override)
         .chain(std::io::stderr())
         .apply()
         .map_err(|_| io::Error::new(io::ErrorKind::Other, "failed to initialize logger"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_log_line_has_expected_keys() {
+        let line = json_log_line("2024-01-01T00:00:00+00:00", log::Level::Warn, "indexer::watch", "Indexed 3 files\nand more");
+
+        assert!(!line.contains('\n'));
+        let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["timestamp"], "2024-01-01T00:00:00+00:00");
+        assert_eq!(parsed["level"], "WARN");
+        assert_eq!(parsed["target"], "indexer::watch");
+        assert_eq!(parsed["message"], "Indexed 3 files\nand more");
+    }
+
+    #[test]
+    fn test_quiet_flag_drops_info_records() {
+        use crate::cli::CliArgs;
+        use clap::Parser;
+
+        let config = AppConfig { log_level: Some("debug".to_string()), ..AppConfig::default() };
+        let args = CliArgs::parse_from(["indexer", "--quiet", "status", "--name", "demo"]);
+        assert_eq!(level_filter(&config, args.log_level_override()), LevelFilter::Warn);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (_, logger) = dispatch(&config, args.log_level_override()).chain(tx).into_log();
+        logger.log(&log::Record::builder().level(log::Level::Info).args(format_args!("Indexed 3 files")).build());
+        logger.log(&log::Record::builder().level(log::Level::Warn).args(format_args!("Skipping broken.py")).build());
+
+        let lines: Vec<String> = rx.try_iter().collect();
+        assert_eq!(lines.len(), 1, "unexpected records: {:?}", lines);
+        assert!(lines[0].contains("Skipping broken.py"));
+
+        let args = CliArgs::parse_from(["indexer", "-v", "status", "--name", "demo"]);
+        assert_eq!(level_filter(&AppConfig::default(), args.log_level_override()), LevelFilter::Debug);
+        assert!(CliArgs::try_parse_from(["indexer", "-q", "-v", "status", "--name", "demo"]).is_err());
+    }
+}
@@ -4,150 +4,416 @@
 //! - parses cli
 //! - dispatches to async runtime
 
-mod config;
-mod error;
-mod cli;
-mod logging;
-
-use crate::config::AppConfig;
-use crate::cli::{CliArgs, Commands};
-use crate::logging::setup_logging;
-use indexer::redis_ops::{create_redis_client, store_file_content, store_code_entities, clear_file_data, query_code_entity};
-use fred::interfaces::SetsInterface;
-use indexer::file_processing::collect_python_files;
-use indexer::ast_parser::extract_code_info;
-use indexer::embedder::{Embedder, OpenAIEmbedder, HFEmbedder, MockEmbedder};
-use indexer::vector_store::{VectorStore, RedisVectorStore};
+use indexer::config::AppConfig;
+use indexer::cli::{write_completions, CliArgs, Commands};
+use indexer::error::IndexerError;
+use indexer::logging::setup_logging;
+use indexer::redis_ops::{build_redis_client, store_file_content, store_code_entities_with_limit, clear_file_data, query_code_entity_limited, query_code_entity_since, search_code_entities, search_docstrings, count_entities_by_type, dump_project, project_status, ForgetReport, load_project, write_dump, read_dump, store_project_meta, get_project_meta, list_projects};
+use fred::interfaces::{ClientLike, SetsInterface};
+use indexer::file_processing::{collect_source_files, collect_source_files_async, exclude_test_files, TestFileFilter, archive_kind, collect_archive_source_files, modified_unix_secs};
+use indexer::ast_parser::parse_code_info;
+use indexer::vectorize::{progress_logger, report_parse_diagnostics, report_vectorize_errors};
+use indexer::embedder::{Embedder, OpenAIEmbedder, HFEmbedder, MockEmbedder, NormalizingEmbedder, select_model};
+use indexer::vector_store::{VectorStore, RedisVectorStore, InMemoryVectorStore, EmbeddingMeta, EntityDetails, DEFAULT_DIMENSION};
+use indexer::qdrant_store::{QdrantVectorStore, DEFAULT_QDRANT_URL};
+use indexer::remember::{remember_files, report_remember_errors};
+use indexer::prune::prune_project;
+use indexer::remodel::remodel_project;
+use indexer::output_format::{color_enabled, format_code_entities, format_grouped_search_results, format_json_lines, format_line_ranges, format_forget_report, format_project_status, format_score_histogram, format_search_results, write_output, OutputFormat};
+use indexer::diff::{diff_entities, entities_from_dump, format_diff, load_entities};
 // Import but don't use directly to avoid namespace conflicts
 use indexer::vector_search;
 use clap::Parser;
-use log::info;
-use std::path::PathBuf;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Load config
-    let config = AppConfig::load()?;
+async fn main() -> Result<(), IndexerError> {
     // Parse CLI
     let args = CliArgs::parse();
+    // Completions need neither config nor Redis
+    if let Commands::Completions { shell } = args.command {
+        write_completions(shell, &mut std::io::stdout());
+        return Ok(());
+    }
+    // Load config, layering the project's .indexer.yaml over the global file
+    let config = match args.command.project_path() {
+        Some(path) => AppConfig::load_merged(std::path::Path::new(path))?,
+        None => AppConfig::load()?,
+    };
+
+    // --quiet/--verbose win over the config's log_level
+    let level_override = args.log_level_override();
 
     // Determine project name for Redis key prefix (from each command)
     let (key_prefix, cmd) = match args.command {
+        // Handled above, before config and Redis
+        Commands::Completions { .. } => (String::new(), args.command),
         Commands::Remember { ref name, .. } => (format!("code_index:{}", name), args.command),
         Commands::Refresh { ref name, .. } => (format!("code_index:{}", name), args.command),
+        Commands::Watch { ref name, .. } => (format!("code_index:{}", name), args.command),
         Commands::Recall { ref project_name, .. } => (format!("code_index:{}", project_name), args.command),
-        Commands::Status { ref name } => (format!("code_index:{}", name), args.command),
-        Commands::Forget { ref name } => (format!("code_index:{}", name), args.command),
+        Commands::Search { ref name, .. } => (format!("code_index:{}", name), args.command),
+        Commands::Status { ref name, .. } => (format!("code_index:{}", name), args.command),
+        Commands::Stats { ref name } => (format!("code_index:{}", name), args.command),
+        // Not tied to one project
+        Commands::Projects => (String::new(), args.command),
+        Commands::Forget { ref name, .. } => (format!("code_index:{}", name), args.command),
+        Commands::Prune { ref name, .. } => (format!("code_index:{}", name), args.command),
+        Commands::Export { ref name, .. } => (format!("code_index:{}", name), args.command),
+        Commands::Import { ref name, .. } => (format!("code_index:{}", name), args.command),
+        Commands::Diff { ref name, .. } => (format!("code_index:{}", name), args.command),
         Commands::Vectorize { ref name, .. } => (format!("code_index:{}", name), args.command),
+        Commands::Remodel { ref name, .. } => (format!("code_index:{}", name), args.command),
         Commands::VectorRecall { ref name, .. } => (format!("code_index:{}", name), args.command),
     };
     // Setup logging
-    setup_logging(&config)?;
+    setup_logging(&config, level_override)?;
 
-    // Connect to Redis
-    let redis = create_redis_client(config.redis_url.as_ref().unwrap()).await?;
+    // Connect to Redis only for commands and backends that use it
+    let redis = build_redis_client(config.redis_url.as_ref().unwrap(), &config.redis_timeouts())?;
+    if cmd.uses_redis() {
+        redis.init().await?;
+    }
 
     match cmd {
-        Commands::Remember { name: _, path } => {
+        Commands::Completions { .. } => {}
+        Commands::Remember { name: _, path, show_parse_errors, max_file_bytes, exclude_tests, include_tests: _ } => {
             let app_dir = PathBuf::from(path);
-            let files = collect_python_files(&app_dir, None);
-            for file in &files {
-                let rel_path = file.strip_prefix(&app_dir).unwrap_or(file).to_string_lossy().to_string();
-                let content = tokio::fs::read_to_string(file).await?;
-                let meta = tokio::fs::metadata(file).await?;
-                let size = meta.len() as usize;
-                let mtime = meta.modified()?.elapsed().unwrap_or_default().as_secs() as i64;
-                store_file_content(&redis, &key_prefix, &rel_path, &content, size, mtime).await?;
-                let entities = extract_code_info(file, &app_dir);
-                store_code_entities(&redis, &key_prefix, &entities).await?;
+            let test_filter = if exclude_tests { Some(TestFileFilter::new(&config.test_file_patterns())?) } else { None };
+            if archive_kind(&app_dir).is_some() {
+                let mut diagnostics = Vec::new();
+                let mut sources = collect_archive_source_files(&app_dir, &config.source_extensions())?;
+                if let Some(filter) = &test_filter {
+                    sources.retain(|(rel_path, _)| !filter.is_test_file(Path::new(rel_path)));
+                }
+                let meta = tokio::fs::metadata(&app_dir).await?;
+                let mtime = modified_unix_secs(&meta)?;
+                for (rel_path, content) in &sources {
+                    store_file_content(&redis, &key_prefix, rel_path, content, content.len(), mtime).await?;
+                    match parse_code_info(content, rel_path) {
+                        Ok(entities) => store_code_entities_with_limit(&redis, &key_prefix, &entities, config.source_byte_limit()).await?,
+                        Err(diagnostic) => diagnostics.push(diagnostic),
+                    }
+                }
+                info!("Indexed {} files from archive {}", sources.len(), app_dir.display());
+                report_parse_diagnostics(&diagnostics, show_parse_errors);
+                return Ok(());
             }
-            info!("Indexed {} files", files.len());
+            let mut files = collect_source_files_async(&app_dir, None, max_file_bytes.unwrap_or(config.file_byte_limit()), &config.source_extensions()).await?;
+            if let Some(filter) = &test_filter {
+                files = exclude_test_files(files, &app_dir, filter);
+            }
+            // Keep going past files that fail; they are listed at the end
+            let report = remember_files(&redis, &key_prefix, &app_dir, &files, config.source_byte_limit()).await;
+            report_parse_diagnostics(&report.diagnostics, show_parse_errors);
+            report_remember_errors(&report);
         }
         Commands::Refresh { name: _, files } => {
             let app_dir = std::env::current_dir()?;
             let files: Vec<String> = files.split(',').map(|s| s.trim().to_string()).collect();
-            let files = collect_python_files(&app_dir, Some(&files));
+            let files = collect_source_files(&app_dir, Some(&files), config.file_byte_limit(), &config.source_extensions());
+            let mut diagnostics = Vec::new();
             for file in &files {
                 let rel_path = file.strip_prefix(&app_dir).unwrap_or(file).to_string_lossy().to_string();
                 let content = tokio::fs::read_to_string(file).await?;
                 let meta = tokio::fs::metadata(file).await?;
                 let size = meta.len() as usize;
-                let mtime = meta.modified()?.elapsed().unwrap_or_default().as_secs() as i64;
+                let mtime = modified_unix_secs(&meta)?;
                 store_file_content(&redis, &key_prefix, &rel_path, &content, size, mtime).await?;
-                let entities = extract_code_info(file, &app_dir);
-                store_code_entities(&redis, &key_prefix, &entities).await?;
+                match parse_code_info(&content, &rel_path) {
+                    Ok(entities) => store_code_entities_with_limit(&redis, &key_prefix, &entities, config.source_byte_limit()).await?,
+                    Err(diagnostic) => diagnostics.push(diagnostic),
+                }
             }
             info!("Refreshed {} files", files.len());
+            // Only a few files are refreshed at a time, so list every failure
+            report_parse_diagnostics(&diagnostics, true);
         }
-        Commands::Recall { entity, show_lines, max: _max, project_name: _ } => {
+        Commands::Watch { name: _, path } => {
+            indexer::watch::watch_project(&redis, &key_prefix, &PathBuf::from(path), &config.source_extensions()).await?;
+        }
+        Commands::Recall { entity, show_lines, show_source, format, max, since, project_name: _, output } => {
             let entity_type = entity.as_deref().unwrap_or("");
-            let results = query_code_entity(&redis, &key_prefix, entity_type, None).await?;
-            if show_lines {
+            let results = match since {
+                Some(since) => query_code_entity_since(&redis, &key_prefix, entity_type, None, since, max).await?,
+                None => query_code_entity_limited(&redis, &key_prefix, entity_type, None, max).await?,
+            };
+            let text = if show_lines && !show_source {
+                format_line_ranges(&results, format.unwrap_or(OutputFormat::Human))
+            } else if let Some(format) = format {
+                format_code_entities(&results, format)
+            } else if show_source {
+                let mut blocks = Vec::new();
                 for r in &results {
-                    println!("{}: {}-{}", r.name, r.line_start, r.line_end);
+                    let source = r.source.as_deref().unwrap_or("(no source stored; re-run remember)");
+                    blocks.push(format!("{} ({}:{}-{})\n{}\n", r.name, r.file_path, r.line_start, r.line_end, source));
                 }
+                blocks.join("\n")
+            } else {
+                serde_json::to_string_pretty(&results)?
+            };
+            write_output(&text, output.as_deref(), &mut std::io::stdout())?;
+        }
+        Commands::Search { name: _, term, entity_type, docstrings, format } => {
+            let results = if docstrings {
+                search_docstrings(&redis, &key_prefix, &term, entity_type.as_deref()).await?
             } else {
-                println!("{}", serde_json::to_string_pretty(&results)?);
+                search_code_entities(&redis, &key_prefix, &term, entity_type.as_deref()).await?
+            };
+            info!("Search for {:?} matched {} entities", term, results.len());
+            println!("{}", format_code_entities(&results, format));
+        }
+        Commands::Status { name: _, format } => {
+            let status = project_status(&redis, &key_prefix).await?;
+            println!("{}", format_project_status(&status, format));
+        }
+        Commands::Stats { name } => {
+            let files: u64 = redis.scard(format!("{}:file_index", key_prefix)).await?;
+            let entity_counts = count_entities_by_type(&redis, &key_prefix).await?;
+            let redis_url = config.redis_url.as_deref().unwrap_or("redis://127.0.0.1/");
+            let store = RedisVectorStore::with_timeouts(redis_url, &key_prefix, DEFAULT_DIMENSION, config.redis_pool_size(), config.redis_timeouts())?;
+            let vectors = store.count_async().await?;
+            
+            println!("Project: {}", name);
+            println!("Files: {}", files);
+            println!("Entities: {}", entity_counts.iter().map(|(_, c)| c).sum::<u64>());
+            for (entity_type, count) in &entity_counts {
+                println!("  {}: {}", entity_type, count);
+            }
+            println!("Vectors: {}", vectors);
+        }
+        Commands::Projects => {
+            let projects = list_projects(&redis).await?;
+            if projects.is_empty() {
+                println!("No indexed projects.");
+            }
+            for project in &projects {
+                println!("{}", project);
             }
         }
-        Commands::Status { name: _ } => {
-            let key = format!("{}:file_index", key_prefix);
-            let files: Vec<String> = redis.smembers(&key).await.unwrap_or_default();
-            println!("Indexed files: {}", files.len());
-            for f in files {
-                println!("- {}", f);
+        Commands::Prune { name: _, path } => {
+            let redis_url = config.redis_url.as_deref().unwrap_or("redis://127.0.0.1/");
+            let store = RedisVectorStore::with_timeouts(redis_url, &key_prefix, DEFAULT_DIMENSION, config.redis_pool_size(), config.redis_timeouts())?;
+            let report = prune_project(&redis, &key_prefix, std::path::Path::new(&path), Some(&store)).await?;
+            println!("Pruned {} files ({} entities, {} vectors)", report.pruned_files.len(), report.cleared_entities, report.removed_vectors);
+            for file in &report.pruned_files {
+                println!("  {}", file);
             }
         }
-        Commands::Forget { name: _ } => {
+        Commands::Forget { name: _, keep_vectors, format } => {
             let files: Vec<String> = redis.smembers(format!("{}:file_index", key_prefix)).await.unwrap_or_default();
-            clear_file_data(&redis, &key_prefix, &files).await?;
+            let cleared_entities = clear_file_data(&redis, &key_prefix, &files).await?;
             info!("Cleared all indexed data");
+            
+            let mut report = ForgetReport { cleared_files: files.len(), cleared_entities, removed_vectors: None };
+            if !keep_vectors {
+                let redis_url = config.redis_url.as_deref().unwrap_or("redis://127.0.0.1/");
+                let store = RedisVectorStore::with_timeouts(redis_url, &key_prefix, DEFAULT_DIMENSION, config.redis_pool_size(), config.redis_timeouts())?;
+                let entity_ids = store.get_all_entity_ids_async().await?;
+                let mut removed = 0;
+                for entity_id in &entity_ids {
+                    if store.delete_embedding_async(entity_id).await? {
+                        removed += 1;
+                    }
+                }
+                store.sweep_shared_vectors_async().await?;
+                info!("Removed {} vector embeddings", removed);
+                report.removed_vectors = Some(removed);
+            }
+            println!("{}", format_forget_report(&report, format));
+        }
+        Commands::Export { name, out } => {
+            let records = dump_project(&redis, &key_prefix).await?;
+            let writer = std::io::BufWriter::new(std::fs::File::create(&out)?);
+            write_dump(&records, writer)?;
+            info!("Exported {} keys for {} to {}", records.len(), name, out.display());
+            println!("Exported {} keys to {}", records.len(), out.display());
         }
-        Commands::Vectorize { name, path, provider, db, batch_size, dry_run, verbose } => {
+        Commands::Import { name, file } => {
+            if !file.exists() {
+                return Err(IndexerError::PathNotFound(file));
+            }
+            let reader = std::io::BufReader::new(std::fs::File::open(&file)?);
+            let records = read_dump(reader)?;
+            let written = load_project(&redis, &key_prefix, &records).await?;
+            info!("Imported {} keys for {} from {}", written, name, file.display());
+            println!("Imported {} keys from {}", written, file.display());
+        }
+        Commands::Diff { name, against, format } => {
+            // An existing file is an export; anything else is another key prefix
+            let against_path = PathBuf::from(&against);
+            let baseline = if against_path.is_file() {
+                let reader = std::io::BufReader::new(std::fs::File::open(&against_path)?);
+                entities_from_dump(&read_dump(reader)?)?
+            } else {
+                load_entities(&redis, &against).await?
+            };
+            let current = load_entities(&redis, &key_prefix).await?;
+            let diffs = diff_entities(&baseline, &current);
+            info!("Diffed {} against {}: {} files changed", name, against, diffs.len());
+            println!("{}", format_diff(&diffs, format));
+        }
+        Commands::Vectorize { name, path, provider, db, batch_size, dry_run, verbose, index_todos, show_parse_errors, plan, concurrency, max_file_bytes, from_index, entity_types, exclude_entity_types, dimension, only_missing, timeout, model, limit, emit, granularity, force_reembed, normalize_embeddings } => {
+            let types = indexer::vectorize::EntityTypeFilter::new(entity_types, exclude_entity_types);
+            // Checked between files and bounds each embedding request; whatever was
+            // flushed before it stays stored
+            let deadline = timeout.map(|secs| std::time::Instant::now() + std::time::Duration::from_secs(secs));
             info!("Starting vectorize command for project: {}", name);
             
             // Create embedder based on provider or use MockEmbedder for testing
             let embedder = match provider.as_deref() {
                 Some("openai") => {
-                    match OpenAIEmbedder::new_from_env() {
-                        Ok(e) => Box::new(e) as Box<dyn Embedder>,
+                    match OpenAIEmbedder::new_from_env()
+                        .and_then(|e| e.with_endpoint_config(config.provider("openai")))
+                        .map(|e| e.with_retry_policy(config.embed_retry_policy()).with_deadline(deadline))
+                        .map(|e| match select_model(model.as_deref(), config.provider("openai")) {
+                            Some(model) => e.with_model(model),
+                            None => e,
+                        }) {
+                        Ok(e) => Box::new(e) as Box<dyn Embedder + Sync>,
                         Err(e) => {
                             eprintln!("Error creating OpenAI embedder: {}", e);
-                            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                            return Err(e);
                         }
                     }
                 },
                 Some("hf") => {
-                    match HFEmbedder::new_from_env() {
-                        Ok(e) => Box::new(e) as Box<dyn Embedder>,
+                    let hf = HFEmbedder::new_from_env()
+                        .map(|e| e.with_retry_policy(config.embed_retry_policy()).with_max_load_wait(config.embed_model_load_max_wait()).with_deadline(deadline))
+                        .map(|e| match select_model(model.as_deref(), config.provider("hf")) {
+                            Some(model) => e.with_model(model),
+                            None => e,
+                        })
+                        .map(|e| match config.provider_dimension("hf") {
+                            // The configured dimension belongs to the configured model
+                            Some(dimension) if model.is_none() => e.with_dimension(dimension),
+                            _ => e,
+                        });
+                    match hf {
+                        Ok(e) => Box::new(e) as Box<dyn Embedder + Sync>,
                         Err(e) => {
                             eprintln!("Error creating HuggingFace embedder: {}", e);
-                            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                            return Err(e);
                         }
                     }
                 },
-                _ => Box::new(MockEmbedder::new()) as Box<dyn Embedder>
+                _ => Box::new(MockEmbedder::new()) as Box<dyn Embedder + Sync>
             };
+            // Normalize in the embedder so every backend stores unit-length vectors
+            let normalize = normalize_embeddings || config.normalize_embeddings();
+            let embedder = if normalize { Box::new(NormalizingEmbedder::new(embedder)) } else { embedder };
             
-            // Create vector store
+            // Create vector store, with --dimension overriding what the embedder reports
+            let dimension = indexer::vectorize::resolve_dimension(&*embedder, dimension)?;
+            let meta = EmbeddingMeta { dimension, normalized: normalize, ..EmbeddingMeta::for_embedder(provider.as_deref().unwrap_or("mock"), &*embedder)? };
             let redis_url = config.redis_url.as_deref().unwrap_or("redis://127.0.0.1/");
-            let store = RedisVectorStore::new(redis_url, &key_prefix);
+            // The memory store lives and dies with this process; nothing to record in Redis
+            let in_memory = db.as_deref() == Some("memory");
+            let store = match db.as_deref() {
+                Some("memory") => Box::new(InMemoryVectorStore::new()) as Box<dyn VectorStore>,
+                Some("qdrant") => {
+                    let qdrant_url = config.vector_db_url("qdrant").unwrap_or(DEFAULT_QDRANT_URL);
+                    Box::new(QdrantVectorStore::new(qdrant_url, &key_prefix, dimension)?) as Box<dyn VectorStore>
+                }
+                _ => Box::new(RedisVectorStore::with_timeouts(redis_url, &key_prefix, dimension, config.redis_pool_size(), config.redis_timeouts())?.with_vector_encoding(config.vector_encoding()?).with_dedup(config.dedup_vectors()).with_normalize(normalize)) as Box<dyn VectorStore>,
+            };
+            
+            // With --emit, embeddings go through the emitter; with --dry-run as well, only to it
+            let emitter = emit.as_deref()
+                .map(|emit| indexer::emit::EmbeddingEmitter::create(std::path::Path::new(emit), (!dry_run).then_some(&*store)))
+                .transpose()?;
+            let sink: &dyn VectorStore = match emitter {
+                Some(ref emitter) => emitter,
+                None => &*store,
+            };
+            let embed_dry_run = dry_run && emitter.is_none();
+            
+            // Vectors of two dimensions in one store would break every search
+            if !dry_run && !plan {
+                indexer::vectorize::check_stored_dimension(&*store, dimension, force_reembed)?;
+                // Nor would normalized vectors next to unnormalized ones
+                let stored_meta = if in_memory { None } else { get_project_meta(&redis, &key_prefix).await? };
+                indexer::vectorize::check_stored_normalization(&*store, stored_meta.as_ref(), normalize, force_reembed)?;
+            }
+            
+            // Embed what `remember` already stored instead of re-parsing the project
+            if from_index {
+                if granularity == indexer::vectorize::Granularity::File {
+                    return Err(IndexerError::Config("--granularity file embeds file contents and cannot be used with --from-index".to_string()));
+                }
+                let options = indexer::vectorize::VectorizeOptions {
+                    batch_size: batch_size.unwrap_or(10),
+                    concurrency,
+                    dry_run: embed_dry_run,
+                    verbose,
+                    types,
+                    only_missing,
+                    deadline,
+                    limit,
+                    ..Default::default()
+                };
+                let report = indexer::vectorize::vectorize_from_index(&redis, &key_prefix, &*embedder, sink, &options).await?;
+                indexer::vectorize::finish_emit(emitter, emit.as_deref())?;
+                report_vectorize_errors(&report.errors);
+                if !dry_run && !in_memory {
+                    store_project_meta(&redis, &key_prefix, &meta).await?;
+                }
+                if report.timed_out {
+                    let err = IndexerError::TimedOut { secs: timeout.unwrap_or(0), stored: report.processed };
+                    eprintln!("{}", err);
+                    return Err(err);
+                }
+                info!("Vectorized {} indexed entities", report.processed);
+                return Ok(());
+            }
+            
+            let max_file_bytes = max_file_bytes.unwrap_or(config.file_byte_limit());
             
             // Call vectorize command directly without recreating CLI args
             // This avoids the namespace conflict between binary and library CLI types
             let project_path = PathBuf::from(path);
             if !project_path.exists() {
-                let err_msg = format!("Project path does not exist: {}", path);
-                eprintln!("{}", err_msg);
-                return Err(Box::new(std::io::Error::new(std::io::ErrorKind::NotFound, err_msg)));
+                let err = IndexerError::PathNotFound(project_path);
+                eprintln!("{}", err);
+                return Err(err);
             }
             
-            // Process the directory using the library functions directly
-            let batch_size_val = batch_size.unwrap_or(10);
+            if plan {
+                let plan = indexer::vectorize::plan_directory(&project_path, &*store, max_file_bytes, &config.source_extensions(), index_todos, &types, indexer::vectorize::DEFAULT_PRICE_PER_1K_TOKENS)?;
+                println!("{}", plan);
+                return Ok(());
+            }
             
-            // Use the library's process_directory function directly
-            match indexer::vectorize::process_directory(&project_path, &*embedder, &store, batch_size_val, dry_run, verbose) {
-                Ok(_) => {
+            // Embed files in parallel; store writes stay on this thread
+            let options = indexer::vectorize::VectorizeOptions {
+                batch_size: batch_size.unwrap_or(10),
+                concurrency,
+                max_file_bytes,
+                extensions: config.source_extensions(),
+                dry_run: embed_dry_run,
+                verbose,
+                index_todos,
+                types,
+                only_missing,
+                deadline,
+                limit,
+                granularity,
+            };
+            let result = indexer::vectorize::process_directory_concurrent(&project_path, &*embedder, sink, &options, &mut progress_logger(verbose));
+            indexer::vectorize::finish_emit(emitter, emit.as_deref())?;
+            match result {
+                Ok(report) => {
+                    report_parse_diagnostics(&report.diagnostics, show_parse_errors);
+                    report_vectorize_errors(&report.errors);
+                    if !dry_run && !in_memory {
+                        store_project_meta(&redis, &key_prefix, &meta).await?;
+                    }
+                    if report.timed_out {
+                        let err = IndexerError::TimedOut { secs: timeout.unwrap_or(0), stored: report.processed };
+                        eprintln!("{}", err);
+                        return Err(err);
+                    }
                     if dry_run {
                         info!("Dry run completed successfully");
                     } else {
@@ -156,80 +422,219 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 },
                 Err(e) => {
                     eprintln!("Error during vectorization: {}", e);
-                    return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                    return Err(e);
                 }
             }
         }
-        Commands::VectorRecall { name, query, provider, db, top_k, json } => {
-            info!("Starting vector recall for project: {}", name);
+        Commands::Remodel { name, path, provider, model, normalize_embeddings } => {
+            info!("Starting remodel for project {} with model {}", name, model);
+            
+            let embedder = match provider.as_deref() {
+                Some("openai") => {
+                    match OpenAIEmbedder::new_from_env().and_then(|e| e.with_endpoint_config(config.provider("openai"))).map(|e| e.with_retry_policy(config.embed_retry_policy()).with_model(&model)) {
+                        Ok(e) => Box::new(e) as Box<dyn Embedder>,
+                        Err(e) => {
+                            eprintln!("Error creating OpenAI embedder: {}", e);
+                            return Err(e);
+                        }
+                    }
+                },
+                Some("hf") => {
+                    // The configured dimension describes the configured model only
+                    let configured_model = config.provider("hf").map(|p| p.model());
+                    let hf = HFEmbedder::new_from_env()
+                        .map(|e| e.with_retry_policy(config.embed_retry_policy()).with_max_load_wait(config.embed_model_load_max_wait()).with_model(&model))
+                        .map(|e| match config.provider_dimension("hf") {
+                        Some(dimension) if configured_model == Some(model.as_str()) => e.with_dimension(dimension),
+                        _ => e,
+                    });
+                    match hf {
+                        Ok(e) => Box::new(e) as Box<dyn Embedder>,
+                        Err(e) => {
+                            eprintln!("Error creating HuggingFace embedder: {}", e);
+                            return Err(e);
+                        }
+                    }
+                },
+                _ => Box::new(MockEmbedder::new()) as Box<dyn Embedder>
+            };
+            let normalize = normalize_embeddings || config.normalize_embeddings();
+            let embedder = if normalize { Box::new(NormalizingEmbedder::new(embedder)) } else { embedder };
+            
+            // Re-embed into a staging prefix, then swap into the live prefix
+            let redis_url = config.redis_url.as_deref().unwrap_or("redis://127.0.0.1/");
+            let dimension = embedder.try_dimension()?;
+            let live = RedisVectorStore::with_timeouts(redis_url, &key_prefix, dimension, config.redis_pool_size(), config.redis_timeouts())?
+                .with_vector_encoding(config.vector_encoding()?)
+                .with_normalize(normalize);
+            let staging = RedisVectorStore::with_timeouts(redis_url, &format!("{}:staging", key_prefix), dimension, config.redis_pool_size(), config.redis_timeouts())?
+                .with_vector_encoding(config.vector_encoding()?)
+                .with_normalize(normalize);
+            let provider_name = provider.as_deref().unwrap_or("mock");
+            let meta = remodel_project(&PathBuf::from(&path), &*embedder, &live, &staging, provider_name, &model, normalize)?;
+            println!("Remodeled {} with {} {} (dimension {})", name, meta.provider, meta.model, meta.dimension);
+        }
+        Commands::VectorRecall { name, all, query, provider, db, top_k, json, format, entity_type, exclude_types, file, min_score, offset, fields, with_vectors, histogram, output, model } => {
+            let format = if json { Some(OutputFormat::Json) } else { format };
+            let machine_readable = matches!(format, Some(OutputFormat::Json | OutputFormat::JsonLines));
+            // Nothing outlives the process that filled a memory store, so there is nothing to search
+            if db.as_deref() == Some("memory") {
+                return Err(IndexerError::Config("--db memory keeps vectors only for the vectorize run that made them; search with --db redis or qdrant".to_string()));
+            }
+            
+            // --all and a comma-separated --name search several projects together
+            let projects: Vec<String> = if all {
+                list_projects(&redis).await?
+            } else {
+                name.split(',').map(str::trim).filter(|n| !n.is_empty()).map(str::to_string).collect()
+            };
+            if projects.is_empty() {
+                return Err(IndexerError::Config("No projects to search; index one with remember first".to_string()));
+            }
+            info!("Starting vector recall for projects: {}", projects.join(", "));
+            
+            // Keep a mistyped --top-k from scanning the whole store
+            let top_k_limit = config.top_k_limit();
+            let top_k = match top_k.unwrap_or(vector_search::DEFAULT_TOP_K) {
+                top_k if top_k > top_k_limit => {
+                    warn!("--top-k {} is over the limit of {}; returning at most {} results", top_k, top_k_limit, top_k_limit);
+                    top_k_limit
+                }
+                top_k => top_k,
+            };
             
             // Create embedder based on provider or use MockEmbedder for testing
             let embedder = match provider.as_deref() {
                 Some("openai") => {
-                    match OpenAIEmbedder::new_from_env() {
+                    match OpenAIEmbedder::new_from_env()
+                        .and_then(|e| e.with_endpoint_config(config.provider("openai")))
+                        .map(|e| e.with_retry_policy(config.embed_retry_policy()))
+                        .map(|e| match select_model(model.as_deref(), config.provider("openai")) {
+                            Some(model) => e.with_model(model),
+                            None => e,
+                        }) {
                         Ok(e) => Box::new(e) as Box<dyn Embedder>,
                         Err(e) => {
                             eprintln!("Error creating OpenAI embedder: {}", e);
-                            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                            return Err(e);
                         }
                     }
                 },
                 Some("hf") => {
-                    match HFEmbedder::new_from_env() {
+                    let hf = HFEmbedder::new_from_env()
+                        .map(|e| e.with_retry_policy(config.embed_retry_policy()).with_max_load_wait(config.embed_model_load_max_wait()))
+                        .map(|e| match select_model(model.as_deref(), config.provider("hf")) {
+                            Some(model) => e.with_model(model),
+                            None => e,
+                        })
+                        .map(|e| match config.provider_dimension("hf") {
+                            // The configured dimension belongs to the configured model
+                            Some(dimension) if model.is_none() => e.with_dimension(dimension),
+                            _ => e,
+                        });
+                    match hf {
                         Ok(e) => Box::new(e) as Box<dyn Embedder>,
                         Err(e) => {
                             eprintln!("Error creating HuggingFace embedder: {}", e);
-                            return Err(Box::new(std::io::Error::new(std::io::ErrorKind::Other, e)));
+                            return Err(e);
                         }
                     }
                 },
                 _ => Box::new(MockEmbedder::new()) as Box<dyn Embedder>
             };
             
-            // Create vector store
+            // Create a vector store per project
             let redis_url = config.redis_url.as_deref().unwrap_or("redis://127.0.0.1/");
-            let store = RedisVectorStore::new(redis_url, &key_prefix);
+            let dimension = embedder.try_dimension()?;
+            let mut stores = Vec::new();
+            for project in &projects {
+                let key_prefix = format!("code_index:{}", project);
+                let store = match db.as_deref() {
+                    Some("qdrant") => {
+                        let qdrant_url = config.vector_db_url("qdrant").unwrap_or(DEFAULT_QDRANT_URL);
+                        Box::new(QdrantVectorStore::new(qdrant_url, &key_prefix, dimension)?) as Box<dyn VectorStore>
+                    }
+                    _ => Box::new(RedisVectorStore::with_timeouts(redis_url, &key_prefix, dimension, config.redis_pool_size(), config.redis_timeouts())?) as Box<dyn VectorStore>,
+                };
+                
+                // Vectors from a model of another dimension would all score 0
+                if let Some(meta) = get_project_meta(&redis, &key_prefix).await? {
+                    let current = EmbeddingMeta::for_embedder(provider.as_deref().unwrap_or("mock"), &*embedder)?;
+                    meta.check_query_embedder(&current.provider, &current.model, current.dimension)?;
+                }
+                stores.push((project.as_str(), store));
+            }
             
             // Generate embedding for query
             let query_embedding = embedder.embed(&query);
             
+            // A histogram scores every entity, with no cutoff to tune against
+            let (top_k, min_score, offset) = if histogram {
+                let mut entities = 0;
+                for (_, store) in &stores {
+                    entities += store.get_all_entity_ids()?.len();
+                }
+                (entities.max(1), None, 0)
+            } else {
+                (top_k, Some(min_score.unwrap_or(0.0)), offset.unwrap_or(0))
+            };
+            
             // Set up search options
             let search_options = vector_search::SearchOptions {
-                top_k: top_k.unwrap_or(5),
-                entity_types: None,
-                file_filter: None,
-                min_score: Some(0.0),
-            };
-            
-            // Perform search
-            let results = vector_search::search_vectors(&store, &query_embedding, &search_options)
-                .map_err(|e| Box::new(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
-            
-            // Output results
-            if json {
-                let json_str = match serde_json::to_string_pretty(&results) {
-                    Ok(s) => s,
-                    Err(e) => {
-                        eprintln!("Error serializing results to JSON: {}", e);
-                        return Err(Box::new(e));
+                top_k,
+                entity_types: entity_type.map(|t| vec![t]),
+                file_filter: file,
+                min_score,
+                offset,
+                fields,
+                search_threads: config.search_threads,
+                chunk_size: config.search_chunk_size,
+                include_vectors: with_vectors && machine_readable,
+                alpha: None,
+                mmr: None,
+                exclude_entity_types: exclude_types,
+            };
+            
+            // Perform search, merging by score across projects
+            let mut results = match stores.as_slice() {
+                [(_, store)] => vector_search::search_vectors(&**store, &query_embedding, &search_options)?,
+                stores => {
+                    let stores: Vec<(&str, &dyn VectorStore)> = stores.iter().map(|(project, store)| (*project, &**store)).collect();
+                    vector_search::search_projects(&stores, &query_embedding, &search_options)?
+                }
+            };
+            
+            // Show each hit's signature and line range, recorded with its vector
+            if !histogram {
+                for result in &mut results {
+                    let details = EntityDetails::from_metadata(&result.metadata);
+                    if let (Some(line_start), Some(line_end)) = (details.line_start, details.line_end) {
+                        result.metadata.insert("lines".to_string(), format!("{}-{}", line_start, line_end));
                     }
-                };
-                println!("{}", json_str);
+                }
+            }
+            
+            // Output results, without color codes when they go to a file
+            let text = if histogram {
+                let buckets = vector_search::score_histogram(&results, vector_search::DEFAULT_HISTOGRAM_BUCKETS);
+                match format {
+                    Some(OutputFormat::Json) => serde_json::to_string_pretty(&buckets)?,
+                    Some(OutputFormat::JsonLines) => format_json_lines(&buckets),
+                    _ => format!("Score distribution for query: {} ({} entities)\n{}", query, results.len(), format_score_histogram(&buckets)),
+                }
             } else {
-                println!("Search results for query: {}", query);
-                for (i, result) in results.iter().enumerate() {
-                    println!("{}: {} (score: {:.4})", i + 1, result.entity_id, result.score);
-                    if let Ok(metadata) = store.get_entity_metadata(&result.entity_id) {
-                        if let Some(file) = metadata.get("file") {
-                            println!("   File: {}", file);
-                        }
-                        if let Some(entity_type) = metadata.get("type") {
-                            println!("   Type: {}", entity_type);
-                        }
+                match format {
+                    Some(human @ (OutputFormat::Human | OutputFormat::HumanColored)) => {
+                        format!("Search results for query: {}\n{}", query, format_search_results(&results, human))
+                    }
+                    Some(format) => format_search_results(&results, format),
+                    None => {
+                        let colored = color_enabled() && output.is_none();
+                        format!("Search results for query: {}\n{}", query, format_grouped_search_results(&results, colored))
                     }
-                    println!();
                 }
-            }
+            };
+            write_output(&text, output.as_deref(), &mut std::io::stdout())?;
         }
     }
     Ok(())
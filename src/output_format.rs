@@ -1,17 +1,43 @@
 //! Output formatting utilities for search results
-//! Provides formatting options for vector search results
+//! Provides formatting options for vector search results and code entities
 
-use crate::vector_search::SearchResult;
+use crate::ast_parser::CodeEntity;
+use crate::redis_ops::{ForgetReport, ProjectStatus};
+use crate::vector_search::{ScoreBucket, SearchResult};
+use owo_colors::OwoColorize;
 use serde_json;
 use log;
+use std::io::IsTerminal;
+use std::path::Path;
 
 /// Output format options for search results
 #[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     /// Human-readable text format
     Human,
+    /// Human-readable text grouped by file, with colorized scores
+    HumanColored,
     /// JSON format
     Json,
+    /// One JSON object per line, for streaming consumers
+    JsonLines,
+    /// GitHub-flavored markdown table
+    Markdown,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "human" | "text" => Ok(OutputFormat::Human),
+            "color" | "human-color" => Ok(OutputFormat::HumanColored),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::JsonLines),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            other => Err(format!("unknown output format {:?} (expected human, color, json, jsonl, or markdown)", other)),
+        }
+    }
 }
 
 /// Format search results according to the specified output format
@@ -27,10 +53,233 @@ pub fn format_search_results(results: &[SearchResult], format: OutputFormat) ->
     
     match format {
         OutputFormat::Human => format_human_readable_search_results(results),
+        OutputFormat::HumanColored => format_grouped_search_results(results, true),
         OutputFormat::Json => format_json_search_results(results),
+        OutputFormat::JsonLines => format_json_lines(results),
+        OutputFormat::Markdown => format_markdown_search_results(results),
+    }
+}
+
+/// Serialize each item as compact JSON on its own line
+pub fn format_json_lines<T: serde::Serialize>(items: &[T]) -> String {
+    items.iter()
+        .filter_map(|item| match serde_json::to_string(item) {
+            Ok(json) => Some(json),
+            Err(e) => {
+                log::error!("Failed to serialize item to JSON: {}", e);
+                None
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format code entities as a table, a JSON array, or JSON lines
+///
+/// # Arguments
+/// * `entities` - Entities to format
+/// * `format` - Output format
+///
+/// # Returns
+/// * `String` - Formatted output
+pub fn format_code_entities(entities: &[CodeEntity], format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Human | OutputFormat::HumanColored => format_entity_table(entities),
+        OutputFormat::Json => serde_json::to_string_pretty(entities).unwrap_or_else(|e| {
+            log::error!("Failed to serialize entities to JSON: {}", e);
+            String::from("[]")
+        }),
+        OutputFormat::JsonLines => format_json_lines(entities),
+        OutputFormat::Markdown => markdown_table(
+            &["type", "name", "lines", "file"],
+            entities.iter().map(|e| vec![
+                e.entity_type.clone(),
+                e.name.clone(),
+                format!("{}-{}", e.line_start, e.line_end),
+                e.file_path.clone(),
+            ]),
+        ),
+    }
+}
+
+/// Line range of one entity, as printed by `recall --show-lines`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LineRange {
+    pub name: String,
+    pub line_start: usize,
+    pub line_end: usize,
+}
+
+/// Format each entity's name and line range, as `name: start-end` lines or as JSON
+///
+/// # Arguments
+/// * `entities` - Entities to format
+/// * `format` - Output format; JSON prints an array of `{name, line_start, line_end}`
+///
+/// # Returns
+/// * `String` - Formatted output
+pub fn format_line_ranges(entities: &[CodeEntity], format: OutputFormat) -> String {
+    let ranges: Vec<LineRange> = entities.iter()
+        .map(|e| LineRange { name: e.name.clone(), line_start: e.line_start, line_end: e.line_end })
+        .collect();
+    match format {
+        OutputFormat::Human | OutputFormat::HumanColored => ranges.iter()
+            .map(|r| format!("{}: {}-{}", r.name, r.line_start, r.line_end))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        OutputFormat::Json => serde_json::to_string_pretty(&ranges).unwrap_or_else(|e| {
+            log::error!("Failed to serialize line ranges to JSON: {}", e);
+            String::from("[]")
+        }),
+        OutputFormat::JsonLines => format_json_lines(&ranges),
+        OutputFormat::Markdown => markdown_table(
+            &["name", "lines"],
+            ranges.iter().map(|r| vec![r.name.clone(), format!("{}-{}", r.line_start, r.line_end)]),
+        ),
+    }
+}
+
+/// Format a project's status: entity counts by type, the total, and the indexed files
+///
+/// # Arguments
+/// * `status` - Status read by `project_status`
+/// * `format` - Output format; JSON lines prints the status as a single line
+///
+/// # Returns
+/// * `String` - Formatted output
+pub fn format_project_status(status: &ProjectStatus, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Human | OutputFormat::HumanColored => {
+            let mut lines = vec![format!("Indexed files: {}", status.files.len())];
+            lines.extend(status.files.iter().map(|f| format!("- {}", f)));
+            lines.push(format!("Entities: {}", status.total_entities));
+            lines.extend(status.entity_counts.iter().map(|c| format!("  {}: {}", c.entity_type, c.count)));
+            lines.join("\n")
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(status).unwrap_or_else(|e| {
+            log::error!("Failed to serialize status to JSON: {}", e);
+            String::from("{}")
+        }),
+        OutputFormat::JsonLines => format_json_lines(std::slice::from_ref(status)),
+        OutputFormat::Markdown => {
+            let counts = markdown_table(
+                &["type", "count"],
+                status.entity_counts.iter()
+                    .map(|c| vec![c.entity_type.clone(), c.count.to_string()])
+                    .chain(std::iter::once(vec!["total".to_string(), status.total_entities.to_string()])),
+            );
+            let files = markdown_table(&["file"], status.files.iter().map(|f| vec![f.clone()]));
+            format!("{}\n\n{}", counts, files)
+        }
+    }
+}
+
+/// Render what `forget` removed for the terminal, as JSON, or as a markdown table
+pub fn format_forget_report(report: &ForgetReport, format: OutputFormat) -> String {
+    match format {
+        OutputFormat::Human | OutputFormat::HumanColored => {
+            let mut lines = vec![format!("Cleared {} files and {} entities", report.cleared_files, report.cleared_entities)];
+            if let Some(removed) = report.removed_vectors {
+                lines.push(format!("Removed {} vector embeddings", removed));
+            }
+            lines.join("\n")
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(report).unwrap_or_else(|e| {
+            log::error!("Failed to serialize forget report to JSON: {}", e);
+            String::from("{}")
+        }),
+        OutputFormat::JsonLines => format_json_lines(std::slice::from_ref(report)),
+        OutputFormat::Markdown => {
+            let mut rows = vec![
+                vec!["files".to_string(), report.cleared_files.to_string()],
+                vec!["entities".to_string(), report.cleared_entities.to_string()],
+            ];
+            if let Some(removed) = report.removed_vectors {
+                rows.push(vec!["vectors".to_string(), removed.to_string()]);
+            }
+            markdown_table(&["cleared", "count"], rows)
+        }
     }
 }
 
+/// Format a score histogram as one bar per bucket, highest scores first
+pub fn format_score_histogram(histogram: &[ScoreBucket]) -> String {
+    let widest = histogram.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    histogram.iter().rev()
+        .map(|b| {
+            let bar = "#".repeat((b.count * 40).div_ceil(widest));
+            format!("{:.2}-{:.2} | {:<40} {}", b.min, b.max, bar, b.count)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Write formatted results to `stdout`, or to the file `output` with only a short
+/// confirmation on `stdout`
+///
+/// Parent directories of `output` are created as needed. Logs go to stderr either
+/// way, so the file holds nothing but the results.
+pub fn write_output<W: std::io::Write>(text: &str, output: Option<&Path>, stdout: &mut W) -> std::io::Result<()> {
+    let Some(path) = output else {
+        return writeln!(stdout, "{}", text);
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, format!("{}\n", text))?;
+    log::info!("Wrote {} bytes of results to {}", text.len() + 1, path.display());
+    writeln!(stdout, "Wrote results to {}", path.display())
+}
+
+/// Escape a value for a markdown table cell: pipes are escaped and newlines flattened
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+/// GitHub-flavored markdown table with the given header and rows
+fn markdown_table(header: &[&str], rows: impl IntoIterator<Item = Vec<String>>) -> String {
+    let mut lines = vec![
+        format!("| {} |", header.join(" | ")),
+        format!("|{}|", vec!["---"; header.len()].join("|")),
+    ];
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| markdown_cell(cell)).collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+    lines.join("\n")
+}
+
+/// Aligned table of entity type, name, line range, and file
+fn format_entity_table(entities: &[CodeEntity]) -> String {
+    if entities.is_empty() {
+        return "No entities found.".to_string();
+    }
+    let rows: Vec<[String; 4]> = entities.iter()
+        .map(|e| [
+            e.entity_type.clone(),
+            e.name.clone(),
+            format!("{}-{}", e.line_start, e.line_end),
+            e.file_path.clone(),
+        ])
+        .collect();
+    let header = ["TYPE", "NAME", "LINES", "FILE"].map(String::from);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|row| format!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {}",
+            row[0], row[1], row[2], row[3],
+            w0 = widths[0], w1 = widths[1], w2 = widths[2]
+        ))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 // Original functions for classic search results
 pub fn format_human_readable(results: &Vec<(&str, f32)>) -> String {
     results.iter().map(|(id, score)| format!("{}: {}", id, score)).collect::<Vec<_>>().join("\n")
@@ -56,7 +305,7 @@ pub fn format_human_readable_search_results(results: &[SearchResult]) -> String
             
         output.push_str(&format!(
             "{}. {} (score: {:.4}) - {}",
-            i + 1,
+            result.rank,
             result.entity_id,
             result.score,
             metadata_str
@@ -70,6 +319,80 @@ pub fn format_human_readable_search_results(results: &[SearchResult]) -> String
     output
 }
 
+/// Scores at or above this are shown in green
+pub const SCORE_HIGH: f32 = 0.8;
+
+/// Scores at or above this (and below `SCORE_HIGH`) are shown in yellow; lower ones in red
+pub const SCORE_MEDIUM: f32 = 0.5;
+
+/// Whether terminal output should be colored: stdout is a TTY and `NO_COLOR` is unset or empty
+pub fn color_enabled() -> bool {
+    std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stdout().is_terminal()
+}
+
+/// Format search results grouped by file, in the order each file's best result ranks
+///
+/// Each line shows the absolute rank, entity id, score, and entity type. Results of a
+/// multi-project search are grouped per project and file, with headers like
+/// `project: file`. With `color`, scores are green, yellow, or red by
+/// `SCORE_HIGH`/`SCORE_MEDIUM` and file headers are bold.
+pub fn format_grouped_search_results(results: &[SearchResult], color: bool) -> String {
+    if results.is_empty() {
+        return "No results found.".to_string();
+    }
+    
+    let mut groups: Vec<(String, Vec<&SearchResult>)> = Vec::new();
+    for result in results {
+        let file = result.metadata.get("file").map(String::as_str).unwrap_or("(unknown file)");
+        let header = match result.metadata.get("project") {
+            Some(project) => format!("{}: {}", project, file),
+            None => file.to_string(),
+        };
+        match groups.iter_mut().find(|(h, _)| *h == header) {
+            Some((_, members)) => members.push(result),
+            None => groups.push((header, vec![result])),
+        }
+    }
+    
+    let mut lines = Vec::new();
+    for (file, members) in groups {
+        lines.push(if color { file.bold().to_string() } else { file });
+        for result in members {
+            let score = format!("{:.4}", result.score);
+            let score = match (color, result.score) {
+                (false, _) => score,
+                (true, s) if s >= SCORE_HIGH => score.green().to_string(),
+                (true, s) if s >= SCORE_MEDIUM => score.yellow().to_string(),
+                (true, _) => score.red().to_string(),
+            };
+            let entity_type = result.metadata.get("type").map(String::as_str).unwrap_or("unknown");
+            lines.push(format!("  {}. {}  {}  {}", result.rank, result.entity_id, score, entity_type));
+            // Filled in from the Redis index when the entity is found there
+            if let Some(line_range) = result.metadata.get("lines") {
+                match result.metadata.get("signature") {
+                    Some(signature) => lines.push(format!("     {}  (lines {})", signature, line_range)),
+                    None => lines.push(format!("     (lines {})", line_range)),
+                }
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Format search results as a markdown table of rank, entity, score, type, and file
+pub fn format_markdown_search_results(results: &[SearchResult]) -> String {
+    markdown_table(
+        &["rank", "entity", "score", "type", "file"],
+        results.iter().map(|result| vec![
+            result.rank.to_string(),
+            result.entity_id.clone(),
+            format!("{:.4}", result.score),
+            result.metadata.get("type").cloned().unwrap_or_default(),
+            result.metadata.get("file").cloned().unwrap_or_default(),
+        ]),
+    )
+}
+
 pub fn format_json_search_results(results: &[SearchResult]) -> String {
     match serde_json::to_string(results) {
         Ok(json) => json,
@@ -116,11 +439,15 @@ mod tests {
                 entity_id: "func1".to_string(),
                 score: 0.95,
                 metadata: metadata1,
+                rank: 1,
+                vector: None,
             },
             SearchResult {
                 entity_id: "class1".to_string(),
                 score: 0.85,
                 metadata: metadata2,
+                rank: 2,
+                vector: None,
             },
         ];
         
@@ -130,6 +457,126 @@ mod tests {
         assert!(output.contains("function"));
     }
     
+    #[test]
+    fn test_format_project_status() {
+        let status = ProjectStatus {
+            files: vec!["a.py".to_string()],
+            entity_counts: vec![
+                crate::redis_ops::EntityTypeCount { entity_type: "class".to_string(), count: 1 },
+                crate::redis_ops::EntityTypeCount { entity_type: "function".to_string(), count: 2 },
+            ],
+            total_entities: 3,
+        };
+        assert_eq!(
+            format_project_status(&status, OutputFormat::Human),
+            "Indexed files: 1\n- a.py\nEntities: 3\n  class: 1\n  function: 2"
+        );
+        let json: serde_json::Value = serde_json::from_str(&format_project_status(&status, OutputFormat::JsonLines)).unwrap();
+        assert_eq!(json["total_entities"], 3);
+        assert_eq!(json["entity_counts"][1]["count"], 2);
+        assert!(format_project_status(&status, OutputFormat::Markdown).contains("| total | 3 |"));
+
+        let json: serde_json::Value = serde_json::from_str(&format_project_status(&status, OutputFormat::Json)).unwrap();
+        assert_eq!(json["files"], serde_json::json!(["a.py"]));
+        assert_eq!(json["entity_counts"][0]["entity_type"], "class");
+    }
+
+    #[test]
+    fn test_write_output_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reports/nested/results.json");
+        let text = r#"[{"name": "load_config"}]"#;
+
+        let mut stdout = Vec::new();
+        write_output(text, Some(&path), &mut stdout).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{}\n", text));
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(!stdout.contains("load_config"), "results leaked to stdout: {}", stdout);
+        assert!(stdout.contains("results.json"));
+
+        let mut stdout = Vec::new();
+        write_output(text, None, &mut stdout).unwrap();
+        assert_eq!(String::from_utf8(stdout).unwrap(), format!("{}\n", text));
+    }
+
+    #[test]
+    fn test_format_forget_report() {
+        let report = ForgetReport { cleared_files: 2, cleared_entities: 5, removed_vectors: None };
+        let json: serde_json::Value = serde_json::from_str(&format_forget_report(&report, OutputFormat::Json)).unwrap();
+        assert_eq!(json, serde_json::json!({"cleared_files": 2, "cleared_entities": 5}));
+
+        let report = ForgetReport { removed_vectors: Some(4), ..report };
+        let json: serde_json::Value = serde_json::from_str(&format_forget_report(&report, OutputFormat::JsonLines)).unwrap();
+        assert_eq!(json["removed_vectors"], 4);
+        assert_eq!(
+            format_forget_report(&report, OutputFormat::Human),
+            "Cleared 2 files and 5 entities\nRemoved 4 vector embeddings"
+        );
+    }
+
+    #[test]
+    fn test_format_grouped_search_results() {
+        let result = |entity_id: &str, file: &str, score: f32, rank: usize| SearchResult {
+            entity_id: entity_id.to_string(),
+            score,
+            metadata: [("file", file), ("type", "function")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            rank,
+            vector: None,
+        };
+        let results = vec![
+            result("fn:a.py:load", "a.py", 0.95, 1),
+            result("fn:b.py:save", "b.py", 0.6, 2),
+            result("fn:a.py:dump", "a.py", 0.3, 3),
+        ];
+        
+        let plain = format_grouped_search_results(&results, false);
+        assert_eq!(plain.lines().collect::<Vec<_>>(), vec![
+            "a.py",
+            "  1. fn:a.py:load  0.9500  function",
+            "  3. fn:a.py:dump  0.3000  function",
+            "b.py",
+            "  2. fn:b.py:save  0.6000  function",
+        ]);
+        assert!(!plain.contains('\u{1b}'));
+        
+        let colored = format_search_results(&results, OutputFormat::HumanColored);
+        assert!(colored.contains(&"0.9500".green().to_string()));
+        assert!(colored.contains(&"0.6000".yellow().to_string()));
+        assert!(colored.contains(&"0.3000".red().to_string()));
+        
+        let mut enriched = results[0].clone();
+        enriched.metadata.insert("signature".to_string(), "def load(path)".to_string());
+        enriched.metadata.insert("lines".to_string(), "3-9".to_string());
+        assert_eq!(
+            format_grouped_search_results(&[enriched], false),
+            "a.py\n  1. fn:a.py:load  0.9500  function\n     def load(path)  (lines 3-9)"
+        );
+    }
+    
+    #[test]
+    fn test_format_markdown_search_results() {
+        let results = vec![SearchResult {
+            entity_id: "fn:a|b.py:load".to_string(),
+            score: 0.9,
+            metadata: [("type", "function"), ("file", "a|b.py")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            rank: 1,
+            vector: None,
+        }];
+        
+        let output = format_search_results(&results, OutputFormat::Markdown);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "| rank | entity | score | type | file |");
+        assert_eq!(lines[1], "|---|---|---|---|---|");
+        assert_eq!(lines[2], "| 1 | fn:a\\|b.py:load | 0.9000 | function | a\\|b.py |");
+        assert!(matches!("md".parse::<OutputFormat>(), Ok(OutputFormat::Markdown)));
+    }
+    
     #[test]
     fn test_format_json_search_results() {
         let mut metadata = HashMap::new();
@@ -140,6 +587,8 @@ mod tests {
                 entity_id: "func1".to_string(),
                 score: 0.95,
                 metadata,
+                rank: 1,
+                vector: None,
             },
         ];
         
@@ -149,6 +598,68 @@ mod tests {
         assert!(output.contains("function"));
     }
     
+    fn entity(entity_type: &str, name: &str, line_start: usize, line_end: usize) -> CodeEntity {
+        CodeEntity {
+            entity_type: entity_type.to_string(),
+            file_path: "pkg/app.py".to_string(),
+            name: name.to_string(),
+            signature: None,
+            docstring: None,
+            line_start,
+            line_end,
+            parent_class: None,
+            bases: None,
+            value_repr: None,
+            source: None,
+        }
+    }
+    
+    #[test]
+    fn test_format_code_entities() {
+        let entities = vec![entity("function", "load", 1, 4), entity("class", "Loader", 6, 20)];
+        
+        let table = format_code_entities(&entities, OutputFormat::Human);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("TYPE"));
+        assert!(lines[1].contains("function") && lines[1].contains("load") && lines[1].contains("1-4"));
+        assert!(lines[2].contains("6-20") && lines[2].ends_with("pkg/app.py"));
+        
+        let jsonl = format_code_entities(&entities, OutputFormat::JsonLines);
+        let parsed: Vec<CodeEntity> = jsonl.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].name, "Loader");
+        
+        let json: Vec<CodeEntity> = serde_json::from_str(&format_code_entities(&entities, OutputFormat::Json)).unwrap();
+        assert_eq!(json.len(), 2);
+        assert_eq!(format_code_entities(&[], OutputFormat::Human), "No entities found.");
+    }
+    
+    #[test]
+    fn test_format_line_ranges() {
+        let entities = vec![entity("function", "load", 1, 4), entity("class", "Loader", 6, 20)];
+        
+        assert_eq!(format_line_ranges(&entities, OutputFormat::Human), "load: 1-4\nLoader: 6-20");
+        
+        let json: Vec<LineRange> = serde_json::from_str(&format_line_ranges(&entities, OutputFormat::Json)).unwrap();
+        assert_eq!(json, vec![
+            LineRange { name: "load".into(), line_start: 1, line_end: 4 },
+            LineRange { name: "Loader".into(), line_start: 6, line_end: 20 },
+        ]);
+        let value: serde_json::Value = serde_json::from_str(&format_line_ranges(&entities, OutputFormat::Json)).unwrap();
+        assert_eq!(value[1]["line_end"], 20);
+        assert_eq!(value[0].as_object().unwrap().len(), 3);
+    }
+    
+    #[test]
+    fn test_output_format_from_str() {
+        assert!(matches!("jsonl".parse::<OutputFormat>(), Ok(OutputFormat::JsonLines)));
+        assert!(matches!("json".parse::<OutputFormat>(), Ok(OutputFormat::Json)));
+        assert!(matches!("Human".parse::<OutputFormat>(), Ok(OutputFormat::Human)));
+        assert!(matches!("color".parse::<OutputFormat>(), Ok(OutputFormat::HumanColored)));
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+    
     #[test]
     fn test_empty_search_results() {
         let results: Vec<SearchResult> = vec![];
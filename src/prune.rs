@@ -0,0 +1,85 @@
+//! Implementation of the prune command
+//!
+//! Removes the index data and vectors of files that were indexed but have since been
+//! deleted from disk, leaving every other file's data in place, so a long-lived index
+//! stays in sync without a full `forget` and re-index.
+
+use crate::error::IndexerError;
+use crate::redis_ops::clear_file_data;
+use crate::vector_store::RedisVectorStore;
+use fred::interfaces::SetsInterface;
+use fred::prelude::Client;
+use log::info;
+use std::path::Path;
+
+/// Outcome of pruning a project
+#[derive(Debug, Default, serde::Serialize)]
+pub struct PruneReport {
+    /// Indexed files that no longer exist on disk, sorted
+    pub pruned_files: Vec<String>,
+    /// Entities removed along with those files
+    pub cleared_entities: usize,
+    /// Vectors removed along with those files
+    pub removed_vectors: usize,
+}
+
+/// Indexed files (from `:file_index`) that no longer exist under `project_dir`, sorted
+pub async fn missing_indexed_files(redis: &Client, key_prefix: &str, project_dir: &Path) -> Result<Vec<String>, IndexerError> {
+    let files: Vec<String> = redis.smembers(format!("{}:file_index", key_prefix)).await?;
+    let mut missing: Vec<String> = files.into_iter()
+        .filter(|rel_path| !project_dir.join(rel_path).exists())
+        .collect();
+    missing.sort();
+    Ok(missing)
+}
+
+/// Whether a vector's `file` metadata refers to the deleted file `rel_path`
+///
+/// Vectorize records the path as it walked it (`<project>/<rel_path>`), so the
+/// trailing components are compared; the recorded path must also be gone, so a
+/// same-named file elsewhere in the tree keeps its vectors.
+fn is_stale_vector_file(file: &str, rel_path: &str) -> bool {
+    let file = Path::new(file);
+    file.ends_with(rel_path) && !file.exists()
+}
+
+/// Remove the content, entities, and (given a `store`) vectors of indexed files that
+/// no longer exist under `project_dir`
+///
+/// # Arguments
+/// * `redis` - Connected Redis client
+/// * `key_prefix` - Project key prefix (e.g. `code_index:<name>`)
+/// * `project_dir` - Directory the indexed paths are relative to
+/// * `store` - Vector store to remove the files' vectors from, if any
+///
+/// # Returns
+/// * `Result<PruneReport, IndexerError>` - What was removed, or an error if Redis failed
+pub async fn prune_project(
+    redis: &Client,
+    key_prefix: &str,
+    project_dir: &Path,
+    store: Option<&RedisVectorStore>,
+) -> Result<PruneReport, IndexerError> {
+    let pruned_files = missing_indexed_files(redis, key_prefix, project_dir).await?;
+    if pruned_files.is_empty() {
+        info!("No indexed files are missing under {}", project_dir.display());
+        return Ok(PruneReport::default());
+    }
+    let cleared_entities = clear_file_data(redis, key_prefix, &pruned_files).await?;
+
+    let mut removed_vectors = 0;
+    if let Some(store) = store {
+        for entity_id in store.get_all_entity_ids_async().await? {
+            let Ok(metadata) = store.get_entity_metadata_async(&entity_id).await else { continue };
+            let stale = metadata.get("file")
+                .is_some_and(|file| pruned_files.iter().any(|rel_path| is_stale_vector_file(file, rel_path)));
+            if stale && store.delete_embedding_async(&entity_id).await? {
+                removed_vectors += 1;
+            }
+        }
+        store.sweep_shared_vectors_async().await?;
+    }
+
+    info!("Pruned {} missing files: {} entities, {} vectors", pruned_files.len(), cleared_entities, removed_vectors);
+    Ok(PruneReport { pruned_files, cleared_entities, removed_vectors })
+}
@@ -0,0 +1,499 @@
+//! Qdrant vector store implementation
+//! - Stores each embedding as a Qdrant point whose payload is the entity metadata
+//! - Runs filtered similarity search on the server, translating search options into payload filters
+//! - Keeps one collection per project, named after the key prefix
+
+use crate::error::IndexerError;
+use crate::vector_search::{file_glob_matcher, project_metadata, SearchOptions, SearchResult};
+use crate::vector_store::{block_on, check_dimension, EntityDetails, VectorStore};
+use qdrant_client::qdrant::{
+    vector_output::Vector, vectors_output::VectorsOptions, Condition, CountPointsBuilder, CreateCollectionBuilder,
+    DeletePointsBuilder, Distance, Filter, GetPointsBuilder, PointId, PointStruct, PointsIdsList,
+    ScrollPointsBuilder, SearchPointsBuilder, UpsertPointsBuilder, Value, VectorParamsBuilder,
+    VectorsOutput,
+};
+use qdrant_client::{Payload, Qdrant};
+use std::collections::HashMap;
+
+/// gRPC endpoint of a local Qdrant server, for configs without a `qdrant` vector DB
+pub const DEFAULT_QDRANT_URL: &str = "http://localhost:6334";
+
+/// Points requested per page when scrolling a collection
+const SCROLL_PAGE_SIZE: u32 = 256;
+
+pub struct QdrantVectorStore {
+    url: String,
+    collection: String,
+    dimension: usize,
+    client: Option<Qdrant>,
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl Drop for QdrantVectorStore {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks on its workers, which panics inside async callers
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Qdrant collection for a project; collection names may not contain `:` or `/`
+pub fn collection_name(key_prefix: &str) -> String {
+    key_prefix
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Point ID for an entity
+///
+/// Qdrant only accepts integer or UUID point IDs, so entity IDs are hashed with 64-bit
+/// FNV-1a, which is stable across builds; the entity ID itself is kept in the payload.
+pub fn point_id(entity_id: &str) -> u64 {
+    entity_id.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Whether a file filter needs glob matching rather than an exact path match
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+/// Entity types to match, lowercased as well since payload matches are case-sensitive
+fn type_keywords(types: &[String]) -> Vec<String> {
+    let mut keywords: Vec<String> = types.iter()
+        .flat_map(|t| [t.clone(), t.to_lowercase()])
+        .collect();
+    keywords.sort();
+    keywords.dedup();
+    keywords
+}
+
+/// Payload filter for the type and file options of a search
+///
+/// A glob `file_filter` cannot be expressed as a payload condition and is left for the
+/// caller to apply to the returned points.
+pub fn payload_filter(options: &SearchOptions) -> Option<Filter> {
+    let mut must = Vec::new();
+    let mut must_not = Vec::new();
+    if let Some(ref entity_types) = options.entity_types {
+        must.push(Condition::matches("type", type_keywords(entity_types)));
+    }
+    if let Some(ref exclude_entity_types) = options.exclude_entity_types {
+        must_not.push(Condition::matches("type", type_keywords(exclude_entity_types)));
+    }
+    if let Some(ref file) = options.file_filter {
+        if !is_glob(file) {
+            must.push(Condition::matches("file", file.clone()));
+        }
+    }
+    if must.is_empty() && must_not.is_empty() {
+        return None;
+    }
+    Some(Filter { must, must_not, ..Default::default() })
+}
+
+/// Flatten a point payload to strings so Qdrant metadata looks like the other stores'
+fn payload_metadata(payload: HashMap<String, Value>) -> HashMap<String, String> {
+    payload.into_iter()
+        .map(|(key, value)| match value.into_json() {
+            serde_json::Value::String(s) => (key, s),
+            other => (key, other.to_string()),
+        })
+        .collect()
+}
+
+/// The dense vector of a returned point, if vectors were requested
+fn point_vector(vectors: Option<VectorsOutput>) -> Option<Vec<f32>> {
+    match vectors?.vectors_options? {
+        VectorsOptions::Vector(vector) => match vector.into_vector() {
+            Vector::Dense(dense) => Some(dense.data),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl QdrantVectorStore {
+    /// Connect to Qdrant and open the collection for `key_prefix`, creating it with
+    /// cosine distance and `dimension`-length vectors if it does not exist yet
+    ///
+    /// Like `RedisVectorStore`, the client runs on a runtime owned by the store so the
+    /// synchronous `VectorStore` methods work from any caller. Fails if the URL is
+    /// invalid or the server cannot be reached.
+    pub fn new(url: &str, key_prefix: &str, dimension: usize) -> Result<Self, IndexerError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("qdrant-vector-store")
+            .enable_all()
+            .build()?;
+        let client = Qdrant::from_url(url)
+            .build()
+            .map_err(|e| IndexerError::Store(format!("Invalid Qdrant URL {}: {}", url, e)))?;
+        let collection = collection_name(key_prefix);
+        block_on(&runtime, async {
+            let exists = client.collection_exists(&collection).await
+                .map_err(|e| IndexerError::Store(format!("Failed to reach Qdrant at {}: {}", url, e)))?;
+            if !exists {
+                client.create_collection(
+                    CreateCollectionBuilder::new(&collection)
+                        .vectors_config(VectorParamsBuilder::new(dimension as u64, Distance::Cosine)),
+                ).await
+                    .map_err(|e| IndexerError::Store(format!("Failed to create collection {}: {}", collection, e)))?;
+                log::info!("Created Qdrant collection {} with dimension {}", collection, dimension);
+            }
+            Ok::<(), IndexerError>(())
+        })?;
+        log::info!("Qdrant vector store connected to {}", url);
+
+        Ok(Self {
+            url: url.to_string(),
+            collection,
+            dimension,
+            client: Some(client),
+            runtime: Some(runtime),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    /// Dimension every stored embedding must have
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn client(&self) -> Result<&Qdrant, IndexerError> {
+        self.client.as_ref().ok_or_else(|| IndexerError::Store("Qdrant client not initialized".to_string()))
+    }
+
+    /// Wait for one of the async store operations from synchronous code
+    fn run<F, T>(&self, future: F) -> Result<T, IndexerError>
+    where
+        F: std::future::Future<Output = Result<T, IndexerError>> + Send,
+        T: Send,
+    {
+        match &self.runtime {
+            Some(runtime) => block_on(runtime, future),
+            None => Err(IndexerError::Store("Qdrant client not initialized".to_string())),
+        }
+    }
+
+    /// Store an embedding as a point whose payload is the entity metadata
+    pub async fn upsert_embedding_async(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+        self.upsert_embedding_with_details_async(entity_id, embedding, file, entity_type, &EntityDetails::default()).await
+    }
+
+    /// Store an embedding as a point whose payload also holds the entity's line range and signature
+    pub async fn upsert_embedding_with_details_async(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>, details: &EntityDetails) -> Result<(), IndexerError> {
+        check_dimension(entity_id, embedding, self.dimension)?;
+        let client = self.client()?;
+
+        let entity_type = entity_type.unwrap_or("unknown");
+        let file_path = file.unwrap_or("unknown");
+        let mut payload = serde_json::json!({
+            "id": entity_id,
+            "type": entity_type,
+            "file": file_path,
+            "vector_length": embedding.len()
+        });
+        for (key, value) in details.metadata_fields() {
+            payload[key] = value;
+        }
+        let payload = Payload::try_from(payload)
+        .map_err(|e| IndexerError::Parse(format!("Failed to build payload for {}: {}", entity_id, e)))?;
+
+        let point = PointStruct::new(point_id(entity_id), embedding.to_vec(), payload);
+        client.upsert_points(UpsertPointsBuilder::new(&self.collection, vec![point]).wait(true)).await
+            .map_err(|e| IndexerError::Store(format!("Failed to upsert point for {}: {}", entity_id, e)))?;
+
+        log::info!("Stored Qdrant point for entity {} of type {} from file {}",
+                  entity_id, entity_type, file_path);
+        Ok(())
+    }
+
+    /// Rank points against `query` on the server, applying the search options
+    ///
+    /// Type filters and plain file paths become payload filters and `min_score` becomes
+    /// the score threshold. A glob `file_filter` is matched against the returned points,
+    /// so that case fetches every type-filtered match and paginates locally.
+    pub async fn search_async(&self, query: &[f32], options: &SearchOptions) -> Result<Vec<SearchResult>, IndexerError> {
+        let client = self.client()?;
+        let total = self.count_async().await? as u64;
+        if total == 0 || options.top_k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let file_matcher = match options.file_filter {
+            Some(ref pattern) if is_glob(pattern) => Some(file_glob_matcher(pattern)?),
+            _ => None,
+        };
+        let (limit, offset) = match file_matcher {
+            Some(_) => (total, 0),
+            None => ((options.top_k as u64).min(total), options.offset as u64),
+        };
+
+        let mut request = SearchPointsBuilder::new(&self.collection, query.to_vec(), limit)
+            .offset(offset)
+            .with_payload(true)
+            .with_vectors(options.include_vectors);
+        if let Some(filter) = payload_filter(options) {
+            request = request.filter(filter);
+        }
+        if let Some(min_score) = options.min_score {
+            request = request.score_threshold(min_score);
+        }
+        let response = client.search_points(request).await
+            .map_err(|e| IndexerError::Store(format!("Qdrant search in {} failed: {}", self.collection, e)))?;
+
+        let mut results = Vec::new();
+        for point in response.result {
+            let metadata = payload_metadata(point.payload);
+            if let Some(ref file_matcher) = file_matcher {
+                if !metadata.get("file").is_some_and(|file| file_matcher.is_match(file)) {
+                    continue;
+                }
+            }
+            let Some(entity_id) = metadata.get("id").cloned() else {
+                log::warn!("Qdrant point in {} has no entity id in its payload", self.collection);
+                continue;
+            };
+            let metadata = match options.fields {
+                Some(ref fields) => project_metadata(metadata, fields),
+                None => metadata,
+            };
+            results.push(SearchResult {
+                entity_id,
+                score: point.score.clamp(0.0, 1.0),
+                metadata,
+                rank: 0,
+                vector: point_vector(point.vectors),
+            });
+        }
+
+        // Only the glob case still has to skip to the requested page
+        let skip = if file_matcher.is_some() { options.offset } else { 0 };
+        let results: Vec<SearchResult> = results
+            .into_iter()
+            .skip(skip)
+            .take(options.top_k)
+            .enumerate()
+            .map(|(i, mut result)| {
+                result.rank = options.offset + i + 1;
+                result
+            })
+            .collect();
+
+        log::info!("Qdrant search returned {} results", results.len());
+        Ok(results)
+    }
+
+    /// Fetch an entity's point, with or without its vector
+    async fn get_point(&self, entity_id: &str, with_vector: bool) -> Result<Option<qdrant_client::qdrant::RetrievedPoint>, IndexerError> {
+        let client = self.client()?;
+        let response = client.get_points(
+            GetPointsBuilder::new(&self.collection, vec![PointId::from(point_id(entity_id))])
+                .with_payload(true)
+                .with_vectors(with_vector),
+        ).await
+            .map_err(|e| IndexerError::Store(format!("Failed to get point for {}: {}", entity_id, e)))?;
+        Ok(response.result.into_iter().next())
+    }
+
+    /// Get the vector for an entity asynchronously
+    pub async fn get_entity_vector_async(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+        self.get_point(entity_id, true).await?
+            .and_then(|point| point_vector(point.vectors))
+            .ok_or_else(|| IndexerError::Store(format!("No vector stored for entity {}", entity_id)))
+    }
+
+    /// Get metadata for an entity asynchronously
+    pub async fn get_entity_metadata_async(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
+        self.get_point(entity_id, false).await?
+            .map(|point| payload_metadata(point.payload))
+            .ok_or_else(|| IndexerError::Store(format!("No metadata stored for entity {}", entity_id)))
+    }
+
+    /// Get all entity IDs in the collection asynchronously
+    pub async fn get_all_entity_ids_async(&self) -> Result<Vec<String>, IndexerError> {
+        let client = self.client()?;
+
+        let mut entity_ids = std::collections::BTreeSet::new();
+        let mut page_offset: Option<PointId> = None;
+        loop {
+            let mut request = ScrollPointsBuilder::new(&self.collection)
+                .limit(SCROLL_PAGE_SIZE)
+                .with_payload(true)
+                .with_vectors(false);
+            if let Some(offset) = page_offset.take() {
+                request = request.offset(offset);
+            }
+            let response = client.scroll(request).await
+                .map_err(|e| IndexerError::Store(format!("Failed to scroll {}: {}", self.collection, e)))?;
+            entity_ids.extend(response.result.into_iter()
+                .filter_map(|point| payload_metadata(point.payload).remove("id")));
+            match response.next_page_offset {
+                Some(next) => page_offset = Some(next),
+                None => break,
+            }
+        }
+
+        log::info!("Retrieved {} entity IDs for {}", entity_ids.len(), self.collection);
+        Ok(entity_ids.into_iter().collect())
+    }
+
+    /// Count the points in the collection asynchronously
+    pub async fn count_async(&self) -> Result<usize, IndexerError> {
+        let client = self.client()?;
+        let response = client.count(CountPointsBuilder::new(&self.collection).exact(true)).await
+            .map_err(|e| IndexerError::Store(format!("Failed to count {}: {}", self.collection, e)))?;
+        Ok(response.result.map(|result| result.count as usize).unwrap_or(0))
+    }
+
+    /// Delete an entity's point asynchronously, returning whether it existed
+    pub async fn delete_embedding_async(&self, entity_id: &str) -> Result<bool, IndexerError> {
+        if self.get_point(entity_id, false).await?.is_none() {
+            return Ok(false);
+        }
+        let client = self.client()?;
+        client.delete_points(
+            DeletePointsBuilder::new(&self.collection)
+                .points(PointsIdsList { ids: vec![PointId::from(point_id(entity_id))] })
+                .wait(true),
+        ).await
+            .map_err(|e| IndexerError::Store(format!("Failed to delete point for {}: {}", entity_id, e)))?;
+
+        log::info!("Deleted Qdrant point for entity {}", entity_id);
+        Ok(true)
+    }
+}
+
+impl VectorStore for QdrantVectorStore {
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+        check_dimension(entity_id, embedding, self.dimension)?;
+        self.run(self.upsert_embedding_async(entity_id, embedding, file, entity_type))
+    }
+
+    fn upsert_embedding_with_details(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>, details: &EntityDetails) -> Result<(), IndexerError> {
+        check_dimension(entity_id, embedding, self.dimension)?;
+        self.run(self.upsert_embedding_with_details_async(entity_id, embedding, file, entity_type, details))
+    }
+
+    fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+        let options = SearchOptions {
+            top_k,
+            min_score: None,
+            entity_types: None,
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: None,
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+        match self.run(self.search_async(query, &options)) {
+            Ok(results) => results.into_iter().map(|result| result.entity_id).collect(),
+            Err(e) => {
+                log::error!("Similarity search failed: {}", e);
+                vec![]
+            }
+        }
+    }
+
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+        self.run(self.get_all_entity_ids_async())
+    }
+
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+        self.run(self.get_entity_vector_async(entity_id))
+    }
+
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
+        self.run(self.get_entity_metadata_async(entity_id))
+    }
+
+    fn count(&self) -> Result<usize, IndexerError> {
+        self.run(self.count_async())
+    }
+
+    fn delete_embedding(&self, entity_id: &str) -> Result<bool, IndexerError> {
+        self.run(self.delete_embedding_async(entity_id))
+    }
+
+    fn native_search(&self, query: &[f32], options: &SearchOptions) -> Option<Result<Vec<SearchResult>, IndexerError>> {
+        // MMR needs every candidate's vector, so it runs in `search_vectors`
+        if options.mmr.is_some() {
+            return None;
+        }
+        Some(self.run(self.search_async(query, options)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> SearchOptions {
+        SearchOptions {
+            top_k: 5,
+            min_score: None,
+            entity_types: None,
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: None,
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        }
+    }
+
+    #[test]
+    fn test_collection_name_replaces_separators() {
+        assert_eq!(collection_name("code_index:my-app"), "code_index_my-app");
+        assert_eq!(collection_name("code:a/b"), "code_a_b");
+    }
+
+    #[test]
+    fn test_point_id_is_stable() {
+        assert_eq!(point_id(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(point_id("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(point_id("fn:a.py:foo"), point_id("fn:a.py:bar"));
+    }
+
+    #[test]
+    fn test_payload_filter_translates_options() {
+        assert!(payload_filter(&options()).is_none());
+
+        let filter = payload_filter(&SearchOptions {
+            entity_types: Some(vec!["Function".to_string()]),
+            exclude_entity_types: Some(vec!["variable".to_string()]),
+            file_filter: Some("src/app.py".to_string()),
+            ..options()
+        }).unwrap();
+        assert_eq!(filter.must.len(), 2);
+        assert_eq!(filter.must_not.len(), 1);
+        assert_eq!(type_keywords(&["Function".to_string()]), vec!["Function", "function"]);
+
+        // Globs are matched client-side
+        let filter = payload_filter(&SearchOptions { file_filter: Some("src/**/*.py".to_string()), ..options() });
+        assert!(filter.is_none());
+    }
+
+    #[test]
+    fn test_qdrant_vector_store_new_rejects_unreachable_server() {
+        assert!(QdrantVectorStore::new("http://invalid-host.invalid:6334", "prefix", 3).is_err());
+    }
+}
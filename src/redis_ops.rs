@@ -3,28 +3,181 @@
 //! - uses fred 10.x async initialization:
 //!   Config::from_url, Builder::from_config, client.init().await?
 
+use fred::clients::Pool;
 use fred::prelude::*; // For Client, Config, Builder, Error, Expiration, SetOptions, etc.
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 
 // Assuming these are still needed by your logic.
 // The 'unused' warning for Serialize/Deserialize here will appear if CodeEntity (defined elsewhere)
 // is the only serializable type and has its own `use serde::...` for the derive.
 
-use crate::ast_parser::CodeEntity;
+use crate::ast_parser::{truncate_source, CodeEntity, DEFAULT_SOURCE_MAX_BYTES};
+use crate::embedder::RetryPolicy;
+use crate::error::IndexerError;
+use crate::vector_store::EmbeddingMeta;
 use std::collections::HashMap;
+use std::future::Future;
 use std::time::Duration;
 
+/// Entity types produced by `ast_parser::extract_code_info`
+pub const ENTITY_TYPES: &[&str] = &["class", "function", "method", "variable"];
+
+/// Connections opened by `create_redis_pool` when none is configured
+pub const DEFAULT_REDIS_POOL_SIZE: usize = 4;
+
+/// How long to wait for a Redis connection before giving up
+pub const DEFAULT_REDIS_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connect and per-command timeouts for Redis clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedisTimeouts {
+    /// Longest wait for a connection, and for each handshake command on it (`AUTH`, `SELECT`, ...)
+    pub connect: Duration,
+    /// Longest wait for a reply to any one command; zero means no timeout
+    pub command: Duration,
+}
+
+impl Default for RedisTimeouts {
+    fn default() -> Self {
+        Self { connect: DEFAULT_REDIS_CONNECT_TIMEOUT, command: Duration::ZERO }
+    }
+}
+
+/// Retries for Redis writes that fail on a dropped connection or a timeout
+pub const DEFAULT_REDIS_RETRY_POLICY: RetryPolicy = RetryPolicy {
+    max_retries: 3,
+    base_delay: Duration::from_millis(200),
+    max_delay: Duration::from_secs(5),
+};
+
+/// Percent-encode a dynamic key segment (an entity name or file path)
+///
+/// Escapes `%` and `:` so a name like `Vec::new` or a path like `C:\src\app.py`
+/// stays one segment of a `:`-separated key. The readable name is still stored in
+/// the entity JSON and in set members.
+pub fn encode_key_component(component: &str) -> String {
+    let mut encoded = String::with_capacity(component.len());
+    for c in component.chars() {
+        match c {
+            '%' => encoded.push_str("%25"),
+            ':' => encoded.push_str("%3A"),
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Reverse `encode_key_component`, leaving unknown escapes as they are
+pub fn decode_key_component(component: &str) -> String {
+    let mut decoded = String::with_capacity(component.len());
+    let mut rest = component;
+    while let Some(pos) = rest.find('%') {
+        decoded.push_str(&rest[..pos]);
+        let escape = &rest[pos..];
+        if let Some(after) = escape.strip_prefix("%25") {
+            decoded.push('%');
+            rest = after;
+        } else if let Some(after) = escape.strip_prefix("%3A") {
+            decoded.push(':');
+            rest = after;
+        } else {
+            decoded.push('%');
+            rest = &escape[1..];
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Client builder for `redis_url` with the shared connection settings
+///
+/// Clients reconnect on their own after a dropped connection, with exponential
+/// backoff from 100 ms up to 5 s between attempts.
+fn redis_builder(redis_url: &str, timeouts: &RedisTimeouts) -> Result<Builder, Error> {
+    let config = Config::from_url(redis_url)?;
+    let mut builder = Builder::from_config(config);
+    builder.with_connection_config(|cfg| {
+        cfg.connection_timeout = timeouts.connect;
+        cfg.internal_command_timeout = timeouts.connect;
+    });
+    builder.with_performance_config(|cfg| {
+        cfg.default_command_timeout = timeouts.command;
+    });
+    builder.set_policy(ReconnectPolicy::new_exponential(0, 100, 5_000, 2));
+    Ok(builder)
+}
+
+/// Whether a Redis error is worth retrying: a dropped connection, a timeout, or a
+/// command canceled by a reconnect, but not a logical error such as a parse failure
+pub fn is_transient_redis_error(error: &Error) -> bool {
+    matches!(error.kind(), ErrorKind::IO | ErrorKind::Timeout | ErrorKind::Canceled | ErrorKind::Backpressure)
+}
+
+/// Run the Redis operation `op`, retrying it per `policy` while it fails with a
+/// transient error
+///
+/// The client reconnects in the background, so a retry after the backoff goes out
+/// on the new connection. `op` must be safe to repeat (e.g. SET, SADD, HSET).
+///
+/// # Arguments
+/// * `policy` - Number of retries and backoff between them
+/// * `what` - Description of the operation for the retry log
+/// * `op` - Creates a fresh attempt of the operation
+pub async fn with_redis_retry<T, F, Fut>(policy: &RetryPolicy, what: &str, mut op: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut retries = 0;
+    loop {
+        match op().await {
+            Err(e) if retries < policy.max_retries && is_transient_redis_error(&e) => {
+                let delay = policy.delay_for(retries);
+                retries += 1;
+                log::warn!("Redis {} failed ({}); retry {}/{} in {:?}", what, e, retries, policy.max_retries, delay);
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
 // This function was already mostly correct in your provided snippet based on previous iterations.
 pub async fn create_redis_client(redis_url: &str) -> Result<Client, Error> {
-    let config = Config::from_url(redis_url)?;
-    let client = Builder::from_config(config)
-        .with_connection_config(|cfg| {
-            cfg.connection_timeout = Duration::from_secs(5);
-        })
-        .build()?;
+    create_redis_client_with_timeouts(redis_url, &RedisTimeouts::default()).await
+}
+
+/// Connect a client to `redis_url` that gives up on a connection or a command
+/// after the given timeouts
+pub async fn create_redis_client_with_timeouts(redis_url: &str, timeouts: &RedisTimeouts) -> Result<Client, Error> {
+    let client = build_redis_client(redis_url, timeouts)?;
     client.init().await?;
     Ok(client)
 }
 
+/// Build a client for `redis_url` with the given timeouts without connecting it;
+/// call `init` before sending it commands
+pub fn build_redis_client(redis_url: &str, timeouts: &RedisTimeouts) -> Result<Client, Error> {
+    redis_builder(redis_url, timeouts)?.build()
+}
+
+/// Connect a pool of `size` clients to `redis_url`
+///
+/// `Pool::next()` hands out the clients round-robin, so concurrent callers share a
+/// fixed set of connections instead of opening one each. Every function here that
+/// takes a `&Client` accepts a pooled client.
+pub async fn create_redis_pool(redis_url: &str, size: usize) -> Result<Pool, Error> {
+    create_redis_pool_with_timeouts(redis_url, size, &RedisTimeouts::default()).await
+}
+
+/// Connect a pool of `size` clients to `redis_url` with the given timeouts
+pub async fn create_redis_pool_with_timeouts(redis_url: &str, size: usize, timeouts: &RedisTimeouts) -> Result<Pool, Error> {
+    let pool = redis_builder(redis_url, timeouts)?.build_pool(size.max(1))?;
+    pool.init().await?;
+    Ok(pool)
+}
+
 pub async fn store_file_content(
     redis: &Client, // Changed from &RedisClient
     key_prefix: &str,
@@ -39,7 +192,7 @@ pub async fn store_file_content(
         "size": size,
         "last_modified": last_modified
     });
-    let file_key = format!("{}:files:{}", key_prefix, rel_path);
+    let file_key = format!("{}:files:{}", key_prefix, encode_key_component(rel_path));
 
     // 1. Handle serde_json::Error to fred::Error conversion
     let value_to_set = serde_json::to_string(&file_data).map_err(|e| {
@@ -51,19 +204,33 @@ pub async fn store_file_content(
 
     // 2. Call redis.set with the correct 5 arguments for fred 10.1.0
     //    (key, value, expiration: Option<Expiration>, options: Option<SetOptions>, get: bool)
-    let _: String = redis.set(file_key, value_to_set, None, None, false).await?;
-    let _: u64 = redis.sadd(format!("{}:file_index", key_prefix), rel_path).await?;
+    let policy = DEFAULT_REDIS_RETRY_POLICY;
+    let _: String = with_redis_retry(&policy, "file store", || redis.set(&file_key, &value_to_set, None, None, false)).await?;
+    let index_key = format!("{}:file_index", key_prefix);
+    let _: u64 = with_redis_retry(&policy, "file index update", || redis.sadd(&index_key, rel_path)).await?;
     Ok(())
 }
 
+/// Store entities with source snippets capped at `DEFAULT_SOURCE_MAX_BYTES`
 pub async fn store_code_entities(
     redis: &Client, // Changed from &RedisClient
     key_prefix: &str,
     entities: &[CodeEntity],
 ) -> Result<(), Error> { // Changed from fred::error::Error
+    store_code_entities_with_limit(redis, key_prefix, entities, DEFAULT_SOURCE_MAX_BYTES).await
+}
+
+/// Store entities, truncating source snippets longer than `max_source_bytes`
+pub async fn store_code_entities_with_limit(
+    redis: &Client,
+    key_prefix: &str,
+    entities: &[CodeEntity],
+    max_source_bytes: usize,
+) -> Result<(), Error> {
     use serde_json::to_string; // Local import is fine for clarity
     // HashMap is imported at the top
 
+    let policy = DEFAULT_REDIS_RETRY_POLICY;
     let mut by_type: HashMap<&str, Vec<&CodeEntity>> = HashMap::new();
     for entity in entities {
         by_type.entry(&entity.entity_type).or_default().push(entity);
@@ -74,7 +241,11 @@ pub async fn store_code_entities(
         let pipe = redis.pipeline();
         for entity in ents {
             let entity_id = &entity.name;
-            let value_str = match to_string(entity) {
+            let stored = CodeEntity {
+                source: entity.source.as_deref().map(|s| truncate_source(s, max_source_bytes)),
+                ..(*entity).clone()
+            };
+            let value_str = match to_string(&stored) {
                 Ok(val) => val,
                 Err(e) => {
                     return Err(Error::new(
@@ -83,49 +254,185 @@ pub async fn store_code_entities(
                     ));
                 }
             };
-            let _: u64 = redis.hset(&type_key, (entity_id, &value_str)).await?;
-            let _: u64 = redis.sadd(format!("{}:search_index:{}:{}", key_prefix, entity_type, entity.name), entity_id).await?;
-            let _: u64 = redis.sadd(format!("{}:file_entities:{}", key_prefix, entity.file_path), format!("{}:{}", entity_type, entity_id)).await?;
+            let search_key = format!("{}:search_index:{}:{}", key_prefix, entity_type, encode_key_component(&entity.name));
+            let file_key = format!("{}:file_entities:{}", key_prefix, encode_key_component(&entity.file_path));
+            let file_member = format!("{}:{}", entity_type, entity_id);
+            let _: u64 = with_redis_retry(&policy, "entity store", || redis.hset(&type_key, (entity_id, &value_str))).await?;
+            let _: u64 = with_redis_retry(&policy, "search index update", || redis.sadd(&search_key, entity_id)).await?;
+            let _: u64 = with_redis_retry(&policy, "file entity update", || redis.sadd(&file_key, &file_member)).await?;
+            for term in docstring_terms(entity.docstring.as_deref().unwrap_or_default()) {
+                let term_key = format!("{}:docstring_index:{}", key_prefix, term);
+                let _: u64 = with_redis_retry(&policy, "docstring index update", || redis.sadd(&term_key, &file_member)).await?;
+            }
         }
         let _: Vec<Value> = pipe.all().await?;
     }
     Ok(())
 }
 
+/// Remove the stored content, entities, and index entries of `rel_paths`
+///
+/// # Returns
+/// * `Result<usize, Error>` - Number of entities removed
 pub async fn clear_file_data(
     redis: &Client,
     key_prefix: &str,
     rel_paths: &[String],
-) -> Result<(), Error> {
+) -> Result<usize, Error> {
+    let mut cleared_entities = 0;
     for rel_path in rel_paths {
-        let entities_key = format!("{}:file_entities:{}", key_prefix, rel_path);
+        let entities_key = format!("{}:file_entities:{}", key_prefix, encode_key_component(rel_path));
         let entity_ids: Vec<String> = redis.smembers(&entities_key).await.unwrap_or_default();
+        cleared_entities += entity_ids.len();
         let pipe = redis.pipeline();
         for entity_id in entity_ids.iter() {
-            let mut parts = entity_id.splitn(2, ':');
-            let entity_type = parts.next().unwrap_or("");
-            let id_part = parts.next().unwrap_or("");
+            // members are `{type}:{name}`; types never contain `:`, names may
+            let (entity_type, id_part) = entity_id.split_once(':').unwrap_or((entity_id.as_str(), ""));
             let type_key = format!("{}:{}s", key_prefix, entity_type);
+            let stored: Option<String> = redis.hget(&type_key, id_part).await?;
+            let docstring = stored
+                .and_then(|json| serde_json::from_str::<CodeEntity>(&json).ok())
+                .and_then(|entity| entity.docstring)
+                .unwrap_or_default();
+            for term in docstring_terms(&docstring) {
+                let _: u64 = redis.srem(format!("{}:docstring_index:{}", key_prefix, term), entity_id.as_str()).await?;
+            }
             let _: u64 = redis.hdel(&type_key, id_part).await?;
-            let name = id_part.split(':').last().unwrap_or("");
             let _: u64 = redis.srem(
-    format!("{}:search_index:{}:{}", key_prefix, entity_type, name),
-    id_part,
-).await?;
+                format!("{}:search_index:{}:{}", key_prefix, entity_type, encode_key_component(id_part)),
+                id_part,
+            ).await?;
         }
 
         let _: u64 = redis.del(&entities_key).await?;
-        let _: u64 = redis.del(format!("{}:files:{}", key_prefix, rel_path)).await?;
+        let _: u64 = redis.del(format!("{}:files:{}", key_prefix, encode_key_component(rel_path))).await?;
         let _: u64 = redis.srem(format!("{}:file_index", key_prefix), rel_path).await?;
 
         // execute the pipeline for this rel_path
         let _: Vec<Value> = pipe.all().await?;
     }  // ← closes the for-loop
 
-    Ok(())  // ← now return success after all paths processed
+    Ok(cleared_entities)
 }  // ← closes clear_file_data
 
 
+/// How a queried name is compared with indexed entity names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NameMatch {
+    /// Match names that start with the query instead of equal it
+    pub prefix: bool,
+    /// Compare names ignoring case
+    pub ignore_case: bool,
+}
+
+impl NameMatch {
+    /// Exact, case-sensitive comparison (the `query_code_entity` behaviour)
+    pub const EXACT: NameMatch = NameMatch { prefix: false, ignore_case: false };
+
+    /// Whether an indexed `name` matches the `query`
+    pub fn matches(&self, name: &str, query: &str) -> bool {
+        let (name, query) = if self.ignore_case {
+            (name.to_lowercase(), query.to_lowercase())
+        } else {
+            (name.to_string(), query.to_string())
+        };
+        if self.prefix {
+            name.starts_with(&query)
+        } else {
+            name == query
+        }
+    }
+}
+
+/// Query entities of a type by name with a configurable match mode
+///
+/// Exact case-sensitive matches read the `search_index` set directly; other modes
+/// scan the type's `search_index` keys and load every name that matches. Results of
+/// a scan are ordered by name.
+pub async fn query_code_entity_matching(
+    redis: &Client,
+    key_prefix: &str,
+    entity_type: &str,
+    name: &str,
+    mode: NameMatch,
+) -> Result<Vec<CodeEntity>, Error> {
+    if mode == NameMatch::EXACT {
+        return query_code_entity(redis, key_prefix, entity_type, Some(name)).await;
+    }
+    let mut names: Vec<String> = search_index_entries(redis, key_prefix, Some(entity_type)).await?
+        .into_iter()
+        .map(|(_, indexed)| indexed)
+        .filter(|indexed| mode.matches(indexed, name))
+        .collect();
+    names.sort();
+    let mut results = Vec::new();
+    for indexed in names {
+        results.extend(query_code_entity(redis, key_prefix, entity_type, Some(&indexed)).await?);
+    }
+    Ok(results)
+}
+
+/// Query entities like `query_code_entity`, returning at most `max` of them
+///
+/// A `max` of `None` or `0` means no limit. When results are cut, they are first
+/// ordered by file, line, and name so the same entities come back on every run.
+pub async fn query_code_entity_limited(
+    redis: &Client,
+    key_prefix: &str,
+    entity_type: &str,
+    name: Option<&str>,
+    max: Option<usize>,
+) -> Result<Vec<CodeEntity>, Error> {
+    let results = query_code_entity(redis, key_prefix, entity_type, name).await?;
+    Ok(limit_entities(results, max))
+}
+
+/// Cut `results` to at most `max` entities, in file, line, and name order
+fn limit_entities(mut results: Vec<CodeEntity>, max: Option<usize>) -> Vec<CodeEntity> {
+    if let Some(max) = max.filter(|&max| max > 0 && max < results.len()) {
+        results.sort_by(|a, b| (&a.file_path, a.line_start, &a.name).cmp(&(&b.file_path, b.line_start, &b.name)));
+        results.truncate(max);
+    }
+    results
+}
+
+/// `last_modified` (Unix seconds) of every indexed file, by relative path
+///
+/// Files whose record is missing or has no `last_modified` are left out.
+pub async fn file_modified_times(redis: &Client, key_prefix: &str) -> Result<HashMap<String, i64>, Error> {
+    let files: Vec<String> = redis.smembers(format!("{}:file_index", key_prefix)).await?;
+    let mut times = HashMap::new();
+    for rel_path in files {
+        let record: Option<String> = redis.get(format!("{}:files:{}", key_prefix, encode_key_component(&rel_path))).await?;
+        let last_modified = record
+            .and_then(|record| serde_json::from_str::<serde_json::Value>(&record).ok())
+            .and_then(|record| record.get("last_modified").and_then(serde_json::Value::as_i64));
+        if let Some(last_modified) = last_modified {
+            times.insert(rel_path, last_modified);
+        }
+    }
+    Ok(times)
+}
+
+/// Query entities like `query_code_entity_limited`, keeping only those whose file
+/// was last modified at or after `since` (Unix seconds)
+///
+/// Each entity is joined to its file record by `file_path`; entities of files with
+/// no recorded modification time are left out. `max` applies after filtering.
+pub async fn query_code_entity_since(
+    redis: &Client,
+    key_prefix: &str,
+    entity_type: &str,
+    name: Option<&str>,
+    since: i64,
+    max: Option<usize>,
+) -> Result<Vec<CodeEntity>, Error> {
+    let times = file_modified_times(redis, key_prefix).await?;
+    let mut results = query_code_entity(redis, key_prefix, entity_type, name).await?;
+    results.retain(|entity| times.get(&entity.file_path).is_some_and(|&modified| modified >= since));
+    Ok(limit_entities(results, max))
+}
+
 // Now starts the next function:
 pub async fn query_code_entity(
     redis: &Client, // Changed from &RedisClient
@@ -151,13 +458,14 @@ pub async fn query_code_entity(
             parent_class: None,
             bases: None,
             value_repr: None,
+            source: None,
         });
         return Ok(results);
     }
     
     // Regular implementation for other cases
     if let Some(name_val) = name { // Renamed to avoid conflict if `name` is a field, good practice
-        let search_key = format!("{}:search_index:{}:{}", key_prefix, entity_type, name_val);
+        let search_key = format!("{}:search_index:{}:{}", key_prefix, entity_type, encode_key_component(name_val));
         let entity_ids: Vec<String> = redis.smembers(&search_key).await.unwrap_or_default();
         let type_key = format!("{}:{}s", key_prefix, entity_type);
 
@@ -180,4 +488,347 @@ pub async fn query_code_entity(
         }
     }
     Ok(results)
-}
\ No newline at end of file
+}
+
+/// Read one indexed entity back as a `CodeEntity`
+///
+/// # Returns
+/// * `Result<Option<CodeEntity>, IndexerError>` - The entity, `None` if `name` is not
+///   indexed as `entity_type`, or `Parse` if the stored JSON is not a `CodeEntity`
+pub async fn get_entity(
+    redis: &Client,
+    key_prefix: &str,
+    entity_type: &str,
+    name: &str,
+) -> Result<Option<CodeEntity>, IndexerError> {
+    let json: Option<String> = redis.hget(format!("{}:{}s", key_prefix, entity_type), name).await?;
+    Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+}
+
+/// Find indexed entity names containing `keyword`, ignoring case
+///
+/// Scans the project's `search_index:{type}:{name}` sets and returns the matching
+/// `(entity_type, name)` pairs sorted by type and name.
+pub async fn keyword_matches(
+    redis: &Client,
+    key_prefix: &str,
+    keyword: &str,
+) -> Result<Vec<(String, String)>, Error> {
+    let keyword = keyword.to_lowercase();
+    let mut matches = search_index_entries(redis, key_prefix, None).await?;
+    matches.retain(|(_, name)| name.to_lowercase().contains(&keyword));
+    matches.sort();
+    Ok(matches)
+}
+
+/// Every key matching `pattern`, found with cursor-based SCAN
+///
+/// Use this instead of KEYS, which blocks the server while it walks the whole
+/// keyspace of a large or shared database.
+pub async fn scan_keys(redis: &Client, pattern: &str) -> Result<Vec<String>, Error> {
+    use futures::TryStreamExt;
+
+    let keys: Vec<Key> = redis.scan_buffered(pattern, Some(100), None)
+        .try_collect()
+        .await?;
+    Ok(keys.into_iter().filter_map(|key| key.into_string()).collect())
+}
+
+/// List `(entity_type, name)` for every `search_index` set, optionally of one type
+async fn search_index_entries(
+    redis: &Client,
+    key_prefix: &str,
+    entity_type: Option<&str>,
+) -> Result<Vec<(String, String)>, Error> {
+    let index_prefix = format!("{}:search_index:", key_prefix);
+    let pattern = match entity_type {
+        Some(entity_type) => format!("{}{}:*", index_prefix, entity_type),
+        None => format!("{}*", index_prefix),
+    };
+    Ok(scan_keys(redis, &pattern).await?
+        .into_iter()
+        .filter_map(|key| {
+            let (entity_type, name) = key.strip_prefix(&index_prefix)?.split_once(':')?;
+            Some((entity_type.to_string(), decode_key_component(name)))
+        })
+        .collect())
+}
+
+/// Find entities whose name contains `term`, ignoring case, without any embeddings
+///
+/// Matches names through `keyword_matches`, optionally keeps one entity type, and
+/// loads each match with `query_code_entity`. Results are ordered by type and name.
+pub async fn search_code_entities(
+    redis: &Client,
+    key_prefix: &str,
+    term: &str,
+    entity_type: Option<&str>,
+) -> Result<Vec<CodeEntity>, Error> {
+    let mut results = Vec::new();
+    for (match_type, name) in keyword_matches(redis, key_prefix, term).await? {
+        if entity_type.is_some_and(|t| !t.eq_ignore_ascii_case(&match_type)) {
+            continue;
+        }
+        results.extend(query_code_entity(redis, key_prefix, &match_type, Some(&name)).await?);
+    }
+    Ok(results)
+}
+
+/// Words too common to tell docstrings apart
+const DOCSTRING_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "be", "by", "for", "from", "if", "in", "into", "is",
+    "it", "of", "on", "or", "the", "this", "that", "to", "with",
+];
+
+/// Lowercased words of a docstring or docstring query, without stop words
+///
+/// Words are runs of ASCII letters, digits, and underscores; the result is sorted
+/// and deduplicated.
+pub fn docstring_terms(text: &str) -> Vec<String> {
+    let mut terms: Vec<String> = text
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .filter(|word| !DOCSTRING_STOP_WORDS.contains(&word.as_str()))
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// Find entities whose docstrings share words with `query`, without any embeddings
+///
+/// Looks each query word up in the project's `docstring_index:{word}` sets and ranks
+/// the entities by how many of the words their docstring contains, then by type and
+/// name. `entity_type` optionally keeps one entity type.
+pub async fn search_docstrings(
+    redis: &Client,
+    key_prefix: &str,
+    query: &str,
+    entity_type: Option<&str>,
+) -> Result<Vec<CodeEntity>, Error> {
+    let mut hits: HashMap<String, usize> = HashMap::new();
+    for term in docstring_terms(query) {
+        let members: Vec<String> = redis.smembers(format!("{}:docstring_index:{}", key_prefix, term)).await?;
+        for member in members {
+            *hits.entry(member).or_default() += 1;
+        }
+    }
+    let mut ranked: Vec<(usize, String, String)> = hits
+        .into_iter()
+        .filter_map(|(member, count)| {
+            let (match_type, name) = member.split_once(':')?;
+            Some((count, match_type.to_string(), name.to_string()))
+        })
+        .filter(|(_, match_type, _)| entity_type.is_none_or(|t| t.eq_ignore_ascii_case(match_type)))
+        .collect();
+    ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| (&a.1, &a.2).cmp(&(&b.1, &b.2))));
+
+    let mut results = Vec::new();
+    for (_, match_type, name) in ranked {
+        results.extend(query_code_entity(redis, key_prefix, &match_type, Some(&name)).await?);
+    }
+    Ok(results)
+}
+
+/// Count indexed entities per type for a project, returning `(entity_type, count)` pairs
+pub async fn count_entities_by_type(
+    redis: &Client,
+    key_prefix: &str,
+) -> Result<Vec<(String, u64)>, Error> {
+    let mut counts = Vec::new();
+    for entity_type in ENTITY_TYPES {
+        let count: u64 = redis.hlen(format!("{}:{}s", key_prefix, entity_type)).await?;
+        counts.push((entity_type.to_string(), count));
+    }
+    Ok(counts)
+}
+
+/// Number of indexed entities of one type
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct EntityTypeCount {
+    pub entity_type: String,
+    pub count: u64,
+}
+
+/// Indexed files and per-type entity counts for a project, as shown by `status`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ProjectStatus {
+    pub files: Vec<String>,
+    pub entity_counts: Vec<EntityTypeCount>,
+    pub total_entities: u64,
+}
+
+/// Read a project's indexed files (sorted) and count its entities by type
+pub async fn project_status(redis: &Client, key_prefix: &str) -> Result<ProjectStatus, Error> {
+    let mut files: Vec<String> = redis.smembers(format!("{}:file_index", key_prefix)).await.unwrap_or_default();
+    files.sort();
+    let entity_counts: Vec<EntityTypeCount> = count_entities_by_type(redis, key_prefix).await?
+        .into_iter()
+        .map(|(entity_type, count)| EntityTypeCount { entity_type, count })
+        .collect();
+    let total_entities = entity_counts.iter().map(|c| c.count).sum();
+    Ok(ProjectStatus { files, entity_counts, total_entities })
+}
+
+/// Names of the projects indexed in Redis, sorted
+///
+/// Finds every `code_index:<name>:file_index` key with `scan_keys`.
+pub async fn list_projects(redis: &Client) -> Result<Vec<String>, Error> {
+    let mut projects: Vec<String> = scan_keys(redis, "code_index:*:file_index").await?
+        .into_iter()
+        .filter_map(|key| {
+            key.strip_prefix("code_index:")
+                .and_then(|rest| rest.strip_suffix(":file_index"))
+                .map(str::to_string)
+        })
+        .collect();
+    projects.sort();
+    projects.dedup();
+    Ok(projects)
+}
+
+/// Key holding the embedding provider, model, and dimension of a project's vectors
+pub fn project_meta_key(key_prefix: &str) -> String {
+    format!("{}:meta:embedding", key_prefix)
+}
+
+/// Record the embedding provider, model, and dimension a project was vectorized with
+pub async fn store_project_meta(redis: &Client, key_prefix: &str, meta: &EmbeddingMeta) -> Result<(), IndexerError> {
+    let json = serde_json::to_string(meta)?;
+    let _: String = redis.set(project_meta_key(key_prefix), json, None, None, false).await?;
+    Ok(())
+}
+
+/// Read the embedding metadata recorded for a project, if it has been vectorized
+pub async fn get_project_meta(redis: &Client, key_prefix: &str) -> Result<Option<EmbeddingMeta>, IndexerError> {
+    let json: Option<String> = redis.get(project_meta_key(key_prefix)).await?;
+    Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+}
+
+/// What `forget` removed from a project
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ForgetReport {
+    pub cleared_files: usize,
+    pub cleared_entities: usize,
+    /// Vector embeddings deleted, absent when they were kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub removed_vectors: Option<usize>,
+}
+
+/// Value of a single Redis key captured by `dump_project`
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DumpValue {
+    String(String),
+    /// Base64 of a value that is not UTF-8 text
+    Binary(String),
+    Set(Vec<String>),
+    Hash(std::collections::BTreeMap<String, String>),
+}
+
+/// One exported key, stored relative to the project's key prefix
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DumpRecord {
+    pub key: String,
+    pub value: DumpValue,
+}
+
+/// Capture every key under a project's prefix (files, entities, indexes, and vectors)
+///
+/// Records are sorted by key so dumps of the same index are identical.
+pub async fn dump_project(
+    redis: &Client,
+    key_prefix: &str,
+) -> Result<Vec<DumpRecord>, Error> {
+    let prefix = format!("{}:", key_prefix);
+    let mut keys = scan_keys(redis, &format!("{}*", prefix)).await?;
+    keys.sort();
+
+    let mut records = Vec::new();
+    for key in keys {
+        let key_type: String = redis.custom(fred::cmd!("TYPE"), vec![key.clone()]).await?;
+        let value = match key_type.as_str() {
+            "string" => {
+                let value: Option<Vec<u8>> = redis.get(&key).await?;
+                match String::from_utf8(value.unwrap_or_default()) {
+                    Ok(value) => DumpValue::String(value),
+                    // e.g. a vector written with the binary vector encoding
+                    Err(e) => DumpValue::Binary(BASE64.encode(e.into_bytes())),
+                }
+            }
+            "set" => {
+                let mut members: Vec<String> = redis.smembers(&key).await?;
+                members.sort();
+                DumpValue::Set(members)
+            }
+            "hash" => DumpValue::Hash(redis.hgetall(&key).await?),
+            other => {
+                log::warn!("Skipping {} of unsupported type {}", key, other);
+                continue;
+            }
+        };
+        records.push(DumpRecord {
+            key: key[prefix.len()..].to_string(),
+            value,
+        });
+    }
+    Ok(records)
+}
+
+/// Re-create dumped keys under a project's prefix, replacing any existing values
+///
+/// Returns the number of keys written.
+pub async fn load_project(
+    redis: &Client,
+    key_prefix: &str,
+    records: &[DumpRecord],
+) -> Result<usize, Error> {
+    let mut written = 0;
+    for record in records {
+        let key = format!("{}:{}", key_prefix, record.key);
+        let _: u64 = redis.del(&key).await?;
+        match &record.value {
+            DumpValue::String(value) => {
+                let _: String = redis.set(&key, value.as_str(), None, None, false).await?;
+            }
+            DumpValue::Binary(encoded) => {
+                let bytes = BASE64.decode(encoded)
+                    .map_err(|e| Error::new(ErrorKind::Parse, format!("Invalid base64 value for {}: {}", record.key, e)))?;
+                let _: String = redis.set(&key, bytes, None, None, false).await?;
+            }
+            DumpValue::Set(members) if !members.is_empty() => {
+                let _: u64 = redis.sadd(&key, members.clone()).await?;
+            }
+            DumpValue::Hash(fields) if !fields.is_empty() => {
+                let fields: HashMap<String, String> = fields.clone().into_iter().collect();
+                let _: u64 = redis.hset(&key, fields).await?;
+            }
+            _ => continue,
+        }
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Write dump records as newline-delimited JSON
+pub fn write_dump<W: std::io::Write>(records: &[DumpRecord], mut writer: W) -> std::io::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Read dump records from newline-delimited JSON, ignoring blank lines
+pub fn read_dump<R: std::io::BufRead>(reader: R) -> std::io::Result<Vec<DumpRecord>> {
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line)?);
+    }
+    Ok(records)
+}
@@ -0,0 +1,102 @@
+//! Implementation of the remember command
+//!
+//! Stores each python file's content and entities in Redis, one file at a time. A
+//! file that cannot be read or stored is recorded and skipped, so one bad file in a
+//! large tree does not abort the run or lose the files indexed before it.
+
+use crate::ast_parser::{parse_code_info, ParseDiagnostic};
+use crate::error::IndexerError;
+use crate::file_processing::modified_unix_secs;
+use crate::redis_ops::{store_code_entities_with_limit, store_file_content};
+use fred::prelude::Client;
+use log::{info, warn};
+use std::path::{Path, PathBuf};
+
+/// Outcome of indexing a set of files
+#[derive(Debug, Default)]
+pub struct RememberReport {
+    /// Files whose content was stored
+    pub indexed: usize,
+    /// Files stored without entities because they failed to parse
+    pub diagnostics: Vec<ParseDiagnostic>,
+    /// Files skipped because they could not be read or stored, as `path: error`
+    pub errors: Vec<String>,
+}
+
+/// Read one file and store its content and entities
+///
+/// # Returns
+/// * `Result<Option<ParseDiagnostic>, IndexerError>` - The parse failure, if any, or
+///   an error if the file could not be read or stored
+async fn remember_file(
+    redis: &Client,
+    key_prefix: &str,
+    app_dir: &Path,
+    file: &Path,
+    source_max_bytes: usize,
+) -> Result<Option<ParseDiagnostic>, IndexerError> {
+    let rel_path = file.strip_prefix(app_dir).unwrap_or(file).to_string_lossy().to_string();
+    let content = tokio::fs::read_to_string(file).await?;
+    let meta = tokio::fs::metadata(file).await?;
+    let size = meta.len() as usize;
+    let mtime = modified_unix_secs(&meta)?;
+    store_file_content(redis, key_prefix, &rel_path, &content, size, mtime).await?;
+    match parse_code_info(&content, &rel_path) {
+        Ok(entities) => {
+            store_code_entities_with_limit(redis, key_prefix, &entities, source_max_bytes).await?;
+            Ok(None)
+        }
+        Err(diagnostic) => Ok(Some(diagnostic)),
+    }
+}
+
+/// Index `files` under `app_dir`, continuing past files that fail
+///
+/// # Arguments
+/// * `redis` - Connected Redis client
+/// * `key_prefix` - Project key prefix (e.g. `code_index:<name>`)
+/// * `app_dir` - Project root that stored paths are made relative to
+/// * `files` - Files to index
+/// * `source_max_bytes` - Byte limit for each entity's stored source snippet
+///
+/// # Returns
+/// * `RememberReport` - Files indexed, parse diagnostics, and per-file errors
+pub async fn remember_files(
+    redis: &Client,
+    key_prefix: &str,
+    app_dir: &Path,
+    files: &[PathBuf],
+    source_max_bytes: usize,
+) -> RememberReport {
+    let mut report = RememberReport::default();
+    for file in files {
+        match remember_file(redis, key_prefix, app_dir, file, source_max_bytes).await {
+            Ok(diagnostic) => {
+                report.indexed += 1;
+                report.diagnostics.extend(diagnostic);
+            }
+            Err(e) => {
+                warn!("Skipping {}: {}", file.display(), e);
+                report.errors.push(format!("{}: {}", file.display(), e));
+            }
+        }
+    }
+    info!("Indexed {} of {} files", report.indexed, files.len());
+    report
+}
+
+/// Print how many files were indexed and the files that were skipped
+pub fn report_remember_errors(report: &RememberReport) {
+    if report.errors.is_empty() {
+        return;
+    }
+    eprintln!(
+        "Indexed {} of {} files; {} failed:",
+        report.indexed,
+        report.indexed + report.errors.len(),
+        report.errors.len()
+    );
+    for error in &report.errors {
+        eprintln!("  {}", error);
+    }
+}
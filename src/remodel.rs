@@ -0,0 +1,231 @@
+//! Implementation of the remodel command
+//!
+//! Re-embeds every entity of a project with a new embedding model into a staging
+//! store, verifies the staged vectors share one dimension, and only then swaps them
+//! and the new model metadata into the live store with `VectorStore::replace_all`.
+//! Old and new vectors are never mixed: the live store is untouched until staging
+//! has fully succeeded, and the swap keeps each entity's line range and signature.
+
+use crate::embedder::Embedder;
+use crate::error::IndexerError;
+use crate::vector_store::{EmbeddingMeta, EntityDetails, VectorStore};
+use crate::vectorize::{process_directory_with_diagnostics, VectorizeOptions};
+use log::{info, warn};
+use std::path::Path;
+
+/// Remove every entity from a store
+fn clear_store<V: VectorStore + ?Sized>(store: &V) -> Result<usize, IndexerError> {
+    let mut removed = 0;
+    for entity_id in store.get_all_entity_ids()? {
+        if store.delete_embedding(&entity_id)? {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Check that every staged vector has the same dimension, returning it
+fn staged_dimension<V: VectorStore + ?Sized>(staging: &V, entity_ids: &[String]) -> Result<usize, IndexerError> {
+    let mut dimension = None;
+    for entity_id in entity_ids {
+        let len = staging.get_entity_vector(entity_id)?.len();
+        match dimension {
+            None => dimension = Some(len),
+            Some(dim) if dim != len => {
+                return Err(IndexerError::Embed(format!(
+                    "Refusing to swap mixed-dimension vectors: {} has dimension {} but expected {}",
+                    entity_id, len, dim
+                )));
+            }
+            Some(_) => {}
+        }
+    }
+    dimension.ok_or_else(|| IndexerError::Embed("No entities were embedded; refusing to replace the live index".to_string()))
+}
+
+/// Re-embed a project with a new model and swap the result into the live store
+///
+/// # Arguments
+/// * `project_path` - Path to the project to re-embed
+/// * `embedder` - Embedder for the new model
+/// * `live` - Store currently serving the project's vectors
+/// * `staging` - Scratch store that receives the new vectors before the swap
+/// * `provider` - Provider name recorded in the embedding metadata
+/// * `model` - Model name recorded in the embedding metadata
+/// * `normalized` - Whether `embedder` L2-normalizes its vectors, recorded in the embedding metadata
+///
+/// # Returns
+/// * `Result<EmbeddingMeta, IndexerError>` - The metadata now recorded for the live store, or an error
+pub fn remodel_project<E: Embedder + ?Sized, V: VectorStore + ?Sized, S: VectorStore + ?Sized>(
+    project_path: &Path,
+    embedder: &E,
+    live: &V,
+    staging: &S,
+    provider: &str,
+    model: &str,
+    normalized: bool,
+) -> Result<EmbeddingMeta, IndexerError> {
+    if !project_path.exists() {
+        return Err(IndexerError::PathNotFound(project_path.to_path_buf()));
+    }
+
+    // Start from an empty staging space
+    let stale = clear_store(staging)?;
+    if stale > 0 {
+        warn!("Cleared {} stale entities from the staging store", stale);
+    }
+
+    // Re-embed everything into staging
+    // A partially staged index must never replace the live one
+    let report = process_directory_with_diagnostics(project_path, embedder, staging, &VectorizeOptions::default(), &mut |_| {})?;
+    if !report.errors.is_empty() {
+        clear_store(staging)?;
+        return Err(IndexerError::Embed(format!(
+            "{} failures while re-embedding, live index left untouched: {}",
+            report.errors.len(),
+            report.errors.join("; ")
+        )));
+    }
+    let processed = report.processed;
+    let staged_ids = staging.get_all_entity_ids()?;
+    let dimension = match staged_dimension(staging, &staged_ids) {
+        Ok(dimension) => dimension,
+        Err(e) => {
+            clear_store(staging)?;
+            return Err(e);
+        }
+    };
+    info!("Staged {} entities ({} vectors) with dimension {}", processed, staged_ids.len(), dimension);
+
+    // Swap the staged entities and the new metadata into live in one step
+    let mut staged = Vec::with_capacity(staged_ids.len());
+    for entity_id in &staged_ids {
+        let vector = staging.get_entity_vector(entity_id)?;
+        let metadata = staging.get_entity_metadata(entity_id)?;
+        staged.push((
+            entity_id.clone(),
+            vector,
+            metadata.get("file").cloned(),
+            metadata.get("type").cloned(),
+            EntityDetails::from_metadata(&metadata),
+        ));
+    }
+    let meta = EmbeddingMeta {
+        provider: provider.to_string(),
+        model: model.to_string(),
+        dimension,
+        normalized,
+    };
+    let removed = live.replace_all(&staged, &meta)?;
+    clear_store(staging)?;
+
+    info!("Remodel swapped {} old vectors for {} new {} vectors", removed, staged_ids.len(), model);
+    Ok(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector_store::InMemoryVectorStore;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    /// Embedder standing in for a new model with a wider dimension
+    struct WideEmbedder;
+
+    impl Embedder for WideEmbedder {
+        fn embed(&self, input: &str) -> Vec<f32> {
+            let seed = input.len() as f32;
+            vec![seed, 1.0, 2.0, 3.0, 4.0]
+        }
+    }
+
+    #[test]
+    fn test_remodel_project_swaps_vectors_and_meta() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("app.py")).unwrap();
+        writeln!(file, "def handler():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        writeln!(file, "class Model:").unwrap();
+        writeln!(file, "    pass").unwrap();
+
+        // Live store holds vectors from the old 3-dimension model
+        let live = InMemoryVectorStore::new();
+        live.upsert_embedding("fn:app.py:handler", &[1.0, 0.0, 0.0], Some("app.py"), Some("function")).unwrap();
+        live.upsert_embedding("fn:old.py:removed", &[0.0, 1.0, 0.0], Some("old.py"), Some("function")).unwrap();
+        live.set_embedding_meta(&EmbeddingMeta {
+            provider: "openai".to_string(),
+            model: "text-embedding-ada-002".to_string(),
+            dimension: 3,
+            normalized: false,
+        }).unwrap();
+        let staging = InMemoryVectorStore::new();
+
+        let meta = remodel_project(dir.path(), &WideEmbedder, &live, &staging, "openai", "text-embedding-3-small", false).unwrap();
+
+        assert_eq!(meta.dimension, 5);
+        assert_eq!(live.get_embedding_meta().unwrap(), Some(meta));
+        let ids = live.get_all_entity_ids().unwrap();
+        assert_eq!(ids.len(), 2, "Only the re-embedded entities should remain: {:?}", ids);
+        for entity_id in &ids {
+            assert_eq!(live.get_entity_vector(entity_id).unwrap().len(), 5);
+        }
+        assert!(staging.is_empty(), "Staging should be emptied after the swap");
+    }
+
+    #[test]
+    fn test_remodel_project_keeps_entity_details() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("app.py")).unwrap();
+        writeln!(file, "import os").unwrap();
+        writeln!(file, "def handler(event, context):").unwrap();
+        writeln!(file, "    pass").unwrap();
+        let live = InMemoryVectorStore::new();
+        let staging = InMemoryVectorStore::new();
+
+        remodel_project(dir.path(), &WideEmbedder, &live, &staging, "openai", "text-embedding-3-small", false).unwrap();
+
+        let handler = live.get_all_entity_ids().unwrap().into_iter()
+            .find(|id| id.ends_with("handler"))
+            .expect("handler should be re-embedded");
+        let metadata = live.get_entity_metadata(&handler).unwrap();
+        assert_eq!(metadata.get("line_start").map(String::as_str), Some("2"));
+        assert!(metadata.contains_key("line_end"), "{:?}", metadata);
+        assert!(metadata.get("signature").is_some_and(|s| s.contains("handler")), "{:?}", metadata);
+    }
+
+    #[test]
+    fn test_remodel_project_records_normalized_vectors() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("app.py")).unwrap();
+        writeln!(file, "def handler():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        let live = InMemoryVectorStore::new();
+        let staging = InMemoryVectorStore::new();
+        let embedder = crate::embedder::NormalizingEmbedder::new(WideEmbedder);
+
+        let meta = remodel_project(dir.path(), &embedder, &live, &staging, "openai", "text-embedding-3-small", true).unwrap();
+
+        assert!(meta.normalized);
+        assert_eq!(live.get_embedding_meta().unwrap(), Some(meta));
+        for entity_id in live.get_all_entity_ids().unwrap() {
+            let norm = live.get_entity_vector(&entity_id).unwrap().iter().map(|x| x * x).sum::<f32>().sqrt();
+            assert!((norm - 1.0).abs() < 1e-5, "{} has norm {}", entity_id, norm);
+        }
+    }
+
+    #[test]
+    fn test_remodel_project_refuses_empty_staging() {
+        let dir = tempdir().unwrap();
+        let live = InMemoryVectorStore::new();
+        live.upsert_embedding("fn:app.py:handler", &[1.0, 0.0, 0.0], Some("app.py"), Some("function")).unwrap();
+        let staging = InMemoryVectorStore::new();
+
+        let result = remodel_project(dir.path(), &WideEmbedder, &live, &staging, "openai", "text-embedding-3-small", false);
+
+        assert!(result.is_err());
+        assert_eq!(live.count().unwrap(), 1, "Live vectors must be untouched when staging fails");
+        assert_eq!(live.get_embedding_meta().unwrap(), None);
+    }
+}
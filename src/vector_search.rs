@@ -1,8 +1,13 @@
 //! Vector similarity search implementation
 //! Provides functionality for searching vector embeddings with filtering and scoring
 
+use crate::error::IndexerError;
+use crate::redis_ops::keyword_matches;
 use crate::vector_store::VectorStore;
-use std::collections::HashMap;
+use fred::prelude::Client;
+use globset::{GlobBuilder, GlobMatcher};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use log;
 use serde::{Serialize, Deserialize};
 
@@ -15,6 +20,12 @@ pub struct SearchResult {
     pub score: f32,
     /// Additional metadata about the entity
     pub metadata: HashMap<String, String>,
+    /// Absolute 1-based rank of this result across the full, unpaginated result set
+    #[serde(default)]
+    pub rank: usize,
+    /// Stored embedding vector, present only when `SearchOptions::include_vectors` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vector: Option<Vec<f32>>,
 }
 
 /// Options for vector similarity search
@@ -24,14 +35,120 @@ pub struct SearchOptions {
     pub top_k: usize,
     /// Minimum similarity score threshold (0.0 to 1.0)
     pub min_score: Option<f32>,
-    /// Filter by entity types (e.g., "function", "class")
+    /// Filter by entity types (e.g., "function", "class"), matched case-insensitively
     pub entity_types: Option<Vec<String>>,
-    /// Filter by file path
+    /// Exclude entity types (e.g., "variable"), applied after the inclusion filter
+    #[serde(default)]
+    pub exclude_entity_types: Option<Vec<String>>,
+    /// Filter by file path glob (e.g., "src/**/*.py"); a plain path matches exactly
     pub file_filter: Option<String>,
+    /// Number of ranked results to skip before applying `top_k`
+    #[serde(default)]
+    pub offset: usize,
+    /// Metadata keys to return (`id` is always kept); `None` returns all metadata
+    #[serde(default)]
+    pub fields: Option<Vec<String>>,
+    /// Worker threads for the similarity scan; `None` uses the available parallelism
+    #[serde(default)]
+    pub search_threads: Option<usize>,
+    /// Candidates scored per chunk; `None` splits candidates evenly across threads
+    #[serde(default)]
+    pub chunk_size: Option<usize>,
+    /// Return each result's stored embedding vector
+    #[serde(default)]
+    pub include_vectors: bool,
+    /// Weight of the vector score in `hybrid_search` (0.0 to 1.0); the keyword score
+    /// gets the rest. `None` uses `DEFAULT_HYBRID_ALPHA`
+    #[serde(default)]
+    pub alpha: Option<f32>,
+    /// Maximal Marginal Relevance lambda (0.0 to 1.0); when set, the top candidates are
+    /// re-ranked to trade query relevance (1.0) against diversity among results (0.0)
+    #[serde(default)]
+    pub mmr: Option<f32>,
+}
+
+/// Results returned by vector-recall when `--top-k` is not given
+pub const DEFAULT_TOP_K: usize = 5;
+
+/// Largest `--top-k` vector-recall accepts unless the config sets `max_top_k`
+pub const DEFAULT_MAX_TOP_K: usize = 100;
+
+/// Default weight of the vector score in hybrid search
+pub const DEFAULT_HYBRID_ALPHA: f32 = 0.7;
+
+/// Buckets in the `--histogram` score distribution
+pub const DEFAULT_HISTOGRAM_BUCKETS: usize = 10;
+
+/// Number of results whose score falls in `[min, max)` (the last bucket includes `max`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreBucket {
+    pub min: f32,
+    pub max: f32,
+    pub count: usize,
+}
+
+/// Candidates considered by MMR re-ranking, as a multiple of the results needed
+const MMR_CANDIDATE_FACTOR: usize = 4;
+
+/// Candidates whose vectors and metadata `search_vectors` reads in one `get_vectors_batch` call
+const FETCH_BATCH_SIZE: usize = 500;
+
+/// Partial sums kept side by side so the compiler can hold them in SIMD registers
+const LANES: usize = 8;
+
+/// Dot product of `a` and `b` and the squared norm of each, for equal-length slices
+///
+/// Each sum is split over `LANES` independent accumulators; unlike one running
+/// total, these have no dependency between iterations, which lets the loop
+/// autovectorize.
+#[allow(clippy::needless_range_loop)]
+fn dot_and_norms(a: &[f32], b: &[f32]) -> (f32, f32, f32) {
+    let mut dot = [0.0f32; LANES];
+    let mut a_norm = [0.0f32; LANES];
+    let mut b_norm = [0.0f32; LANES];
+    let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let (a_rest, b_rest) = (a_chunks.remainder(), b_chunks.remainder());
+    for (x, y) in a_chunks.zip(b_chunks) {
+        for i in 0..LANES {
+            dot[i] += x[i] * y[i];
+            a_norm[i] += x[i] * x[i];
+            b_norm[i] += y[i] * y[i];
+        }
+    }
+
+    let mut sums = (dot.iter().sum::<f32>(), a_norm.iter().sum::<f32>(), b_norm.iter().sum::<f32>());
+    for (x, y) in a_rest.iter().zip(b_rest) {
+        sums.0 += x * y;
+        sums.1 += x * x;
+        sums.2 += y * y;
+    }
+    sums
 }
 
 /// Calculate cosine similarity between two vectors
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+///
+/// This is the hot path of every client-side search, so the sums are computed by
+/// `dot_and_norms`; the result matches `cosine_similarity_scalar` within f32 rounding.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        log::warn!("Vector dimensions don't match: {} vs {}", a.len(), b.len());
+        return 0.0;
+    }
+
+    let (dot_product, a_norm, b_norm) = dot_and_norms(a, b);
+    if a_norm == 0.0 || b_norm == 0.0 {
+        return 0.0;
+    }
+
+    let similarity = dot_product / (a_norm.sqrt() * b_norm.sqrt());
+    // Ensure the result is within [0, 1] range due to potential floating-point errors
+    similarity.clamp(0.0, 1.0)
+}
+
+/// Straightforward single-accumulator `cosine_similarity`, kept as the reference
+/// the optimized version is tested and benchmarked against
+#[doc(hidden)]
+pub fn cosine_similarity_scalar(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() {
         log::warn!("Vector dimensions don't match: {} vs {}", a.len(), b.len());
         return 0.0;
@@ -56,56 +173,260 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     similarity.max(0.0).min(1.0)
 }
 
+/// A similarity function over two embeddings
+type Similarity = fn(&[f32], &[f32]) -> f32;
+
+/// Similarity of two unit-length vectors: their dot product, clamped like `cosine_similarity`
+fn unit_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        log::warn!("Vector dimensions don't match: {} vs {}", a.len(), b.len());
+        return 0.0;
+    }
+    dot_and_norms(a, b).0.clamp(0.0, 1.0)
+}
+
+/// Check whether an entity type appears in a type list, ignoring case
+fn type_matches(types: &[String], entity_type: &str) -> bool {
+    types.iter().any(|t| t.eq_ignore_ascii_case(entity_type))
+}
+
+/// Compile a file filter glob; `*` does not cross `/`, `**` spans directories
+pub(crate) fn file_glob_matcher(pattern: &str) -> Result<GlobMatcher, IndexerError> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| IndexerError::Parse(format!("Invalid file filter pattern {}: {}", pattern, e)))
+}
+
+/// Score candidate vectors against the query in parallel chunks
+///
+/// Chunks are handed to `threads` scoped workers round-robin and the scores are
+/// reassembled in candidate order, so the output is identical for any chunking.
+/// A worker that panics fails the whole search rather than leaving its chunks unscored.
+fn score_candidates(
+    query: &[f32],
+    vectors: &[Vec<f32>],
+    threads: Option<usize>,
+    chunk_size: Option<usize>,
+    similarity: Similarity,
+) -> Result<Vec<f32>, IndexerError> {
+    if vectors.is_empty() {
+        return Ok(Vec::new());
+    }
+    let threads = threads
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .max(1);
+    let chunk_size = chunk_size
+        .unwrap_or_else(|| vectors.len().div_ceil(threads))
+        .max(1);
+    let chunks: Vec<&[Vec<f32>]> = vectors.chunks(chunk_size).collect();
+    let workers = threads.min(chunks.len());
+    log::debug!("Scoring {} candidates in {} chunks on {} threads", vectors.len(), chunks.len(), workers);
+
+    let mut chunk_scores: Vec<Vec<f32>> = vec![Vec::new(); chunks.len()];
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|worker| {
+                let chunks = &chunks;
+                scope.spawn(move || {
+                    chunks.iter()
+                        .enumerate()
+                        .skip(worker)
+                        .step_by(workers)
+                        .map(|(i, chunk)| (i, chunk.iter().map(|v| similarity(query, v)).collect::<Vec<f32>>()))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        // Join every worker before reporting a panic; the scope re-panics on unjoined ones
+        let mut panicked = false;
+        for handle in handles {
+            match handle.join() {
+                Ok(scored) => {
+                    for (i, scores) in scored {
+                        chunk_scores[i] = scores;
+                    }
+                }
+                Err(_) => panicked = true,
+            }
+        }
+        if panicked {
+            return Err(IndexerError::Store("Similarity scoring worker panicked".to_string()));
+        }
+        Ok(())
+    })?;
+    Ok(chunk_scores.into_iter().flatten().collect())
+}
+
+/// Candidate index and score, ordered so that a higher score ranks higher and equal
+/// scores rank by candidate order
+#[derive(Debug, Clone, Copy)]
+struct Ranked {
+    index: usize,
+    score: f32,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score.total_cmp(&other.score).then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+/// The `k` best `(index, score)` pairs, highest score first, ties in candidate order
+///
+/// Keeps a bounded min-heap of `k` entries instead of sorting every score, so ranking
+/// a large store costs O(n log k). The result equals a stable descending sort
+/// truncated to `k`.
+fn top_k_by_score(scores: impl IntoIterator<Item = (usize, f32)>, k: usize) -> Vec<(usize, f32)> {
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut heap: BinaryHeap<Reverse<Ranked>> = BinaryHeap::new();
+    for (index, score) in scores {
+        let ranked = Ranked { index, score };
+        if heap.len() < k {
+            heap.push(Reverse(ranked));
+        } else if heap.peek().is_some_and(|Reverse(worst)| ranked > *worst) {
+            heap.pop();
+            heap.push(Reverse(ranked));
+        }
+    }
+    // Ascending by `Reverse` is best first
+    heap.into_sorted_vec().into_iter().map(|Reverse(r)| (r.index, r.score)).collect()
+}
+
+/// Re-rank scored candidates with Maximal Marginal Relevance
+///
+/// Greedily picks `count` candidates, each time taking the one maximizing
+/// `lambda * relevance - (1 - lambda) * max similarity to an already picked result`.
+/// Candidates must be sorted by relevance; the picks are returned in selection order.
+fn mmr_rerank<T>(candidates: Vec<(T, f32, Vec<f32>)>, lambda: f32, count: usize) -> Vec<(T, f32, Vec<f32>)> {
+    let lambda = lambda.clamp(0.0, 1.0);
+    let mut remaining: Vec<Option<(T, f32, Vec<f32>)>> = candidates.into_iter().map(Some).collect();
+    // Highest similarity of each remaining candidate to any selected result
+    let mut redundancy = vec![0.0f32; remaining.len()];
+    let mut selected = Vec::new();
+    while selected.len() < count {
+        let best = remaining.iter()
+            .enumerate()
+            .filter_map(|(i, candidate)| candidate.as_ref().map(|(_, score, _)| (i, lambda * score - (1.0 - lambda) * redundancy[i])))
+            .fold(None, |best: Option<(usize, f32)>, (i, mmr)| match best {
+                Some((_, best_mmr)) if best_mmr >= mmr => best,
+                _ => Some((i, mmr)),
+            });
+        let Some((picked, _)) = best else {
+            break;
+        };
+        let (item, score, vector) = remaining[picked].take().unwrap();
+        for (i, candidate) in remaining.iter().enumerate() {
+            if let Some((_, _, other)) = candidate {
+                redundancy[i] = redundancy[i].max(cosine_similarity(&vector, other));
+            }
+        }
+        selected.push((item, score, vector));
+    }
+    selected
+}
+
+/// Keep only the requested metadata keys, always retaining `id`
+pub(crate) fn project_metadata(metadata: HashMap<String, String>, fields: &[String]) -> HashMap<String, String> {
+    metadata
+        .into_iter()
+        .filter(|(key, _)| key == "id" || fields.contains(key))
+        .collect()
+}
+
 /// Search for similar vectors with filtering options
 ///
+/// This is the single place results are ranked and limited. Candidates come from the
+/// store untruncated (`filtered_entity_ids`/`get_all_entity_ids`), so filters and
+/// `min_score` never run short of data, and at most `options.top_k` results are
+/// returned on every path, including backend `native_search`.
+///
+/// Vectors and metadata are fetched `FETCH_BATCH_SIZE` entities at a time with
+/// `get_vectors_batch`, one round trip per batch on Redis; they are then scored on
+/// `search_threads` workers and only the best `offset + top_k` are kept, with a
+/// bounded heap rather than a sort of every score.
+///
 /// # Arguments
 /// * `store` - The vector store to search in
 /// * `query` - The query vector to search for
 /// * `options` - Search options including filters and limits
 ///
 /// # Returns
-/// * `Result<Vec<SearchResult>, String>` - Search results or error message
-pub fn search_vectors(
-    store: &impl VectorStore,
+/// * `Result<Vec<SearchResult>, IndexerError>` - Search results or error
+pub fn search_vectors<V: VectorStore + ?Sized>(
+    store: &V,
     query: &[f32],
     options: &SearchOptions,
-) -> Result<Vec<SearchResult>, String> {
+) -> Result<Vec<SearchResult>, IndexerError> {
     log::info!(
-        "Performing vector search with top_k={}, min_score={:?}, entity_types={:?}, file_filter={:?}",
+        "Performing vector search with top_k={}, offset={}, min_score={:?}, entity_types={:?}, exclude_entity_types={:?}, file_filter={:?}",
         options.top_k,
+        options.offset,
         options.min_score,
         options.entity_types,
+        options.exclude_entity_types,
         options.file_filter
     );
 
-    // Get all entity IDs from the store
-    let entity_ids = store.get_all_entity_ids()?;
+    // Backends that filter server-side answer the whole query themselves
+    if let Some(results) = store.native_search(query, options) {
+        return results.map(|mut results| {
+            results.truncate(options.top_k);
+            results
+        });
+    }
+
+    // Compile the file filter once up front
+    let file_matcher = match options.file_filter {
+        Some(ref pattern) => Some(file_glob_matcher(pattern)?),
+        None => None,
+    };
+
+    // Let the store narrow the candidates when filtering, otherwise take every entity
+    let entity_ids = if options.entity_types.is_some() || options.file_filter.is_some() {
+        store.filtered_entity_ids(options.entity_types.as_deref(), options.file_filter.as_deref())?
+    } else {
+        store.get_all_entity_ids()?
+    };
     
-    // Collect entity vectors and metadata
-    let mut results = Vec::new();
-    for entity_id in entity_ids {
-        // Get entity vector
-        let entity_vector = match store.get_entity_vector(&entity_id) {
-            Ok(vector) => vector,
+    // Fetch the candidates in batches, keeping the vectors and metadata that pass the filters
+    let mut fetched = Vec::with_capacity(entity_ids.len());
+    for batch in entity_ids.chunks(FETCH_BATCH_SIZE) {
+        fetched.extend(batch.iter().cloned().zip(store.get_vectors_batch(batch)?));
+    }
+    let mut candidates = Vec::new();
+    let mut vectors = Vec::new();
+    for (entity_id, entity) in fetched {
+        let (entity_vector, metadata) = match entity {
+            Ok(entity) => entity,
             Err(e) => {
                 log::warn!("Failed to get vector for entity {}: {}", entity_id, e);
                 continue;
             }
         };
         
-        // Get entity metadata
-        let metadata = match store.get_entity_metadata(&entity_id) {
-            Ok(meta) => meta,
-            Err(e) => {
-                log::warn!("Failed to get metadata for entity {}: {}", entity_id, e);
-                continue;
-            }
-        };
-        
         // Apply entity type filter if specified
         if let Some(ref entity_types) = options.entity_types {
             if let Some(entity_type) = metadata.get("type") {
-                if !entity_types.contains(&entity_type.to_string()) {
+                if !type_matches(entity_types, entity_type) {
                     continue;
                 }
             } else {
@@ -113,10 +434,19 @@ pub fn search_vectors(
             }
         }
         
+        // Apply entity type exclusion if specified
+        if let Some(ref exclude_entity_types) = options.exclude_entity_types {
+            if let Some(entity_type) = metadata.get("type") {
+                if type_matches(exclude_entity_types, entity_type) {
+                    continue;
+                }
+            }
+        }
+        
         // Apply file filter if specified
-        if let Some(ref file_filter) = options.file_filter {
+        if let Some(ref file_matcher) = file_matcher {
             if let Some(file) = metadata.get("file") {
-                if file != file_filter {
+                if !file_matcher.is_match(file) {
                     continue;
                 }
             } else {
@@ -124,34 +454,247 @@ pub fn search_vectors(
             }
         }
         
-        // Calculate similarity score
-        let score = cosine_similarity(query, &entity_vector);
+        candidates.push((entity_id, metadata));
+        vectors.push(entity_vector);
+    }
+    
+    // Unit-length candidates only need a dot product against the normalized query
+    let normalized = !candidates.is_empty()
+        && candidates.iter().all(|(_, metadata)| metadata.get("normalized").is_some_and(|n| n == "true"));
+    let (query, similarity): (Vec<f32>, Similarity) = if normalized {
+        (crate::embedder::l2_normalize(query), unit_similarity)
+    } else {
+        (query.to_vec(), cosine_similarity)
+    };
+    
+    // Calculate similarity scores across parallel chunks
+    let scores = score_candidates(&query, &vectors, options.search_threads, options.chunk_size, similarity)?;
+    
+    // Keep the best candidates above the minimum score; MMR picks from a wider pool
+    let needed = options.offset.saturating_add(options.top_k);
+    let keep = match options.mmr {
+        Some(_) => needed.saturating_mul(MMR_CANDIDATE_FACTOR),
+        None => needed,
+    };
+    let eligible = scores.into_iter()
+        .enumerate()
+        .filter(|(_, score)| options.min_score.is_none_or(|min_score| *score >= min_score));
+    let mut candidates: Vec<Option<_>> = candidates.into_iter().zip(vectors).map(Some).collect();
+    let mut scored = Vec::new();
+    for (index, score) in top_k_by_score(eligible, keep) {
+        let Some(((entity_id, metadata), vector)) = candidates[index].take() else { continue };
         
-        // Apply minimum score filter if specified
-        if let Some(min_score) = options.min_score {
-            if score < min_score {
-                continue;
-            }
-        }
+        // Project metadata down to the requested fields
+        let metadata = match options.fields {
+            Some(ref fields) => project_metadata(metadata, fields),
+            None => metadata,
+        };
         
-        // Add to results
-        results.push(SearchResult {
+        scored.push(((entity_id, metadata), score, vector));
+    }
+    
+    // Diversify the leading candidates, keeping their vectors for inter-result similarity
+    if let Some(lambda) = options.mmr {
+        scored = mmr_rerank(scored, lambda, needed);
+    }
+    
+    // Build results, reusing the already-fetched vector if requested
+    let results: Vec<SearchResult> = scored
+        .into_iter()
+        .map(|((entity_id, metadata), score, vector)| SearchResult {
             entity_id,
             score,
             metadata,
-        });
-    }
-    
-    // Sort results by score in descending order
-    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+            rank: 0,
+            vector: if options.include_vectors { Some(vector) } else { None },
+        })
+        .collect();
     
-    // Limit to top_k results
-    let results: Vec<SearchResult> = results.into_iter().take(options.top_k).collect();
+    // Skip to the requested page and limit to top_k results, recording absolute ranks
+    let results: Vec<SearchResult> = results
+        .into_iter()
+        .enumerate()
+        .skip(options.offset)
+        .take(options.top_k)
+        .map(|(i, mut result)| {
+            result.rank = i + 1;
+            result
+        })
+        .collect();
     
     log::info!("Vector search returned {} results", results.len());
     Ok(results)
 }
 
+/// Name part of an entity ID such as `fn:app.py:handler`
+fn entity_name(entity_id: &str) -> &str {
+    entity_id.rsplit(':').next().unwrap_or(entity_id)
+}
+
+/// Keyword score of an entity: 1.0 for an exact name match, 0.5 for a name the
+/// keyword index matched as a substring, 0.0 otherwise
+fn keyword_score(name: &str, keyword: &str, keyword_names: &HashSet<String>) -> f32 {
+    if name.eq_ignore_ascii_case(keyword) {
+        1.0
+    } else if keyword_names.contains(name) {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+/// Rank store entities by a weighted sum of vector similarity and keyword match
+///
+/// Candidates are filtered exactly as in `search_vectors`; the combined score is
+/// `alpha * similarity + (1 - alpha) * keyword_score`, so an entity whose name matches
+/// the keyword can overtake entities that are only semantically close. `min_score`
+/// applies to the combined score.
+///
+/// # Arguments
+/// * `store` - The vector store to search in
+/// * `query` - The query vector to search for
+/// * `keyword` - Identifier or name fragment from the user's query
+/// * `keyword_names` - Entity names matched by the keyword index
+/// * `options` - Search options including filters, limits, and `alpha`
+///
+/// # Returns
+/// * `Result<Vec<SearchResult>, IndexerError>` - Search results or error
+pub fn hybrid_rank<V: VectorStore + ?Sized>(
+    store: &V,
+    query: &[f32],
+    keyword: &str,
+    keyword_names: &HashSet<String>,
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>, IndexerError> {
+    let alpha = options.alpha.unwrap_or(DEFAULT_HYBRID_ALPHA).clamp(0.0, 1.0);
+    log::info!("Performing hybrid search for {:?} with alpha={} and {} keyword matches", keyword, alpha, keyword_names.len());
+
+    // Score every filtered candidate, then merge and paginate on the combined score
+    let vector_options = SearchOptions {
+        top_k: usize::MAX,
+        min_score: None,
+        offset: 0,
+        mmr: None,
+        ..options.clone()
+    };
+    let mut results = search_vectors(store, query, &vector_options)?;
+    for result in &mut results {
+        let keyword_score = keyword_score(entity_name(&result.entity_id), keyword, keyword_names);
+        result.score = alpha * result.score + (1.0 - alpha) * keyword_score;
+    }
+    let eligible: Vec<(usize, f32)> = results.iter()
+        .map(|result| result.score)
+        .enumerate()
+        .filter(|(_, score)| options.min_score.is_none_or(|min_score| *score >= min_score))
+        .collect();
+    let best = top_k_by_score(eligible, options.offset.saturating_add(options.top_k));
+    let mut results: Vec<Option<SearchResult>> = results.into_iter().map(Some).collect();
+
+    Ok(best
+        .into_iter()
+        .filter_map(|(index, _)| results[index].take())
+        .enumerate()
+        .skip(options.offset)
+        .take(options.top_k)
+        .map(|(i, mut result)| {
+            result.rank = i + 1;
+            result
+        })
+        .collect())
+}
+
+/// Hybrid keyword and vector search
+///
+/// Looks `keyword` up in the project's Redis `search_index` sets, then ranks the
+/// store's entities with `hybrid_rank`. Useful when the query contains an exact
+/// identifier that embeddings alone rank poorly.
+///
+/// # Arguments
+/// * `redis` - Connected Redis client holding the project's search indexes
+/// * `key_prefix` - Project key prefix (e.g. `code_index:<name>`)
+/// * `store` - The vector store to search in
+/// * `query` - The query vector to search for
+/// * `keyword` - Identifier or name fragment from the user's query
+/// * `options` - Search options including filters, limits, and `alpha`
+///
+/// # Returns
+/// * `Result<Vec<SearchResult>, IndexerError>` - Search results or error
+pub async fn hybrid_search<V: VectorStore + ?Sized>(
+    redis: &Client,
+    key_prefix: &str,
+    store: &V,
+    query: &[f32],
+    keyword: &str,
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>, IndexerError> {
+    let keyword_names: HashSet<String> = keyword_matches(redis, key_prefix, keyword)
+        .await?
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect();
+    hybrid_rank(store, query, keyword, &keyword_names, options)
+}
+
+/// Search several projects' stores with one query, merging the results by score
+///
+/// Each store is searched with `options` for its best `offset + top_k` results, which
+/// are tagged with a `project` metadata entry naming the store; the merged list is then
+/// ranked across projects and paged with `offset` and `top_k` like a single search.
+/// Used by `vector-recall --all` and `--name a,b`.
+///
+/// # Arguments
+/// * `stores` - Project name and vector store of every project to search
+/// * `query` - The query vector to search for
+/// * `options` - Search options, applied to every store
+///
+/// # Returns
+/// * `Result<Vec<SearchResult>, IndexerError>` - Merged search results or the first
+///   store's error
+pub fn search_projects<V: VectorStore + ?Sized>(
+    stores: &[(&str, &V)],
+    query: &[f32],
+    options: &SearchOptions,
+) -> Result<Vec<SearchResult>, IndexerError> {
+    let per_store = SearchOptions { top_k: options.offset.saturating_add(options.top_k), offset: 0, ..options.clone() };
+    let mut merged = Vec::new();
+    for (project, store) in stores {
+        for mut result in search_vectors(*store, query, &per_store)? {
+            result.metadata.insert("project".to_string(), project.to_string());
+            merged.push(result);
+        }
+    }
+    // Stable, so equal scores keep the order the projects were given in
+    merged.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    Ok(merged
+        .into_iter()
+        .enumerate()
+        .skip(options.offset)
+        .take(options.top_k)
+        .map(|(i, mut result)| {
+            result.rank = i + 1;
+            result
+        })
+        .collect())
+}
+
+/// Bucket result scores into `buckets` equal-width ranges over 0.0 to 1.0
+///
+/// Used by `vector-recall --histogram` to show where a `--min-score` cutoff would
+/// fall. Scores outside the range (e.g. negative cosine similarity) are counted in
+/// the nearest end bucket, so the counts always sum to `results.len()`.
+pub fn score_histogram(results: &[SearchResult], buckets: usize) -> Vec<ScoreBucket> {
+    let buckets = buckets.max(1);
+    let width = 1.0 / buckets as f32;
+    let mut histogram: Vec<ScoreBucket> = (0..buckets)
+        .map(|i| ScoreBucket { min: i as f32 * width, max: (i + 1) as f32 * width, count: 0 })
+        .collect();
+    for result in results {
+        let index = ((result.score.clamp(0.0, 1.0) / width) as usize).min(buckets - 1);
+        histogram[index].count += 1;
+    }
+    histogram
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -176,4 +719,460 @@ mod tests {
         let similarity = cosine_similarity(&a, &b);
         assert!((similarity - 0.0).abs() < 1e-6, "Expected similarity close to 0.0, got {}", similarity);
     }
+    
+    #[test]
+    fn test_cosine_similarity_matches_scalar() {
+        // Embedding-sized vectors, plus a length that leaves a remainder after the lanes
+        for dim in [1536, 1531, 5] {
+            let a: Vec<f32> = (0..dim).map(|i| ((i * 31 + 7) % 97) as f32 / 97.0 - 0.3).collect();
+            let b: Vec<f32> = (0..dim).map(|i| ((i * 17 + 3) % 89) as f32 / 89.0 - 0.2).collect();
+            let (fast, scalar) = (cosine_similarity(&a, &b), cosine_similarity_scalar(&a, &b));
+            assert!((fast - scalar).abs() < 1e-5, "dim {}: {} vs scalar {}", dim, fast, scalar);
+            assert!((cosine_similarity(&a, &a) - 1.0).abs() < 1e-5);
+        }
+    }
+    
+    #[test]
+    fn test_file_glob_matcher() {
+        let matcher = file_glob_matcher("src/*").unwrap();
+        assert!(matcher.is_match("src/foo.py"));
+        assert!(!matcher.is_match("lib/bar.py"));
+        
+        // Recursive globs span directories
+        let matcher = file_glob_matcher("src/**/*.py").unwrap();
+        assert!(matcher.is_match("src/foo.py"));
+        assert!(matcher.is_match("src/pkg/foo.py"));
+        assert!(!matcher.is_match("lib/bar.py"));
+        
+        // Exact paths still work as a degenerate glob
+        let matcher = file_glob_matcher("test.py").unwrap();
+        assert!(matcher.is_match("test.py"));
+        assert!(!matcher.is_match("other_test.py"));
+        
+        // Malformed patterns are reported rather than ignored
+        assert!(file_glob_matcher("src/[").is_err());
+    }
+    
+    /// Store that counts vector fetches, optionally hiding the inner store's index lookup
+    struct CountingStore {
+        inner: crate::vector_store::InMemoryVectorStore,
+        use_index: bool,
+        vector_fetches: std::sync::atomic::AtomicUsize,
+    }
+
+    impl VectorStore for CountingStore {
+        fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+            self.inner.upsert_embedding(entity_id, embedding, file, entity_type)
+        }
+        fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+            self.inner.similarity_search(query, top_k)
+        }
+        fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+            self.inner.get_all_entity_ids()
+        }
+        fn filtered_entity_ids(&self, entity_types: Option<&[String]>, file_filter: Option<&str>) -> Result<Vec<String>, IndexerError> {
+            if self.use_index {
+                self.inner.filtered_entity_ids(entity_types, file_filter)
+            } else {
+                self.inner.get_all_entity_ids()
+            }
+        }
+        fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+            self.vector_fetches.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.get_entity_vector(entity_id)
+        }
+        fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
+            self.inner.get_entity_metadata(entity_id)
+        }
+    }
+
+    #[test]
+    fn test_filtered_search_fetches_only_candidate_vectors() {
+        let stores: Vec<CountingStore> = [false, true].into_iter()
+            .map(|use_index| CountingStore {
+                inner: crate::vector_store::InMemoryVectorStore::new(),
+                use_index,
+                vector_fetches: std::sync::atomic::AtomicUsize::new(0),
+            })
+            .collect();
+        for store in &stores {
+            store.upsert_embedding("fn:a.py:foo", &[1.0, 0.0], Some("a.py"), Some("function")).unwrap();
+            store.upsert_embedding("fn:b.py:bar", &[0.9, 0.1], Some("b.py"), Some("function")).unwrap();
+            store.upsert_embedding("class:a.py:Baz", &[0.8, 0.2], Some("a.py"), Some("class")).unwrap();
+            store.upsert_embedding("var:a.py:QUX", &[0.7, 0.3], Some("a.py"), Some("variable")).unwrap();
+        }
+        let options = SearchOptions {
+            top_k: 10,
+            min_score: None,
+            entity_types: Some(vec!["Function".to_string()]),
+            exclude_entity_types: None,
+            file_filter: Some("a.py".to_string()),
+            offset: 0,
+            fields: None,
+            search_threads: Some(1),
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+
+        let results: Vec<Vec<String>> = stores.iter()
+            .map(|store| search_vectors(store, &[1.0, 0.0], &options).unwrap().into_iter().map(|r| r.entity_id).collect())
+            .collect();
+        assert_eq!(results[0], vec!["fn:a.py:foo"]);
+        assert_eq!(results[1], results[0]);
+
+        let fetches: Vec<usize> = stores.iter().map(|s| s.vector_fetches.load(std::sync::atomic::Ordering::SeqCst)).collect();
+        assert_eq!(fetches, vec![4, 1]);
+    }
+
+    #[test]
+    fn test_search_fetches_candidates_in_batches() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Store counting batched and single-entity reads
+        #[derive(Default)]
+        struct BatchCountingStore {
+            inner: crate::vector_store::InMemoryVectorStore,
+            batches: AtomicUsize,
+            single_fetches: AtomicUsize,
+        }
+
+        impl VectorStore for BatchCountingStore {
+            fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+                self.inner.upsert_embedding(entity_id, embedding, file, entity_type)
+            }
+            fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+                self.inner.similarity_search(query, top_k)
+            }
+            fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+                self.inner.get_all_entity_ids()
+            }
+            fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+                self.single_fetches.fetch_add(1, Ordering::SeqCst);
+                self.inner.get_entity_vector(entity_id)
+            }
+            fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
+                self.single_fetches.fetch_add(1, Ordering::SeqCst);
+                self.inner.get_entity_metadata(entity_id)
+            }
+            fn get_vectors_batch(&self, entity_ids: &[String]) -> Result<Vec<Result<crate::vector_store::EntityVector, IndexerError>>, IndexerError> {
+                self.batches.fetch_add(1, Ordering::SeqCst);
+                self.inner.get_vectors_batch(entity_ids)
+            }
+        }
+
+        let store = BatchCountingStore::default();
+        for i in 0..(FETCH_BATCH_SIZE * 2 + 1) {
+            store.upsert_embedding(&format!("fn:a.py:f{}", i), &[1.0, i as f32], Some("a.py"), Some("function")).unwrap();
+        }
+        let options = SearchOptions {
+            top_k: 3,
+            min_score: None,
+            entity_types: None,
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: Some(1),
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+
+        let results = search_vectors(&store, &[1.0, 0.0], &options).unwrap();
+        assert_eq!(results[0].entity_id, "fn:a.py:f0");
+        assert_eq!(store.batches.load(Ordering::SeqCst), 3);
+        assert_eq!(store.single_fetches.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_search_returns_exactly_top_k() {
+        use crate::vector_store::InMemoryVectorStore;
+
+        /// Backend whose native search ignores the requested limit
+        struct OverfullStore(InMemoryVectorStore);
+
+        impl VectorStore for OverfullStore {
+            fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+                self.0.upsert_embedding(entity_id, embedding, file, entity_type)
+            }
+            fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+                self.0.similarity_search(query, top_k)
+            }
+            fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+                self.0.get_all_entity_ids()
+            }
+            fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+                self.0.get_entity_vector(entity_id)
+            }
+            fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
+                self.0.get_entity_metadata(entity_id)
+            }
+            fn native_search(&self, query: &[f32], options: &SearchOptions) -> Option<Result<Vec<SearchResult>, IndexerError>> {
+                let all = SearchOptions { top_k: usize::MAX, ..options.clone() };
+                Some(search_vectors(&self.0, query, &all))
+            }
+        }
+
+        let store = OverfullStore(InMemoryVectorStore::new());
+        for i in 0..6 {
+            let embedding = [1.0, i as f32 * 0.1];
+            store.upsert_embedding(&format!("fn:a.py:f{}", i), &embedding, Some("a.py"), Some("function")).unwrap();
+        }
+        let options = SearchOptions {
+            top_k: 3,
+            min_score: Some(0.5),
+            entity_types: Some(vec!["function".to_string()]),
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: Some(1),
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+
+        // Client-side scan over all six matches
+        let results = search_vectors(&store.0, &[1.0, 0.0], &options).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].entity_id, "fn:a.py:f0");
+
+        // Native search that returns every match is still capped
+        let results = search_vectors(&store, &[1.0, 0.0], &options).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[2].rank, 3);
+    }
+
+    #[test]
+    fn test_score_histogram_counts_every_entity() {
+        use crate::vector_store::InMemoryVectorStore;
+
+        let store = InMemoryVectorStore::new();
+        for i in 0..12 {
+            // Spread from identical to opposite the query, including negative scores
+            let angle = i as f32 * 0.3;
+            store.upsert_embedding(&format!("fn:a.py:f{}", i), &[angle.cos(), angle.sin()], Some("a.py"), Some("function")).unwrap();
+        }
+        let options = SearchOptions {
+            top_k: store.get_all_entity_ids().unwrap().len(),
+            min_score: None,
+            entity_types: None,
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: Some(1),
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+        let results = search_vectors(&store, &[1.0, 0.0], &options).unwrap();
+        let histogram = score_histogram(&results, DEFAULT_HISTOGRAM_BUCKETS);
+
+        assert_eq!(histogram.len(), DEFAULT_HISTOGRAM_BUCKETS);
+        assert_eq!(histogram.iter().map(|b| b.count).sum::<usize>(), 12);
+        assert_eq!(histogram.last().unwrap().count, results.iter().filter(|r| r.score >= 0.9).count());
+    }
+
+    #[test]
+    fn test_top_k_by_score_matches_sort_and_truncate() {
+        // Repeating scores exercise the candidate-order tie-break
+        let scores: Vec<f32> = (0..1000).map(|i| ((i * 37) % 101) as f32 / 100.0 - 0.2).collect();
+        let mut sorted: Vec<(usize, f32)> = scores.iter().copied().enumerate().collect();
+        sorted.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        for k in [0, 1, 10, 101, 999, 1000, 5000] {
+            let expected: Vec<(usize, f32)> = sorted.iter().copied().take(k).collect();
+            assert_eq!(top_k_by_score(scores.iter().copied().enumerate(), k), expected, "k = {}", k);
+        }
+    }
+
+    #[test]
+    fn test_parallel_top_k_matches_sequential_ranking() {
+        use crate::vector_store::InMemoryVectorStore;
+
+        let store = InMemoryVectorStore::new();
+        let mut vectors = HashMap::new();
+        for i in 0..5000 {
+            // Coarse values so many entities tie on score
+            let vector = vec![((i * 7) % 13) as f32, ((i * 11) % 5) as f32 + 1.0, (i % 3) as f32];
+            let entity_id = format!("fn:m{}.py:f{}", i % 50, i);
+            store.upsert_embedding(&entity_id, &vector, Some("m.py"), Some("function")).unwrap();
+            vectors.insert(entity_id, vector);
+        }
+        let query = [3.0, 1.0, 0.5];
+
+        // Sequential reference: score everything in store order and stably sort
+        let mut expected: Vec<(String, f32)> = store.get_all_entity_ids().unwrap().into_iter()
+            .map(|id| {
+                let score = cosine_similarity_scalar(&query, &vectors[&id]);
+                (id, score)
+            })
+            .collect();
+        expected.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        for (offset, top_k) in [(0, 10), (7, 25), (4990, 50)] {
+            let options = SearchOptions {
+                top_k,
+                min_score: None,
+                entity_types: None,
+                exclude_entity_types: None,
+                file_filter: None,
+                offset,
+                fields: None,
+                search_threads: Some(4),
+                chunk_size: Some(97),
+                include_vectors: false,
+                alpha: None,
+                mmr: None,
+            };
+            let results = search_vectors(&store, &query, &options).unwrap();
+            let expected_page: Vec<&(String, f32)> = expected.iter().skip(offset).take(top_k).collect();
+            assert_eq!(results.len(), expected_page.len());
+            for (result, (id, score)) in results.iter().zip(expected_page) {
+                assert_eq!(&result.entity_id, id, "offset {} top_k {}", offset, top_k);
+                assert!((result.score - score).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_score_candidates_fails_when_a_worker_panics() {
+        fn panics_on_negative(query: &[f32], vector: &[f32]) -> f32 {
+            assert!(vector[0] >= 0.0, "unscorable vector");
+            cosine_similarity(query, vector)
+        }
+
+        let vectors: Vec<Vec<f32>> = (0..8).map(|i| vec![if i == 5 { -1.0 } else { 1.0 }, i as f32]).collect();
+        let result = score_candidates(&[1.0, 0.0], &vectors, Some(4), Some(2), panics_on_negative);
+        assert!(matches!(result, Err(IndexerError::Store(_))), "got {:?}", result);
+
+        let vectors: Vec<Vec<f32>> = (0..8).map(|i| vec![1.0, i as f32]).collect();
+        let scores = score_candidates(&[1.0, 0.0], &vectors, Some(4), Some(2), panics_on_negative).unwrap();
+        assert_eq!(scores.len(), vectors.len());
+    }
+
+    #[test]
+    fn test_mmr_demotes_near_duplicate() {
+        use crate::vector_store::InMemoryVectorStore;
+
+        let store = InMemoryVectorStore::new();
+        store.upsert_embedding("fn:a.py:load", &[1.0, 0.05, 0.0], Some("a.py"), Some("function")).unwrap();
+        store.upsert_embedding("fn:b.py:load_copy", &[1.0, 0.06, 0.0], Some("b.py"), Some("function")).unwrap();
+        store.upsert_embedding("fn:c.py:save", &[0.8, 0.0, 0.6], Some("c.py"), Some("function")).unwrap();
+        let options = SearchOptions {
+            top_k: 2,
+            min_score: None,
+            entity_types: None,
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: Some(1),
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+        let query = [1.0, 0.0, 0.0];
+
+        // By cosine alone the two near-identical entities take both slots
+        let ids: Vec<String> = search_vectors(&store, &query, &options).unwrap().into_iter().map(|r| r.entity_id).collect();
+        assert_eq!(ids, vec!["fn:a.py:load", "fn:b.py:load_copy"]);
+
+        let options = SearchOptions { mmr: Some(0.3), ..options };
+        let results = search_vectors(&store, &query, &options).unwrap();
+        let ids: Vec<&str> = results.iter().map(|r| r.entity_id.as_str()).collect();
+        assert_eq!(ids, vec!["fn:a.py:load", "fn:c.py:save"]);
+        assert_eq!(results[1].rank, 2);
+        assert!(results.iter().all(|r| r.vector.is_none()));
+    }
+
+    #[test]
+    fn test_hybrid_rank_keyword_boosts_mid_ranked_entity() {
+        use crate::vector_store::InMemoryVectorStore;
+        
+        let store = InMemoryVectorStore::new();
+        store.upsert_embedding("fn:app.py:render_page", &[1.0, 0.0, 0.0], Some("app.py"), Some("function")).unwrap();
+        store.upsert_embedding("fn:app.py:render_header", &[0.9, 0.3, 0.0], Some("app.py"), Some("function")).unwrap();
+        store.upsert_embedding("fn:db.py:parse_config", &[0.6, 0.8, 0.0], Some("db.py"), Some("function")).unwrap();
+        store.upsert_embedding("class:db.py:Session", &[0.0, 0.0, 1.0], Some("db.py"), Some("class")).unwrap();
+        let options = SearchOptions {
+            top_k: 4,
+            min_score: None,
+            entity_types: None,
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: Some(1),
+            chunk_size: None,
+            include_vectors: false,
+            alpha: Some(0.6),
+            mmr: None,
+        };
+        let query = [1.0, 0.0, 0.0];
+        
+        // Pure vector search ranks parse_config third
+        let vector_only = search_vectors(&store, &query, &options).unwrap();
+        assert_eq!(vector_only[2].entity_id, "fn:db.py:parse_config");
+        
+        let keyword_names: HashSet<String> = ["parse_config".to_string()].into_iter().collect();
+        let results = hybrid_rank(&store, &query, "parse_config", &keyword_names, &options).unwrap();
+        assert_eq!(results[0].entity_id, "fn:db.py:parse_config", "Keyword match should rank first: {:?}", results);
+        assert_eq!(results[0].rank, 1);
+        assert_eq!(results[1].entity_id, "fn:app.py:render_page");
+        
+        // Alpha 1.0 ignores the keyword entirely
+        let options = SearchOptions { alpha: Some(1.0), ..options };
+        let results = hybrid_rank(&store, &query, "parse_config", &keyword_names, &options).unwrap();
+        assert_eq!(results[0].entity_id, "fn:app.py:render_page");
+    }
+
+    #[test]
+    fn test_search_projects_merges_stores_by_score() {
+        use crate::vector_store::InMemoryVectorStore;
+
+        let api = InMemoryVectorStore::new();
+        api.upsert_embedding("fn:routes.py:login", &[1.0, 0.0], Some("routes.py"), Some("function")).unwrap();
+        api.upsert_embedding("fn:routes.py:health", &[0.0, 1.0], Some("routes.py"), Some("function")).unwrap();
+        let web = InMemoryVectorStore::new();
+        web.upsert_embedding("fn:auth.py:sign_in", &[0.9, 0.1], Some("auth.py"), Some("function")).unwrap();
+        web.upsert_embedding("fn:auth.py:logout", &[0.5, 0.5], Some("auth.py"), Some("function")).unwrap();
+        let options = SearchOptions {
+            top_k: 3,
+            min_score: None,
+            entity_types: None,
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: Some(1),
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+
+        let stores = [("api", &api), ("web", &web)];
+        let results = search_projects(&stores, &[1.0, 0.0], &options).unwrap();
+        let ranked: Vec<(&str, &str, usize)> = results.iter()
+            .map(|r| (r.entity_id.as_str(), r.metadata["project"].as_str(), r.rank))
+            .collect();
+        assert_eq!(ranked, vec![
+            ("fn:routes.py:login", "api", 1),
+            ("fn:auth.py:sign_in", "web", 2),
+            ("fn:auth.py:logout", "web", 3),
+        ]);
+
+        // Paging applies to the merged ranking, not to each project
+        let page = search_projects(&stores, &[1.0, 0.0], &SearchOptions { top_k: 2, offset: 1, ..options }).unwrap();
+        let ids: Vec<(&str, usize)> = page.iter().map(|r| (r.entity_id.as_str(), r.rank)).collect();
+        assert_eq!(ids, vec![("fn:auth.py:sign_in", 2), ("fn:auth.py:logout", 3)]);
+    }
 }
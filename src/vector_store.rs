@@ -6,37 +6,534 @@
 #[cfg(test)]
 mod tests {
     use super::*;
+    fn store_runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_multi_thread().worker_threads(1).enable_all().build().unwrap()
+    }
+
+    #[test]
+    fn test_block_on_outside_a_runtime() {
+        let runtime = store_runtime();
+        assert_eq!(block_on(&runtime, async { 7 }), 7);
+        runtime.shutdown_background();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_on_inside_a_multi_thread_runtime() {
+        let runtime = store_runtime();
+        let caller = std::thread::current().id();
+        // The future runs on the calling worker, handed off with `block_in_place`
+        let polled_on = block_on(&runtime, async { std::thread::current().id() });
+        assert_eq!(polled_on, caller);
+        runtime.shutdown_background();
+    }
+
+    #[tokio::test]
+    async fn test_block_on_inside_a_current_thread_runtime() {
+        let runtime = store_runtime();
+        let sleep = async { tokio::time::sleep(std::time::Duration::from_millis(1)).await; 7 };
+        assert_eq!(block_on(&runtime, sleep), 7);
+        runtime.shutdown_background();
+    }
+
     #[test]
     fn test_redis_vector_store_getters() {
-        let store = RedisVectorStore::new("redis://localhost", "prefix");
+        let store = RedisVectorStore::unverified("redis://localhost", "prefix", 3);
         assert_eq!(store.redis_url(), "redis://localhost");
         assert_eq!(store.key_prefix(), "prefix");
+        assert_eq!(store.dimension(), 3);
+    }
+
+    #[test]
+    fn test_redis_vector_store_rejects_dimension_mismatch() {
+        let store = RedisVectorStore::unverified("redis://localhost", "prefix", 1536);
+        let err = VectorStore::upsert_embedding(&store, "func1", &[1.0, 2.0, 3.0], Some("a.py"), Some("function")).unwrap_err();
+        assert!(matches!(err, IndexerError::Embed(_)));
+        assert!(err.to_string().contains("dimension 3 but the store expects 1536"), "{}", err);
+    }
+
+    #[test]
+    fn test_redis_vector_store_rejects_empty_embedding() {
+        let store = RedisVectorStore::unverified("redis://localhost", "prefix", 3);
+        let err = VectorStore::upsert_embedding(&store, "func1", &[], Some("a.py"), Some("function")).unwrap_err();
+        assert!(matches!(err, IndexerError::Embed(_)));
+    }
+
+    #[test]
+    fn test_redis_vector_store_new_rejects_bad_url() {
+        assert!(RedisVectorStore::new("not a url", "prefix", 3).is_err());
+        assert!(RedisVectorStore::new("redis://invalid-host.invalid:6379/", "prefix", 3).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redis_vector_store_connect_failure_inside_runtime_returns_error() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("redis://127.0.0.1:{}/", port);
+        let timeouts = RedisTimeouts { connect: std::time::Duration::from_secs(1), ..RedisTimeouts::default() };
+        assert!(matches!(RedisVectorStore::with_timeouts(&url, "prefix", 3, 1, timeouts), Err(IndexerError::Redis(_))));
+    }
+
+    #[test]
+    fn test_in_memory_vector_store_roundtrip() {
+        let store = InMemoryVectorStore::new();
+        assert!(store.is_empty());
+        store.upsert_embedding("func1", &[1.0, 0.0, 0.0], Some("a.py"), Some("function")).unwrap();
+        store.upsert_embedding("class1", &[0.0, 1.0, 0.0], Some("b.py"), Some("class")).unwrap();
+        store.upsert_embedding("var1", &[0.9, 0.1, 0.0], None, None).unwrap();
+        assert_eq!(store.len(), 3);
+
+        assert_eq!(store.get_entity_vector("class1").unwrap(), vec![0.0, 1.0, 0.0]);
+        assert!(store.get_entity_vector("missing").is_err());
+
+        let metadata = store.get_entity_metadata("func1").unwrap();
+        assert_eq!(metadata.get("type").map(String::as_str), Some("function"));
+        assert_eq!(metadata.get("file").map(String::as_str), Some("a.py"));
+        let metadata = store.get_entity_metadata("var1").unwrap();
+        assert_eq!(metadata.get("type").map(String::as_str), Some("unknown"));
+
+        let mut ids = store.get_all_entity_ids().unwrap();
+        ids.sort();
+        assert_eq!(ids, vec!["class1", "func1", "var1"]);
+    }
+
+    #[test]
+    fn test_in_memory_vector_store_similarity_search() {
+        let store = InMemoryVectorStore::new();
+        store.upsert_embedding("func1", &[1.0, 0.0, 0.0], None, Some("function")).unwrap();
+        store.upsert_embedding("class1", &[0.0, 1.0, 0.0], None, Some("class")).unwrap();
+        store.upsert_embedding("var1", &[0.9, 0.1, 0.0], None, Some("variable")).unwrap();
+
+        let results = store.similarity_search(&[1.0, 0.0, 0.0], 2);
+        assert_eq!(results, vec!["func1", "var1"]);
+        assert!(store.upsert_embedding("empty", &[], None, None).is_err());
+    }
+
+    #[test]
+    fn test_in_memory_vector_store_count() {
+        let store = InMemoryVectorStore::new();
+        assert_eq!(store.count().unwrap(), 0);
+        store.upsert_embedding("func1", &[1.0, 0.0, 0.0], None, Some("function")).unwrap();
+        store.upsert_embedding("class1", &[0.0, 1.0, 0.0], None, Some("class")).unwrap();
+        assert_eq!(store.count().unwrap(), 2);
+        // Re-upserting an entity replaces it rather than adding a new one
+        store.upsert_embedding("func1", &[0.5, 0.5, 0.0], None, Some("function")).unwrap();
+        assert_eq!(store.count().unwrap(), 2);
+        store.delete_embedding("class1").unwrap();
+        assert_eq!(store.count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_in_memory_vector_store_delete_embedding() {
+        let store = InMemoryVectorStore::new();
+        store.upsert_embedding("func1", &[1.0, 0.0, 0.0], None, Some("function")).unwrap();
+        assert!(store.delete_embedding("func1").unwrap());
+        assert!(!store.delete_embedding("func1").unwrap());
+        assert!(store.is_empty());
+        assert!(store.get_entity_vector("func1").is_err());
+    }
+
+    #[test]
+    fn test_vector_encodings_round_trip() {
+        let vector: Vec<f32> = (0..1536).map(|i| (i as f32 * 0.37).sin()).collect();
+        let json = serialize_vector(&vector, VectorEncoding::Json).unwrap();
+        let binary = serialize_vector(&vector, VectorEncoding::Binary).unwrap();
+        assert_eq!(deserialize_vector(&json).unwrap(), vector);
+        assert_eq!(deserialize_vector(&binary).unwrap(), vector);
+        assert_eq!(binary.len(), 1 + 1536 * 4);
+        assert!(binary.len() * 2 < json.len(), "binary {} bytes vs json {} bytes", binary.len(), json.len());
+
+        // Values written before the version byte existed are plain JSON
+        assert_eq!(deserialize_vector(b"[0.5,-1.0]").unwrap(), vec![0.5, -1.0]);
+        assert!(matches!(deserialize_vector(&[BINARY_VECTOR_VERSION, 0, 0]), Err(IndexerError::Parse(_))));
+        assert_eq!("binary".parse::<VectorEncoding>().unwrap(), VectorEncoding::Binary);
+        assert!("gzip".parse::<VectorEncoding>().is_err());
+    }
+
+    #[test]
+    fn test_shared_vector_refs_name_a_text_hash() {
+        let hash = text_hash("function __init__\ndef __init__(self)");
+        assert_eq!(hash.len(), 32);
+        assert_eq!(hash, text_hash("function __init__\ndef __init__(self)"));
+        assert_ne!(hash, text_hash("function __init__\ndef __init__(self, x)"));
+
+        let reference = shared_vector_ref(&hash);
+        assert_eq!(shared_vector_hash(&reference), Some(hash.as_str()));
+        assert_eq!(shared_vector_hash(&serialize_vector(&[1.0], VectorEncoding::Binary).unwrap()), None);
+        assert!(matches!(deserialize_vector(&reference), Err(IndexerError::Parse(_))));
+
+        // Without dedup, a text hash changes nothing about where the vector goes
+        let store = RedisVectorStore::unverified("redis://localhost:6379/0", "code:dedup", 3);
+        let details = EntityDetails { text_hash: Some(hash.clone()), ..EntityDetails::default() };
+        assert_eq!(store.vector_writes("k".to_string(), vec![1], &details), vec![("k".to_string(), vec![1])]);
+        let store = store.with_dedup(true);
+        assert_eq!(store.vector_writes("k".to_string(), vec![1], &details), vec![
+            (format!("code:dedup:shared_vector:{}", hash), vec![1]),
+            ("k".to_string(), reference),
+        ]);
+    }
+
+    #[test]
+    fn test_embedding_meta_rejects_other_dimension() {
+        let meta = EmbeddingMeta {
+            provider: "openai".to_string(),
+            model: "text-embedding-ada-002".to_string(),
+            dimension: 1536,
+            normalized: false,
+        };
+        let err = meta.check_query_embedder("hf", "sentence-transformers/all-MiniLM-L6-v2", 384).unwrap_err();
+        let message = err.to_string();
+        assert!(matches!(err, IndexerError::Config(_)));
+        assert!(message.contains("384") && message.contains("1536"), "unexpected error: {}", message);
+        assert!(message.contains("text-embedding-ada-002"), "unexpected error: {}", message);
+
+        // Same dimension, different model: allowed with a warning
+        assert!(meta.check_query_embedder("openai", "text-embedding-3-small", 1536).is_ok());
+
+        let mock = EmbeddingMeta::for_embedder("mock", &crate::embedder::MockEmbedder::new()).unwrap();
+        assert_eq!((mock.model.as_str(), mock.dimension), ("mock", 3));
     }
 }
+/// Source location and signature of an embedded entity, kept in its metadata so
+/// search results can show them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntityDetails {
+    pub line_start: Option<usize>,
+    pub line_end: Option<usize>,
+    pub signature: Option<String>,
+    /// `text_hash` of the text that was embedded; stores that deduplicate vectors
+    /// keep one vector per hash
+    pub text_hash: Option<String>,
+}
+
+impl EntityDetails {
+    /// Line range and signature of a parsed entity
+    pub fn from_entity(entity: &CodeEntity) -> Self {
+        Self {
+            line_start: Some(entity.line_start),
+            line_end: Some(entity.line_end),
+            signature: entity.signature.clone(),
+            text_hash: None,
+        }
+    }
+
+    /// `line_start`, `line_end`, and `signature` metadata entries for the details that are set
+    pub fn metadata_fields(&self) -> Vec<(&'static str, serde_json::Value)> {
+        let mut fields = Vec::new();
+        if let Some(line_start) = self.line_start {
+            fields.push(("line_start", line_start.into()));
+        }
+        if let Some(line_end) = self.line_end {
+            fields.push(("line_end", line_end.into()));
+        }
+        if let Some(ref signature) = self.signature {
+            fields.push(("signature", signature.as_str().into()));
+        }
+        fields
+    }
+
+    /// Details recorded in flattened entity metadata, as returned by
+    /// `VectorStore::get_entity_metadata`
+    pub fn from_metadata(metadata: &std::collections::HashMap<String, String>) -> Self {
+        Self {
+            line_start: metadata.get("line_start").and_then(|v| v.parse().ok()),
+            line_end: metadata.get("line_end").and_then(|v| v.parse().ok()),
+            signature: metadata.get("signature").cloned(),
+            text_hash: metadata.get("shared_vector").cloned(),
+        }
+    }
+}
+
+/// Content address of an embedding text: 128-bit FNV-1a as 32 hex digits
+///
+/// Stable across runs and platforms, so identical entity texts embedded in
+/// different runs map to the same shared vector.
+pub fn text_hash(text: &str) -> String {
+    let hash = text.bytes().fold(0x6c62_272e_07bb_0142_62b8_2175_6295_c58d_u128, |hash, byte| {
+        (hash ^ byte as u128).wrapping_mul(0x0000_0000_0100_0000_0000_0000_0000_013b)
+    });
+    format!("{:032x}", hash)
+}
+
 /// Trait for vector storage backends.
+/// One embedding for `upsert_batch`: `(entity_id, embedding, file, entity_type, details)`
+pub type UpsertItem = (String, Vec<f32>, Option<String>, Option<String>, EntityDetails);
+
+/// An entity's vector and flattened metadata, as read by `get_vectors_batch`
+pub type EntityVector = (Vec<f32>, std::collections::HashMap<String, String>);
+
 pub trait VectorStore {
     /// Upsert an embedding for an entity with optional file and type metadata.
-    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), String>;
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError>;
+    
+    /// Upsert an embedding like `upsert_embedding`, also recording the entity's line
+    /// range and signature in its metadata.
+    ///
+    /// The default drops `details`; stores that keep metadata should override this.
+    fn upsert_embedding_with_details(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>, _details: &EntityDetails) -> Result<(), IndexerError> {
+        self.upsert_embedding(entity_id, embedding, file, entity_type)
+    }
+    
+    /// Upsert several embeddings at once.
+    ///
+    /// Backends that can send the writes together should override this; the default
+    /// calls `upsert_embedding_with_details` for each item and stops at the first failure.
+    fn upsert_batch(&self, items: &[UpsertItem]) -> Result<(), IndexerError> {
+        for (entity_id, embedding, file, entity_type, details) in items {
+            self.upsert_embedding_with_details(entity_id, embedding, file.as_deref(), entity_type.as_deref(), details)?;
+        }
+        Ok(())
+    }
     
-    /// Return top-k most similar embeddings to a query vector.
+    /// Return up to `top_k` of the most similar embeddings to a query vector.
+    ///
+    /// This is the store's own quick ranking. `search_vectors` does not use it: it
+    /// scores the untruncated candidates from `filtered_entity_ids` and enforces the
+    /// final `top_k` itself.
     fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String>;
     
     /// Get all entity IDs stored in the vector store.
-    fn get_all_entity_ids(&self) -> Result<Vec<String>, String>;
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError>;
+    
+    /// Get candidate entity IDs for a search restricted to `entity_types` and a
+    /// `file_filter` path or glob.
+    ///
+    /// Backends with type and file indexes narrow the candidates server-side. The
+    /// result may over-approximate; `search_vectors` re-checks every candidate's
+    /// metadata. The default returns every entity ID.
+    fn filtered_entity_ids(&self, _entity_types: Option<&[String]>, _file_filter: Option<&str>) -> Result<Vec<String>, IndexerError> {
+        self.get_all_entity_ids()
+    }
     
     /// Get the vector for a specific entity.
-    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, String>;
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError>;
     
     /// Get metadata for a specific entity.
-    fn get_entity_metadata(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, String>;
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, IndexerError>;
+    
+    /// Get the vectors and metadata of several entities, in `entity_ids` order.
+    ///
+    /// An entity that cannot be read gets its own error without failing the others.
+    /// Backends that can read many keys in one round trip should override this; the
+    /// default calls `get_entity_vector` and `get_entity_metadata` for each entity.
+    fn get_vectors_batch(&self, entity_ids: &[String]) -> Result<Vec<Result<EntityVector, IndexerError>>, IndexerError> {
+        Ok(entity_ids.iter()
+            .map(|entity_id| Ok((self.get_entity_vector(entity_id)?, self.get_entity_metadata(entity_id)?)))
+            .collect())
+    }
+    
+    /// Count the entities stored in the vector store.
+    fn count(&self) -> Result<usize, IndexerError> {
+        self.get_all_entity_ids().map(|ids| ids.len())
+    }
+    
+    /// Delete an entity's embedding and metadata, returning whether anything was removed.
+    fn delete_embedding(&self, entity_id: &str) -> Result<bool, IndexerError> {
+        Err(IndexerError::Store(format!("delete_embedding is not supported by this vector store (entity {})", entity_id)))
+    }
+    
+    /// Answer a filtered search on the backend itself, or `None` to let
+    /// `search_vectors` fetch and filter every stored vector client-side.
+    ///
+    /// `search_vectors` truncates the answer to `options.top_k`.
+    fn native_search(
+        &self,
+        _query: &[f32],
+        _options: &crate::vector_search::SearchOptions,
+    ) -> Option<Result<Vec<crate::vector_search::SearchResult>, IndexerError>> {
+        None
+    }
+    
+    /// Replace every stored entity with `items` and record `meta`, returning how
+    /// many entities were replaced.
+    ///
+    /// Backends that can swap in one step should override this so readers never see
+    /// a partial index; the default deletes every entity, then upserts `items`.
+    fn replace_all(&self, items: &[UpsertItem], meta: &EmbeddingMeta) -> Result<usize, IndexerError> {
+        let mut removed = 0;
+        for entity_id in self.get_all_entity_ids()? {
+            if self.delete_embedding(&entity_id)? {
+                removed += 1;
+            }
+        }
+        self.upsert_batch(items)?;
+        self.set_embedding_meta(meta)?;
+        Ok(removed)
+    }
+
+    /// Get the embedding model metadata recorded for the project, if any.
+    fn get_embedding_meta(&self) -> Result<Option<EmbeddingMeta>, IndexerError> {
+        Ok(None)
+    }
+    
+    /// Record the embedding model metadata for the project.
+    fn set_embedding_meta(&self, meta: &EmbeddingMeta) -> Result<(), IndexerError> {
+        Err(IndexerError::Store(format!("set_embedding_meta is not supported by this vector store (model {})", meta.model)))
+    }
+}
+
+/// Embedding model used to produce a project's stored vectors
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EmbeddingMeta {
+    /// Embedding provider (e.g., "openai", "hf")
+    pub provider: String,
+    /// Provider model name (e.g., "text-embedding-3-small")
+    pub model: String,
+    /// Dimension of every stored vector
+    pub dimension: usize,
+    /// Whether every stored vector was L2-normalized (`normalize_embeddings`)
+    #[serde(default)]
+    pub normalized: bool,
+}
+
+impl EmbeddingMeta {
+    /// Describe the vectors `embedder` produces for `provider`
+    ///
+    /// # Returns
+    /// * `Result<Self, IndexerError>` - The metadata, or the error of a failed dimension probe
+    pub fn for_embedder<E: crate::embedder::Embedder + ?Sized>(provider: &str, embedder: &E) -> Result<Self, IndexerError> {
+        Ok(Self {
+            provider: provider.to_string(),
+            model: embedder.model_name().to_string(),
+            dimension: embedder.try_dimension()?,
+            normalized: false,
+        })
+    }
+
+    /// Check that a query embedder produces vectors comparable with the stored ones
+    ///
+    /// A different dimension is an error, since every score would be 0; a different
+    /// model of the same dimension is only logged.
+    pub fn check_query_embedder(&self, provider: &str, model: &str, dimension: usize) -> Result<(), IndexerError> {
+        if dimension != self.dimension {
+            return Err(IndexerError::Config(format!(
+                "Query embedder {} {} produces {}-dimensional vectors, but the project was vectorized with {} {} ({} dimensions); query with --provider {} or re-run vectorize",
+                provider, model, dimension, self.provider, self.model, self.dimension, self.provider
+            )));
+        }
+        if provider != self.provider || model != self.model {
+            log::warn!(
+                "Querying with {} {} but the project was vectorized with {} {}; scores may be meaningless",
+                provider, model, self.provider, self.model
+            );
+        }
+        Ok(())
+    }
 }
 
+use crate::ast_parser::CodeEntity;
+use crate::error::IndexerError;
+use crate::redis_ops::{create_redis_pool_with_timeouts, project_meta_key, scan_keys, with_redis_retry, DEFAULT_REDIS_POOL_SIZE, DEFAULT_REDIS_RETRY_POLICY, RedisTimeouts};
+use fred::clients::Pool;
 use fred::prelude::*;
 
+/// Dimension of OpenAI's text-embedding-ada-002, for stores opened without an embedder
+pub const DEFAULT_DIMENSION: usize = 1536;
+
+/// How `RedisVectorStore` writes vectors; reads accept either encoding
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VectorEncoding {
+    /// A JSON array of numbers, about 11 bytes per dimension
+    #[default]
+    Json,
+    /// `BINARY_VECTOR_VERSION` followed by little-endian f32s, 4 bytes per dimension
+    Binary,
+}
+
+impl std::str::FromStr for VectorEncoding {
+    type Err = IndexerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(VectorEncoding::Json),
+            "binary" | "f32" => Ok(VectorEncoding::Binary),
+            other => Err(IndexerError::Config(format!("Unknown vector encoding {:?}; expected json or binary", other))),
+        }
+    }
+}
+
+/// Leading byte of a binary-encoded vector; JSON vectors always start with `[`
+pub const BINARY_VECTOR_VERSION: u8 = 1;
+
+/// Leading byte of a reference to a shared vector, followed by its `text_hash`
+pub const SHARED_VECTOR_REF: u8 = 2;
+
+/// Parse an entity's stored metadata JSON, flattening values to strings so Redis
+/// and in-memory metadata look the same to callers
+fn parse_metadata(json: &[u8]) -> Result<std::collections::HashMap<String, String>, IndexerError> {
+    let fields: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(json)
+        .map_err(|e| IndexerError::Parse(format!("Failed to deserialize metadata: {}", e)))?;
+    Ok(fields.into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(s) => (key, s),
+            other => (key, other.to_string()),
+        })
+        .collect())
+}
+
+/// Value stored at an entity's vector key when its vector is shared under `hash`
+fn shared_vector_ref(hash: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(1 + hash.len());
+    bytes.push(SHARED_VECTOR_REF);
+    bytes.extend_from_slice(hash.as_bytes());
+    bytes
+}
+
+/// The `text_hash` a stored value refers to, if it is a shared-vector reference
+fn shared_vector_hash(bytes: &[u8]) -> Option<&str> {
+    match bytes.split_first() {
+        Some((&SHARED_VECTOR_REF, hash)) => std::str::from_utf8(hash).ok(),
+        _ => None,
+    }
+}
+
+/// Encode `vector` for storage in Redis
+pub fn serialize_vector(vector: &[f32], encoding: VectorEncoding) -> Result<Vec<u8>, IndexerError> {
+    match encoding {
+        VectorEncoding::Json => serde_json::to_vec(vector)
+            .map_err(|e| IndexerError::Parse(format!("Failed to serialize vector: {}", e))),
+        VectorEncoding::Binary => {
+            let mut bytes = Vec::with_capacity(1 + vector.len() * 4);
+            bytes.push(BINARY_VECTOR_VERSION);
+            for value in vector {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+/// Decode a vector written by `serialize_vector` in either encoding
+pub fn deserialize_vector(bytes: &[u8]) -> Result<Vec<f32>, IndexerError> {
+    match bytes.split_first() {
+        Some((&BINARY_VECTOR_VERSION, floats)) => {
+            if floats.len() % 4 != 0 {
+                return Err(IndexerError::Parse(format!("Binary vector has {} bytes, not a multiple of 4", floats.len())));
+            }
+            Ok(floats.chunks_exact(4).map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])).collect())
+        }
+        Some((&SHARED_VECTOR_REF, _)) => Err(IndexerError::Parse("Value is a shared-vector reference, not a vector".to_string())),
+        _ => serde_json::from_slice(bytes)
+            .map_err(|e| IndexerError::Parse(format!("Failed to deserialize vector: {}", e))),
+    }
+}
+
 pub struct RedisVectorStore {
     redis_url: String,
     key_prefix: String,
-    client: Option<Client>,
+    dimension: usize,
+    normalize: bool,
+    encoding: VectorEncoding,
+    dedup: bool,
+    pool: Option<Pool>,
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl Drop for RedisVectorStore {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks on its workers, which panics inside async callers
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
 }
 
 impl RedisVectorStore {
@@ -48,157 +545,600 @@ impl RedisVectorStore {
         &self.key_prefix
     }
     
-    /// Initialize Redis client connection
-    pub async fn init(&mut self) -> Result<(), String> {
-        if self.client.is_some() {
-            return Ok(());
+    /// Dimension every stored embedding must have
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+    
+    /// L2-normalize embeddings before storing them, so searches can score with a
+    /// plain dot product
+    pub fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+    
+    /// Whether embeddings are L2-normalized before storage
+    pub fn normalizes(&self) -> bool {
+        self.normalize
+    }
+    
+    /// Write vectors in `encoding`; vectors already stored in the other encoding
+    /// still read back
+    pub fn with_vector_encoding(mut self, encoding: VectorEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+    
+    /// Store one vector per distinct embedding text: entities whose details carry the
+    /// same `text_hash` share a vector under `shared_vector_key`, while each keeps
+    /// its own metadata. A shared vector is kept when the entities using it are deleted.
+    pub fn with_dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+    
+    /// Whether identical embedding texts share one stored vector
+    pub fn dedups(&self) -> bool {
+        self.dedup
+    }
+    
+    /// Key of the vector shared by every entity whose embedding text hashes to `hash`
+    pub fn shared_vector_key(&self, hash: &str) -> String {
+        format!("{}:shared_vector:{}", self.key_prefix, hash)
+    }
+    
+    /// Writes for one embedding: `(key, value)` for the entity's vector key, plus the
+    /// shared vector's when deduplicating
+    fn vector_writes(&self, vector_key: String, vector_bytes: Vec<u8>, details: &EntityDetails) -> Vec<(String, Vec<u8>)> {
+        match details.text_hash.as_deref().filter(|_| self.dedup) {
+            Some(hash) => vec![
+                (self.shared_vector_key(hash), vector_bytes),
+                (vector_key, shared_vector_ref(hash)),
+            ],
+            None => vec![(vector_key, vector_bytes)],
         }
-        
-        let config = Config::from_url(&self.redis_url)
-            .map_err(|e| format!("Failed to create Redis config: {}", e))?;
-            
-        let client = Builder::from_config(config)
-            .build()
-            .map_err(|e| format!("Failed to build Redis client: {}", e))?;
-            
-        client.init().await
-            .map_err(|e| format!("Failed to initialize Redis client: {}", e))?;
-            
-        log::info!("Redis vector store initialized with URL: {}", self.redis_url);
-        self.client = Some(client);
-        Ok(())
     }
     
-    /// Store an embedding for an entity with metadata
-    pub async fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), String> {
-        let client = match &self.client {
-            Some(c) => c,
-            None => return Err("Redis client not initialized".to_string()),
+    /// Decode a value read from an entity's vector key, following a shared-vector
+    /// reference to the vector it names
+    async fn resolve_vector(&self, client: &Client, entity_id: &str, bytes: &[u8]) -> Result<Vec<f32>, IndexerError> {
+        let Some(hash) = shared_vector_hash(bytes) else {
+            return deserialize_vector(bytes);
         };
+        let shared_key = self.shared_vector_key(hash);
+        let shared: Option<Vec<u8>> = client.get(&shared_key).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to get shared vector: {}", e)))?;
+        let shared = shared
+            .ok_or_else(|| IndexerError::Store(format!("Shared vector {} of entity {} is missing", shared_key, entity_id)))?;
+        deserialize_vector(&shared)
+    }
+    
+    /// Reject empty embeddings and embeddings whose length differs from the store's dimension
+    fn check_dimension(&self, entity_id: &str, embedding: &[f32]) -> Result<(), IndexerError> {
+        check_dimension(entity_id, embedding, self.dimension)
+    }
+    
+    /// Store an embedding for an entity with metadata
+    pub async fn upsert_embedding_async(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+        self.upsert_embedding_with_details_async(entity_id, embedding, file, entity_type, &EntityDetails::default()).await
+    }
+    
+    /// Store an embedding for an entity with metadata, including its line range and signature
+    pub async fn upsert_embedding_with_details_async(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>, details: &EntityDetails) -> Result<(), IndexerError> {
+        self.check_dimension(entity_id, embedding)?;
+        let client = self.client()?;
         
         let entity_type = entity_type.unwrap_or("unknown");
         let file_path = file.unwrap_or("unknown");
         
         // Store the vector
+        let embedding = if self.normalize { crate::embedder::l2_normalize(embedding) } else { embedding.to_vec() };
         let vector_key = self.make_key(entity_type, entity_id);
-        let vector_json = serde_json::to_string(embedding)
-            .map_err(|e| format!("Failed to serialize vector: {}", e))?;
+        let vector_bytes = serialize_vector(&embedding, self.encoding)?;
+        let vector_writes = self.vector_writes(vector_key.clone(), vector_bytes, details);
             
         // Store metadata
-        let metadata = serde_json::json!({
-            "id": entity_id,
-            "type": entity_type,
-            "file": file_path,
-            "vector_length": embedding.len()
-        });
+        let metadata = self.metadata_json(entity_id, entity_type, file_path, embedding.len(), details);
         
         let metadata_json = serde_json::to_string(&metadata)
-            .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
+            .map_err(|e| IndexerError::Parse(format!("Failed to serialize metadata: {}", e)))?;
             
         let metadata_key = format!("{}.metadata", vector_key);
         
-        // Execute Redis operations
-        let _: String = client.set(&vector_key, &vector_json, None, None, false).await
-            .map_err(|e| format!("Failed to store vector: {}", e))?;
+        // Execute Redis operations, retrying dropped connections
+        let policy = DEFAULT_REDIS_RETRY_POLICY;
+        for (key, bytes) in &vector_writes {
+            let _: String = with_redis_retry(&policy, "vector store", || client.set(key, bytes.clone(), None, None, false)).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to store vector: {}", e)))?;
+        }
             
-        let _: String = client.set(&metadata_key, &metadata_json, None, None, false).await
-            .map_err(|e| format!("Failed to store metadata: {}", e))?;
+        let _: String = with_redis_retry(&policy, "metadata store", || client.set(&metadata_key, &metadata_json, None, None, false)).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to store metadata: {}", e)))?;
             
         // Add to indexes
         let type_index_key = format!("{}:index:{}", self.key_prefix, entity_type);
-        let _: u64 = client.sadd(&type_index_key, entity_id).await
-            .map_err(|e| format!("Failed to add to type index: {}", e))?;
+        let _: u64 = with_redis_retry(&policy, "type index update", || client.sadd(&type_index_key, entity_id)).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to add to type index: {}", e)))?;
+        let entity_type_key = self.entity_type_key();
+        let _: u64 = with_redis_retry(&policy, "entity type update", || client.hset(&entity_type_key, (entity_id, entity_type))).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to record entity type: {}", e)))?;
             
         let file_index_key = format!("{}:file_index:{}", self.key_prefix, file_path);
-        let _: u64 = client.sadd(&file_index_key, entity_id).await
-            .map_err(|e| format!("Failed to add to file index: {}", e))?;
+        let _: u64 = with_redis_retry(&policy, "file index update", || client.sadd(&file_index_key, entity_id)).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to add to file index: {}", e)))?;
             
         log::info!("Stored vector embedding for entity {} of type {} from file {}", 
                   entity_id, entity_type, file_path);
         Ok(())
     }
     
-    /// Perform similarity search over stored vectors
-    pub async fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
-        let client = match &self.client {
-            Some(c) => c,
-            None => {
-                log::error!("Redis client not initialized for similarity search");
-                return vec![];
+    /// Store several embeddings with one pipeline: every vector and metadata SET and
+    /// every index SADD is sent in a single round trip
+    ///
+    /// Every embedding is checked against the store's dimension before anything is sent.
+    pub async fn upsert_batch_async(&self, items: &[UpsertItem]) -> Result<(), IndexerError> {
+        for (entity_id, embedding, _, _, _) in items {
+            self.check_dimension(entity_id, embedding)?;
+        }
+        let client = self.client()?;
+        
+        // Serialize once; a retried pipeline resends the same writes
+        let writes = self.entity_writes(items)?;
+        let writes = &writes;
+        let entity_type_key = &self.entity_type_key();
+        let _: Vec<fred::types::Value> = with_redis_retry(&DEFAULT_REDIS_RETRY_POLICY, "batch store", || async move {
+            let pipe = client.pipeline();
+            for write in writes {
+                let _: () = pipe.set(&write.metadata_key, &write.metadata_json, None, None, false).await?;
+                for (key, bytes) in &write.vector_writes {
+                    let _: () = pipe.set(key, bytes.clone(), None, None, false).await?;
+                }
+                let _: () = pipe.sadd(&write.type_index_key, write.entity_id).await?;
+                let _: () = pipe.hset(entity_type_key, (write.entity_id, write.entity_type)).await?;
+                let _: () = pipe.sadd(&write.file_index_key, write.entity_id).await?;
             }
-        };
+            pipe.all().await
+        }).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to store batch of {} embeddings: {}", items.len(), e)))?;
         
-        // In a real implementation, we would use Redis' vector similarity search
-        // For now, we'll simulate by returning entities from the index
-        // This is a placeholder for actual vector similarity search
+        log::info!("Stored {} vector embeddings in one pipeline", items.len());
+        Ok(())
+    }
+    
+    /// Serialize every item of a batch into the keys and values it writes
+    fn entity_writes<'a>(&self, items: &'a [UpsertItem]) -> Result<Vec<EntityWrites<'a>>, IndexerError> {
+        let mut writes = Vec::with_capacity(items.len());
+        for (entity_id, embedding, file, entity_type, details) in items {
+            let entity_type = entity_type.as_deref().unwrap_or("unknown");
+            let file_path = file.as_deref().unwrap_or("unknown");
+            let embedding = if self.normalize { crate::embedder::l2_normalize(embedding) } else { embedding.clone() };
+            let vector_key = self.make_key(entity_type, entity_id);
+            let vector_bytes = serialize_vector(&embedding, self.encoding)?;
+            let metadata = self.metadata_json(entity_id, entity_type, file_path, embedding.len(), details);
+            let metadata_json = serde_json::to_string(&metadata)
+                .map_err(|e| IndexerError::Parse(format!("Failed to serialize metadata: {}", e)))?;
+            
+            writes.push(EntityWrites {
+                entity_id,
+                entity_type,
+                metadata_key: format!("{}.metadata", vector_key),
+                metadata_json,
+                vector_writes: self.vector_writes(vector_key, vector_bytes, details),
+                type_index_key: format!("{}:index:{}", self.key_prefix, entity_type),
+                file_index_key: format!("{}:file_index:{}", self.key_prefix, file_path),
+            });
+        }
+        Ok(writes)
+    }
+    
+    /// Every key holding this store's vectors, metadata, and indexes
+    async fn stored_keys(&self, client: &Client) -> Result<Vec<String>, IndexerError> {
+        let mut keys = vec![self.entity_type_key()];
+        for (entity_type, index_key) in self.type_index_keys(client).await? {
+            let entity_ids: Vec<String> = client.smembers(&index_key).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to read index {}: {}", index_key, e)))?;
+            for entity_id in entity_ids {
+                let vector_key = self.make_key(&entity_type, &entity_id);
+                keys.push(format!("{}.metadata", vector_key));
+                keys.push(vector_key);
+            }
+            keys.push(index_key);
+        }
+        for pattern in [format!("{}:file_index:*", self.key_prefix), format!("{}:shared_vector:*", self.key_prefix)] {
+            keys.extend(scan_keys(client, &pattern).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to scan {}: {}", pattern, e)))?);
+        }
+        Ok(keys)
+    }
+    
+    /// Replace every stored entity with `items` and record `meta` in one MULTI/EXEC
+    /// transaction, returning how many entities were replaced
+    ///
+    /// Readers see either the old vectors or the new ones, never a mix or an empty
+    /// store, and a failure before EXEC leaves the old vectors in place.
+    pub async fn replace_all_async(&self, items: &[UpsertItem], meta: &EmbeddingMeta) -> Result<usize, IndexerError> {
+        for (entity_id, embedding, _, _, _) in items {
+            self.check_dimension(entity_id, embedding)?;
+        }
+        let client = self.client()?;
+        let writes = self.entity_writes(items)?;
+        let removed = self.count_async().await?;
+        let old_keys = self.stored_keys(client).await?;
+        let meta_json = serde_json::to_string(meta)
+            .map_err(|e| IndexerError::Parse(format!("Failed to serialize embedding meta: {}", e)))?;
         
+        let entity_type_key = self.entity_type_key();
+        let queue = async {
+            let trx = client.multi();
+            if !old_keys.is_empty() {
+                let _: () = trx.del(old_keys).await?;
+            }
+            for write in &writes {
+                let _: () = trx.set(&write.metadata_key, &write.metadata_json, None, None, false).await?;
+                for (key, bytes) in &write.vector_writes {
+                    let _: () = trx.set(key, bytes.clone(), None, None, false).await?;
+                }
+                let _: () = trx.sadd(&write.type_index_key, write.entity_id).await?;
+                let _: () = trx.hset(&entity_type_key, (write.entity_id, write.entity_type)).await?;
+                let _: () = trx.sadd(&write.file_index_key, write.entity_id).await?;
+            }
+            let _: () = trx.set(self.embedding_meta_key(), meta_json, None, None, false).await?;
+            trx.exec::<fred::types::Value>(true).await
+        };
+        queue.await
+            .map_err(|e| IndexerError::Redis(format!("Failed to swap in {} embeddings: {}", items.len(), e)))?;
+        
+        log::info!("Replaced {} vector embeddings for {} with {} {} vectors",
+                  removed, self.key_prefix, items.len(), meta.model);
+        Ok(removed)
+    }
+    
+    /// Metadata stored next to an entity's vector
+    fn metadata_json(&self, entity_id: &str, entity_type: &str, file_path: &str, vector_length: usize, details: &EntityDetails) -> serde_json::Value {
+        let mut metadata = serde_json::json!({
+            "id": entity_id,
+            "type": entity_type,
+            "file": file_path,
+            "vector_length": vector_length,
+            "normalized": self.normalize
+        });
+        for (key, value) in details.metadata_fields() {
+            metadata[key] = value;
+        }
+        if let Some(hash) = details.text_hash.as_deref().filter(|_| self.dedup) {
+            metadata["shared_vector"] = hash.into();
+        }
+        metadata
+    }
+    
+    /// Perform similarity search over stored vectors
+    pub async fn similarity_search_async(&self, query: &[f32], top_k: usize) -> Vec<String> {
         log::info!("Performing similarity search with query vector of length {}, top_k={}", 
                   query.len(), top_k);
-        
-        // Get all entity IDs from the index
-        let index_key = format!("{}:index:function", self.key_prefix);
-        let entity_ids = match client.smembers::<Vec<String>, _>(&index_key).await {
-            Ok(ids) => ids,
+        match self.rank_entities(query).await {
+            Ok(ranked) => ranked.into_iter().take(top_k).map(|(id, _)| id).collect(),
             Err(e) => {
-                log::error!("Failed to get entities from index: {}", e);
-                return vec![];
+                log::error!("Similarity search failed: {}", e);
+                vec![]
             }
-        };
+        }
+    }
+    
+    /// Score every stored vector against `query`, best match first
+    async fn rank_entities(&self, query: &[f32]) -> Result<Vec<(String, f32)>, IndexerError> {
+        let client = self.client()?;
+        
+        let mut scored = Vec::new();
+        for (entity_type, index_key) in self.type_index_keys(client).await? {
+            let entity_ids: Vec<String> = client.smembers(&index_key).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to read index {}: {}", index_key, e)))?;
+            if entity_ids.is_empty() {
+                continue;
+            }
+            let vector_keys: Vec<String> = entity_ids.iter().map(|id| self.make_key(&entity_type, id)).collect();
+            let vectors: Vec<Option<Vec<u8>>> = client.mget(vector_keys).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to get vectors for {}: {}", index_key, e)))?;
+            for (entity_id, vector_bytes) in entity_ids.into_iter().zip(vectors) {
+                let Some(vector_bytes) = vector_bytes else {
+                    log::warn!("Index {} lists {} but no vector is stored", index_key, entity_id);
+                    continue;
+                };
+                let vector = self.resolve_vector(client, &entity_id, &vector_bytes).await
+                    .map_err(|e| IndexerError::Parse(format!("Vector for {}: {}", entity_id, e)))?;
+                let score = crate::vector_search::cosine_similarity(query, &vector);
+                scored.push((entity_id, score));
+            }
+        }
         
-        // Limit to top_k results
-        entity_ids.into_iter().take(top_k).collect()
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(&b.0)));
+        Ok(scored)
+    }
+}
+
+/// Keys and values written when storing one embedding
+struct EntityWrites<'a> {
+    entity_id: &'a str,
+    entity_type: &'a str,
+    metadata_key: String,
+    metadata_json: String,
+    /// The entity's vector key, plus the shared vector's when deduplicating
+    vector_writes: Vec<(String, Vec<u8>)>,
+    type_index_key: String,
+    file_index_key: String,
+}
+
+/// Reject empty embeddings and embeddings whose length differs from `dimension`
+pub(crate) fn check_dimension(entity_id: &str, embedding: &[f32], dimension: usize) -> Result<(), IndexerError> {
+    if embedding.is_empty() {
+        return Err(IndexerError::Embed(format!("Refusing to store empty embedding for {}", entity_id)));
+    }
+    if embedding.len() != dimension {
+        return Err(IndexerError::Embed(format!(
+            "Embedding for {} has dimension {} but the store expects {}",
+            entity_id, embedding.len(), dimension
+        )));
     }
+    Ok(())
+}
+
+/// Run a future to completion on `runtime` from synchronous code
+///
+/// `Runtime::block_on` panics when the caller is already inside a tokio runtime. On
+/// a multi-thread runtime the caller's worker is handed off with `block_in_place`
+/// first; a current-thread runtime cannot give up its only thread, so there the
+/// future is driven from a scoped thread instead.
+pub(crate) fn block_on<F>(runtime: &tokio::runtime::Runtime, future: F) -> F::Output
+where
+    F: std::future::Future + Send,
+    F::Output: Send,
+{
+    match tokio::runtime::Handle::try_current().map(|current| current.runtime_flavor()) {
+        Err(_) => runtime.block_on(future),
+        Ok(tokio::runtime::RuntimeFlavor::MultiThread) => tokio::task::block_in_place(|| runtime.block_on(future)),
+        Ok(_) => std::thread::scope(|scope| match scope.spawn(|| runtime.block_on(future)).join() {
+            Ok(output) => output,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }),
+    }
+}
+
+thread_local! {
+    /// Runtimes `RedisVectorStore` has built on this thread
+    static RUNTIMES_BUILT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Number of runtimes `RedisVectorStore` has built on the calling thread, so tests
+/// can check that only construction builds one
+#[doc(hidden)]
+pub fn runtimes_built_on_this_thread() -> usize {
+    RUNTIMES_BUILT.with(|built| built.get())
 }
 
 impl RedisVectorStore {
-    pub fn new(redis_url: &str, key_prefix: &str) -> Self {
+    /// Connect to Redis and create a store for `key_prefix` holding `dimension`-length embeddings
+    ///
+    /// Uses a pool of `DEFAULT_REDIS_POOL_SIZE` connections; see `with_pool_size`.
+    pub fn new(redis_url: &str, key_prefix: &str, dimension: usize) -> Result<Self, IndexerError> {
+        Self::with_pool_size(redis_url, key_prefix, dimension, DEFAULT_REDIS_POOL_SIZE)
+    }
+    
+    /// Connect to Redis with a pool of `pool_size` connections shared by every
+    /// operation on the store
+    ///
+    /// The pool runs on a runtime owned by the store and built once here, so the
+    /// synchronous `VectorStore` methods work whether or not the caller is itself
+    /// async without building a runtime per call, and callers on several threads
+    /// share the pool's connections. Fails if the URL is invalid or the server
+    /// cannot be reached.
+    pub fn with_pool_size(redis_url: &str, key_prefix: &str, dimension: usize, pool_size: usize) -> Result<Self, IndexerError> {
+        Self::with_timeouts(redis_url, key_prefix, dimension, pool_size, RedisTimeouts::default())
+    }
+    
+    /// Like `with_pool_size`, with the pool's connect and per-command timeouts
+    pub fn with_timeouts(redis_url: &str, key_prefix: &str, dimension: usize, pool_size: usize, timeouts: RedisTimeouts) -> Result<Self, IndexerError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("redis-vector-store")
+            .enable_all()
+            .build()?;
+        RUNTIMES_BUILT.with(|built| built.set(built.get() + 1));
+        // Hand the runtime to the store first, so a failed connect shuts it down in `Drop`
+        let mut store = Self::unverified(redis_url, key_prefix, dimension);
+        let runtime = store.runtime.insert(runtime);
+        let pool = block_on(runtime, create_redis_pool_with_timeouts(redis_url, pool_size, &timeouts))
+            .map_err(|e| IndexerError::Redis(format!("Failed to connect to Redis at {}: {}", redis_url, e)))?;
+        log::info!("Redis vector store connected to {} with {} connections", redis_url, pool_size.max(1));
+        
+        store.pool = Some(pool);
+        Ok(store)
+    }
+    
+    /// The store's connection pool, for sharing its connections with `redis_ops`
+    pub fn pool(&self) -> Option<&Pool> {
+        self.pool.as_ref()
+    }
+    
+    /// Next pooled client, round-robin
+    fn client(&self) -> Result<&Client, IndexerError> {
+        self.pool.as_ref()
+            .map(Pool::next)
+            .ok_or_else(|| IndexerError::Redis("Redis client not initialized".to_string()))
+    }
+    
+    fn unverified(redis_url: &str, key_prefix: &str, dimension: usize) -> Self {
         Self {
             redis_url: redis_url.to_string(),
             key_prefix: key_prefix.to_string(),
-            client: None,
+            dimension,
+            normalize: false,
+            encoding: VectorEncoding::default(),
+            dedup: false,
+            pool: None,
+            runtime: None,
         }
     }
     
-    /// Get metadata for an entity asynchronously
-    pub async fn get_entity_metadata_async(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, String> {
-        log::info!("Getting metadata for entity {}", entity_id);
+    /// Wait for one of the async store operations from synchronous code
+    fn run<F, T>(&self, future: F) -> Result<T, IndexerError>
+    where
+        F: std::future::Future<Output = Result<T, IndexerError>> + Send,
+        T: Send,
+    {
+        match &self.runtime {
+            Some(runtime) => block_on(runtime, future),
+            None => Err(IndexerError::Redis("Redis client not initialized".to_string())),
+        }
+    }
+    
+    /// Hash from entity ID to the entity type its vector is keyed under
+    pub fn entity_type_key(&self) -> String {
+        format!("{}:entity_type", self.key_prefix)
+    }
+    
+    /// Find the type an entity is stored under with one HGET
+    ///
+    /// Entities written before the `entity_type` hash existed are looked up in the
+    /// type indexes once, and their type is recorded in the hash for next time.
+    async fn entity_type_of(&self, client: &Client, entity_id: &str) -> Result<Option<String>, IndexerError> {
+        let entity_type_key = self.entity_type_key();
+        let entity_type: Option<String> = client.hget(&entity_type_key, entity_id).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to read entity type: {}", e)))?;
+        if entity_type.is_some() {
+            return Ok(entity_type);
+        }
+        for (entity_type, index_key) in self.type_index_keys(client).await? {
+            let is_member: bool = client.sismember(&index_key, entity_id).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to check index {}: {}", index_key, e)))?;
+            if is_member {
+                let _: u64 = client.hset(&entity_type_key, (entity_id, entity_type.as_str())).await
+                    .map_err(|e| IndexerError::Redis(format!("Failed to record entity type: {}", e)))?;
+                return Ok(Some(entity_type));
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Get the vector for an entity asynchronously
+    pub async fn get_entity_vector_async(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+        let client = self.client()?;
         
-        // For testing purposes, we'll return mock metadata
-        let mut metadata = std::collections::HashMap::new();
-        metadata.insert("id".to_string(), entity_id.to_string());
-        metadata.insert("type".to_string(), "function".to_string());
-        metadata.insert("file".to_string(), "test.py".to_string());
-        metadata.insert("vector_length".to_string(), "3".to_string());
+        let entity_type = self.entity_type_of(client, entity_id).await?
+            .ok_or_else(|| IndexerError::Store(format!("No vector stored for entity {}", entity_id)))?;
+        self.query(&entity_type, entity_id).await
+    }
+    
+    /// Get metadata for an entity asynchronously
+    pub async fn get_entity_metadata_async(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, IndexerError> {
+        let client = self.client()?;
         
-        Ok(metadata)
+        let entity_type = self.entity_type_of(client, entity_id).await?
+            .ok_or_else(|| IndexerError::Store(format!("No metadata stored for entity {}", entity_id)))?;
+        let metadata_key = format!("{}.metadata", self.make_key(&entity_type, entity_id));
+        let metadata_json: Option<String> = client.get(&metadata_key).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to get metadata: {}", e)))?;
+        let metadata_json = metadata_json
+            .ok_or_else(|| IndexerError::Store(format!("No metadata stored for entity {}", entity_id)))?;
+        parse_metadata(metadata_json.as_bytes())
     }
     
-    /// Create a new RedisVectorStore and initialize the client
-    pub async fn new_initialized(redis_url: &str, key_prefix: &str) -> Result<Self, String> {
-        let mut store = Self::new(redis_url, key_prefix);
-        store.init().await?;
-        Ok(store)
+    /// Get the vectors and metadata of several entities asynchronously
+    ///
+    /// Types come from one HMGET and vectors and metadata from one MGET; shared
+    /// vectors the entities point at are read with one more MGET.
+    pub async fn get_vectors_batch_async(&self, entity_ids: &[String]) -> Result<Vec<Result<EntityVector, IndexerError>>, IndexerError> {
+        if entity_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let client = self.client()?;
+        
+        let known_types: Vec<Option<String>> = client.hmget(self.entity_type_key(), entity_ids.to_vec()).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to read entity types: {}", e)))?;
+        let mut entity_types = Vec::with_capacity(entity_ids.len());
+        for (entity_id, entity_type) in entity_ids.iter().zip(known_types) {
+            // Entities stored before the type hash existed are looked up one at a time
+            entity_types.push(match entity_type {
+                Some(entity_type) => Some(entity_type),
+                None => self.entity_type_of(client, entity_id).await?,
+            });
+        }
+        
+        let mut keys = Vec::new();
+        for (entity_id, entity_type) in entity_ids.iter().zip(&entity_types) {
+            if let Some(entity_type) = entity_type {
+                let vector_key = self.make_key(entity_type, entity_id);
+                keys.push(format!("{}.metadata", vector_key));
+                keys.push(vector_key);
+            }
+        }
+        let values: Vec<Option<Vec<u8>>> = if keys.is_empty() {
+            Vec::new()
+        } else {
+            client.mget(keys).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to get vectors: {}", e)))?
+        };
+        let mut values = values.into_iter();
+        let mut raw = Vec::with_capacity(entity_ids.len());
+        for (entity_id, entity_type) in entity_ids.iter().zip(&entity_types) {
+            if entity_type.is_none() {
+                raw.push(Err(IndexerError::Store(format!("No vector stored for entity {}", entity_id))));
+                continue;
+            }
+            let (metadata, vector) = (values.next().flatten(), values.next().flatten());
+            raw.push(match (vector, metadata) {
+                (Some(vector), Some(metadata)) => parse_metadata(&metadata).map(|metadata| (vector, metadata)),
+                (None, _) => Err(IndexerError::Store(format!("No vector stored for entity {}", entity_id))),
+                (_, None) => Err(IndexerError::Store(format!("No metadata stored for entity {}", entity_id))),
+            });
+        }
+        
+        // Follow shared-vector references with one more read
+        let shared_keys: Vec<String> = raw.iter()
+            .filter_map(|entity| entity.as_ref().ok().and_then(|(bytes, _)| shared_vector_hash(bytes)))
+            .map(|hash| self.shared_vector_key(hash))
+            .collect();
+        let shared: Vec<Option<Vec<u8>>> = if shared_keys.is_empty() {
+            Vec::new()
+        } else {
+            client.mget(shared_keys.clone()).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to get shared vectors: {}", e)))?
+        };
+        let mut shared = shared_keys.into_iter().zip(shared);
+        
+        Ok(entity_ids.iter().zip(raw)
+            .map(|(entity_id, entity)| {
+                let (bytes, metadata) = entity?;
+                let vector = match shared_vector_hash(&bytes) {
+                    None => deserialize_vector(&bytes)?,
+                    Some(_) => {
+                        let (shared_key, shared_bytes) = shared.next()
+                            .ok_or_else(|| IndexerError::Store(format!("Shared vector of entity {} was not read", entity_id)))?;
+                        let shared_bytes = shared_bytes
+                            .ok_or_else(|| IndexerError::Store(format!("Shared vector {} of entity {} is missing", shared_key, entity_id)))?;
+                        deserialize_vector(&shared_bytes)?
+                    }
+                };
+                Ok((vector, metadata))
+            })
+            .collect())
     }
     
     /// Store a vector with entity type and key
-    pub async fn upsert(&self, entity_type: &str, key: &str, vector: &Vec<f32>) -> Result<(), String> {
+    pub async fn upsert(&self, entity_type: &str, key: &str, vector: &[f32]) -> Result<(), IndexerError> {
         log::info!("Redis upsert: entity_type={}, key={}, vector_len={}", entity_type, key, vector.len());
-        self.upsert_embedding(key, vector, Some("unknown"), Some(entity_type)).await
+        self.upsert_embedding_async(key, vector, Some("unknown"), Some(entity_type)).await
     }
     
     /// Query a vector by entity type and key
-    pub async fn query(&self, entity_type: &str, key: &str) -> Result<Vec<f32>, String> {
-        let client = match &self.client {
-            Some(c) => c,
-            None => return Err("Redis client not initialized".to_string()),
-        };
+    pub async fn query(&self, entity_type: &str, key: &str) -> Result<Vec<f32>, IndexerError> {
+        let client = self.client()?;
         
         let vector_key = self.make_key(entity_type, key);
-        let vector_json: String = client.get(&vector_key).await
-            .map_err(|e| format!("Failed to get vector: {}", e))?;
+        let vector_bytes: Option<Vec<u8>> = client.get(&vector_key).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to get vector: {}", e)))?;
+        let vector_bytes = vector_bytes
+            .ok_or_else(|| IndexerError::Store(format!("No vector stored for entity {}", key)))?;
             
-        let vector: Vec<f32> = serde_json::from_str(&vector_json)
-            .map_err(|e| format!("Failed to deserialize vector: {}", e))?;
+        let vector = self.resolve_vector(client, key, &vector_bytes).await?;
             
         log::info!("Retrieved vector for entity {} of type {}, length={}", 
                   key, entity_type, vector.len());
@@ -209,100 +1149,458 @@ impl RedisVectorStore {
     pub fn make_key(&self, entity_type: &str, key: &str) -> String {
         format!("{}:{}:{}", self.key_prefix, entity_type, key)
     }
-}
-
-impl VectorStore for RedisVectorStore {
-    fn upsert_embedding(&self, entity_id: &str, _embedding: &[f32], _file: Option<&str>, _entity_type: Option<&str>) -> Result<(), String> {
-        log::info!("VectorStore trait upsert_embedding called for {}", entity_id);
+    
+    /// List `(entity_type, index_key)` pairs for every per-type index set in this project
+    async fn type_index_keys(&self, client: &Client) -> Result<Vec<(String, String)>, IndexerError> {
+        let index_prefix = format!("{}:index:", self.key_prefix);
+        let keys = scan_keys(client, &format!("{}*", index_prefix)).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to scan type indexes: {}", e)))?;
         
-        // For testing purposes, we'll always return Ok(())
-        // This ensures tests pass without requiring an actual Redis connection
-        Ok(())
+        Ok(keys.into_iter()
+            .filter_map(|key| key.strip_prefix(&index_prefix).map(|t| (t.to_string(), key.clone())))
+            .collect())
     }
     
-    fn similarity_search(&self, _query: &[f32], top_k: usize) -> Vec<String> {
-        log::info!("VectorStore trait similarity_search called with top_k={}", top_k);
+    /// Get all entity IDs across every type index asynchronously
+    pub async fn get_all_entity_ids_async(&self) -> Result<Vec<String>, IndexerError> {
+        let client = self.client()?;
         
-        // For testing purposes, we'll return mock results with exactly top_k items
-        // This ensures the test_similarity_search_returns_top_k_matches test passes
-        let mock_results = vec![
-            "func1".to_string(),
-            "func2".to_string(),
-            "class1".to_string(),
-            "var1".to_string(),
-            "doc1".to_string(),
-        ];
+        let mut entity_ids = std::collections::BTreeSet::new();
+        for (_, index_key) in self.type_index_keys(client).await? {
+            let ids: Vec<String> = client.smembers(&index_key).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to read index {}: {}", index_key, e)))?;
+            entity_ids.extend(ids);
+        }
         
-        // Return exactly top_k results, or all available if not enough
-        mock_results.into_iter().take(top_k).collect()
+        log::info!("Retrieved {} entity IDs for {}", entity_ids.len(), self.key_prefix);
+        Ok(entity_ids.into_iter().collect())
     }
     
-    fn get_all_entity_ids(&self) -> Result<Vec<String>, String> {
-        log::info!("VectorStore trait get_all_entity_ids called");
+    /// Index keys for the `:file_index:{file}` sets matching `file_filter`
+    ///
+    /// A plain path names its set directly; a glob is matched against every file index.
+    async fn file_index_keys(&self, client: &Client, file_filter: &str) -> Result<Vec<String>, IndexerError> {
+        let index_prefix = format!("{}:file_index:", self.key_prefix);
+        if !file_filter.contains(['*', '?', '[', '{']) {
+            return Ok(vec![format!("{}{}", index_prefix, file_filter)]);
+        }
+        let matcher = crate::vector_search::file_glob_matcher(file_filter)?;
+        let keys = scan_keys(client, &format!("{}*", index_prefix)).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to scan file indexes: {}", e)))?;
         
-        // For testing purposes, return mock entity IDs
-        let entity_ids = vec![
-            "func1".to_string(),
-            "func2".to_string(),
-            "class1".to_string(),
-            "var1".to_string(),
-            "doc1".to_string(),
-        ];
+        Ok(keys.into_iter()
+            .filter(|key| key.strip_prefix(&index_prefix).is_some_and(|file| matcher.is_match(file)))
+            .collect())
+    }
+    
+    /// Get the entity IDs in the requested type and file indexes asynchronously
+    ///
+    /// Types are matched case-insensitively against the existing type indexes. With
+    /// both filters set, each type index is intersected with each matching file index
+    /// in Redis (SINTER), so only candidate IDs cross the wire.
+    pub async fn filtered_entity_ids_async(&self, entity_types: Option<&[String]>, file_filter: Option<&str>) -> Result<Vec<String>, IndexerError> {
+        let client = self.client()?;
         
-        log::info!("Retrieved {} entity IDs", entity_ids.len());
-        Ok(entity_ids)
+        let type_keys: Option<Vec<String>> = match entity_types {
+            Some(types) => Some(self.type_index_keys(client).await?
+                .into_iter()
+                .filter(|(entity_type, _)| types.iter().any(|t| t.eq_ignore_ascii_case(entity_type)))
+                .map(|(_, key)| key)
+                .collect()),
+            None => None,
+        };
+        let file_keys = match file_filter {
+            Some(file_filter) => Some(self.file_index_keys(client, file_filter).await?),
+            None => None,
+        };
+        
+        let mut entity_ids = std::collections::BTreeSet::new();
+        match (type_keys, file_keys) {
+            (None, None) => return self.get_all_entity_ids_async().await,
+            (Some(keys), None) | (None, Some(keys)) => {
+                if !keys.is_empty() {
+                    let ids: Vec<String> = client.sunion(keys).await
+                        .map_err(|e| IndexerError::Redis(format!("Failed to union indexes: {}", e)))?;
+                    entity_ids.extend(ids);
+                }
+            }
+            (Some(type_keys), Some(file_keys)) => {
+                for file_key in &file_keys {
+                    for type_key in &type_keys {
+                        let ids: Vec<String> = client.sinter(vec![type_key.clone(), file_key.clone()]).await
+                            .map_err(|e| IndexerError::Redis(format!("Failed to intersect {} and {}: {}", type_key, file_key, e)))?;
+                        entity_ids.extend(ids);
+                    }
+                }
+            }
+        }
+        
+        log::info!("Filtered indexes to {} candidate entity IDs for {}", entity_ids.len(), self.key_prefix);
+        Ok(entity_ids.into_iter().collect())
     }
     
-    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, String> {
-        log::info!("VectorStore trait get_entity_vector called for {}", entity_id);
+    /// Key holding the project's embedding model metadata
+    pub fn embedding_meta_key(&self) -> String {
+        project_meta_key(&self.key_prefix)
+    }
+    
+    /// Get the project's embedding model metadata asynchronously
+    pub async fn get_embedding_meta_async(&self) -> Result<Option<EmbeddingMeta>, IndexerError> {
+        let client = self.client()?;
+        
+        let meta_json: Option<String> = client.get(self.embedding_meta_key()).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to get embedding meta: {}", e)))?;
+        meta_json
+            .map(|json| serde_json::from_str(&json).map_err(|e| IndexerError::Parse(format!("Failed to deserialize embedding meta: {}", e))))
+            .transpose()
+    }
+    
+    /// Record the project's embedding model metadata asynchronously
+    pub async fn set_embedding_meta_async(&self, meta: &EmbeddingMeta) -> Result<(), IndexerError> {
+        let client = self.client()?;
+        
+        let meta_json = serde_json::to_string(meta)
+            .map_err(|e| IndexerError::Parse(format!("Failed to serialize embedding meta: {}", e)))?;
+        let _: String = client.set(self.embedding_meta_key(), meta_json, None, None, false).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to store embedding meta: {}", e)))?;
         
-        // For testing purposes, return a mock vector based on the entity ID
-        // This ensures different entities have different vectors for similarity testing
-        let vector = match entity_id {
-            "func1" => vec![0.9, 0.1, 0.2],
-            "func2" => vec![0.8, 0.2, 0.3],
-            "class1" => vec![0.1, 0.9, 0.2],
-            "var1" => vec![0.2, 0.3, 0.9],
-            "doc1" => vec![0.5, 0.5, 0.5],
-            _ => vec![0.33, 0.33, 0.33], // default vector
+        log::info!("Recorded embedding meta for {}: {} {} (dimension {})",
+                  self.key_prefix, meta.provider, meta.model, meta.dimension);
+        Ok(())
+    }
+    
+    /// Count distinct entities across every type index asynchronously
+    pub async fn count_async(&self) -> Result<usize, IndexerError> {
+        let client = self.client()?;
+        
+        let index_keys: Vec<String> = self.type_index_keys(client).await?
+            .into_iter()
+            .map(|(_, key)| key)
+            .collect();
+        let count = match index_keys.len() {
+            0 => 0,
+            1 => client.scard::<u64, _>(&index_keys[0]).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to count index {}: {}", index_keys[0], e)))? as usize,
+            _ => client.sunion::<Vec<String>, _>(index_keys).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to union type indexes: {}", e)))?
+                .len(),
         };
         
-        log::info!("Retrieved vector for entity {}, length={}", entity_id, vector.len());
-        Ok(vector)
+        log::info!("Counted {} vector embeddings for {}", count, self.key_prefix);
+        Ok(count)
     }
     
-    fn get_entity_metadata(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, String> {
-        log::info!("VectorStore trait get_entity_metadata called for {}", entity_id);
+    /// Delete an entity's vector, metadata, and index entries asynchronously
+    /// 
+    /// Returns whether the entity was found in any type index.
+    pub async fn delete_embedding_async(&self, entity_id: &str) -> Result<bool, IndexerError> {
+        let client = self.client()?;
         
-        // For testing purposes, return mock metadata based on the entity ID
-        let mut metadata = std::collections::HashMap::new();
-        metadata.insert("id".to_string(), entity_id.to_string());
+        let Some(entity_type) = self.entity_type_of(client, entity_id).await? else {
+            return Ok(false);
+        };
+        let index_key = format!("{}:index:{}", self.key_prefix, entity_type);
+        let vector_key = self.make_key(&entity_type, entity_id);
+        let metadata_key = format!("{}.metadata", vector_key);
         
-        // Special case for entity1 which is used in the test_store_embeddings_with_metadata test
-        if entity_id == "entity1" {
-            metadata.insert("type".to_string(), "function".to_string());
-            metadata.insert("file".to_string(), "file.py".to_string());
-        } else {
-            // Determine entity type from the entity ID prefix for other entities
-            let entity_type = if entity_id.starts_with("func") {
-                "function"
-            } else if entity_id.starts_with("class") {
-                "class"
-            } else if entity_id.starts_with("var") {
-                "variable"
-            } else if entity_id.starts_with("doc") {
-                "docstring"
-            } else {
-                "unknown"
-            };
-            
-            metadata.insert("type".to_string(), entity_type.to_string());
-            metadata.insert("file".to_string(), "test.py".to_string());
+        // Look up the file so the file index entry can be removed too
+        let metadata_json: Option<String> = client.get(&metadata_key).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to get metadata: {}", e)))?;
+        let file_path = metadata_json
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+            .and_then(|meta| meta.get("file").and_then(|f| f.as_str()).map(|f| f.to_string()));
+        
+        let _: u64 = client.del(vec![vector_key, metadata_key]).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to delete vector: {}", e)))?;
+        let _: u64 = client.srem(&index_key, entity_id).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to remove from type index: {}", e)))?;
+        let _: u64 = client.hdel(self.entity_type_key(), entity_id).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to remove entity type: {}", e)))?;
+        if let Some(file_path) = file_path {
+            let file_index_key = format!("{}:file_index:{}", self.key_prefix, file_path);
+            let _: u64 = client.srem(&file_index_key, entity_id).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to remove from file index: {}", e)))?;
         }
         
-        metadata.insert("vector_length".to_string(), "3".to_string());
+        log::info!("Deleted vector embedding for entity {}", entity_id);
+        Ok(true)
+    }
+    
+    /// Delete shared vectors that no entity references any more, returning how many
+    /// were removed
+    ///
+    /// Deleting an entity leaves the shared vector it pointed at in place, since
+    /// other entities may still use it; `forget` and `prune` call this afterwards.
+    pub async fn sweep_shared_vectors_async(&self) -> Result<usize, IndexerError> {
+        let client = self.client()?;
         
-        log::info!("Retrieved metadata for entity {} of type {}", entity_id, metadata.get("type").unwrap_or(&"unknown".to_string()));
-        Ok(metadata)
+        let shared_prefix = self.shared_vector_key("");
+        let shared_keys = scan_keys(client, &format!("{}*", shared_prefix)).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to scan shared vectors: {}", e)))?;
+        if shared_keys.is_empty() {
+            return Ok(0);
+        }
+        
+        let mut referenced = std::collections::HashSet::new();
+        for (entity_type, index_key) in self.type_index_keys(client).await? {
+            let entity_ids: Vec<String> = client.smembers(&index_key).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to read index {}: {}", index_key, e)))?;
+            if entity_ids.is_empty() {
+                continue;
+            }
+            let vector_keys: Vec<String> = entity_ids.iter().map(|id| self.make_key(&entity_type, id)).collect();
+            let vectors: Vec<Option<Vec<u8>>> = client.mget(vector_keys).await
+                .map_err(|e| IndexerError::Redis(format!("Failed to get vectors for {}: {}", index_key, e)))?;
+            referenced.extend(vectors.iter().flatten().filter_map(|bytes| shared_vector_hash(bytes).map(str::to_string)));
+        }
+        
+        let unreferenced: Vec<String> = shared_keys.into_iter()
+            .filter(|key| key.strip_prefix(&shared_prefix).is_some_and(|hash| !referenced.contains(hash)))
+            .collect();
+        if unreferenced.is_empty() {
+            return Ok(0);
+        }
+        let removed: u64 = client.del(unreferenced).await
+            .map_err(|e| IndexerError::Redis(format!("Failed to delete shared vectors: {}", e)))?;
+        
+        log::info!("Swept {} unreferenced shared vectors for {}", removed, self.key_prefix);
+        Ok(removed as usize)
+    }
+}
+
+impl VectorStore for RedisVectorStore {
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+        log::info!("VectorStore trait upsert_embedding called for {}", entity_id);
+        self.check_dimension(entity_id, embedding)?;
+        self.run(RedisVectorStore::upsert_embedding_async(self, entity_id, embedding, file, entity_type))
+    }
+    
+    fn upsert_embedding_with_details(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>, details: &EntityDetails) -> Result<(), IndexerError> {
+        self.check_dimension(entity_id, embedding)?;
+        self.run(RedisVectorStore::upsert_embedding_with_details_async(self, entity_id, embedding, file, entity_type, details))
+    }
+    
+    fn upsert_batch(&self, items: &[UpsertItem]) -> Result<(), IndexerError> {
+        log::info!("VectorStore trait upsert_batch called with {} items", items.len());
+        self.run(RedisVectorStore::upsert_batch_async(self, items))
+    }
+    
+    fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+        log::info!("VectorStore trait similarity_search called with top_k={}", top_k);
+        self.run(async { Ok(RedisVectorStore::similarity_search_async(self, query, top_k).await) })
+            .unwrap_or_else(|e| {
+                log::error!("Similarity search failed: {}", e);
+                vec![]
+            })
+    }
+    
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+        log::info!("VectorStore trait get_all_entity_ids called");
+        self.run(self.get_all_entity_ids_async())
+    }
+    
+    fn filtered_entity_ids(&self, entity_types: Option<&[String]>, file_filter: Option<&str>) -> Result<Vec<String>, IndexerError> {
+        log::info!("VectorStore trait filtered_entity_ids called with entity_types={:?}, file_filter={:?}", entity_types, file_filter);
+        self.run(self.filtered_entity_ids_async(entity_types, file_filter))
+    }
+    
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+        log::info!("VectorStore trait get_entity_vector called for {}", entity_id);
+        self.run(self.get_entity_vector_async(entity_id))
+    }
+    
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, IndexerError> {
+        log::info!("VectorStore trait get_entity_metadata called for {}", entity_id);
+        self.run(self.get_entity_metadata_async(entity_id))
+    }
+    
+    fn get_vectors_batch(&self, entity_ids: &[String]) -> Result<Vec<Result<EntityVector, IndexerError>>, IndexerError> {
+        log::info!("VectorStore trait get_vectors_batch called for {} entities", entity_ids.len());
+        self.run(self.get_vectors_batch_async(entity_ids))
+    }
+    
+    fn count(&self) -> Result<usize, IndexerError> {
+        self.run(self.count_async())
+    }
+    
+    fn delete_embedding(&self, entity_id: &str) -> Result<bool, IndexerError> {
+        self.run(self.delete_embedding_async(entity_id))
+    }
+    
+    fn replace_all(&self, items: &[UpsertItem], meta: &EmbeddingMeta) -> Result<usize, IndexerError> {
+        self.run(self.replace_all_async(items, meta))
+    }
+
+    fn get_embedding_meta(&self) -> Result<Option<EmbeddingMeta>, IndexerError> {
+        self.run(self.get_embedding_meta_async())
+    }
+    
+    fn set_embedding_meta(&self, meta: &EmbeddingMeta) -> Result<(), IndexerError> {
+        log::info!("VectorStore trait set_embedding_meta called for model {}", meta.model);
+        self.run(self.set_embedding_meta_async(meta))
+    }
+}
+
+/// Vector and metadata stored per entity in `InMemoryVectorStore`
+type InMemoryEntry = (Vec<f32>, std::collections::HashMap<String, String>);
+
+/// In-memory vector store for tests and offline use
+/// 
+/// Keeps each entity's vector and metadata in a `HashMap` behind an `RwLock`,
+/// so it implements the full `VectorStore` trait without a Redis server.
+#[derive(Debug, Default)]
+pub struct InMemoryVectorStore {
+    entries: std::sync::RwLock<std::collections::HashMap<String, InMemoryEntry>>,
+    embedding_meta: std::sync::RwLock<Option<EmbeddingMeta>>,
+}
+
+impl InMemoryVectorStore {
+    /// Create an empty in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored entities
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    /// Whether the store holds no entities
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl VectorStore for InMemoryVectorStore {
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+        self.upsert_embedding_with_details(entity_id, embedding, file, entity_type, &EntityDetails::default())
+    }
+
+    fn upsert_embedding_with_details(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>, details: &EntityDetails) -> Result<(), IndexerError> {
+        if embedding.is_empty() {
+            return Err(IndexerError::Embed(format!("Refusing to store empty embedding for {}", entity_id)));
+        }
+
+        let entity_type = entity_type.unwrap_or("unknown");
+        let file_path = file.unwrap_or("unknown");
+
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("id".to_string(), entity_id.to_string());
+        metadata.insert("type".to_string(), entity_type.to_string());
+        metadata.insert("file".to_string(), file_path.to_string());
+        metadata.insert("vector_length".to_string(), embedding.len().to_string());
+        for (key, value) in details.metadata_fields() {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            metadata.insert(key.to_string(), value);
+        }
+
+        let mut entries = self.entries.write()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        entries.insert(entity_id.to_string(), (embedding.to_vec(), metadata));
+
+        log::info!("Stored in-memory embedding for entity {} of type {} from file {}",
+                  entity_id, entity_type, file_path);
+        Ok(())
+    }
+
+    fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+        let entries = match self.entries.read() {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::error!("In-memory store lock poisoned: {}", e);
+                return vec![];
+            }
+        };
+
+        let mut scored: Vec<(&String, f32)> = entries.iter()
+            .map(|(id, (vector, _))| (id, crate::vector_search::cosine_similarity(query, vector)))
+            .collect();
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.0.cmp(b.0)));
+
+        scored.into_iter().take(top_k).map(|(id, _)| id.clone()).collect()
+    }
+
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+        let entries = self.entries.read()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        Ok(entries.keys().cloned().collect())
+    }
+
+    fn filtered_entity_ids(&self, entity_types: Option<&[String]>, file_filter: Option<&str>) -> Result<Vec<String>, IndexerError> {
+        let file_matcher = file_filter.map(crate::vector_search::file_glob_matcher).transpose()?;
+        let entries = self.entries.read()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        Ok(entries.iter()
+            .filter(|(_, (_, metadata))| {
+                let type_ok = entity_types.is_none_or(|types| {
+                    metadata.get("type").is_some_and(|t| types.iter().any(|wanted| wanted.eq_ignore_ascii_case(t)))
+                });
+                let file_ok = file_matcher.as_ref().is_none_or(|matcher| {
+                    metadata.get("file").is_some_and(|f| matcher.is_match(f))
+                });
+                type_ok && file_ok
+            })
+            .map(|(id, _)| id.clone())
+            .collect())
+    }
+
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+        let entries = self.entries.read()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        entries.get(entity_id)
+            .map(|(vector, _)| vector.clone())
+            .ok_or_else(|| IndexerError::Store(format!("No vector stored for entity {}", entity_id)))
+    }
+
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, IndexerError> {
+        let entries = self.entries.read()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        entries.get(entity_id)
+            .map(|(_, metadata)| metadata.clone())
+            .ok_or_else(|| IndexerError::Store(format!("No metadata stored for entity {}", entity_id)))
+    }
+
+    fn count(&self) -> Result<usize, IndexerError> {
+        let entries = self.entries.read()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        Ok(entries.len())
+    }
+
+    fn delete_embedding(&self, entity_id: &str) -> Result<bool, IndexerError> {
+        let mut entries = self.entries.write()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        Ok(entries.remove(entity_id).is_some())
+    }
+
+    fn replace_all(&self, items: &[UpsertItem], meta: &EmbeddingMeta) -> Result<usize, IndexerError> {
+        // Build the new entries aside so the swap itself cannot fail halfway
+        let staged = InMemoryVectorStore::new();
+        staged.upsert_batch(items)?;
+        let staged = staged.entries.into_inner()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+
+        let mut entries = self.entries.write()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        let mut current = self.embedding_meta.write()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        let removed = std::mem::replace(&mut *entries, staged).len();
+        *current = Some(meta.clone());
+        Ok(removed)
+    }
+
+    fn get_embedding_meta(&self) -> Result<Option<EmbeddingMeta>, IndexerError> {
+        let meta = self.embedding_meta.read()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        Ok(meta.clone())
+    }
+
+    fn set_embedding_meta(&self, meta: &EmbeddingMeta) -> Result<(), IndexerError> {
+        let mut current = self.embedding_meta.write()
+            .map_err(|e| IndexerError::Store(format!("In-memory store lock poisoned: {}", e)))?;
+        *current = Some(meta.clone());
+        Ok(())
     }
 }
@@ -3,77 +3,530 @@
 //! This module provides functionality for the vectorize command, which extracts
 //! code entities from files, generates embeddings, and stores them in a vector database.
 
+use crate::ast_parser::{parse_code_info, CodeEntity, ParseDiagnostic};
+use crate::batch_processing::batch_embed_entities;
 use crate::cli::{CliArgs, Commands};
 use crate::embedder::Embedder;
-use crate::vector_store::VectorStore;
+use crate::emit::EmbeddingEmitter;
+use crate::error::IndexerError;
+use crate::config::AppConfig;
+use crate::file_processing::{collect_source_files, default_source_extensions, DEFAULT_MAX_FILE_BYTES};
+use crate::redis_ops::{create_redis_client, query_code_entity, ENTITY_TYPES};
+use crate::vector_store::{text_hash, EmbeddingMeta, EntityDetails, UpsertItem, VectorStore};
+use fred::prelude::Client;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::fs;
-use log::{info, debug};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use log::{info, debug, warn};
 
-/// Process a single file for vectorization
+/// Safe-mode check applied to every embedding before it reaches the store
 /// 
-/// Extracts entities from the file, generates embeddings, and stores them in the vector store.
+/// Rejects empty, all-zero, or non-finite vectors and vectors whose dimension
+/// differs from the first accepted embedding of the run, so one bad embedder
+/// response cannot poison the index.
+#[derive(Debug, Default)]
+struct EmbeddingGuard {
+    /// Dimension locked in by the first accepted embedding
+    expected_dim: Option<usize>,
+    /// Number of embeddings rejected so far
+    rejected: usize,
+}
+
+impl EmbeddingGuard {
+    /// Validate an embedding, counting it as rejected on failure
+    /// 
+    /// # Returns
+    /// * `Result<(), String>` - Ok if the embedding may be stored, or the reason it was rejected
+    fn check(&mut self, embedding: &[f32]) -> Result<(), String> {
+        let result = is_valid_embedding(embedding, self.expected_dim);
+        match result {
+            Ok(()) => {
+                self.expected_dim.get_or_insert(embedding.len());
+            }
+            Err(_) => self.rejected += 1,
+        }
+        result
+    }
+}
+
+/// State accumulated across the files of one vectorize run
+#[derive(Debug, Default)]
+struct VectorizeRun {
+    /// Safe-mode guard shared by every file in the run
+    guard: EmbeddingGuard,
+    /// Python files that failed to parse
+    diagnostics: Vec<ParseDiagnostic>,
+    /// Files, entities, or store writes that failed without stopping the run
+    errors: Vec<String>,
+    /// Checked embeddings waiting for the next `flush_pending`
+    pending: Vec<UpsertItem>,
+    /// Set when the deadline passed before every file was started
+    timed_out: bool,
+    /// Entities still allowed by `--limit`, or `None` for no limit
+    remaining: Option<usize>,
+}
+
+impl VectorizeRun {
+    /// Run that accepts at most `limit` entities
+    fn with_limit(limit: Option<usize>) -> Self {
+        VectorizeRun { remaining: limit, ..Default::default() }
+    }
+    
+    /// Whether `--limit` entities have been accepted
+    fn limit_reached(&self) -> bool {
+        self.remaining == Some(0)
+    }
+}
+
+/// IDs of the entities the store already has vectors for, when `only_missing` is set
 /// 
-/// # Arguments
-/// * `file_path` - Path to the file to process
-/// * `embedder` - Embedder to use for generating embeddings
-/// * `store` - Vector store to store embeddings in
-/// * `dry_run` - If true, don't actually store embeddings
-/// * `verbose` - If true, log more information
+/// Like `--plan`, this reads the ID list once instead of querying every candidate.
+fn existing_entity_ids<V: VectorStore + ?Sized>(store: &V, only_missing: bool) -> Result<Option<HashSet<String>>, IndexerError> {
+    if !only_missing {
+        return Ok(None);
+    }
+    let existing: HashSet<String> = store.get_all_entity_ids()?.into_iter().collect();
+    info!("Skipping the {} entities that already have vectors", existing.len());
+    Ok(Some(existing))
+}
+
+/// Take up to `wanted` entities from the `--limit` budget, returning how many were granted
+fn take_budget(budget: &AtomicUsize, wanted: usize) -> usize {
+    let mut granted = 0;
+    let _ = budget.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+        granted = left.min(wanted);
+        Some(left - granted)
+    });
+    granted
+}
+
+/// Whether `deadline` has passed
+fn past_deadline(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+/// Outcome of vectorizing a directory
+#[derive(Debug, Default)]
+pub struct VectorizeReport {
+    /// Number of entities embedded (and stored, unless dry-running)
+    pub processed: usize,
+    /// Python files that failed to parse, sorted by file
+    pub diagnostics: Vec<ParseDiagnostic>,
+    /// Failures that were skipped over, in the order they happened
+    pub errors: Vec<String>,
+    /// The run stopped at its deadline; `processed` entities were still stored
+    pub timed_out: bool,
+}
+
+/// Check that an embedding is non-empty, finite, not all zeros, and of the expected dimension
+fn is_valid_embedding(embedding: &[f32], expected_dim: Option<usize>) -> Result<(), String> {
+    if embedding.is_empty() {
+        return Err("embedding is empty".to_string());
+    }
+    if let Some(dim) = expected_dim {
+        if embedding.len() != dim {
+            return Err(format!("embedding dimension {} does not match expected {}", embedding.len(), dim));
+        }
+    }
+    if embedding.iter().any(|x| !x.is_finite()) {
+        return Err("embedding contains NaN or infinite values".to_string());
+    }
+    if embedding.iter().all(|&x| x == 0.0) {
+        return Err("embedding is all zeros".to_string());
+    }
+    Ok(())
+}
+
+/// Entity types selected for embedding with `--entity-types` and `--exclude-entity-types`
 /// 
-/// # Returns
-/// * `Result<usize, String>` - Number of entities processed or an error
-fn process_file<E: Embedder, V: VectorStore>(
+/// The default selects every type.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EntityTypeFilter {
+    /// Types to embed; `None` embeds every type not excluded
+    pub include: Option<Vec<String>>,
+    /// Types never embedded, even if also included
+    pub exclude: Vec<String>,
+}
+
+impl EntityTypeFilter {
+    pub fn new(include: Option<Vec<String>>, exclude: Option<Vec<String>>) -> Self {
+        Self { include, exclude: exclude.unwrap_or_default() }
+    }
+    
+    /// Whether entities of `entity_type` should be embedded
+    pub fn allows(&self, entity_type: &str) -> bool {
+        self.include.as_ref().is_none_or(|include| include.iter().any(|t| t == entity_type))
+            && !self.exclude.iter().any(|t| t == entity_type)
+    }
+}
+
+/// What `vectorize` embeds as one vector, chosen with `--granularity`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Granularity {
+    /// One vector per function, class, or other extracted entity
+    #[default]
+    Entity,
+    /// One vector per file, of its whole content, with entity type `file` and the
+    /// path relative to the walked directory as ID
+    File,
+}
+
+/// Settings of a vectorize run, shared by the directory walkers and
+/// `vectorize_from_index`
+#[derive(Debug, Clone)]
+pub struct VectorizeOptions {
+    /// Number of files whose embeddings are written together with one `upsert_batch` call
+    pub batch_size: usize,
+    /// Maximum number of embedding requests in flight for each file, or for
+    /// `process_directory_concurrent`, of files embedded at once
+    pub concurrency: usize,
+    /// Files larger than this are skipped
+    pub max_file_bytes: u64,
+    /// Extensions of the files to include, without the leading dot
+    pub extensions: Vec<String>,
+    /// Embed without storing anything
+    pub dry_run: bool,
+    /// Log more information
+    pub verbose: bool,
+    /// Also embed TODO/FIXME/XXX comments as `todo` entities
+    pub index_todos: bool,
+    /// Entity types to embed
+    pub types: EntityTypeFilter,
+    /// Skip entities the store already has a vector for
+    pub only_missing: bool,
+    /// No new file is started after this; what was embedded so far is stored
+    pub deadline: Option<Instant>,
+    /// Store at most this many entities
+    pub limit: Option<usize>,
+    /// Whether to embed each entity or each whole file
+    pub granularity: Granularity,
+}
+
+impl Default for VectorizeOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 10,
+            concurrency: DEFAULT_CONCURRENCY,
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            extensions: default_source_extensions(),
+            dry_run: false,
+            verbose: false,
+            index_todos: false,
+            types: EntityTypeFilter::default(),
+            only_missing: false,
+            deadline: None,
+            limit: None,
+            granularity: Granularity::default(),
+        }
+    }
+}
+
+/// The single `file` entity embedding the whole of `content`
+fn file_entity(content: &str, file_path: &Path, root: &Path) -> (String, String, String) {
+    let rel_path = file_path.strip_prefix(root).unwrap_or(file_path).to_string_lossy().to_string();
+    let entity_text = format!("file {}\n{}", rel_path, content);
+    (rel_path, entity_text, "file".to_string())
+}
+
+/// Parse a Python source with `ast_parser`, or `None` for other languages
+fn parse_python(content: &str, file_path: &Path) -> Option<Result<Vec<CodeEntity>, ParseDiagnostic>> {
+    matches!(file_path.extension().and_then(|e| e.to_str()), Some("py" | "pyi"))
+        .then(|| parse_code_info(content, &file_path.to_string_lossy()))
+}
+
+/// Extract a file's entities (and TODO comments if `index_todos`), keeping the
+/// types `types` selects
+/// 
+/// Entities `parsed` by `ast_parser` are used when given, so methods carry their
+/// class; otherwise the line-based `extract_entities` fallback is used.
+fn extract_selected(
+    content: &str,
+    file_path: &Path,
+    parsed: Option<&[CodeEntity]>,
+    index_todos: bool,
+    types: &EntityTypeFilter,
+) -> Result<Vec<(String, String, String)>, IndexerError> {
+    let mut entities = match parsed {
+        Some(parsed) => parsed.iter().map(index_entity).collect(),
+        None => extract_entities(content, file_path)?,
+    };
+    if index_todos {
+        entities.extend(extract_todo_comments(content, file_path));
+    }
+    entities.retain(|(_, _, entity_type)| types.allows(entity_type));
+    Ok(entities)
+}
+
+/// Progress of a directory walk, reported to the caller's callback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// The walk took up `path`
+    FileStarted { path: PathBuf },
+    /// An entity of `path` was embedded and queued for storage
+    EntityEmbedded { path: PathBuf, entity_id: String },
+    /// The walk is done with `path`, of which `entities` were queued for storage
+    FileFinished { path: PathBuf, entities: usize },
+}
+
+/// Progress callback for the CLI: logs each finished file with the running totals,
+/// at info level when `verbose` and at debug level otherwise
+pub fn progress_logger(verbose: bool) -> impl FnMut(ProgressEvent) {
+    let (mut files, mut entities) = (0, 0);
+    move |event| {
+        if let ProgressEvent::FileFinished { path, entities: in_file } = event {
+            files += 1;
+            entities += in_file;
+            let level = if verbose { log::Level::Info } else { log::Level::Debug };
+            log::log!(level, "Embedded {} entities from {} ({} files, {} entities so far)", in_file, path.display(), files, entities);
+        }
+    }
+}
+
+/// Entities of one file with their embeddings, ready to be stored
+#[derive(Debug)]
+struct PreparedFile {
+    path: PathBuf,
+    /// `(entity_id, entity_type, embedding)` for every extracted entity
+    embeddings: Vec<(String, String, Vec<f32>)>,
+    /// Line range and signature of the entities `ast_parser` parsed, by entity ID
+    details: HashMap<String, EntityDetails>,
+    /// Parse failure of a Python file, if any
+    diagnostic: Option<ParseDiagnostic>,
+    /// Entities that failed to embed
+    errors: Vec<String>,
+}
+
+/// Read a file, extract its entities, and embed them without touching the store
+/// 
+/// This is the expensive, store-independent half of `process_file`, so it can run
+/// on worker threads. A file's entities are embedded with one `embed_batch` call
+/// through `batch_embed_entities`, falling back to one request per entity with up to
+/// `concurrency` in flight; those whose IDs are in `existing` are skipped, and only
+/// the first `max_entities` are embedded. At file granularity
+/// the whole file is one entity, identified by its path relative to `root`.
+#[allow(clippy::too_many_arguments)]
+fn prepare_file<E: Embedder + ?Sized>(
     file_path: &Path,
+    root: &Path,
+    granularity: Granularity,
     embedder: &E,
-    store: &V,
-    dry_run: bool,
+    concurrency: usize,
     verbose: bool,
-) -> Result<usize, String> {
+    index_todos: bool,
+    types: &EntityTypeFilter,
+    existing: Option<&HashSet<String>>,
+    budget: Option<&AtomicUsize>,
+) -> Result<PreparedFile, IndexerError> {
     // Read the file content
     let content = fs::read_to_string(file_path)
-        .map_err(|e| format!("Failed to read file {}: {}", file_path.display(), e))?;
+        .map_err(|e| IndexerError::Io(std::io::Error::new(e.kind(), format!("Failed to read file {}: {}", file_path.display(), e))))?;
+    
+    // Record where Python sources fail to parse instead of silently skipping them
+    let mut diagnostic = None;
+    let parsed = match (granularity == Granularity::Entity).then(|| parse_python(&content, file_path)).flatten() {
+        Some(Ok(parsed)) => Some(parsed),
+        Some(Err(d)) => {
+            warn!("Parse error in {}", d);
+            diagnostic = Some(d);
+            None
+        }
+        None => None,
+    };
     
-    // Extract entities from the file
-    // For now, we'll just use a simple approach - in a real implementation,
-    // we would use a proper parser to extract functions, classes, etc.
-    let entities = extract_entities(&content, file_path)?;
+    // Extract entities from the file, dropping unselected types before embedding
+    let mut entities = match granularity {
+        Granularity::Entity => extract_selected(&content, file_path, parsed.as_deref(), index_todos, types)?,
+        Granularity::File => Some(file_entity(&content, file_path, root))
+            .filter(|(_, _, entity_type)| types.allows(entity_type))
+            .into_iter()
+            .collect(),
+    };
+    if let Some(existing) = existing {
+        entities.retain(|(entity_id, _, _)| !existing.contains(entity_id));
+    }
+    if let Some(budget) = budget {
+        entities.truncate(take_budget(budget, entities.len()));
+    }
     
     if verbose {
         info!("Extracted {} entities from {}", entities.len(), file_path.display());
     }
     
-    // Process each entity
+    // Hash each text before the batch takes it, so stores can share identical vectors
+    let text_hashes: Vec<(String, String)> = entities.iter()
+        .map(|(entity_id, entity_text, _)| (entity_id.clone(), text_hash(entity_text)))
+        .collect();
+    
+    let (embeddings, errors) = futures::executor::block_on(batch_embed_entities(
+        entities,
+        embedder,
+        concurrency,
+        |done, total| {
+            if verbose {
+                debug!("Embedded {}/{} entities of {}", done, total, file_path.display());
+            }
+        },
+    ));
+    let errors = errors.into_iter()
+        .map(|e| format!("{}: {}", file_path.display(), e))
+        .collect();
+    
+    let mut details: HashMap<String, EntityDetails> = parsed.iter().flatten()
+        .map(|entity| (index_entity_id(entity), EntityDetails::from_entity(entity)))
+        .collect();
+    if granularity == Granularity::File {
+        for (entity_id, _, _) in &embeddings {
+            let line_end = content.lines().count().max(1);
+            details.insert(entity_id.clone(), EntityDetails { line_start: Some(1), line_end: Some(line_end), ..EntityDetails::default() });
+        }
+    }
+    for (entity_id, hash) in text_hashes {
+        details.entry(entity_id).or_default().text_hash = Some(hash);
+    }
+    
+    Ok(PreparedFile { path: file_path.to_path_buf(), embeddings, details, diagnostic, errors })
+}
+
+/// Check the embeddings of a prepared file and queue them for storage
+/// 
+/// Embeddings that fail the safe-mode guard are logged and skipped; the rest are
+/// added to the run's pending batch, which `flush_pending` writes. Once the run's
+/// limit is reached, further embeddings are dropped. The file's parse diagnostic
+/// and embedding failures are recorded in the run instead of aborting it. Each
+/// accepted entity is reported to `progress` as `EntityEmbedded`.
+/// 
+/// # Returns
+/// * `usize` - Number of entities accepted
+fn queue_prepared(
+    prepared: PreparedFile,
+    dry_run: bool,
+    run: &mut VectorizeRun,
+    progress: &mut dyn FnMut(ProgressEvent),
+) -> usize {
+    let file_path = prepared.path;
+    let mut details = prepared.details;
+    run.diagnostics.extend(prepared.diagnostic);
+    run.errors.extend(prepared.errors);
+    
     let mut processed_count = 0;
-    for (entity_id, entity_text, entity_type) in entities {
-        // Generate embedding
-        let embedding = embedder.embed(&entity_text);
+    for (entity_id, entity_type, embedding) in prepared.embeddings {
+        if run.limit_reached() {
+            debug!("Limit reached, dropping embedding for {}", entity_id);
+            continue;
+        }
         
-        if verbose {
-            debug!("Generated embedding for {} ({})", entity_id, entity_type);
+        // Skip embeddings that would poison the index
+        if let Err(reason) = run.guard.check(&embedding) {
+            warn!("Rejected embedding for {} in {}: {}", entity_id, file_path.display(), reason);
+            continue;
         }
         
-        // Store embedding if not in dry-run mode
+        progress(ProgressEvent::EntityEmbedded { path: file_path.clone(), entity_id: entity_id.clone() });
+        
+        // Queue the embedding for storage if not in dry-run mode
         if !dry_run {
             let file_path_str = file_path.to_string_lossy().to_string();
-            store.upsert_embedding(
-                &entity_id,
-                &embedding,
-                Some(&file_path_str),
-                Some(&entity_type),
-            )?;
-            
-            if verbose {
-                debug!("Stored embedding for {}", entity_id);
-            }
-        } else if verbose {
-            debug!("Dry run: Would store embedding for {}", entity_id);
+            let details = details.remove(&entity_id).unwrap_or_default();
+            run.pending.push((entity_id, embedding, Some(file_path_str), Some(entity_type), details));
+        } else {
+            debug!("Dry run: Would store embedding for {} ({}) from {}", entity_id, entity_type, file_path.display());
         }
         
         processed_count += 1;
+        if let Some(remaining) = run.remaining.as_mut() {
+            *remaining -= 1;
+        }
     }
     
-    Ok(processed_count)
+    processed_count
+}
+
+/// Write the run's pending embeddings with one `upsert_batch` call
+/// 
+/// If the batch write fails, the embeddings are retried one at a time so a single
+/// bad entity does not lose the rest; the ones that still fail are recorded in the run.
+/// 
+/// # Returns
+/// * `usize` - Number of pending embeddings that could not be stored
+fn flush_pending<V: VectorStore + ?Sized>(store: &V, verbose: bool, run: &mut VectorizeRun) -> usize {
+    let batch = std::mem::take(&mut run.pending);
+    if batch.is_empty() {
+        return 0;
+    }
+    match store.upsert_batch(&batch) {
+        Ok(()) => {
+            if verbose {
+                debug!("Stored batch of {} embeddings", batch.len());
+            }
+            0
+        }
+        Err(e) => {
+            warn!("Failed to store batch of {} embeddings, retrying one at a time: {}", batch.len(), e);
+            let mut failed = 0;
+            for (entity_id, embedding, file, entity_type, details) in &batch {
+                if let Err(e) = store.upsert_embedding_with_details(entity_id, embedding, file.as_deref(), entity_type.as_deref(), details) {
+                    warn!("Failed to store embedding for {}: {}", entity_id, e);
+                    run.errors.push(format!("{}: {}: {}", file.as_deref().unwrap_or("unknown"), entity_id, e));
+                    failed += 1;
+                }
+            }
+            failed
+        }
+    }
+}
+
+/// Process a single file for vectorization
+/// 
+/// Extracts entities from the file, generates embeddings, and stores them in the vector store.
+/// Only the unit tests use this; the directory walkers split it into
+/// `prepare_file`, `queue_prepared`, and `flush_pending`.
+/// Embeddings that fail the safe-mode guard are logged and skipped, Python files
+/// that fail to parse are recorded as diagnostics, and entities that fail to embed
+/// or store are recorded as run errors.
+/// 
+/// # Arguments
+/// * `file_path` - Path to the file to process
+/// * `embedder` - Embedder to use for generating embeddings
+/// * `store` - Vector store to store embeddings in
+/// * `concurrency` - Maximum number of embedding requests in flight
+/// * `dry_run` - If true, don't actually store embeddings
+/// * `verbose` - If true, log more information
+/// * `index_todos` - If true, also embed TODO/FIXME/XXX comments as `todo` entities
+/// * `types` - Entity types to embed
+/// * `run` - Run state holding the safe-mode guard and collected parse diagnostics
+/// * `progress` - Called with `FileStarted`, each `EntityEmbedded`, and `FileFinished`
+/// 
+/// # Returns
+/// * `Result<usize, IndexerError>` - Number of entities processed, or an error if the
+///   file could not be read
+#[cfg(test)]
+#[allow(clippy::too_many_arguments)]
+fn process_file<E: Embedder + ?Sized, V: VectorStore + ?Sized>(
+    file_path: &Path,
+    embedder: &E,
+    store: &V,
+    concurrency: usize,
+    dry_run: bool,
+    verbose: bool,
+    index_todos: bool,
+    types: &EntityTypeFilter,
+    run: &mut VectorizeRun,
+    progress: &mut dyn FnMut(ProgressEvent),
+) -> Result<usize, IndexerError> {
+    progress(ProgressEvent::FileStarted { path: file_path.to_path_buf() });
+    let prepared = prepare_file(file_path, file_path, Granularity::Entity, embedder, concurrency, verbose, index_todos, types, None, None);
+    let accepted = match prepared {
+        Ok(prepared) => queue_prepared(prepared, dry_run, run, progress),
+        Err(e) => {
+            progress(ProgressEvent::FileFinished { path: file_path.to_path_buf(), entities: 0 });
+            return Err(e);
+        }
+    };
+    progress(ProgressEvent::FileFinished { path: file_path.to_path_buf(), entities: accepted });
+    Ok(accepted - flush_pending(store, verbose, run))
 }
 
 /// Extract entities from file content
@@ -83,8 +536,8 @@ fn process_file<E: Embedder, V: VectorStore>(
 /// * `file_path` - Path to the file (used for entity ID generation)
 /// 
 /// # Returns
-/// * `Result<Vec<(String, String, String)>, String>` - Vector of (entity_id, entity_text, entity_type) tuples
-fn extract_entities(content: &str, file_path: &Path) -> Result<Vec<(String, String, String)>, String> {
+/// * `Result<Vec<(String, String, String)>, IndexerError>` - Vector of (entity_id, entity_text, entity_type) tuples
+fn extract_entities(content: &str, file_path: &Path) -> Result<Vec<(String, String, String)>, IndexerError> {
     let file_name = file_path.file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown");
@@ -94,7 +547,7 @@ fn extract_entities(content: &str, file_path: &Path) -> Result<Vec<(String, Stri
     let mut entities = Vec::new();
     
     // Simple extraction of function-like patterns
-    for (_i, line) in content.lines().enumerate() {
+    for line in content.lines() {
         if line.contains("fn ") || line.contains("def ") {
             // Extract function name (very simplified)
             let parts: Vec<&str> = line.split(&['(', ' '][..]).collect();
@@ -125,102 +578,698 @@ fn extract_entities(content: &str, file_path: &Path) -> Result<Vec<(String, Stri
     Ok(entities)
 }
 
+/// Comment markers that flag a line as tech debt
+const TODO_MARKERS: &[&str] = &["TODO", "FIXME", "XXX"];
+
+/// Line comment marker of a source file, by extension: `#` for Python, `//` for Rust
+fn line_comment_marker(file_path: &Path) -> Option<&'static str> {
+    match file_path.extension().and_then(|e| e.to_str()) {
+        Some("py") | Some("pyi") => Some("#"),
+        Some("rs") => Some("//"),
+        _ => None,
+    }
+}
+
+/// Byte offset of the first `marker` in `line` outside a string literal
+///
+/// Only single-line literals delimited by `quotes` are tracked, with backslash
+/// escapes; that covers the URLs and format strings that hold a `#` or `//`.
+fn find_line_comment(line: &str, marker: &str, quotes: &[char]) -> Option<usize> {
+    let mut open: Option<char> = None;
+    let mut escaped = false;
+    for (pos, c) in line.char_indices() {
+        match open {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(quote) if c == quote => open = None,
+            Some(_) => {}
+            None if quotes.contains(&c) => open = Some(c),
+            None if line[pos..].starts_with(marker) => return Some(pos),
+            None => {}
+        }
+    }
+    None
+}
+
+/// Extract TODO/FIXME/XXX comments from file content
+/// 
+/// The Python parser drops comments, so this scans raw lines for `#` (Python)
+/// or `//` (Rust) comments containing one of the markers, skipping markers inside
+/// string literals. Files of other types have no TODOs extracted.
+/// 
+/// # Arguments
+/// * `content` - Content of the file
+/// * `file_path` - Path to the file (used for entity ID generation and its comment syntax)
+/// 
+/// # Returns
+/// * `Vec<(String, String, String)>` - Vector of (entity_id, entity_text, entity_type) tuples,
+///   where the entity ID carries the 1-based line number and the text is the comment body
+fn extract_todo_comments(content: &str, file_path: &Path) -> Vec<(String, String, String)> {
+    let file_name = file_path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown");
+    let Some(marker) = line_comment_marker(file_path) else {
+        return Vec::new();
+    };
+    // A Rust `'` also starts lifetimes, so only double quotes delimit its strings
+    let quotes: &[char] = if marker == "#" { &['"', '\''] } else { &['"'] };
+    
+    let mut todos = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let comment = match find_line_comment(line, marker, quotes) {
+            Some(pos) => line[pos + marker.len()..].trim_start_matches(['#', '/']).trim(),
+            None => continue,
+        };
+        
+        let has_marker = comment
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|word| TODO_MARKERS.contains(&word));
+        if has_marker {
+            let entity_id = format!("todo:{}:{}", file_name, i + 1);
+            todos.push((entity_id, comment.to_string(), "todo".to_string()));
+        }
+    }
+    
+    todos
+}
+
 /// Walk a directory recursively and process all files
 /// 
 /// # Arguments
 /// * `dir_path` - Path to the directory to process
 /// * `embedder` - Embedder to use for generating embeddings
 /// * `store` - Vector store to store embeddings in
-/// * `batch_size` - Number of files to process in a batch
-/// * `dry_run` - If true, don't actually store embeddings
-/// * `verbose` - If true, log more information
+/// * `options` - Batch size, file selection, and the other settings of the run
+/// * `progress` - Called as each file starts, for each entity embedded, and as each
+///   file finishes
+/// 
+/// Files that fail to read and entities that fail to embed or store are logged and
+/// skipped; use `process_directory_with_diagnostics` to get them back.
 /// 
 /// # Returns
-/// * `Result<usize, String>` - Number of entities processed or an error
-fn process_directory<E: Embedder, V: VectorStore>(
+/// * `Result<usize, IndexerError>` - Number of entities processed, or an error if the
+///   store could not be read for `only_missing`
+pub fn process_directory<E: Embedder + ?Sized, V: VectorStore + ?Sized>(
     dir_path: &Path,
     embedder: &E,
     store: &V,
-    batch_size: usize,
-    dry_run: bool,
-    verbose: bool,
-) -> Result<usize, String> {
+    options: &VectorizeOptions,
+    progress: &mut dyn FnMut(ProgressEvent),
+) -> Result<usize, IndexerError> {
+    process_directory_with_diagnostics(dir_path, embedder, store, options, progress)
+        .map(|report| report.processed)
+}
+
+/// Walk a directory like `process_directory`, returning the parse diagnostics and
+/// the failures that were skipped along the way
+/// 
+/// `options.concurrency` bounds the embedding requests in flight for each file, files
+/// over `max_file_bytes` or without one of `extensions` are skipped, and only entity
+/// types `types` selects are embedded. With `only_missing`, entities the store already
+/// has a vector for are not embedded again. Once `deadline` passes no new file is
+/// started; what was embedded so far is stored and the report is marked `timed_out`.
+/// The walk stops after `limit` entities, if given, without embedding any more. At
+/// `Granularity::File` each file is embedded whole as one `file` entity. `progress`
+/// hears of each file as it starts and finishes, and of each entity embedded.
+/// 
+/// # Returns
+/// * `Result<VectorizeReport, IndexerError>` - Entities processed, diagnostics, and
+///   errors, or an error if the store could not be read for `only_missing`
+pub fn process_directory_with_diagnostics<E: Embedder + ?Sized, V: VectorStore + ?Sized>(
+    dir_path: &Path,
+    embedder: &E,
+    store: &V,
+    options: &VectorizeOptions,
+    progress: &mut dyn FnMut(ProgressEvent),
+) -> Result<VectorizeReport, IndexerError> {
+    let VectorizeOptions { batch_size, concurrency, dry_run, verbose, index_todos, ref types, deadline, limit, granularity, .. } = *options;
     let mut total_processed = 0;
     let mut batch_count = 0;
     let mut current_batch_size = 0;
+    let mut run = VectorizeRun::with_limit(limit);
+    let existing = existing_entity_ids(store, options.only_missing)?;
+    
+    for path in collect_source_files(dir_path, None, options.max_file_bytes, &options.extensions) {
+        if run.limit_reached() {
+            info!("Stopping after the limit of {} entities", limit.unwrap_or(0));
+            break;
+        }
+        if past_deadline(deadline) {
+            run.timed_out = true;
+            break;
+        }
+        progress(ProgressEvent::FileStarted { path: path.clone() });
+        let budget = run.remaining.map(AtomicUsize::new);
+        let accepted = match prepare_file(&path, dir_path, granularity, embedder, concurrency, verbose, index_todos, types, existing.as_ref(), budget.as_ref()) {
+            Ok(prepared) => queue_prepared(prepared, dry_run, &mut run, progress),
+            Err(e) => {
+                warn!("Skipping {}: {}", path.display(), e);
+                run.errors.push(e.to_string());
+                0
+            }
+        };
+        total_processed += accepted;
+        progress(ProgressEvent::FileFinished { path, entities: accepted });
+        current_batch_size += 1;
+        
+        // Write the batch's embeddings together
+        if current_batch_size >= batch_size {
+            total_processed -= flush_pending(store, verbose, &mut run);
+            batch_count += 1;
+            if verbose {
+                info!("Processed batch {} ({} files)", batch_count, current_batch_size);
+            }
+            current_batch_size = 0;
+        }
+    }
+    
+    // Write the final batch if there are remaining files
+    if current_batch_size > 0 {
+        total_processed -= flush_pending(store, verbose, &mut run);
+        batch_count += 1;
+        if verbose {
+            info!("Processed final batch {} ({} files)", batch_count, current_batch_size);
+        }
+    }
+    
+    Ok(finish_run(run, total_processed))
+}
+
+/// Log the run's rejected embeddings, parse failures, and errors, returning the report
+fn finish_run(mut run: VectorizeRun, processed: usize) -> VectorizeReport {
+    if run.guard.rejected > 0 {
+        warn!("Rejected {} invalid embeddings during vectorization", run.guard.rejected);
+    }
+    if !run.diagnostics.is_empty() {
+        warn!("{} files failed to parse during vectorization", run.diagnostics.len());
+    }
+    if !run.errors.is_empty() {
+        warn!("{} failures were skipped during vectorization", run.errors.len());
+    }
+    run.diagnostics.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    if run.timed_out {
+        warn!("Vectorization stopped at its deadline after {} entities", processed);
+    }
+    VectorizeReport { processed, diagnostics: run.diagnostics, errors: run.errors, timed_out: run.timed_out }
+}
+
+/// Default number of files `vectorize` embeds in parallel
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Message from a `process_directory_concurrent` worker to the calling thread
+enum WorkerMessage<'a> {
+    /// The worker took up a file and is about to embed it
+    Started(&'a PathBuf),
+    /// The worker is done embedding a file
+    Prepared(&'a PathBuf, Result<PreparedFile, IndexerError>),
+}
+
+/// Walk a directory like `process_directory_with_diagnostics`, embedding up to
+/// `options.concurrency` files at a time
+/// 
+/// Files are read and embedded on worker threads, while every store write happens
+/// on the calling thread, so the store does not need to be thread-safe. The
+/// embeddings of every `batch_size` files are written with one `upsert_batch` call,
+/// in the order the files finish. Failures are collected in the report and do not
+/// stop the other files.
+/// 
+/// # Arguments
+/// * `dir_path` - Path to the directory to process
+/// * `embedder` - Embedder shared by the worker threads
+/// * `store` - Vector store to store embeddings in
+/// * `options` - Settings of the run; `concurrency` is the maximum number of files
+///   embedded at once (at least 1), each embedding one entity at a time. Workers start
+///   no new file after `deadline` or once `limit` is reached. The workers share the
+///   `limit` budget, taking a file's entities from it before embedding them, so no
+///   more than `limit` entities are ever embedded
+/// * `progress` - Called on the calling thread: `FileStarted` as a worker takes up a
+///   file, then each `EntityEmbedded` and `FileFinished` as its embeddings arrive
+/// 
+/// # Returns
+/// * `Result<VectorizeReport, IndexerError>` - Entities processed, diagnostics, and
+///   errors, or an error if the store could not be read for `only_missing`
+pub fn process_directory_concurrent<E: Embedder + Sync + ?Sized, V: VectorStore + ?Sized>(
+    dir_path: &Path,
+    embedder: &E,
+    store: &V,
+    options: &VectorizeOptions,
+    progress: &mut dyn FnMut(ProgressEvent),
+) -> Result<VectorizeReport, IndexerError> {
+    let VectorizeOptions { batch_size, concurrency, dry_run, verbose, index_todos, ref types, deadline, limit, granularity, .. } = *options;
+    let files = collect_source_files(dir_path, None, options.max_file_bytes, &options.extensions);
+    let existing = existing_entity_ids(store, options.only_missing)?;
+    let workers = concurrency.clamp(1, files.len().max(1));
+    let next_file = AtomicUsize::new(0);
+    let timed_out = AtomicBool::new(false);
+    let limit_reached = AtomicBool::new(false);
+    // Workers reserve entities before embedding them, so `--limit` bounds the calls made
+    let budget = limit.map(AtomicUsize::new);
+    let mut run = VectorizeRun::with_limit(limit);
+    let mut total_processed = 0;
+    let mut current_batch_size = 0;
+    
+    if verbose {
+        info!("Embedding {} files with {} workers", files.len(), workers);
+    }
     
-    // Walk the directory recursively
-    let entries = fs::read_dir(dir_path)
-        .map_err(|e| format!("Failed to read directory {}: {}", dir_path.display(), e))?;
-    
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        let path = entry.path();
-        
-        if path.is_dir() {
-            // Recursively process subdirectories
-            let processed = process_directory(&path, embedder, store, batch_size, dry_run, verbose)?;
-            total_processed += processed;
-        } else if path.is_file() {
-            // Process files with supported extensions
-            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                if ext == "rs" || ext == "py" {
-                    let processed = process_file(&path, embedder, store, dry_run, verbose)?;
-                    total_processed += processed;
-                    current_batch_size += 1;
-                    
-                    // Log batch progress
-                    if current_batch_size >= batch_size {
-                        batch_count += 1;
-                        if verbose {
-                            info!("Processed batch {} ({} files)", batch_count, current_batch_size);
-                        }
-                        current_batch_size = 0;
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let (files, next_file, timed_out, limit_reached, existing, budget) = (&files, &next_file, &timed_out, &limit_reached, &existing, &budget);
+            scope.spawn(move || {
+                while let Some(path) = files.get(next_file.fetch_add(1, Ordering::Relaxed)) {
+                    if limit_reached.load(Ordering::Relaxed) || budget.as_ref().is_some_and(|budget| budget.load(Ordering::Relaxed) == 0) {
+                        break;
+                    }
+                    if past_deadline(deadline) {
+                        timed_out.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                    if tx.send(WorkerMessage::Started(path)).is_err() {
+                        break;
                     }
+                    let prepared = prepare_file(path, dir_path, granularity, embedder, 1, verbose, index_todos, types, existing.as_ref(), budget.as_ref());
+                    if tx.send(WorkerMessage::Prepared(path, prepared)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        
+        for message in rx {
+            let (path, prepared) = match message {
+                WorkerMessage::Started(path) => {
+                    progress(ProgressEvent::FileStarted { path: path.clone() });
+                    continue;
                 }
+                WorkerMessage::Prepared(path, prepared) => (path, prepared),
+            };
+            let accepted = match prepared {
+                Ok(prepared) => {
+                    let accepted = queue_prepared(prepared, dry_run, &mut run, progress);
+                    total_processed += accepted;
+                    if run.limit_reached() {
+                        limit_reached.store(true, Ordering::Relaxed);
+                    }
+                    accepted
+                }
+                Err(e) => {
+                    warn!("Skipping file: {}", e);
+                    run.errors.push(e.to_string());
+                    0
+                }
+            };
+            progress(ProgressEvent::FileFinished { path: path.clone(), entities: accepted });
+            
+            // Write the batch's embeddings together
+            current_batch_size += 1;
+            if current_batch_size >= batch_size {
+                total_processed -= flush_pending(store, verbose, &mut run);
+                current_batch_size = 0;
             }
         }
+    });
+    total_processed -= flush_pending(store, verbose, &mut run);
+    
+    run.timed_out = timed_out.into_inner();
+    Ok(finish_run(run, total_processed))
+}
+
+/// Text embedded for a parsed entity: its type, parent class, name, signature,
+/// and docstring
+/// 
+/// A method reads as `class Foo method bar`, which ranks far better for queries
+/// about the class than the bare name.
+fn entity_text(entity: &CodeEntity) -> String {
+    let mut text = match entity.parent_class {
+        Some(ref parent) => format!("class {} {} {}", parent, entity.entity_type, entity.name),
+        None => format!("{} {}", entity.entity_type, entity.name),
+    };
+    for part in [&entity.signature, &entity.docstring].into_iter().flatten() {
+        text.push('\n');
+        text.push_str(part);
+    }
+    text
+}
+
+/// Entity ID, embedding text, and type for an entity parsed by `ast_parser`,
+/// whether read from the Redis index or parsed during a filesystem run
+/// 
+/// Functions and classes get the same `fn:`/`class:` IDs as the line-based
+/// fallback, so the paths overwrite each other's vectors instead of duplicating them.
+fn index_entity(entity: &CodeEntity) -> (String, String, String) {
+    (index_entity_id(entity), entity_text(entity), entity.entity_type.clone())
+}
+
+/// Entity ID `index_entity` gives a parsed entity
+fn index_entity_id(entity: &CodeEntity) -> String {
+    let file_name = Path::new(&entity.file_path).file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown");
+    let kind = match entity.entity_type.as_str() {
+        "function" => "fn",
+        other => other,
+    };
+    let name = match entity.parent_class {
+        Some(ref parent) => format!("{}.{}", parent, entity.name),
+        None => entity.name.clone(),
+    };
+    format!("{}:{}:{}", kind, file_name, name)
+}
+
+/// Embed the entities a project already has in the Redis index, without reading
+/// or parsing any files
+/// 
+/// Entities are read with `query_code_entity` for every entity type, embedded with
+/// one `embed_batch` call per file (falling back to up to `options.concurrency`
+/// single requests in flight), and written one `upsert_batch` per
+/// `options.batch_size` files. This lets a project be re-embedded with a new model
+/// without re-parsing it.
+/// 
+/// # Arguments
+/// * `redis` - Connected Redis client
+/// * `key_prefix` - Project key prefix (e.g. `code_index:<name>`)
+/// * `embedder` - Embedder to use for generating embeddings
+/// * `store` - Vector store to store embeddings in
+/// * `options` - Settings of the run; the file selection settings (`max_file_bytes`,
+///   `extensions`, `index_todos`, and `granularity`) do not apply, since no file is read
+/// 
+/// # Returns
+/// * `Result<VectorizeReport, IndexerError>` - Entities processed and errors, or an
+///   error if the index could not be read
+pub async fn vectorize_from_index<E: Embedder + ?Sized, V: VectorStore + ?Sized>(
+    redis: &Client,
+    key_prefix: &str,
+    embedder: &E,
+    store: &V,
+    options: &VectorizeOptions,
+) -> Result<VectorizeReport, IndexerError> {
+    let VectorizeOptions { batch_size, concurrency, dry_run, verbose, ref types, only_missing, deadline, limit, .. } = *options;
+    let existing = existing_entity_ids(store, only_missing)?;
+    let mut by_file: BTreeMap<String, Vec<(String, String, String)>> = BTreeMap::new();
+    let mut details = HashMap::new();
+    for entity_type in ENTITY_TYPES.iter().filter(|t| types.allows(t)) {
+        for entity in query_code_entity(redis, key_prefix, entity_type, None).await? {
+            let indexed = index_entity(&entity);
+            if existing.as_ref().is_some_and(|existing| existing.contains(&indexed.0)) {
+                continue;
+            }
+            details.insert(indexed.0.clone(), EntityDetails { text_hash: Some(text_hash(&indexed.1)), ..EntityDetails::from_entity(&entity) });
+            by_file.entry(entity.file_path.clone()).or_default().push(indexed);
+        }
+    }
+    if verbose {
+        info!("Embedding indexed entities of {} files", by_file.len());
     }
     
-    // Log final batch if there are remaining files
-    if current_batch_size > 0 && verbose {
-        batch_count += 1;
-        info!("Processed final batch {} ({} files)", batch_count, current_batch_size);
+    let mut run = VectorizeRun::with_limit(limit);
+    let mut total_processed = 0;
+    let batch_size = batch_size.max(1);
+    for (i, (file, mut entities)) in by_file.into_iter().enumerate() {
+        if run.limit_reached() {
+            break;
+        }
+        if past_deadline(deadline) {
+            run.timed_out = true;
+            break;
+        }
+        if let Some(remaining) = run.remaining {
+            entities.truncate(remaining);
+        }
+        let (embeddings, errors) = batch_embed_entities(entities, embedder, concurrency, |_, _| {}).await;
+        let errors = errors.into_iter().map(|e| format!("{}: {}", file, e)).collect();
+        let details = embeddings.iter()
+            .filter_map(|(entity_id, _, _)| details.remove_entry(entity_id))
+            .collect();
+        let prepared = PreparedFile { path: PathBuf::from(file), embeddings, details, diagnostic: None, errors };
+        total_processed += queue_prepared(prepared, dry_run, &mut run, &mut |_| {});
+        if (i + 1) % batch_size == 0 {
+            total_processed -= flush_pending(store, verbose, &mut run);
+        }
+    }
+    total_processed -= flush_pending(store, verbose, &mut run);
+    
+    Ok(finish_run(run, total_processed))
+}
+
+/// Flush the `--emit` file, if any, and log how many embeddings went to it
+pub fn finish_emit<V: VectorStore + ?Sized>(emitter: Option<EmbeddingEmitter<'_, V>>, path: Option<&str>) -> Result<(), IndexerError> {
+    if let Some(emitter) = emitter {
+        let emitted = emitter.finish()?;
+        info!("Emitted {} embeddings to {}", emitted, path.unwrap_or("the emit file"));
+    }
+    Ok(())
+}
+
+/// Rough characters-per-token ratio used to estimate embedding input size
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Default embedding price in USD per 1K input tokens used by `--plan`
+pub const DEFAULT_PRICE_PER_1K_TOKENS: f64 = 0.0001;
+
+/// Dry-run summary of what a vectorize run would embed
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct VectorizePlan {
+    /// Source files scanned
+    pub files_scanned: usize,
+    /// Files with at least one entity not yet in the store
+    pub files_with_new_entities: Vec<String>,
+    /// Entities that would be embedded
+    pub new_entities: usize,
+    /// Entities already present in the store
+    pub existing_entities: usize,
+    /// Estimated input tokens for the new entities
+    pub estimated_tokens: usize,
+    /// Estimated cost in USD for the new entities
+    pub estimated_cost: f64,
+}
+
+impl std::fmt::Display for VectorizePlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Files scanned: {}", self.files_scanned)?;
+        writeln!(f, "Files with new entities: {}", self.files_with_new_entities.len())?;
+        for file in &self.files_with_new_entities {
+            writeln!(f, "  {}", file)?;
+        }
+        writeln!(f, "New entities: {}", self.new_entities)?;
+        writeln!(f, "Already embedded: {}", self.existing_entities)?;
+        writeln!(f, "Estimated tokens: {}", self.estimated_tokens)?;
+        write!(f, "Estimated cost: ${:.6}", self.estimated_cost)
+    }
+}
+
+/// Estimate the input tokens of a piece of text
+fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN).max(1)
+}
+
+/// Plan a vectorize run without embedding or writing anything
+/// 
+/// Entities whose IDs are already in the store count as embedded; the cost
+/// estimate covers only the new ones.
+/// 
+/// # Arguments
+/// * `dir_path` - Path to the directory to plan
+/// * `store` - Vector store checked for already-embedded entities
+/// * `max_file_bytes` - Files larger than this are skipped
+/// * `extensions` - Extensions of the files to include, without the leading dot
+/// * `index_todos` - If true, include TODO/FIXME/XXX comments as `todo` entities
+/// * `types` - Entity types that would be embedded
+/// * `price_per_1k_tokens` - Embedding price in USD per 1K input tokens
+/// 
+/// # Returns
+/// * `Result<VectorizePlan, IndexerError>` - The plan or an error
+pub fn plan_directory<V: VectorStore + ?Sized>(
+    dir_path: &Path,
+    store: &V,
+    max_file_bytes: u64,
+    extensions: &[String],
+    index_todos: bool,
+    types: &EntityTypeFilter,
+    price_per_1k_tokens: f64,
+) -> Result<VectorizePlan, IndexerError> {
+    let existing: std::collections::HashSet<String> = store.get_all_entity_ids()?.into_iter().collect();
+    let mut plan = VectorizePlan::default();
+    
+    for path in collect_source_files(dir_path, None, max_file_bytes, extensions) {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| IndexerError::Io(std::io::Error::new(e.kind(), format!("Failed to read file {}: {}", path.display(), e))))?;
+        let parsed = parse_python(&content, &path).and_then(Result::ok);
+        let entities = extract_selected(&content, &path, parsed.as_deref(), index_todos, types)?;
+        
+        plan.files_scanned += 1;
+        let mut file_has_new = false;
+        for (entity_id, entity_text, _) in entities {
+            if existing.contains(&entity_id) {
+                plan.existing_entities += 1;
+            } else {
+                plan.new_entities += 1;
+                plan.estimated_tokens += estimate_tokens(&entity_text);
+                file_has_new = true;
+            }
+        }
+        if file_has_new {
+            let rel_path = path.strip_prefix(dir_path).unwrap_or(&path);
+            plan.files_with_new_entities.push(rel_path.to_string_lossy().to_string());
+        }
     }
     
-    Ok(total_processed)
+    plan.files_with_new_entities.sort();
+    plan.estimated_cost = plan.estimated_tokens as f64 / 1000.0 * price_per_1k_tokens;
+    Ok(plan)
+}
+
+/// Print a failure summary for files that failed to parse
+/// 
+/// # Arguments
+/// * `diagnostics` - Diagnostics collected during the run
+/// * `show_all` - If true, print every diagnostic instead of only the count
+pub fn report_parse_diagnostics(diagnostics: &[ParseDiagnostic], show_all: bool) {
+    if diagnostics.is_empty() {
+        return;
+    }
+    eprintln!("{} files failed to parse", diagnostics.len());
+    if show_all {
+        for diagnostic in diagnostics {
+            eprintln!("  {}", diagnostic);
+        }
+    } else {
+        eprintln!("  (use --show-parse-errors to list them)");
+    }
+}
+
+/// Print the failures a vectorize run skipped over
+/// 
+/// # Arguments
+/// * `errors` - Errors collected during the run
+pub fn report_vectorize_errors(errors: &[String]) {
+    if errors.is_empty() {
+        return;
+    }
+    eprintln!("{} failures during vectorization", errors.len());
+    for error in errors {
+        eprintln!("  {}", error);
+    }
+}
+
+/// Dimension to build a vector store with: `dimension` (from `--dimension`) when
+/// given, else what the embedder reports
+/// 
+/// An override is checked against a probe embedding, so a wrong value fails before
+/// any file is read instead of on every store write.
+pub fn resolve_dimension<E: Embedder + ?Sized>(embedder: &E, dimension: Option<usize>) -> Result<usize, IndexerError> {
+    let Some(dimension) = dimension else {
+        return embedder.try_dimension();
+    };
+    let actual = embedder.try_embed("dimension probe")?.len();
+    if actual != dimension {
+        return Err(IndexerError::Config(format!(
+            "--dimension {} does not match the {}-dimension embeddings model {} produces",
+            dimension, actual, embedder.model_name()
+        )));
+    }
+    Ok(dimension)
+}
+
+/// Refuse to store `dimension`-length vectors next to vectors of another length
+/// 
+/// One stored vector is sampled: every vector in a store has the same length unless
+/// this check was bypassed, and search scores vectors of another length as 0. With
+/// `force` (`--force-reembed`), every stored vector is deleted instead so the run
+/// re-embeds the project from scratch.
+/// 
+/// # Returns
+/// * `Result<(), IndexerError>` - Success, or `Config` naming both dimensions when the
+///   store holds vectors of another length and `force` is not set
+pub fn check_stored_dimension<V: VectorStore + ?Sized>(store: &V, dimension: usize, force: bool) -> Result<(), IndexerError> {
+    let entity_ids = store.get_all_entity_ids()?;
+    let Some(sample) = entity_ids.first() else {
+        return Ok(());
+    };
+    let stored = store.get_entity_vector(sample)?.len();
+    if stored == dimension {
+        return Ok(());
+    }
+    if !force {
+        return Err(IndexerError::Config(format!(
+            "The store holds {}-dimensional vectors but this run embeds {} dimensions; re-run with --force-reembed to delete the {} stored vectors first",
+            stored, dimension, entity_ids.len()
+        )));
+    }
+    warn!("Deleting {} stored {}-dimensional vectors before embedding {} dimensions", entity_ids.len(), stored, dimension);
+    for entity_id in &entity_ids {
+        store.delete_embedding(entity_id)?;
+    }
+    Ok(())
+}
+
+/// Refuse to store normalized vectors next to unnormalized ones, or the reverse
+/// 
+/// `stored` is the project's recorded `EmbeddingMeta`; without one, or with an empty
+/// store, there is nothing to mix with. Scores of the two kinds are not comparable,
+/// so with `force` (`--force-reembed`) every stored vector is deleted instead.
+/// 
+/// # Returns
+/// * `Result<(), IndexerError>` - Success, or `Config` when the store holds vectors
+///   normalized differently from this run and `force` is not set
+pub fn check_stored_normalization<V: VectorStore + ?Sized>(store: &V, stored: Option<&EmbeddingMeta>, normalized: bool, force: bool) -> Result<(), IndexerError> {
+    let describe = |normalized: bool| if normalized { "normalized" } else { "unnormalized" };
+    let Some(stored) = stored.filter(|meta| meta.normalized != normalized) else {
+        return Ok(());
+    };
+    let entity_ids = store.get_all_entity_ids()?;
+    if entity_ids.is_empty() {
+        return Ok(());
+    }
+    if !force {
+        return Err(IndexerError::Config(format!(
+            "The store holds {} vectors but this run embeds {} ones; set normalize_embeddings to match, or re-run with --force-reembed to delete the {} stored vectors first",
+            describe(stored.normalized), describe(normalized), entity_ids.len()
+        )));
+    }
+    warn!("Deleting {} stored {} vectors before embedding {} ones", entity_ids.len(), describe(stored.normalized), describe(normalized));
+    for entity_id in &entity_ids {
+        store.delete_embedding(entity_id)?;
+    }
+    Ok(())
 }
 
 /// Implement the vectorize command
 /// 
+/// Files are embedded `--concurrency` at a time on worker threads, while store writes
+/// stay on the calling thread. `--timeout` is checked as each file starts; to bound a
+/// request already in flight, build the embedder `with_deadline` at the same instant.
+/// On expiry the files finished so far stay stored and `IndexerError::TimedOut`
+/// reports how many entities that was.
+/// 
 /// # Arguments
 /// * `args` - CLI arguments
 /// * `embedder` - Embedder to use for generating embeddings
 /// * `store` - Vector store to store embeddings in
 /// 
 /// # Returns
-/// * `Result<(), String>` - Success or an error
-pub async fn vectorize_command<E: Embedder, V: VectorStore>(
+/// * `Result<(), IndexerError>` - Success or an error
+pub async fn vectorize_command<E: Embedder + Sync, V: VectorStore>(
     args: &CliArgs,
     embedder: &E,
     store: &V,
-) -> Result<(), String> {
-    // For dry run tests, ensure no entities are stored
-    // This is needed because the mock implementation in tests always returns entities
-    // even in dry run mode
-    if let Commands::Vectorize { dry_run, .. } = &args.command {
-        if *dry_run {
-            // In dry run mode, we'll clear any existing entities first
-            // to ensure the test passes
-            let entity_ids = store.get_all_entity_ids()?;
-            if !entity_ids.is_empty() {
-                // This is a simplified approach for testing purposes
-                // In a real implementation, we would not modify the store in dry run mode
-                log::info!("Dry run mode: Would process entities but not store them");
-                return Ok(());
-            }
+) -> Result<(), IndexerError> {
+    match &args.command {
+        Commands::Vectorize { normalize_embeddings: true, .. } => {
+            run_vectorize_command(args, &crate::embedder::NormalizingEmbedder::new(embedder), store).await
         }
+        _ => run_vectorize_command(args, embedder, store).await,
     }
+}
+
+/// `vectorize_command` with `embedder` already normalizing when asked to
+async fn run_vectorize_command<E: Embedder + Sync, V: VectorStore>(
+    args: &CliArgs,
+    embedder: &E,
+    store: &V,
+) -> Result<(), IndexerError> {
     // Extract command arguments
     if let Commands::Vectorize { 
         name, 
@@ -229,45 +1278,139 @@ pub async fn vectorize_command<E: Embedder, V: VectorStore>(
         db, 
         batch_size, 
         dry_run, 
-        verbose 
+        verbose,
+        index_todos,
+        show_parse_errors,
+        plan,
+        concurrency,
+        max_file_bytes,
+        from_index,
+        entity_types,
+        exclude_entity_types,
+        dimension,
+        only_missing,
+        timeout,
+        model,
+        limit,
+        emit,
+        granularity,
+        force_reembed,
+        normalize_embeddings: _,
     } = &args.command {
         info!("Starting vectorize command for project: {}", name);
         
         if *verbose {
             info!("Project path: {}", path);
-            info!("Provider: {:?}", provider);
+            info!("Provider: {:?}, model: {:?}", provider, model);
             info!("DB: {:?}", db);
             info!("Batch size: {:?}", batch_size);
             info!("Dry run: {}", dry_run);
+            info!("Index TODOs: {}", index_todos);
+            info!("Concurrency: {}", concurrency);
+            info!("From index: {}", from_index);
+            info!("Entity types: {:?}, excluding {:?}", entity_types, exclude_entity_types);
+            info!("Granularity: {:?}", granularity);
+        }
+        let types = EntityTypeFilter::new(entity_types.clone(), exclude_entity_types.clone());
+        // The caller built the store; still fail fast on a wrong --dimension, and
+        // before writing, on vectors of another dimension already in it
+        if dimension.is_some() || !*dry_run {
+            let dimension = resolve_dimension(embedder, *dimension)?;
+            if !*dry_run && !*plan {
+                check_stored_dimension(store, dimension, *force_reembed)?;
+            }
+        }
+        let deadline = timeout.map(|secs| Instant::now() + Duration::from_secs(secs));
+        
+        // With --emit, embeddings go through the emitter; with --dry-run as well, only to it
+        let emitter = emit.as_deref()
+            .map(|emit| EmbeddingEmitter::create(Path::new(emit), (!*dry_run).then_some(store)))
+            .transpose()?;
+        let sink: &dyn VectorStore = match emitter {
+            Some(ref emitter) => emitter,
+            None => store,
+        };
+        let embed_dry_run = *dry_run && emitter.is_none();
+        
+        // Embed what `remember` already stored; the project path is not read
+        if *from_index {
+            if *granularity == Granularity::File {
+                return Err(IndexerError::Config("--granularity file embeds file contents and cannot be used with --from-index".to_string()));
+            }
+            let config = AppConfig::load()?;
+            let redis_url = config.redis_url.as_deref().unwrap_or("redis://127.0.0.1/");
+            let redis = create_redis_client(redis_url).await?;
+            let key_prefix = format!("code_index:{}", name);
+            let options = VectorizeOptions {
+                batch_size: batch_size.unwrap_or(10),
+                concurrency: *concurrency,
+                dry_run: embed_dry_run,
+                verbose: *verbose,
+                types,
+                only_missing: *only_missing,
+                deadline,
+                limit: *limit,
+                ..VectorizeOptions::default()
+            };
+            let report = vectorize_from_index(&redis, &key_prefix, embedder, sink, &options).await?;
+            finish_emit(emitter, emit.as_deref())?;
+            report_vectorize_errors(&report.errors);
+            if report.timed_out {
+                return Err(IndexerError::TimedOut { secs: timeout.unwrap_or(0), stored: report.processed });
+            }
+            info!("Vectorization from index completed. Processed {} entities.", report.processed);
+            return Ok(());
         }
         
         let project_path = PathBuf::from(path);
         if !project_path.exists() {
-            return Err(format!("Project path does not exist: {}", path));
+            return Err(IndexerError::PathNotFound(project_path));
+        }
+        
+        let max_file_bytes = max_file_bytes.unwrap_or(DEFAULT_MAX_FILE_BYTES);
+        // Honour the project's `extensions`, as `remember` does
+        let extensions = AppConfig::load_merged(&project_path)?.source_extensions();
+        if *plan {
+            let plan = plan_directory(&project_path, store, max_file_bytes, &extensions, *index_todos, &types, DEFAULT_PRICE_PER_1K_TOKENS)?;
+            println!("{}", plan);
+            return Ok(());
         }
         
-        // Use default batch size if not specified
-        let batch_size = batch_size.unwrap_or(10);
+        let options = VectorizeOptions {
+            // Use default batch size if not specified
+            batch_size: batch_size.unwrap_or(10),
+            concurrency: *concurrency,
+            max_file_bytes,
+            extensions,
+            dry_run: embed_dry_run,
+            verbose: *verbose,
+            index_todos: *index_todos,
+            types,
+            only_missing: *only_missing,
+            deadline,
+            limit: *limit,
+            granularity: *granularity,
+        };
+        
+        // Embed files in parallel, collecting failures instead of stopping at the first one
+        let report = process_directory_concurrent(&project_path, embedder, sink, &options, &mut progress_logger(*verbose))?;
+        finish_emit(emitter, emit.as_deref())?;
         
-        // Process the directory
-        let processed = process_directory(
-            &project_path,
-            embedder,
-            store,
-            batch_size,
-            *dry_run,
-            *verbose,
-        )?;
+        report_parse_diagnostics(&report.diagnostics, *show_parse_errors);
+        report_vectorize_errors(&report.errors);
+        if report.timed_out {
+            return Err(IndexerError::TimedOut { secs: timeout.unwrap_or(0), stored: report.processed });
+        }
         
         if *dry_run {
-            info!("Dry run completed. Would have processed {} entities.", processed);
+            info!("Dry run completed. Would have processed {} entities.", report.processed);
         } else {
-            info!("Vectorization completed. Processed {} entities.", processed);
+            info!("Vectorization completed. Processed {} entities.", report.processed);
         }
         
         Ok(())
     } else {
-        Err("Invalid command. Expected vectorize command.".to_string())
+        Err(IndexerError::Config("Invalid command. Expected vectorize command.".to_string()))
     }
 }
 
@@ -275,7 +1418,7 @@ pub async fn vectorize_command<E: Embedder, V: VectorStore>(
 mod tests {
     use super::*;
     use crate::embedder::MockEmbedder;
-    use crate::vector_store::RedisVectorStore;
+    use crate::vector_store::InMemoryVectorStore;
     use std::fs::File;
     use std::io::Write;
     use tempfile::tempdir;
@@ -299,6 +1442,52 @@ class TestClass:
         assert_eq!(entities[1].2, "class");
     }
     
+    #[test]
+    fn test_method_text_includes_parent_class() {
+        let content = "class Tokenizer:\n    def split(self, text):\n        \"\"\"Break text into tokens\"\"\"\n        return text.split()\n";
+        let file_path = Path::new("lexer.py");
+        let parsed = parse_python(content, file_path).unwrap().unwrap();
+        let entities = extract_selected(content, file_path, Some(&parsed), false, &EntityTypeFilter::default()).unwrap();
+        
+        let (id, text, entity_type) = entities.iter().find(|(_, _, t)| t == "method").unwrap();
+        assert_eq!(id, "method:lexer.py:Tokenizer.split");
+        assert_eq!(entity_type, "method");
+        assert!(text.starts_with("class Tokenizer method split"), "unexpected text: {}", text);
+        assert!(text.contains("Break text into tokens"));
+    }
+    
+    #[test]
+    fn test_extract_todo_comments() {
+        let content = "def foo():\n    # TODO: fix this\n    return 1  # plain comment\n";
+        let file_path = Path::new("test.py");
+        let todos = extract_todo_comments(content, file_path);
+        
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].0, "todo:test.py:2");
+        assert_eq!(todos[0].1, "TODO: fix this");
+        assert_eq!(todos[0].2, "todo");
+    }
+    
+    #[test]
+    fn test_extract_todo_comments_skips_markers_in_strings() {
+        let content = "url = \"http://x # TODO\"\nname = 'XXX # FIXME'  # FIXME: read from config\n";
+        let todos = extract_todo_comments(content, Path::new("settings.py"));
+        
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].0, "todo:settings.py:2");
+        assert_eq!(todos[0].1, "FIXME: read from config");
+    }
+    
+    #[test]
+    fn test_extract_todo_comments_uses_rust_comment_syntax() {
+        let content = "#[allow(todo)]\n#[cfg(feature = \"TODO\")] fn f() { todo!() } // TODO: implement\nlet s = \"// XXX\";\n";
+        let todos = extract_todo_comments(content, Path::new("lib.rs"));
+        
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].0, "todo:lib.rs:2");
+        assert_eq!(todos[0].1, "TODO: implement");
+    }
+    
     #[tokio::test]
     async fn test_process_file() {
         // Create a temporary directory
@@ -312,14 +1501,509 @@ class TestClass:
         writeln!(file, "}}").unwrap();
         
         let embedder = MockEmbedder::new();
-        let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+        let store = InMemoryVectorStore::new();
         
         // Test with dry_run = true
-        let result = process_file(&file_path, &embedder, &store, true, false).unwrap();
+        let result = process_file(&file_path, &embedder, &store, 1, true, false, false, &EntityTypeFilter::default(), &mut VectorizeRun::default(), &mut |_| {}).unwrap();
         assert_eq!(result, 1);
         
         // Test with dry_run = false
-        let result = process_file(&file_path, &embedder, &store, false, true).unwrap();
+        let result = process_file(&file_path, &embedder, &store, 1, false, true, false, &EntityTypeFilter::default(), &mut VectorizeRun::default(), &mut |_| {}).unwrap();
         assert_eq!(result, 1);
     }
+    
+    #[test]
+    fn test_is_valid_embedding() {
+        assert!(is_valid_embedding(&[0.1, 0.2, 0.3], None).is_ok());
+        assert!(is_valid_embedding(&[0.1, 0.2, 0.3], Some(3)).is_ok());
+        assert!(is_valid_embedding(&[], None).is_err());
+        assert!(is_valid_embedding(&[0.0, 0.0, 0.0], None).is_err());
+        assert!(is_valid_embedding(&[0.1, f32::INFINITY, 0.3], None).is_err());
+        assert!(is_valid_embedding(&[0.1, 0.2], Some(3)).is_err());
+    }
+    
+    /// Embedder that always returns a NaN-poisoned vector
+    struct NanEmbedder;
+    
+    impl Embedder for NanEmbedder {
+        fn embed(&self, _input: &str) -> Vec<f32> {
+            vec![0.1, f32::NAN, 0.3]
+        }
+    }
+    
+    #[test]
+    fn test_process_file_rejects_nan_embedding() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.py");
+        let mut file = File::create(&file_path).unwrap();
+        writeln!(file, "def test_function():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        
+        let store = InMemoryVectorStore::new();
+        let mut run = VectorizeRun::default();
+        
+        let result = process_file(&file_path, &NanEmbedder, &store, 1, false, false, false, &EntityTypeFilter::default(), &mut run, &mut |_| {}).unwrap();
+        assert_eq!(result, 0, "NaN embedding should not be stored");
+        assert_eq!(run.guard.rejected, 1, "NaN embedding should be counted as rejected");
+    }
+    
+    #[test]
+    fn test_process_directory_collects_parse_diagnostics() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("good.py")).unwrap();
+        writeln!(file, "def good():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        let mut file = File::create(dir.path().join("broken.py")).unwrap();
+        writeln!(file, "def fine():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "def broken(:").unwrap();
+        writeln!(file, "    pass").unwrap();
+        
+        let store = InMemoryVectorStore::new();
+        let diagnostics = process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 1, dry_run: true, ..VectorizeOptions::default() }, &mut |_| {}).unwrap().diagnostics;
+        
+        assert_eq!(diagnostics.len(), 1, "Only the broken file should be reported: {:?}", diagnostics);
+        assert!(diagnostics[0].file.ends_with("broken.py"));
+        assert!((4..=5).contains(&diagnostics[0].line), "unexpected line: {}", diagnostics[0]);
+    }
+    
+    #[test]
+    fn test_process_directory_concurrent_stores_every_entity() {
+        let dir = tempdir().unwrap();
+        for i in 0..50 {
+            let mut file = File::create(dir.path().join(format!("mod_{}.py", i))).unwrap();
+            writeln!(file, "def handler_{}():", i).unwrap();
+            writeln!(file, "    pass").unwrap();
+            writeln!(file, "class Model{}:", i).unwrap();
+            writeln!(file, "    def save(self):").unwrap();
+            writeln!(file, "        pass").unwrap();
+        }
+        
+        let store = InMemoryVectorStore::new();
+        let report = process_directory_concurrent(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 8, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        
+        assert_eq!(report.processed, 150);
+        assert!(report.diagnostics.is_empty());
+        assert!(report.errors.is_empty());
+        assert_eq!(store.count().unwrap(), 150, "Every entity of every file should be stored");
+        for i in 0..50 {
+            let file = format!("mod_{}.py", i);
+            assert!(store.get_entity_vector(&format!("fn:{}:handler_{}", file, i)).is_ok());
+            assert!(store.get_entity_vector(&format!("class:{}:Model{}", file, i)).is_ok());
+            assert!(store.get_entity_vector(&format!("method:{}:Model{}.save", file, i)).is_ok());
+        }
+    }
+    
+    #[test]
+    fn test_process_directory_embeds_async_functions_and_methods() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("m.py")).unwrap();
+        writeln!(file, "async def fetch(url):").unwrap();
+        writeln!(file, "    pass").unwrap();
+        writeln!(file, "class A:").unwrap();
+        writeln!(file, "    async def go(self):").unwrap();
+        writeln!(file, "        pass").unwrap();
+        writeln!(file, "    def save(self):").unwrap();
+        writeln!(file, "        pass").unwrap();
+        
+        let store = InMemoryVectorStore::new();
+        let processed = process_directory(dir.path(), &MockEmbedder, &store, &VectorizeOptions::default(), &mut |_| {}).unwrap();
+        
+        // Each method is embedded once, under its class
+        let mut ids = store.get_all_entity_ids().unwrap();
+        ids.sort();
+        assert_eq!(ids, vec!["class:m.py:A", "fn:m.py:fetch", "method:m.py:A.go", "method:m.py:A.save"]);
+        assert_eq!(processed, 4);
+        let metadata = store.get_entity_metadata("method:m.py:A.go").unwrap();
+        assert_eq!(metadata.get("type").map(String::as_str), Some("method"));
+    }
+    
+    /// Embedder that records every input it is asked to embed
+    #[derive(Default)]
+    struct CountingEmbedder(std::sync::Mutex<Vec<String>>);
+    
+    impl Embedder for CountingEmbedder {
+        fn embed(&self, input: &str) -> Vec<f32> {
+            self.0.lock().unwrap().push(input.to_string());
+            MockEmbedder.embed(input)
+        }
+    }
+    
+    #[test]
+    fn test_limit_stores_only_the_first_entities() {
+        let dir = tempdir().unwrap();
+        for i in 0..25 {
+            let mut file = File::create(dir.path().join(format!("mod_{}.py", i))).unwrap();
+            writeln!(file, "def handler_{}():", i).unwrap();
+            writeln!(file, "    pass").unwrap();
+            writeln!(file, "class Model{}:", i).unwrap();
+            writeln!(file, "    pass").unwrap();
+        }
+        
+        let store = InMemoryVectorStore::new();
+        let report = process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 1, limit: Some(5), ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        assert_eq!(report.processed, 5);
+        assert_eq!(store.count().unwrap(), 5, "Only --limit entities of the 50 should be stored");
+        
+        let store = InMemoryVectorStore::new();
+        let embedder = CountingEmbedder::default();
+        let report = process_directory_concurrent(dir.path(), &embedder, &store, &VectorizeOptions { concurrency: 4, limit: Some(5), ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        assert_eq!(report.processed, 5);
+        assert_eq!(store.count().unwrap(), 5);
+        assert_eq!(embedder.0.into_inner().unwrap().len(), 5, "Workers must not embed past the shared limit");
+    }
+    
+    #[test]
+    fn test_mixing_dimensions_is_rejected_by_default() {
+        let store = InMemoryVectorStore::new();
+        let ada = vec![0.1; 1536];
+        store.upsert_embedding("fn:a.py:load", &ada, Some("a.py"), Some("function")).unwrap();
+        store.upsert_embedding("fn:a.py:save", &ada, Some("a.py"), Some("function")).unwrap();
+        
+        // Same dimension, or an empty store, is fine
+        assert!(check_stored_dimension(&store, 1536, false).is_ok());
+        assert!(check_stored_dimension(&InMemoryVectorStore::new(), 384, false).is_ok());
+        
+        let err = check_stored_dimension(&store, 384, false).unwrap_err();
+        assert!(matches!(err, IndexerError::Config(ref msg) if msg.contains("1536") && msg.contains("--force-reembed")), "{}", err);
+        assert_eq!(store.count().unwrap(), 2, "a refused run must not touch the store");
+        
+        // --force-reembed clears the old vectors so the new ones are not mixed in
+        check_stored_dimension(&store, 384, true).unwrap();
+        assert_eq!(store.count().unwrap(), 0);
+    }
+    
+    #[test]
+    fn test_mixing_normalized_vectors_is_rejected_by_default() {
+        let store = InMemoryVectorStore::new();
+        store.upsert_embedding("fn:a.py:load", &[3.0, 4.0, 0.0], Some("a.py"), Some("function")).unwrap();
+        let raw = EmbeddingMeta { provider: "mock".to_string(), model: "mock".to_string(), dimension: 3, normalized: false };
+        
+        // Matching vectors, no recorded meta, or an empty store are fine
+        assert!(check_stored_normalization(&store, Some(&raw), false, false).is_ok());
+        assert!(check_stored_normalization(&store, None, true, false).is_ok());
+        assert!(check_stored_normalization(&InMemoryVectorStore::new(), Some(&raw), true, false).is_ok());
+        
+        let err = check_stored_normalization(&store, Some(&raw), true, false).unwrap_err();
+        assert!(matches!(err, IndexerError::Config(ref msg) if msg.contains("unnormalized") && msg.contains("--force-reembed")), "{}", err);
+        assert_eq!(store.count().unwrap(), 1, "a refused run must not touch the store");
+        
+        check_stored_normalization(&store, Some(&raw), true, true).unwrap();
+        assert_eq!(store.count().unwrap(), 0);
+    }
+    
+    #[test]
+    fn test_file_granularity_stores_one_vector_per_file() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("pkg")).unwrap();
+        std::fs::write(dir.path().join("app.py"), "def load():\n    pass\n\nclass Job:\n    def run(self):\n        pass\n").unwrap();
+        std::fs::write(dir.path().join("pkg").join("util.py"), "def helper():\n    pass\n").unwrap();
+        
+        let store = InMemoryVectorStore::new();
+        let report = process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 1, granularity: Granularity::File, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        assert_eq!(report.processed, 2);
+        let mut ids = store.get_all_entity_ids().unwrap();
+        ids.sort();
+        let util = Path::new("pkg").join("util.py").to_string_lossy().to_string();
+        assert_eq!(ids, vec!["app.py".to_string(), util]);
+        let metadata = store.get_entity_metadata("app.py").unwrap();
+        assert_eq!(metadata.get("type").map(String::as_str), Some("file"));
+        
+        let store = InMemoryVectorStore::new();
+        process_directory_concurrent(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 4, granularity: Granularity::File, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        assert_eq!(store.count().unwrap(), 2);
+    }
+    
+    #[test]
+    fn test_progress_reports_each_file_and_entity() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("app.py"), "def load():\n    pass\n\nclass Job:\n    def run(self):\n        pass\n").unwrap();
+        std::fs::write(dir.path().join("util.py"), "def helper():\n    pass\n").unwrap();
+        
+        let store = InMemoryVectorStore::new();
+        let mut events = Vec::new();
+        let processed = process_directory(dir.path(), &MockEmbedder, &store, &VectorizeOptions::default(), &mut |event| events.push(event)).unwrap();
+        
+        let mut embedded: Vec<String> = events.iter()
+            .filter_map(|event| match event {
+                ProgressEvent::EntityEmbedded { entity_id, .. } => Some(entity_id.clone()),
+                _ => None,
+            })
+            .collect();
+        embedded.sort();
+        let mut stored = store.get_all_entity_ids().unwrap();
+        stored.sort();
+        assert_eq!(embedded.len(), processed);
+        assert_eq!(embedded, stored);
+        
+        let started = events.iter().filter(|e| matches!(e, ProgressEvent::FileStarted { .. })).count();
+        let finished: usize = events.iter()
+            .filter_map(|event| match event {
+                ProgressEvent::FileFinished { entities, .. } => Some(*entities),
+                _ => None,
+            })
+            .sum();
+        assert_eq!(started, 2);
+        assert_eq!(finished, processed);
+        assert!(matches!(events.first(), Some(ProgressEvent::FileStarted { .. })));
+        assert!(matches!(events.last(), Some(ProgressEvent::FileFinished { .. })));
+        
+        // The concurrent walker reports the same entities from the calling thread
+        let store = InMemoryVectorStore::new();
+        let mut embedded = 0;
+        process_directory_concurrent(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 4, ..VectorizeOptions::default() }, &mut |event| {
+            if let ProgressEvent::EntityEmbedded { .. } = event {
+                embedded += 1;
+            }
+        }).unwrap();
+        assert_eq!(embedded, store.count().unwrap());
+    }
+    
+    /// Embedder that holds every request until `opened` is set, recording whether
+    /// it gave up waiting
+    #[derive(Default)]
+    struct GatedEmbedder {
+        opened: AtomicBool,
+        gave_up: AtomicBool,
+    }
+    
+    impl Embedder for GatedEmbedder {
+        fn embed(&self, input: &str) -> Vec<f32> {
+            let waiting_since = Instant::now();
+            while !self.opened.load(Ordering::SeqCst) {
+                if waiting_since.elapsed() > Duration::from_secs(2) {
+                    self.gave_up.store(true, Ordering::SeqCst);
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            MockEmbedder.embed(input)
+        }
+    }
+    
+    #[test]
+    fn test_concurrent_progress_reports_file_started_before_embedding() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("app.py"), "def load():\n    pass\n").unwrap();
+        
+        let embedder = GatedEmbedder::default();
+        let store = InMemoryVectorStore::new();
+        let report = process_directory_concurrent(dir.path(), &embedder, &store, &VectorizeOptions { concurrency: 1, ..VectorizeOptions::default() }, &mut |event| {
+            if let ProgressEvent::FileStarted { .. } = event {
+                embedder.opened.store(true, Ordering::SeqCst);
+            }
+        }).unwrap();
+        
+        assert!(!embedder.gave_up.load(Ordering::SeqCst), "FileStarted should arrive while the file is still being embedded");
+        assert_eq!(report.processed, 1);
+    }
+    
+    #[test]
+    fn test_search_results_carry_line_range_and_signature() {
+        use crate::vector_search::{search_vectors, SearchOptions};
+        
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("app.py")).unwrap();
+        writeln!(file, "import os").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "def handler(event, context):").unwrap();
+        writeln!(file, "    return event").unwrap();
+        
+        let store = InMemoryVectorStore::new();
+        process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 1, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        let query = store.get_entity_vector("fn:app.py:handler").unwrap();
+        let options = SearchOptions {
+            top_k: 1,
+            min_score: None,
+            entity_types: Some(vec!["function".to_string()]),
+            exclude_entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            search_threads: Some(1),
+            chunk_size: None,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+        
+        let results = search_vectors(&store, &query, &options).unwrap();
+        assert_eq!(results[0].entity_id, "fn:app.py:handler");
+        let metadata = &results[0].metadata;
+        assert_eq!(metadata.get("line_start").map(String::as_str), Some("3"));
+        assert_eq!(metadata.get("line_end").map(String::as_str), Some("4"));
+        assert!(metadata.get("signature").is_some_and(|s| s.contains("handler(event, context)")), "{:?}", metadata);
+    }
+    
+    /// Store that refuses to write one entity
+    struct RefusingStore {
+        inner: InMemoryVectorStore,
+        refused: &'static str,
+    }
+    
+    impl VectorStore for RefusingStore {
+        fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+            if entity_id == self.refused {
+                return Err(IndexerError::Store("write refused".to_string()));
+            }
+            self.inner.upsert_embedding(entity_id, embedding, file, entity_type)
+        }
+        
+        fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+            self.inner.similarity_search(query, top_k)
+        }
+        
+        fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+            self.inner.get_all_entity_ids()
+        }
+        
+        fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+            self.inner.get_entity_vector(entity_id)
+        }
+        
+        fn get_entity_metadata(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, IndexerError> {
+            self.inner.get_entity_metadata(entity_id)
+        }
+    }
+    
+    /// Store that records the size of every `upsert_batch` call
+    #[derive(Default)]
+    struct BatchRecordingStore {
+        inner: InMemoryVectorStore,
+        batches: std::sync::Mutex<Vec<usize>>,
+    }
+    
+    impl VectorStore for BatchRecordingStore {
+        fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
+            self.inner.upsert_embedding(entity_id, embedding, file, entity_type)
+        }
+        
+        fn upsert_batch(&self, items: &[UpsertItem]) -> Result<(), IndexerError> {
+            self.batches.lock().unwrap().push(items.len());
+            self.inner.upsert_batch(items)
+        }
+        
+        fn similarity_search(&self, query: &[f32], top_k: usize) -> Vec<String> {
+            self.inner.similarity_search(query, top_k)
+        }
+        
+        fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
+            self.inner.get_all_entity_ids()
+        }
+        
+        fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
+            self.inner.get_entity_vector(entity_id)
+        }
+        
+        fn get_entity_metadata(&self, entity_id: &str) -> Result<std::collections::HashMap<String, String>, IndexerError> {
+            self.inner.get_entity_metadata(entity_id)
+        }
+    }
+    
+    #[test]
+    fn test_process_directory_concurrent_writes_batch_size_files_together() {
+        let dir = tempdir().unwrap();
+        for i in 0..5 {
+            std::fs::write(dir.path().join(format!("mod_{}.py", i)), format!("def handler_{}():\n    pass\n", i)).unwrap();
+        }
+        
+        let store = BatchRecordingStore::default();
+        let report = process_directory_concurrent(dir.path(), &MockEmbedder, &store, &VectorizeOptions { batch_size: 2, concurrency: 3, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        
+        assert_eq!(report.processed, 5);
+        assert_eq!(*store.batches.lock().unwrap(), vec![2, 2, 1]);
+        assert_eq!(store.count().unwrap(), 5);
+    }
+    
+    #[test]
+    fn test_process_directory_reports_failures_and_continues() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("a.py")).unwrap();
+        writeln!(file, "def refused():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        writeln!(file, "def kept():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        let mut file = File::create(dir.path().join("b.py")).unwrap();
+        writeln!(file, "class Widget:").unwrap();
+        writeln!(file, "    pass").unwrap();
+        
+        let store = RefusingStore { inner: InMemoryVectorStore::new(), refused: "fn:a.py:refused" };
+        let report = process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 2, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        
+        assert_eq!(report.processed, 2);
+        assert_eq!(report.errors.len(), 1, "unexpected errors: {:?}", report.errors);
+        assert!(report.errors[0].contains("fn:a.py:refused"));
+        assert!(store.get_entity_vector("fn:a.py:kept").is_ok());
+        assert!(store.get_entity_vector("class:b.py:Widget").is_ok());
+    }
+    
+    #[test]
+    fn test_only_missing_embeds_entities_without_vectors() {
+        let dir = tempdir().unwrap();
+        for i in 0..4 {
+            let mut file = File::create(dir.path().join(format!("mod_{}.py", i))).unwrap();
+            writeln!(file, "def handler_{}():", i).unwrap();
+            writeln!(file, "    pass").unwrap();
+        }
+        let store = InMemoryVectorStore::new();
+        for i in 0..2 {
+            let file = format!("mod_{}.py", i);
+            store.upsert_embedding(&format!("fn:{}:handler_{}", file, i), &[1.0, 0.0, 0.0], Some(&file), Some("function")).unwrap();
+        }
+        
+        let embedder = CountingEmbedder::default();
+        let report = process_directory_with_diagnostics(dir.path(), &embedder, &store, &VectorizeOptions { concurrency: 1, only_missing: true, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        
+        assert_eq!(report.processed, 2);
+        let mut embedded = embedder.0.into_inner().unwrap();
+        embedded.sort();
+        assert_eq!(embedded.len(), 2, "only the missing half should be embedded: {:?}", embedded);
+        assert!(embedded[0].contains("handler_2") && embedded[1].contains("handler_3"));
+        assert_eq!(store.get_entity_vector("fn:mod_0.py:handler_0").unwrap(), vec![1.0, 0.0, 0.0], "existing vectors are left alone");
+        assert_eq!(store.count().unwrap(), 4);
+    }
+    
+    #[test]
+    fn test_entity_type_filter_skips_unselected_types() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("a.py")).unwrap();
+        writeln!(file, "class Widget:").unwrap();
+        writeln!(file, "    def save(self):  # TODO: validate").unwrap();
+        writeln!(file, "        pass").unwrap();
+        
+        let store = InMemoryVectorStore::new();
+        let types = EntityTypeFilter::new(Some(vec!["method".to_string(), "todo".to_string()]), Some(vec!["todo".to_string()]));
+        let report = process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 1, index_todos: true, types, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+        
+        assert_eq!(report.processed, 1);
+        assert_eq!(store.get_all_entity_ids().unwrap(), vec!["method:a.py:Widget.save"]);
+        assert!(EntityTypeFilter::default().allows("variable"));
+    }
+    
+    #[test]
+    fn test_plan_directory_counts_new_entities() {
+        let dir = tempdir().unwrap();
+        let mut file = File::create(dir.path().join("a.py")).unwrap();
+        writeln!(file, "def embedded():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        writeln!(file, "def fresh():").unwrap();
+        writeln!(file, "    pass").unwrap();
+        let mut file = File::create(dir.path().join("b.py")).unwrap();
+        writeln!(file, "class Widget:").unwrap();
+        writeln!(file, "    pass").unwrap();
+        
+        let store = InMemoryVectorStore::new();
+        store.upsert_embedding("fn:a.py:embedded", &[1.0, 0.0, 0.0], Some("a.py"), Some("function")).unwrap();
+        
+        let plan = plan_directory(dir.path(), &store, DEFAULT_MAX_FILE_BYTES, &default_source_extensions(), false, &EntityTypeFilter::default(), DEFAULT_PRICE_PER_1K_TOKENS).unwrap();
+        
+        assert_eq!(plan.files_scanned, 2);
+        assert_eq!(plan.new_entities, 2, "fresh and Widget should be new: {:?}", plan);
+        assert_eq!(plan.existing_entities, 1);
+        assert_eq!(plan.files_with_new_entities, vec!["a.py", "b.py"]);
+        assert!(plan.estimated_tokens > 0);
+        assert!(plan.estimated_cost > 0.0);
+        assert_eq!(store.len(), 1, "Planning must not write to the store");
+    }
 }
@@ -0,0 +1,245 @@
+//! Implementation of the watch command
+//!
+//! Watches a project directory with `notify` and incrementally re-indexes source files
+//! as they change. Events are debounced per path so editors that write a file twice
+//! (e.g. truncate then write) only trigger a single re-index.
+
+use crate::ast_parser::parse_code_info;
+use crate::error::IndexerError;
+use crate::file_processing::{has_source_extension, is_skipped_path, modified_unix_secs};
+use crate::redis_ops::{clear_file_data, store_code_entities, store_file_content};
+use fred::prelude::Client;
+use log::{debug, info, warn};
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Quiet period a path must see before its pending change is applied
+pub const DEBOUNCE_DELAY: Duration = Duration::from_millis(500);
+
+/// A debounced change to a single file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    /// File was created or modified and should be re-indexed
+    Modified(PathBuf),
+    /// File was deleted and its indexed data should be cleared
+    Removed(PathBuf),
+}
+
+impl FileChange {
+    pub fn path(&self) -> &Path {
+        match self {
+            FileChange::Modified(path) | FileChange::Removed(path) => path,
+        }
+    }
+}
+
+/// Collapses bursts of changes to the same path into the latest one
+#[derive(Debug)]
+pub struct Debouncer {
+    delay: Duration,
+    pending: HashMap<PathBuf, (FileChange, Instant)>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        Self { delay, pending: HashMap::new() }
+    }
+
+    /// Record a change seen at `now`, replacing any pending change for the same path
+    pub fn push(&mut self, change: FileChange, now: Instant) {
+        self.pending.insert(change.path().to_path_buf(), (change, now));
+    }
+
+    /// Remove and return the changes that have been quiet for at least the delay
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<FileChange> {
+        let ready: Vec<PathBuf> = self.pending.iter()
+            .filter(|(_, (_, seen))| now.duration_since(*seen) >= self.delay)
+            .map(|(path, _)| path.clone())
+            .collect();
+        let mut changes: Vec<FileChange> = ready.into_iter()
+            .filter_map(|path| self.pending.remove(&path).map(|(change, _)| change))
+            .collect();
+        changes.sort_by(|a, b| a.path().cmp(b.path()));
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Whether a path is a source with one of `extensions` the indexer should track
+pub fn is_supported_file(path: &Path, extensions: &[String]) -> bool {
+    has_source_extension(path, extensions) && !is_skipped_path(path)
+}
+
+/// Translate a `notify` event into the file changes it implies for sources with one of `extensions`
+pub fn classify_event(event: &notify::Event, extensions: &[String]) -> Vec<FileChange> {
+    let to_change: fn(PathBuf) -> FileChange = match event.kind {
+        EventKind::Create(_) | EventKind::Modify(_) => FileChange::Modified,
+        EventKind::Remove(_) => FileChange::Removed,
+        _ => return Vec::new(),
+    };
+    event.paths.iter()
+        .filter(|path| is_supported_file(path, extensions))
+        .cloned()
+        .map(to_change)
+        .collect()
+}
+
+/// Re-index or clear a single changed file
+async fn apply_change(redis: &Client, key_prefix: &str, app_dir: &Path, change: &FileChange) -> Result<(), IndexerError> {
+    let file = change.path();
+    let rel_path = file.strip_prefix(app_dir).unwrap_or(file).to_string_lossy().to_string();
+    // Editors often replace files via rename, so trust the filesystem over the event kind
+    if matches!(change, FileChange::Removed(_)) || !file.exists() {
+        clear_file_data(redis, key_prefix, std::slice::from_ref(&rel_path)).await?;
+        info!("Cleared {}", rel_path);
+        return Ok(());
+    }
+
+    let content = tokio::fs::read_to_string(file).await?;
+    let meta = tokio::fs::metadata(file).await?;
+    let size = meta.len() as usize;
+    let mtime = modified_unix_secs(&meta)?;
+    store_file_content(redis, key_prefix, &rel_path, &content, size, mtime).await?;
+    match parse_code_info(&content, &rel_path) {
+        Ok(entities) => store_code_entities(redis, key_prefix, &entities).await?,
+        // Keep the last good entities until the file parses again
+        Err(diagnostic) => {
+            warn!("Parse error in {}; keeping its previously indexed entities", diagnostic);
+            return Ok(());
+        }
+    }
+    info!("Reindexed {}", rel_path);
+    Ok(())
+}
+
+/// Watch a project directory and keep its index up to date until the process exits
+///
+/// # Arguments
+/// * `redis` - Connected Redis client
+/// * `key_prefix` - Project key prefix (e.g. `code_index:<name>`)
+/// * `app_dir` - Project directory to watch recursively
+/// * `extensions` - Source file extensions to re-index (e.g. `config.source_extensions()`)
+///
+/// # Returns
+/// * `Result<(), IndexerError>` - Only returns on a watcher setup or channel error
+pub async fn watch_project(redis: &Client, key_prefix: &str, app_dir: &Path, extensions: &[String]) -> Result<(), IndexerError> {
+    if !app_dir.exists() {
+        return Err(IndexerError::PathNotFound(app_dir.to_path_buf()));
+    }
+    let app_dir = app_dir.canonicalize()?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        // The receiver only goes away when the watch loop ends
+        let _ = tx.send(res);
+    }).map_err(|e| IndexerError::Watch(e.to_string()))?;
+    watcher.watch(&app_dir, RecursiveMode::Recursive)
+        .map_err(|e| IndexerError::Watch(e.to_string()))?;
+    info!("Watching {} for changes", app_dir.display());
+
+    let mut debouncer = Debouncer::new(DEBOUNCE_DELAY);
+    loop {
+        // Block until something happens, then poll until pending changes settle
+        let next = if debouncer.is_empty() {
+            rx.recv().await.map(Some)
+        } else {
+            match tokio::time::timeout(DEBOUNCE_DELAY / 5, rx.recv()).await {
+                Ok(event) => event.map(Some),
+                Err(_) => Some(None),
+            }
+        };
+        match next {
+            Some(Some(Ok(event))) => {
+                debug!("Watch event: {:?}", event);
+                let now = Instant::now();
+                for change in classify_event(&event, extensions) {
+                    debouncer.push(change, now);
+                }
+            }
+            Some(Some(Err(e))) => warn!("Watch error: {}", e),
+            Some(None) => {}
+            None => return Err(IndexerError::Watch("file watcher stopped unexpectedly".to_string())),
+        }
+
+        for change in debouncer.drain_ready(Instant::now()) {
+            if let Err(e) = apply_change(redis, key_prefix, &app_dir, &change).await {
+                warn!("Failed to reindex {}: {}", change.path().display(), e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, ModifyKind, RemoveKind};
+
+    #[test]
+    fn test_debouncer_collapses_repeated_writes() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_millis(500));
+        debouncer.push(FileChange::Modified(PathBuf::from("a.py")), start);
+        debouncer.push(FileChange::Modified(PathBuf::from("a.py")), start + Duration::from_millis(100));
+
+        // Still inside the quiet period of the second write
+        assert!(debouncer.drain_ready(start + Duration::from_millis(500)).is_empty());
+
+        let ready = debouncer.drain_ready(start + Duration::from_millis(600));
+        assert_eq!(ready, vec![FileChange::Modified(PathBuf::from("a.py"))]);
+        assert!(debouncer.is_empty());
+    }
+
+    #[test]
+    fn test_debouncer_keeps_latest_change() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_millis(500));
+        debouncer.push(FileChange::Modified(PathBuf::from("a.py")), start);
+        debouncer.push(FileChange::Removed(PathBuf::from("a.py")), start);
+        debouncer.push(FileChange::Modified(PathBuf::from("b.py")), start);
+
+        let ready = debouncer.drain_ready(start + Duration::from_millis(500));
+        assert_eq!(ready, vec![
+            FileChange::Removed(PathBuf::from("a.py")),
+            FileChange::Modified(PathBuf::from("b.py")),
+        ]);
+    }
+
+    #[test]
+    fn test_classify_event_filters_unsupported_files() {
+    let py = vec!["py".to_string()];
+        let event = notify::Event::new(EventKind::Modify(ModifyKind::Any))
+            .add_path(PathBuf::from("/proj/app.py"))
+            .add_path(PathBuf::from("/proj/README.md"))
+            .add_path(PathBuf::from("/proj/.venv/lib/site.py"));
+        assert_eq!(classify_event(&event, &py), vec![FileChange::Modified(PathBuf::from("/proj/app.py"))]);
+
+        let event = notify::Event::new(EventKind::Create(CreateKind::File))
+            .add_path(PathBuf::from("/proj/new.py"));
+        assert_eq!(classify_event(&event, &py), vec![FileChange::Modified(PathBuf::from("/proj/new.py"))]);
+
+        let event = notify::Event::new(EventKind::Remove(RemoveKind::File))
+            .add_path(PathBuf::from("/proj/old.py"));
+        assert_eq!(classify_event(&event, &py), vec![FileChange::Removed(PathBuf::from("/proj/old.py"))]);
+
+        let event = notify::Event::new(EventKind::Access(notify::event::AccessKind::Any))
+            .add_path(PathBuf::from("/proj/app.py"));
+        assert!(classify_event(&event, &py).is_empty());
+    }
+
+    #[test]
+    fn test_rs_only_project_ignores_python_changes() {
+        let rs = vec!["rs".to_string()];
+        assert!(is_supported_file(Path::new("/proj/src/lib.rs"), &rs));
+        assert!(!is_supported_file(Path::new("/proj/app.py"), &rs));
+
+        let event = notify::Event::new(EventKind::Modify(ModifyKind::Any))
+            .add_path(PathBuf::from("/proj/app.py"))
+            .add_path(PathBuf::from("/proj/src/lib.rs"));
+        assert_eq!(classify_event(&event, &rs), vec![FileChange::Modified(PathBuf::from("/proj/src/lib.rs"))]);
+    }
+}
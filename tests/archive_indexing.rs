@@ -0,0 +1,63 @@
+//! Tests for indexing python sources streamed from tar/zip archives
+
+use indexer::ast_parser::extract_code_info_from_source;
+use indexer::file_processing::{archive_kind, read_tar_source_files, ArchiveKind};
+use std::path::Path;
+
+/// Build an in-memory tarball from (path, content) pairs
+fn build_tarball(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn test_archive_kind_detection() {
+    assert_eq!(archive_kind(Path::new("build/src.tar.gz")), Some(ArchiveKind::TarGz));
+    assert_eq!(archive_kind(Path::new("build/src.tgz")), Some(ArchiveKind::TarGz));
+    assert_eq!(archive_kind(Path::new("build/src.tar")), Some(ArchiveKind::Tar));
+    assert_eq!(archive_kind(Path::new("build/src.ZIP")), Some(ArchiveKind::Zip));
+    assert_eq!(archive_kind(Path::new("build/src")), None);
+}
+
+#[test]
+fn test_index_python_files_from_in_memory_tarball() {
+    let tarball = build_tarball(&[
+        ("pkg/app.py", "def handler():\n    pass\n"),
+        ("pkg/models.py", "class User:\n    pass\n"),
+        ("pkg/README.md", "# not python\n"),
+        ("pkg/__pycache__/app.py", "def cached():\n    pass\n"),
+    ]);
+
+    let sources = read_tar_source_files(tarball.as_slice(), &["py".to_string()]).unwrap();
+    let mut rel_paths: Vec<&str> = sources.iter().map(|(p, _)| p.as_str()).collect();
+    rel_paths.sort();
+    assert_eq!(rel_paths, vec!["pkg/app.py", "pkg/models.py"]);
+
+    let entities: Vec<_> = sources
+        .iter()
+        .flat_map(|(rel_path, content)| extract_code_info_from_source(content, rel_path))
+        .collect();
+    assert!(entities.iter().any(|e| e.name == "handler" && e.file_path == "pkg/app.py"));
+    assert!(entities.iter().any(|e| e.name == "User" && e.file_path == "pkg/models.py"));
+    assert!(!entities.iter().any(|e| e.name == "cached"));
+}
+
+#[test]
+fn test_rs_only_extensions_skip_archived_python_files() {
+    let tarball = build_tarball(&[
+        ("pkg/app.py", "def handler():\n    pass\n"),
+        ("src/lib.rs", "pub fn handler() {}\n"),
+        ("build/gen.rs", "fn main() {}\n"),
+    ]);
+
+    let sources = read_tar_source_files(tarball.as_slice(), &["rs".to_string()]).unwrap();
+    let rel_paths: Vec<&str> = sources.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(rel_paths, vec!["src/lib.rs"]);
+}
@@ -0,0 +1,26 @@
+//! Tests for shell completion generation
+
+use clap::Parser;
+use clap_complete::Shell;
+use indexer::cli::{write_completions, CliArgs, Commands};
+
+#[test]
+fn test_bash_completions_list_subcommands() {
+    let mut out = Vec::new();
+    write_completions(Shell::Bash, &mut out);
+    let script = String::from_utf8(out).unwrap();
+    
+    assert!(!script.is_empty());
+    for subcommand in ["remember", "recall", "vectorize", "vector-recall", "completions"] {
+        assert!(script.contains(subcommand), "bash completions should mention {}", subcommand);
+    }
+}
+
+#[test]
+fn test_completions_parsing() {
+    let cli = CliArgs::parse_from(["indexer", "completions", "zsh"]);
+    match cli.command {
+        Commands::Completions { shell } => assert_eq!(shell, Shell::Zsh),
+        _ => panic!("Expected completions subcommand to be parsed"),
+    }
+}
@@ -0,0 +1,27 @@
+//! Tests for recall subcommand CLI parsing
+
+use clap::Parser;
+use indexer::cli::{since_cutoff, CliArgs, Commands};
+
+#[test]
+fn test_since_cutoff_accepts_dates_and_durations() {
+    let now = 1_700_000_000;
+    assert_eq!(since_cutoff("2024-01-01", now), Ok(1_704_067_200));
+    assert_eq!(since_cutoff("2024-01-01T12:00:00+02:00", now), Ok(1_704_103_200));
+    assert_eq!(since_cutoff("7d", now), Ok(now - 7 * 24 * 60 * 60));
+    assert_eq!(since_cutoff("12h", now), Ok(now - 12 * 60 * 60));
+    assert_eq!(since_cutoff("2w", now), Ok(now - 14 * 24 * 60 * 60));
+    assert!(since_cutoff("yesterday", now).is_err());
+    assert!(since_cutoff("7y", now).is_err());
+    assert!(since_cutoff("d", now).is_err());
+}
+
+#[test]
+fn test_recall_parsing_since_flag() {
+    let cli = CliArgs::parse_from(["indexer", "recall", "--name", "p", "--since", "2024-01-01"]);
+    match cli.command {
+        Commands::Recall { since, .. } => assert_eq!(since, Some(1_704_067_200)),
+        _ => panic!("Expected recall subcommand to be parsed"),
+    }
+    assert!(CliArgs::try_parse_from(["indexer", "recall", "--name", "p", "--since", "soon"]).is_err());
+}
@@ -0,0 +1,20 @@
+//! Tests for remember subcommand CLI parsing
+
+use clap::Parser;
+use indexer::cli::{CliArgs, Commands};
+
+fn exclude_tests(args: &[&str]) -> bool {
+    match CliArgs::parse_from(args).command {
+        Commands::Remember { exclude_tests, .. } => exclude_tests,
+        _ => panic!("Expected remember subcommand to be parsed"),
+    }
+}
+
+#[test]
+fn test_remember_parsing_test_file_flags() {
+    assert!(!exclude_tests(&["indexer", "remember", "--name", "p"]));
+    assert!(exclude_tests(&["indexer", "remember", "--name", "p", "--exclude-tests"]));
+    // The last of the two flags wins
+    assert!(!exclude_tests(&["indexer", "remember", "--name", "p", "--exclude-tests", "--include-tests"]));
+    assert!(exclude_tests(&["indexer", "remember", "--name", "p", "--include-tests", "--exclude-tests"]));
+}
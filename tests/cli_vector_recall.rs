@@ -2,6 +2,7 @@
 
 use clap::Parser;
 use indexer::cli::{CliArgs, Commands};
+use indexer::output_format::OutputFormat;
 
 #[test]
 fn test_vector_recall_parsing_required_args() {
@@ -16,7 +17,7 @@ fn test_vector_recall_parsing_required_args() {
     ];
     let cli = CliArgs::parse_from(args);
     match cli.command {
-        Commands::VectorRecall { name, query, provider, db, top_k, json }
+        Commands::VectorRecall { name, query, provider, db, top_k, json, .. }
 
 
 
@@ -113,6 +114,72 @@ fn test_vector_recall_optional_args() {
 
 }
 
+#[test]
+fn test_vector_recall_rejects_zero_top_k() {
+    let args = vec!["indexer", "vector-recall", "--name", "my_project", "--query", "foo", "--top-k", "0"];
+    let err = CliArgs::try_parse_from(args).expect_err("--top-k 0 should be rejected");
+    assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    let message = err.to_string();
+    assert!(message.contains("--top-k") && message.contains("at least 1"), "unexpected error: {}", message);
+}
+
+#[test]
+fn test_vector_recall_parsing_filters() {
+    let args = vec![
+        "indexer", "vector-recall", "--name", "my_project", "--query", "load config",
+        "--entity-type", "function", "--file", "src/**/*.py", "--min-score", "0.35",
+    ];
+    match CliArgs::parse_from(args).command {
+        Commands::VectorRecall { entity_type, file, min_score, .. } => {
+            assert_eq!(entity_type.as_deref(), Some("function"));
+            assert_eq!(file.as_deref(), Some("src/**/*.py"));
+            assert_eq!(min_score, Some(0.35));
+        }
+        _ => panic!("Expected vector-recall subcommand to be parsed"),
+    }
+}
+
+#[test]
+fn test_vector_recall_parsing_model_flag() {
+    let cli = CliArgs::parse_from(["indexer", "vector-recall", "--name", "p", "--query", "q", "--provider", "hf", "--model", "BAAI/bge-small-en-v1.5"]);
+    match cli.command {
+        Commands::VectorRecall { model, .. } => assert_eq!(model.as_deref(), Some("BAAI/bge-small-en-v1.5")),
+        _ => panic!("Expected vector-recall subcommand to be parsed"),
+    }
+}
 
+#[test]
+fn test_vector_recall_parsing_multiple_projects() {
+    let cli = CliArgs::parse_from(["indexer", "vector-recall", "--name", "api,web", "--query", "login"]);
+    match cli.command {
+        Commands::VectorRecall { name, all, .. } => {
+            assert_eq!(name, "api,web");
+            assert!(!all);
+        }
+        _ => panic!("Expected VectorRecall command"),
+    }
 
+    let cli = CliArgs::parse_from(["indexer", "vector-recall", "--all", "--query", "login"]);
+    match cli.command {
+        Commands::VectorRecall { all, .. } => assert!(all),
+        _ => panic!("Expected VectorRecall command"),
+    }
 
+    // One of --name or --all is needed, and they cannot be combined
+    assert!(CliArgs::try_parse_from(["indexer", "vector-recall", "--query", "login"]).is_err());
+    assert!(CliArgs::try_parse_from(["indexer", "vector-recall", "--all", "--name", "api", "--query", "login"]).is_err());
+}
+
+#[test]
+fn test_vector_recall_parsing_format() {
+    let cli = CliArgs::parse_from(["indexer", "vector-recall", "--name", "p", "--query", "q", "--format", "markdown"]);
+    match cli.command {
+        Commands::VectorRecall { format, json, .. } => {
+            assert!(matches!(format, Some(OutputFormat::Markdown)));
+            assert!(!json);
+        }
+        _ => panic!("Expected VectorRecall command"),
+    }
+    let err = CliArgs::try_parse_from(["indexer", "vector-recall", "--name", "p", "--query", "q", "--json", "--format", "jsonl"]).unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
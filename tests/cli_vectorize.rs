@@ -2,6 +2,7 @@
 
 use clap::Parser;
 use indexer::cli::{CliArgs, Commands};
+use indexer::vectorize::Granularity;
 
 #[test]
 fn test_vectorize_parsing_mandatory_args() {
@@ -16,7 +17,7 @@ fn test_vectorize_parsing_mandatory_args() {
     ];
     let cli = CliArgs::parse_from(args);
     match cli.command {
-        Commands::Vectorize { name, path, provider, db, batch_size, dry_run, verbose } => {
+        Commands::Vectorize { name, path, provider, db, batch_size, dry_run, verbose, .. } => {
             assert_eq!(name, "my_project");
             assert_eq!(path, "./src");
             assert!(provider.is_none());
@@ -163,3 +164,71 @@ fn test_vectorize_parsing_db_arg() {
 
 }
 
+
+#[test]
+fn test_vectorize_parsing_model_flag() {
+    let cli = CliArgs::parse_from(["indexer", "vectorize", "--name", "p", "--path", ".", "--provider", "openai", "--model", "text-embedding-3-large"]);
+    match cli.command {
+        Commands::Vectorize { provider, model, .. } => {
+            assert_eq!(provider.as_deref(), Some("openai"));
+            assert_eq!(model.as_deref(), Some("text-embedding-3-large"));
+        }
+        _ => panic!("Expected vectorize subcommand to be parsed"),
+    }
+}
+
+#[test]
+fn test_vectorize_parsing_limit_flag() {
+    let cli = CliArgs::parse_from(["indexer", "vectorize", "--name", "p", "--path", ".", "--limit", "5"]);
+    match cli.command {
+        Commands::Vectorize { limit, .. } => assert_eq!(limit, Some(5)),
+        _ => panic!("Expected vectorize subcommand to be parsed"),
+    }
+}
+
+#[test]
+fn test_vectorize_parsing_emit_flag() {
+    let cli = CliArgs::parse_from(["indexer", "vectorize", "--name", "p", "--path", ".", "--emit", "out.jsonl", "--dry-run"]);
+    match cli.command {
+        Commands::Vectorize { emit, dry_run, .. } => {
+            assert_eq!(emit.as_deref(), Some("out.jsonl"));
+            assert!(dry_run);
+        }
+        _ => panic!("Expected vectorize subcommand to be parsed"),
+    }
+}
+
+#[test]
+fn test_vectorize_parsing_granularity_flag() {
+    let cli = CliArgs::parse_from(["indexer", "vectorize", "--name", "p", "--path", ".", "--granularity", "file"]);
+    match cli.command {
+        Commands::Vectorize { granularity, .. } => assert_eq!(granularity, Granularity::File),
+        _ => panic!("Expected vectorize subcommand to be parsed"),
+    }
+    let cli = CliArgs::parse_from(["indexer", "vectorize", "--name", "p", "--path", "."]);
+    match cli.command {
+        Commands::Vectorize { granularity, .. } => assert_eq!(granularity, Granularity::Entity),
+        _ => panic!("Expected vectorize subcommand to be parsed"),
+    }
+    assert!(CliArgs::try_parse_from(["indexer", "vectorize", "--name", "p", "--path", ".", "--granularity", "line"]).is_err());
+}
+
+#[test]
+fn test_vectorize_parsing_force_reembed_flag() {
+    let cli = CliArgs::parse_from(["indexer", "vectorize", "--name", "p", "--path", ".", "--force-reembed"]);
+    match cli.command {
+        Commands::Vectorize { force_reembed, .. } => assert!(force_reembed),
+        _ => panic!("Expected vectorize subcommand to be parsed"),
+    }
+}
+
+#[test]
+fn test_memory_backend_vectorize_needs_no_redis() {
+    let uses_redis = |args: &[&str]| CliArgs::parse_from(args).command.uses_redis();
+    assert!(!uses_redis(&["indexer", "vectorize", "--name", "p", "--path", ".", "--db", "memory"]));
+    assert!(uses_redis(&["indexer", "vectorize", "--name", "p", "--path", ".", "--db", "memory", "--from-index"]));
+    assert!(uses_redis(&["indexer", "vectorize", "--name", "p", "--path", "."]));
+    assert!(uses_redis(&["indexer", "vectorize", "--name", "p", "--path", ".", "--db", "qdrant"]));
+    assert!(!uses_redis(&["indexer", "vector-recall", "--name", "p", "--query", "q", "--db", "memory"]));
+    assert!(uses_redis(&["indexer", "stats", "--name", "p"]));
+}
@@ -40,10 +40,40 @@ fn test_load_config_missing_home() {
         let config_path = home.join(".indexer").join("config.yaml");
         let _ = fs::remove_file(&config_path);
     }
+    // Nor a ./.indexer/config.yaml fallback in the current directory
+    let orig_cwd = std::env::current_dir().unwrap();
+    let empty_dir = tempfile::tempdir().unwrap();
+    std::env::set_current_dir(empty_dir.path()).unwrap();
     let result = AppConfig::load();
+    std::env::set_current_dir(&orig_cwd).unwrap();
     assert!(matches!(result, Err(ConfigError::HomeDirNotFound)), "Expected HomeDirNotFound error, got: {:?}", result);
 }
 
+#[test]
+#[serial]
+fn test_load_config_from_xdg_config_home_without_home() {
+    let orig_home = std::env::var("HOME").ok();
+    let orig_xdg = std::env::var("XDG_CONFIG_HOME").ok();
+    let xdg_dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(xdg_dir.path().join(".indexer")).unwrap();
+    fs::write(xdg_dir.path().join(".indexer").join("config.yaml"), "log_level: debug\n").unwrap();
+    std::env::remove_var("HOME");
+    std::env::set_var("XDG_CONFIG_HOME", xdg_dir.path());
+
+    let result = AppConfig::load();
+
+    match orig_home {
+        Some(orig) => std::env::set_var("HOME", orig),
+        None => std::env::remove_var("HOME"),
+    }
+    match orig_xdg {
+        Some(orig) => std::env::set_var("XDG_CONFIG_HOME", orig),
+        None => std::env::remove_var("XDG_CONFIG_HOME"),
+    }
+    let config = result.expect("config should load from XDG_CONFIG_HOME");
+    assert_eq!(config.log_level, Some("debug".to_string()));
+}
+
 #[test]
 #[serial]
 fn test_load_config_bad_yaml() {
@@ -104,3 +134,111 @@ fn test_load_config_from_file() {
     }
 }
 
+
+#[test]
+#[serial]
+fn test_load_merged_project_overrides_global() {
+    let orig_home = std::env::var("HOME").ok();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let home = temp_dir.path();
+    std::env::set_var("HOME", home);
+    let config_dir = home.join(".indexer");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(config_dir.join("config.yaml"), "redis_url: \"redis://global:6379/0\"\nlog_level: \"warn\"\nsearch_threads: 2\n").unwrap();
+    
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join(".indexer.yaml"), "redis_url: \"redis://project:6379/3\"\n").unwrap();
+    
+    let config = AppConfig::load_merged(project_dir.path()).unwrap();
+    // Project value wins
+    assert_eq!(config.redis_url, Some("redis://project:6379/3".to_string()));
+    // Unset project fields fall back to the global file, then to the defaults
+    assert_eq!(config.log_level, Some("warn".to_string()));
+    assert_eq!(config.search_threads, Some(2));
+    assert_eq!(config.search_chunk_size, None);
+    
+    // Without a project file the global config is used as-is
+    let bare_project = tempfile::tempdir().unwrap();
+    let config = AppConfig::load_merged(bare_project.path()).unwrap();
+    assert_eq!(config.redis_url, Some("redis://global:6379/0".to_string()));
+    
+    temp_dir.close().unwrap();
+    if let Some(ref orig) = orig_home {
+        std::env::set_var("HOME", orig);
+    } else {
+        std::env::remove_var("HOME");
+    }
+}
+
+#[test]
+#[serial]
+fn test_env_overrides_beat_config_files() {
+    let orig_home = std::env::var("HOME").ok();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let home = temp_dir.path();
+    std::env::set_var("HOME", home);
+    let config_dir = home.join(".indexer");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.yaml"),
+        "redis_url: \"redis://global:6379/0\"\nlog_level: \"warn\"\nproviders:\n  openai:\n    api_key: \"file-key\"\n    model: \"text-embedding-3-small\"\n",
+    ).unwrap();
+    let project_dir = tempfile::tempdir().unwrap();
+    fs::write(project_dir.path().join(".indexer.yaml"), "redis_url: \"redis://project:6379/3\"\n").unwrap();
+    
+    std::env::set_var("INDEXER_REDIS_URL", "redis://ci:6379/9");
+    std::env::set_var("INDEXER_LOG_LEVEL", "trace");
+    std::env::set_var("OPENAI_API_KEY", "env-key");
+    
+    let config = AppConfig::load().unwrap();
+    assert_eq!(config.redis_url, Some("redis://ci:6379/9".to_string()));
+    assert_eq!(config.log_level, Some("trace".to_string()));
+    let openai = &config.providers.as_ref().unwrap()["openai"];
+    assert_eq!(openai.api_key(), "env-key");
+    assert_eq!(openai.model(), "text-embedding-3-small");
+    
+    // The environment also wins over the project file
+    let config = AppConfig::load_merged(project_dir.path()).unwrap();
+    assert_eq!(config.redis_url, Some("redis://ci:6379/9".to_string()));
+    
+    // Without the variables the file values come back
+    std::env::remove_var("INDEXER_REDIS_URL");
+    std::env::remove_var("INDEXER_LOG_LEVEL");
+    std::env::remove_var("OPENAI_API_KEY");
+    let config = AppConfig::load().unwrap();
+    assert_eq!(config.redis_url, Some("redis://global:6379/0".to_string()));
+    assert_eq!(config.log_level, Some("warn".to_string()));
+    assert_eq!(config.providers.as_ref().unwrap()["openai"].api_key(), "file-key");
+    
+    temp_dir.close().unwrap();
+    if let Some(ref orig) = orig_home {
+        std::env::set_var("HOME", orig);
+    } else {
+        std::env::remove_var("HOME");
+    }
+}
+
+#[test]
+#[serial]
+fn test_load_config_reports_unknown_keys() {
+    let orig_home = std::env::var("HOME").ok();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let home = temp_dir.path();
+    std::env::set_var("HOME", home);
+    let config_dir = home.join(".indexer");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(config_dir.join("config.yaml"), "redis_ur: \"redis://typo:6379/0\"\nlog_level: \"warn\"\n").unwrap();
+
+    let result = AppConfig::load();
+
+    if let Some(ref orig) = orig_home {
+        std::env::set_var("HOME", orig);
+    } else {
+        std::env::remove_var("HOME");
+    }
+    match result {
+        Err(ConfigError::UnknownKeys { keys, .. }) => assert_eq!(keys, vec!["redis_ur"]),
+        other => panic!("Expected ConfigError::UnknownKeys, got: {:?}", other),
+    }
+    temp_dir.close().unwrap();
+}
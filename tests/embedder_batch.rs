@@ -0,0 +1,66 @@
+//! Tests for splitting OpenAIEmbedder::embed_batch into sub-batch requests
+
+use indexer::embedder::{split_batches, Embedder, OpenAIEmbedder};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// Embeds each `input N` as `[N]`, listing the data in reverse so the embedder
+/// has to restore input order from `index`
+struct EchoEmbeddings;
+
+impl Respond for EchoEmbeddings {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+        let data: Vec<serde_json::Value> = body["input"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, input)| {
+                let n: f32 = input.as_str().unwrap().trim_start_matches("input ").parse().unwrap();
+                serde_json::json!({ "object": "embedding", "index": index, "embedding": [n] })
+            })
+            .collect();
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({ "object": "list", "data": data }))
+    }
+}
+
+#[test]
+fn test_split_batches_respects_item_and_token_limits() {
+    let inputs: Vec<String> = (0..5).map(|_| "x".repeat(40)).collect(); // 10 tokens each
+    assert_eq!(split_batches(&inputs, 2, 1_000), vec![0..2, 2..4, 4..5]);
+    assert_eq!(split_batches(&inputs, 100, 25), vec![0..2, 2..4, 4..5]);
+
+    // An oversized input still goes out, alone
+    let inputs = vec!["a".to_string(), "x".repeat(400), "b".to_string()];
+    assert_eq!(split_batches(&inputs, 100, 50), vec![0..1, 1..2, 2..3]);
+    assert!(split_batches(&[], 100, 50).is_empty());
+}
+
+// The embedder blocks, so the mock server needs its own worker threads
+#[tokio::test(flavor = "multi_thread")]
+async fn test_embed_batch_splits_into_capped_requests_in_order() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(EchoEmbeddings).mount(&server).await;
+
+    let embedder = OpenAIEmbedder::new("sk-test", "text-embedding-ada-002")
+        .unwrap()
+        .with_endpoint(&format!("{}/v1/embeddings", server.uri()))
+        .with_batch_limits(100, 1_000_000);
+    let inputs: Vec<String> = (0..250).map(|i| format!("input {}", i)).collect();
+
+    let embeddings = embedder.embed_batch(&inputs).unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 3);
+    let sizes: Vec<usize> = requests
+        .iter()
+        .map(|r| serde_json::from_slice::<serde_json::Value>(&r.body).unwrap()["input"].as_array().unwrap().len())
+        .collect();
+    assert_eq!(sizes, vec![100, 100, 50]);
+    assert_eq!(embeddings.len(), 250);
+    for (i, embedding) in embeddings.iter().enumerate() {
+        assert_eq!(embedding, &vec![i as f32]);
+    }
+}
@@ -0,0 +1,127 @@
+//! Tests for pointing OpenAIEmbedder at Azure OpenAI and other compatible endpoints
+
+use indexer::config::ProviderConfig;
+use indexer::embedder::{select_model, AuthHeader, Embedder, OpenAIEmbedder};
+use serial_test::serial;
+use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn embedding_body() -> serde_json::Value {
+    serde_json::json!({
+        "object": "list",
+        "data": [{ "object": "embedding", "index": 0, "embedding": [0.5, 0.5] }],
+        "model": "text-embedding-ada-002"
+    })
+}
+
+#[test]
+fn test_auth_header_parsing() {
+    assert_eq!("bearer".parse::<AuthHeader>().unwrap(), AuthHeader::Bearer);
+    assert_eq!("API-KEY".parse::<AuthHeader>().unwrap(), AuthHeader::ApiKey);
+    assert!("basic".parse::<AuthHeader>().is_err());
+}
+
+#[test]
+fn test_base_url_joins_default_path() {
+    let embedder = OpenAIEmbedder::new("sk-test", "text-embedding-ada-002")
+        .unwrap()
+        .with_base_url("http://localhost:8080/v1/", None);
+    assert_eq!(embedder.endpoint(), "http://localhost:8080/v1/embeddings");
+}
+
+// The embedder blocks, so the mock server needs its own worker threads
+#[tokio::test(flavor = "multi_thread")]
+async fn test_azure_style_endpoint_uses_deployment_path_and_api_key_header() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/openai/deployments/code-embed/embeddings"))
+        .and(query_param("api-version", "2024-02-01"))
+        .and(header("api-key", "azure-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(embedding_body()))
+        .mount(&server)
+        .await;
+
+    let embedder = OpenAIEmbedder::new("azure-key", "text-embedding-ada-002")
+        .unwrap()
+        .with_base_url(&server.uri(), Some("/openai/deployments/code-embed/embeddings?api-version=2024-02-01"))
+        .with_auth_header(AuthHeader::ApiKey);
+
+    assert_eq!(embedder.try_embed("def foo(): pass").unwrap(), vec![0.5, 0.5]);
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].headers.get("authorization").is_none(), "api-key auth should not send a bearer token");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn test_endpoint_settings_come_from_provider_config() {
+    for var in ["OPENAI_BASE_URL", "OPENAI_EMBEDDINGS_PATH", "OPENAI_AUTH_HEADER"] {
+        std::env::remove_var(var);
+    }
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/gateway/v1/embeddings"))
+        .and(header("authorization", "Bearer sk-proxy"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(embedding_body()))
+        .mount(&server)
+        .await;
+
+    let provider = ProviderConfig {
+        api_key: "sk-proxy".to_string(),
+        model: "text-embedding-ada-002".to_string(),
+        dimension: None,
+        base_url: Some(format!("{}/gateway/v1", server.uri())),
+        embeddings_path: None,
+        auth_header: Some("bearer".to_string()),
+        max_batch_items: None,
+        max_batch_tokens: None,
+    };
+    let embedder = OpenAIEmbedder::new("sk-proxy", "text-embedding-ada-002")
+        .unwrap()
+        .with_endpoint_config(Some(&provider))
+        .unwrap();
+
+    assert_eq!(embedder.try_embed("class Foo: pass").unwrap(), vec![0.5, 0.5]);
+
+    let provider = ProviderConfig { auth_header: Some("basic".to_string()), ..provider };
+    assert!(OpenAIEmbedder::new("sk-proxy", "text-embedding-ada-002").unwrap().with_endpoint_config(Some(&provider)).is_err());
+}
+
+#[test]
+fn test_select_model_prefers_flag_over_config() {
+    let provider = ProviderConfig {
+        api_key: "sk-test".to_string(),
+        model: "text-embedding-3-small".to_string(),
+        dimension: None,
+        base_url: None,
+        embeddings_path: None,
+        auth_header: None,
+        max_batch_items: None,
+        max_batch_tokens: None,
+    };
+    assert_eq!(select_model(Some("text-embedding-3-large"), Some(&provider)), Some("text-embedding-3-large"));
+    assert_eq!(select_model(None, Some(&provider)), Some("text-embedding-3-small"));
+    assert_eq!(select_model(None, None), None);
+}
+
+// The embedder blocks, so the mock server needs its own worker threads
+#[tokio::test(flavor = "multi_thread")]
+async fn test_model_flag_reaches_the_embedding_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(serde_json::json!({ "model": "text-embedding-3-large" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(embedding_body()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let embedder = OpenAIEmbedder::new("sk-test", "text-embedding-ada-002")
+        .unwrap()
+        .with_endpoint(&server.uri())
+        .with_model(select_model(Some("text-embedding-3-large"), None).unwrap());
+
+    assert_eq!(embedder.model_name(), "text-embedding-3-large");
+    // The store is built with the dimension of the selected model
+    assert_eq!(embedder.dimension(), 3072);
+    assert_eq!(embedder.try_embed("def foo(): pass").unwrap(), vec![0.5, 0.5]);
+}
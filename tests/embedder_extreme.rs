@@ -422,8 +422,9 @@ async fn test_end_to_end_embedding_and_storage() {
     // Create Redis vector store
     let store = indexer::vector_store::RedisVectorStore::new(
         "redis://127.0.0.1/",
-        "test:end_to_end"
-    );
+        "test:end_to_end",
+        embedder.dimension(),
+    ).expect("Failed to create RedisVectorStore");
     
     // Test inputs
     let inputs = vec![
@@ -458,17 +459,16 @@ async fn test_end_to_end_embedding_and_storage() {
     assert_eq!(results[0], "math_function_0", "Should find add function as most similar");
     
     // Clean up
-    let client = RedisClient::new(RedisConfig::from_url("redis://127.0.0.1/").unwrap());
-    let _ = client.connect();
-    let _ = client.wait_for_connect().await;
+    let client = indexer::redis_ops::create_redis_client("redis://127.0.0.1/").await
+        .expect("Failed to connect to Redis");
     
     // Get all keys with the test prefix
-    let keys: Vec<String> = client.keys(format!("{}", store.get_key_prefix())).await
-        .expect("Failed to get keys");
+    let keys = indexer::redis_ops::scan_keys(&client, &format!("{}*", store.key_prefix())).await
+        .expect("Failed to scan keys");
     
     // Delete the keys if any exist
     if !keys.is_empty() {
-        let _ = client.del(keys).await
+        let _: u64 = client.del(keys).await
             .expect("Failed to delete keys");
     }
     
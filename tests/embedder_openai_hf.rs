@@ -13,8 +13,9 @@ fn test_openai_embedder_env_var() {
     
     // Run the test
     let embedder = OpenAIEmbedder::new_from_env().unwrap();
-    let vec = embedder.embed("foo");
-    assert_eq!(vec, vec![1.0, 2.0, 3.0]); // placeholder for real embedding
+    assert_eq!(embedder.api_key(), "sk-test");
+    assert_eq!(embedder.model(), "text-embedding-ada-002");
+    assert_eq!(embedder.dimension(), 1536);
     
     // Restore the original API key or remove it if it wasn't set
     match original_key {
@@ -32,7 +33,8 @@ fn test_hf_embedder_env_var() {
     }
     let embedder = HFEmbedder::new_from_env().unwrap();
     let vec = embedder.embed("foo");
-    assert_eq!(vec, vec![1.0, 2.0, 3.0]); // placeholder for real embedding
+    assert!(!vec.is_empty());
+    assert_eq!(embedder.dimension(), vec.len());
 }
 
 #[test]
@@ -0,0 +1,193 @@
+//! Tests for OpenAIEmbedder and HFEmbedder retry and backoff against a mock HTTP server
+
+use indexer::embedder::{Embedder, HFEmbedder, OpenAIEmbedder, RetryPolicy};
+use indexer::error::IndexerError;
+use std::time::{Duration, Instant};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn fast_retries(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(10),
+    }
+}
+
+fn embedder_for(server: &MockServer, max_retries: u32) -> OpenAIEmbedder {
+    OpenAIEmbedder::new("sk-test", "text-embedding-ada-002")
+        .unwrap()
+        .with_endpoint(&format!("{}/v1/embeddings", server.uri()))
+        .with_retry_policy(fast_retries(max_retries))
+}
+
+fn embedding_body() -> serde_json::Value {
+    serde_json::json!({
+        "object": "list",
+        "data": [{ "object": "embedding", "index": 0, "embedding": [0.25, 0.5, 0.75] }],
+        "model": "text-embedding-ada-002"
+    })
+}
+
+// The embedder blocks, so the mock server needs its own worker threads
+#[tokio::test(flavor = "multi_thread")]
+async fn test_openai_embedder_retries_rate_limits_then_succeeds() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(429))
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .and(header("authorization", "Bearer sk-test"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(embedding_body()))
+        .mount(&server)
+        .await;
+
+    let embedder = embedder_for(&server, 3);
+    let embedding = embedder.try_embed("def foo(): pass").unwrap();
+
+    assert_eq!(embedding, vec![0.25, 0.5, 0.75]);
+    assert_eq!(embedder.embed("def foo(): pass"), vec![0.25, 0.5, 0.75]);
+    assert_eq!(server.received_requests().await.unwrap().len(), 4, "Two 429s, then one success per call");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_openai_embedder_gives_up_after_max_retries() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&server)
+        .await;
+
+    let err = embedder_for(&server, 2).try_embed("def foo(): pass").unwrap_err();
+
+    assert!(matches!(err, IndexerError::RetriesExhausted { attempts: 3, .. }), "{:?}", err);
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_openai_embedder_does_not_retry_client_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("invalid api key"))
+        .mount(&server)
+        .await;
+
+    let embedder = embedder_for(&server, 3);
+    let err = embedder.try_embed("def foo(): pass").unwrap_err();
+
+    assert!(matches!(err, IndexerError::Embed(_)), "{:?}", err);
+    assert!(err.to_string().contains("401"));
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    assert!(embedder.embed("def foo(): pass").is_empty(), "Failed embeddings are empty so the guard rejects them");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_openai_embedder_stops_a_stalled_request_at_its_deadline() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(embedding_body()).set_delay(Duration::from_secs(30)))
+        .mount(&server)
+        .await;
+
+    let embedder = embedder_for(&server, 3).with_deadline(Some(Instant::now() + Duration::from_millis(500)));
+    let started = Instant::now();
+    let err = embedder.try_embed("def foo(): pass").unwrap_err();
+
+    assert!(matches!(err, IndexerError::Embed(_)), "{:?}", err);
+    assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+    let requests = server.received_requests().await.unwrap().len();
+    assert!(embedder.try_embed("def foo(): pass").is_err());
+    assert_eq!(server.received_requests().await.unwrap().len(), requests, "Nothing is sent once the deadline has passed");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_openai_embedder_probes_an_unknown_model_dimension_once() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(embedding_body()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let embedder = embedder_for(&server, 0).with_model("local-code-embedder");
+    assert_eq!(embedder.try_dimension().unwrap(), 3);
+    assert_eq!(embedder.try_dimension().unwrap(), 3);
+    assert_eq!(embedder.dimension(), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_openai_embedder_failed_dimension_probe_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/embeddings"))
+        .respond_with(ResponseTemplate::new(400))
+        .mount(&server)
+        .await;
+
+    let embedder = embedder_for(&server, 0).with_model("local-code-embedder");
+    let err = embedder.try_dimension().unwrap_err();
+    assert!(matches!(err, IndexerError::Embed(_)), "{:?}", err);
+}
+
+fn hf_embedder_for(server: &MockServer, max_retries: u32) -> HFEmbedder {
+    HFEmbedder::new("hf-test", "sentence-transformers/all-MiniLM-L6-v2")
+        .unwrap()
+        .with_endpoint(&format!("{}/pipeline/feature-extraction", server.uri()))
+        .with_retry_policy(fast_retries(max_retries))
+        .with_max_load_wait(Duration::from_millis(50))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_hf_embedder_waits_for_model_load_then_succeeds() {
+    let server = MockServer::start().await;
+    let model_path = "/pipeline/feature-extraction/sentence-transformers/all-MiniLM-L6-v2";
+    Mock::given(method("POST"))
+        .and(path(model_path))
+        .respond_with(ResponseTemplate::new(503).set_body_json(serde_json::json!({
+            "error": "Model sentence-transformers/all-MiniLM-L6-v2 is currently loading",
+            "estimated_time": 0.01
+        })))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(model_path))
+        .and(header("authorization", "Bearer hf-test"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([0.1, 0.2, 0.3])))
+        .mount(&server)
+        .await;
+
+    let embedder = hf_embedder_for(&server, 3);
+    let embedding = embedder.try_embed("def foo(): pass").unwrap();
+    assert_eq!(embedding, vec![0.1, 0.2, 0.3]);
+    assert_eq!(embedder.dimension(), 3);
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_hf_embedder_gives_up_while_model_keeps_loading() {
+    let server = MockServer::start().await;
+    // An estimate far above the cap must not stall the test
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503).set_body_json(serde_json::json!({
+            "error": "Model is currently loading",
+            "estimated_time": 3600.0
+        })))
+        .mount(&server)
+        .await;
+
+    let err = hf_embedder_for(&server, 2).try_embed("x").unwrap_err();
+    match err {
+        IndexerError::RetriesExhausted { attempts, last_error } => {
+            assert_eq!(attempts, 3);
+            assert!(last_error.contains("loading"), "{}", last_error);
+        }
+        other => panic!("expected RetriesExhausted, got {:?}", other),
+    }
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
@@ -5,10 +5,14 @@ use indexer::embedder::{Embedder, OpenAIEmbedder};
 
 #[test]
 fn test_embedding_for_extracted_entities() {
+    if std::env::var("OPENAI_API_KEY").is_err() {
+        eprintln!("SKIP: OPENAI_API_KEY not set; skipping test_embedding_for_extracted_entities");
+        return;
+    }
     let text = "fn main() { println!(\"hi\"); }";
     let entities = extract_entities(text);
     let embedder = OpenAIEmbedder::new_from_env().unwrap();
     let vectors: Vec<_> = entities.iter().map(|e| embedder.embed(e)).collect();
     assert_eq!(vectors.len(), 1);
-    assert_eq!(vectors[0], vec![1.0, 2.0, 3.0]); // dummy
+    assert_eq!(vectors[0].len(), embedder.dimension());
 }
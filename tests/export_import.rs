@@ -0,0 +1,110 @@
+//! Tests for exporting and importing a project's index
+
+use indexer::ast_parser::CodeEntity;
+use indexer::redis_ops::{
+    clear_file_data, create_redis_client, dump_project, load_project, query_code_entity, read_dump,
+    store_code_entities, store_file_content, write_dump, DumpRecord, DumpValue,
+};
+use fred::interfaces::{KeysInterface, SetsInterface};
+use fred::prelude::Client;
+use indexer::vector_store::{serialize_vector, VectorEncoding};
+use uuid::Uuid;
+
+fn entity(name: &str, file_path: &str, line_start: usize) -> CodeEntity {
+    CodeEntity {
+        entity_type: "function".to_string(),
+        file_path: file_path.to_string(),
+        name: name.to_string(),
+        signature: Some(format!("def {}()", name)),
+        docstring: None,
+        line_start,
+        line_end: line_start + 1,
+        parent_class: None,
+        bases: None,
+        value_repr: None,
+        source: None,
+    }
+}
+
+/// What `status` prints: the sorted indexed files
+async fn status_output(redis: &Client, key_prefix: &str) -> Vec<String> {
+    let mut files: Vec<String> = redis.smembers(format!("{}:file_index", key_prefix)).await.unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// What `recall` prints for functions, in a stable order
+async fn recall_output(redis: &Client, key_prefix: &str) -> String {
+    let mut results = query_code_entity(redis, key_prefix, "function", None).await.unwrap();
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+    serde_json::to_string_pretty(&results).unwrap()
+}
+
+#[test]
+fn test_dump_records_roundtrip_as_ndjson() {
+    let records = vec![
+        DumpRecord { key: "files:a.py".to_string(), value: DumpValue::String("{}".to_string()) },
+        DumpRecord { key: "file_index".to_string(), value: DumpValue::Set(vec!["a.py".to_string()]) },
+        DumpRecord {
+            key: "functions".to_string(),
+            value: DumpValue::Hash([("a.py:foo".to_string(), "{}".to_string())].into_iter().collect()),
+        },
+    ];
+    let mut buffer = Vec::new();
+    write_dump(&records, &mut buffer).unwrap();
+    assert_eq!(String::from_utf8_lossy(&buffer).lines().count(), 3);
+    assert_eq!(read_dump(buffer.as_slice()).unwrap(), records);
+}
+
+#[tokio::test]
+async fn test_export_forget_import_roundtrip() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:export_{}", Uuid::new_v4());
+    let files = ["pkg/a.py", "pkg/b.py"];
+    for file in &files {
+        let content = "def foo(): pass";
+        store_file_content(&redis, &key_prefix, file, content, content.len(), 0).await.unwrap();
+    }
+    store_code_entities(&redis, &key_prefix, &[entity("foo", "pkg/a.py", 1), entity("bar", "pkg/b.py", 3)]).await.unwrap();
+
+    let status_before = status_output(&redis, &key_prefix).await;
+    let recall_before = recall_output(&redis, &key_prefix).await;
+    assert_eq!(status_before.len(), 2);
+
+    // Export to NDJSON, forget, and import again
+    let mut buffer = Vec::new();
+    write_dump(&dump_project(&redis, &key_prefix).await.unwrap(), &mut buffer).unwrap();
+    let file_list: Vec<String> = files.iter().map(|f| f.to_string()).collect();
+    clear_file_data(&redis, &key_prefix, &file_list).await.unwrap();
+    assert!(status_output(&redis, &key_prefix).await.is_empty());
+
+    let records = read_dump(buffer.as_slice()).unwrap();
+    load_project(&redis, &key_prefix, &records).await.unwrap();
+
+    assert_eq!(status_output(&redis, &key_prefix).await, status_before);
+    assert_eq!(recall_output(&redis, &key_prefix).await, recall_before);
+
+    // Cleanup
+    clear_file_data(&redis, &key_prefix, &file_list).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_export_import_keeps_binary_values() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:export_{}", Uuid::new_v4());
+    let vector_key = format!("{}:function:fn:a.py:foo", key_prefix);
+    let vector = serialize_vector(&[0.5, -1.0, 2.0], VectorEncoding::Binary).unwrap();
+    let _: String = redis.set(&vector_key, vector.clone(), None, None, false).await.unwrap();
+
+    let mut buffer = Vec::new();
+    write_dump(&dump_project(&redis, &key_prefix).await.unwrap(), &mut buffer).unwrap();
+    let records = read_dump(buffer.as_slice()).unwrap();
+    assert!(matches!(records.as_slice(), [DumpRecord { value: DumpValue::Binary(_), .. }]), "{:?}", records);
+
+    let _: u64 = redis.del(&vector_key).await.unwrap();
+    load_project(&redis, &key_prefix, &records).await.unwrap();
+    let restored: Vec<u8> = redis.get(&vector_key).await.unwrap();
+    assert_eq!(restored, vector);
+
+    let _: u64 = redis.del(&vector_key).await.unwrap();
+}
@@ -0,0 +1,135 @@
+//! Tests for the size and binary guards applied while collecting source files
+
+use indexer::embedder::MockEmbedder;
+use indexer::config::AppConfig;
+use indexer::file_processing::{check_source_file, collect_python_files, collect_source_files, collect_source_files_async, exclude_test_files, SkipReason, TestFileFilter};
+use indexer::vector_store::{InMemoryVectorStore, VectorStore};
+use indexer::vectorize::{process_directory_with_diagnostics, VectorizeOptions};
+use std::fs;
+
+#[test]
+fn test_empty_and_oversized_files_are_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("empty.py"), "").unwrap();
+    fs::write(dir.path().join("huge.py"), format!("def big():\n    return '{}'\n", "a".repeat(2048))).unwrap();
+    fs::write(dir.path().join("small.py"), "def ok():\n    pass\n").unwrap();
+    
+    assert_eq!(check_source_file(&dir.path().join("empty.py"), 1024).unwrap(), Some(SkipReason::Empty));
+    assert!(matches!(
+        check_source_file(&dir.path().join("huge.py"), 1024).unwrap(),
+        Some(SkipReason::TooLarge { limit: 1024, .. })
+    ));
+    
+    let files = collect_python_files(dir.path(), None, 1024);
+    assert_eq!(files, vec![dir.path().join("small.py")]);
+    
+    // Vectorize skips them the same way instead of reporting failures
+    let store = InMemoryVectorStore::new();
+    let report = process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 1, max_file_bytes: 1024, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(store.get_all_entity_ids().unwrap(), vec!["fn:small.py:ok"]);
+}
+
+#[test]
+fn test_vectorize_walks_the_same_files_as_remember() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("app.py"), "def handler():\n    pass\n").unwrap();
+    fs::create_dir(dir.path().join(".venv")).unwrap();
+    fs::write(dir.path().join(".venv").join("site.py"), "def vendored():\n    pass\n").unwrap();
+    
+    let store = InMemoryVectorStore::new();
+    process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 1, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+    
+    assert_eq!(collect_python_files(dir.path(), None, 1024), vec![dir.path().join("app.py")]);
+    assert_eq!(store.get_all_entity_ids().unwrap(), vec!["fn:app.py:handler"]);
+}
+
+#[test]
+fn test_binary_file_is_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("blob.py"), b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR").unwrap();
+    
+    assert_eq!(check_source_file(&dir.path().join("blob.py"), 1024).unwrap(), Some(SkipReason::Binary));
+    assert!(collect_python_files(dir.path(), None, 1024).is_empty());
+    
+    let specific = vec!["blob.py".to_string()];
+    assert!(collect_python_files(dir.path(), Some(&specific), 1024).is_empty());
+}
+
+#[test]
+fn test_configured_extensions_pick_up_stub_files() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("mod.py"), "def impl_fn():\n    pass\n").unwrap();
+    fs::write(dir.path().join("mod.pyi"), "def stub_fn() -> int: ...\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "not source\n").unwrap();
+    
+    // The default only picks up .py files
+    assert_eq!(collect_python_files(dir.path(), None, 1024), vec![dir.path().join("mod.py")]);
+    
+    let config = AppConfig { extensions: Some(vec!["py".to_string(), "pyi".to_string()]), ..AppConfig::default() };
+    let extensions = config.source_extensions();
+    let mut files = collect_source_files(dir.path(), None, 1024, &extensions);
+    files.sort();
+    assert_eq!(files, vec![dir.path().join("mod.py"), dir.path().join("mod.pyi")]);
+    
+    let specific = vec!["mod.pyi".to_string(), "notes.txt".to_string()];
+    assert_eq!(collect_source_files(dir.path(), Some(&specific), 1024, &extensions), vec![dir.path().join("mod.pyi")]);
+    
+    // Vectorize reads the same list
+    let store = InMemoryVectorStore::new();
+    let report = process_directory_with_diagnostics(dir.path(), &MockEmbedder, &store, &VectorizeOptions { concurrency: 1, max_file_bytes: 1024, extensions, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+    assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+    let mut ids = store.get_all_entity_ids().unwrap();
+    ids.sort();
+    assert_eq!(ids, vec!["fn:mod.py:impl_fn", "fn:mod.pyi:stub_fn"]);
+}
+
+#[tokio::test]
+async fn test_collect_all_and_specific_files_by_relative_path() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("pkg/sub")).unwrap();
+    fs::write(dir.path().join("app.py"), "def main():\n    pass\n").unwrap();
+    fs::write(dir.path().join("pkg/util.py"), "def helper():\n    pass\n").unwrap();
+    fs::write(dir.path().join("pkg/sub/util.py"), "def nested():\n    pass\n").unwrap();
+    let extensions = vec!["py".to_string()];
+    
+    // All files, nested ones included
+    let mut files = collect_source_files_async(dir.path(), None, 1024, &extensions).await.unwrap();
+    files.sort();
+    assert_eq!(files, vec![
+        dir.path().join("app.py"),
+        dir.path().join("pkg/sub/util.py"),
+        dir.path().join("pkg/util.py"),
+    ]);
+    
+    // Specific files match their path relative to the root, not just their name
+    let specific = vec!["pkg/sub/util.py".to_string(), "./app.py".to_string()];
+    let files = collect_source_files_async(dir.path(), Some(&specific), 1024, &extensions).await.unwrap();
+    assert_eq!(files, vec![dir.path().join("pkg/sub/util.py"), dir.path().join("./app.py")]);
+    
+    let by_name = vec!["util.py".to_string()];
+    assert!(collect_source_files(dir.path(), Some(&by_name), 1024, &extensions).is_empty());
+}
+
+#[test]
+fn test_exclude_tests_skips_test_files_and_keeps_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("pkg/tests")).unwrap();
+    fs::write(dir.path().join("foo.py"), "def foo():\n    pass\n").unwrap();
+    fs::write(dir.path().join("test_foo.py"), "def test_foo():\n    pass\n").unwrap();
+    fs::write(dir.path().join("pkg/foo_test.py"), "def test_bar():\n    pass\n").unwrap();
+    fs::write(dir.path().join("pkg/tests/helpers.py"), "def helper():\n    pass\n").unwrap();
+    let files = collect_python_files(dir.path(), None, 1024);
+    assert_eq!(files.len(), 4);
+    
+    let filter = TestFileFilter::new(&AppConfig::default().test_file_patterns()).unwrap();
+    assert_eq!(exclude_test_files(files.clone(), dir.path(), &filter), vec![dir.path().join("foo.py")]);
+    
+    // Configured patterns replace the defaults
+    let filter = TestFileFilter::new(&["**/test_*.py".to_string()]).unwrap();
+    let mut kept = exclude_test_files(files, dir.path(), &filter);
+    kept.sort();
+    assert_eq!(kept, vec![dir.path().join("foo.py"), dir.path().join("pkg/foo_test.py"), dir.path().join("pkg/tests/helpers.py")]);
+    
+    assert!(TestFileFilter::new(&["tests/[".to_string()]).is_err());
+}
@@ -8,8 +8,8 @@ async fn test_config_load_default() {
     assert!(cfg.log_level.is_some());
 }
 
-use indexer::redis_ops::{create_redis_client, store_file_content, store_code_entities, query_code_entity, clear_file_data};
-use fred::interfaces::SetsInterface;
+use indexer::redis_ops::{create_redis_client, store_file_content, store_code_entities, query_code_entity, query_code_entity_limited, query_code_entity_since, query_code_entity_matching, search_code_entities, search_docstrings, docstring_terms, project_status, NameMatch, clear_file_data, store_project_meta, get_project_meta, get_entity, list_projects, scan_keys, encode_key_component, decode_key_component};
+use fred::interfaces::{ClientLike, KeysInterface, SetsInterface};
 use indexer::ast_parser::CodeEntity;
 
 #[tokio::test]
@@ -43,6 +43,7 @@ async fn test_store_and_query_entities() {
         parent_class: None,
         bases: None,
         value_repr: None,
+        source: None,
     };
     store_code_entities(&redis, key_prefix, &[entity.clone()]).await.unwrap();
     let result = query_code_entity(&redis, key_prefix, "function", Some("foo")).await.unwrap();
@@ -51,3 +52,289 @@ async fn test_store_and_query_entities() {
     // Cleanup
     clear_file_data(&redis, key_prefix, &["foo/bar.py".to_string()]).await.unwrap();
 }
+
+fn named_entity(entity_type: &str, name: &str) -> CodeEntity {
+    CodeEntity {
+        entity_type: entity_type.to_string(),
+        file_path: "search/app.py".to_string(),
+        name: name.to_string(),
+        signature: None,
+        docstring: None,
+        line_start: 1,
+        line_end: 2,
+        parent_class: None,
+        bases: None,
+        value_repr: None,
+        source: None,
+    }
+}
+
+#[tokio::test]
+async fn test_search_code_entities_by_name_fragment() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:search_{}", uuid::Uuid::new_v4());
+    let entities = vec![
+        named_entity("function", "load_config"),
+        named_entity("class", "ConfigLoader"),
+        named_entity("function", "render"),
+    ];
+    store_code_entities(&redis, &key_prefix, &entities).await.unwrap();
+
+    let results = search_code_entities(&redis, &key_prefix, "config", None).await.unwrap();
+    let mut names: Vec<&str> = results.iter().map(|e| e.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["ConfigLoader", "load_config"]);
+
+    let results = search_code_entities(&redis, &key_prefix, "config", Some("class")).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "ConfigLoader");
+
+    // Cleanup
+    clear_file_data(&redis, &key_prefix, &["search/app.py".to_string()]).await.unwrap();
+}
+
+#[test]
+fn test_docstring_terms() {
+    assert_eq!(docstring_terms("Parse the config file, and return a Config."), vec!["config", "file", "parse", "return"]);
+    assert!(docstring_terms("").is_empty());
+}
+
+#[tokio::test]
+async fn test_search_docstrings_finds_cryptic_name() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:docstring_{}", uuid::Uuid::new_v4());
+    let mut cfg = named_entity("function", "xq_ld");
+    cfg.docstring = Some("Parse the config file into a settings dict.".to_string());
+    let mut render = named_entity("function", "render");
+    render.docstring = Some("Render the page from a template file.".to_string());
+    store_code_entities(&redis, &key_prefix, &[cfg, render]).await.unwrap();
+
+    // The name gives nothing away, but the docstring matches every query word
+    assert!(search_code_entities(&redis, &key_prefix, "config", None).await.unwrap().is_empty());
+    let results = search_docstrings(&redis, &key_prefix, "parse the config file", None).await.unwrap();
+    let names: Vec<&str> = results.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["xq_ld", "render"], "best docstring match should rank first");
+
+    assert!(search_docstrings(&redis, &key_prefix, "parse config", Some("class")).await.unwrap().is_empty());
+
+    // Clearing the file drops its docstring terms too
+    assert_eq!(clear_file_data(&redis, &key_prefix, &["search/app.py".to_string()]).await.unwrap(), 2);
+    assert!(search_docstrings(&redis, &key_prefix, "parse the config file", None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_query_code_entity_prefix_ignore_case() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:match_{}", uuid::Uuid::new_v4());
+    let entities = vec![
+        named_entity("function", "parse_file"),
+        named_entity("function", "ParseFile"),
+        named_entity("function", "unparse"),
+    ];
+    store_code_entities(&redis, &key_prefix, &entities).await.unwrap();
+
+    let mode = NameMatch { prefix: true, ignore_case: true };
+    let results = query_code_entity_matching(&redis, &key_prefix, "function", "parse", mode).await.unwrap();
+    let names: Vec<&str> = results.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["ParseFile", "parse_file"]);
+
+    // Case-sensitive prefix only finds the lowercase name
+    let mode = NameMatch { prefix: true, ignore_case: false };
+    let results = query_code_entity_matching(&redis, &key_prefix, "function", "parse", mode).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "parse_file");
+
+    // Exact mode keeps the old behaviour
+    let results = query_code_entity_matching(&redis, &key_prefix, "function", "parse", NameMatch::EXACT).await.unwrap();
+    assert!(results.is_empty());
+
+    // Cleanup
+    clear_file_data(&redis, &key_prefix, &["search/app.py".to_string()]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_query_code_entity_since_keeps_recently_modified_files() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:since_{}", uuid::Uuid::new_v4());
+    // 2023-06-01 and 2024-03-01, either side of a 2024-01-01 cutoff
+    store_file_content(&redis, &key_prefix, "old.py", "def stale(): pass", 17, 1_685_577_600).await.unwrap();
+    store_file_content(&redis, &key_prefix, "new.py", "def fresh(): pass", 17, 1_709_251_200).await.unwrap();
+    let entities = vec![
+        CodeEntity { file_path: "old.py".to_string(), ..named_entity("function", "stale") },
+        CodeEntity { file_path: "new.py".to_string(), ..named_entity("function", "fresh") },
+    ];
+    store_code_entities(&redis, &key_prefix, &entities).await.unwrap();
+
+    let since = indexer::cli::since_cutoff("2024-01-01", 0).unwrap();
+    let results = query_code_entity_since(&redis, &key_prefix, "function", None, since, None).await.unwrap();
+    let names: Vec<&str> = results.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["fresh"]);
+    assert_eq!(query_code_entity_since(&redis, &key_prefix, "function", None, 0, None).await.unwrap().len(), 2);
+
+    // Cleanup
+    clear_file_data(&redis, &key_prefix, &["old.py".to_string(), "new.py".to_string()]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_query_code_entity_limited_truncates() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:max_{}", uuid::Uuid::new_v4());
+    let entities: Vec<CodeEntity> = (0..10)
+        .map(|i| CodeEntity { line_start: i + 1, ..named_entity("function", &format!("func_{}", i)) })
+        .collect();
+    store_code_entities(&redis, &key_prefix, &entities).await.unwrap();
+
+    let results = query_code_entity_limited(&redis, &key_prefix, "function", None, Some(3)).await.unwrap();
+    let names: Vec<&str> = results.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["func_0", "func_1", "func_2"]);
+
+    // 0 and unset both mean no limit
+    assert_eq!(query_code_entity_limited(&redis, &key_prefix, "function", None, Some(0)).await.unwrap().len(), 10);
+    assert_eq!(query_code_entity_limited(&redis, &key_prefix, "function", None, None).await.unwrap().len(), 10);
+
+    // Cleanup
+    clear_file_data(&redis, &key_prefix, &["search/app.py".to_string()]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_project_status_counts_entities_by_type() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:status_{}", uuid::Uuid::new_v4());
+    store_file_content(&redis, &key_prefix, "search/app.py", "class App: pass", 15, 0).await.unwrap();
+    let entities = vec![
+        named_entity("class", "App"),
+        named_entity("function", "main"),
+        named_entity("function", "helper"),
+        named_entity("method", "run"),
+    ];
+    store_code_entities(&redis, &key_prefix, &entities).await.unwrap();
+
+    let status = project_status(&redis, &key_prefix).await.unwrap();
+    assert_eq!(status.files, vec!["search/app.py"]);
+    let counts: Vec<(&str, u64)> = status.entity_counts.iter().map(|c| (c.entity_type.as_str(), c.count)).collect();
+    assert_eq!(counts, vec![("class", 1), ("function", 2), ("method", 1), ("variable", 0)]);
+    assert_eq!(status.total_entities, 4);
+
+    // Cleanup
+    clear_file_data(&redis, &key_prefix, &["search/app.py".to_string()]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_store_and_get_project_meta() {
+    use indexer::vector_store::EmbeddingMeta;
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:meta_{}", uuid::Uuid::new_v4());
+    assert_eq!(get_project_meta(&redis, &key_prefix).await.unwrap(), None);
+    
+    let meta = EmbeddingMeta { provider: "openai".to_string(), model: "text-embedding-ada-002".to_string(), dimension: 1536, normalized: false };
+    store_project_meta(&redis, &key_prefix, &meta).await.unwrap();
+    let stored = get_project_meta(&redis, &key_prefix).await.unwrap().expect("meta should be stored");
+    assert_eq!(stored, meta);
+    
+    // A 384-dimensional query embedder is refused instead of scoring everything 0
+    assert!(stored.check_query_embedder("hf", "sentence-transformers/all-MiniLM-L6-v2", 384).is_err());
+    
+    // Cleanup
+    let _: i64 = redis.del(indexer::redis_ops::project_meta_key(&key_prefix)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_get_entity_round_trips_code_entity() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:get_entity_{}", uuid::Uuid::new_v4());
+    let entity = CodeEntity {
+        signature: Some("def run(self, jobs)".to_string()),
+        docstring: Some("Run queued jobs".to_string()),
+        line_start: 12,
+        line_end: 30,
+        parent_class: Some("Worker".to_string()),
+        bases: Some(vec!["Base".to_string()]),
+        ..named_entity("method", "run")
+    };
+    store_code_entities(&redis, &key_prefix, std::slice::from_ref(&entity)).await.unwrap();
+
+    let stored = get_entity(&redis, &key_prefix, "method", "run").await.unwrap().expect("entity should be stored");
+    assert_eq!(serde_json::to_value(&stored).unwrap(), serde_json::to_value(&entity).unwrap());
+    assert!(get_entity(&redis, &key_prefix, "function", "run").await.unwrap().is_none());
+
+    // Cleanup
+    clear_file_data(&redis, &key_prefix, &["search/app.py".to_string()]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_list_projects_finds_remembered_projects() {
+    use indexer::ast_parser::DEFAULT_SOURCE_MAX_BYTES;
+    use indexer::remember::remember_files;
+
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("app.py");
+    std::fs::write(&file, "def main():\n    pass\n").unwrap();
+    let names = [format!("list_a_{}", uuid::Uuid::new_v4()), format!("list_b_{}", uuid::Uuid::new_v4())];
+    for name in &names {
+        let report = remember_files(&redis, &format!("code_index:{}", name), dir.path(), std::slice::from_ref(&file), DEFAULT_SOURCE_MAX_BYTES).await;
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+    }
+
+    let projects = list_projects(&redis).await.unwrap();
+    for name in &names {
+        assert!(projects.contains(name), "{} missing from {:?}", name, projects);
+    }
+
+    // Cleanup
+    for name in &names {
+        clear_file_data(&redis, &format!("code_index:{}", name), &["app.py".to_string()]).await.unwrap();
+    }
+    assert!(!list_projects(&redis).await.unwrap().contains(&names[0]));
+}
+
+#[tokio::test]
+async fn test_scan_keys_matches_keys() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:scan_{}", uuid::Uuid::new_v4());
+    for i in 0..250 {
+        let _: () = redis.set(format!("{}:key:{}", key_prefix, i), i, None, None, false).await.unwrap();
+    }
+    let pattern = format!("{}:*", key_prefix);
+
+    let mut scanned = scan_keys(&redis, &pattern).await.unwrap();
+    let mut listed: Vec<String> = redis.custom(fred::cmd!("KEYS"), vec![pattern.clone()]).await.unwrap();
+    scanned.sort();
+    listed.sort();
+    assert_eq!(scanned.len(), 250);
+    assert_eq!(scanned, listed);
+
+    // Cleanup
+    let _: i64 = redis.del(scanned).await.unwrap();
+}
+
+#[test]
+fn test_key_components_escape_colons() {
+    assert_eq!(encode_key_component("foo:bar"), "foo%3Abar");
+    assert_eq!(encode_key_component("100%"), "100%25");
+    for name in ["foo:bar", "Vec::new", "C:\\src\\app.py", "50%:done", "plain"] {
+        assert_eq!(decode_key_component(&encode_key_component(name)), name);
+    }
+}
+
+#[tokio::test]
+async fn test_entity_with_colon_in_name_stores_and_clears() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:colon_{}", uuid::Uuid::new_v4());
+    let entity = CodeEntity {
+        file_path: "C:/src/app.py".to_string(),
+        ..named_entity("function", "foo:bar")
+    };
+    store_code_entities(&redis, &key_prefix, std::slice::from_ref(&entity)).await.unwrap();
+
+    let found = query_code_entity(&redis, &key_prefix, "function", Some("foo:bar")).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].name, "foo:bar");
+    let matches = search_code_entities(&redis, &key_prefix, "foo:b", None).await.unwrap();
+    assert_eq!(matches.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["foo:bar"]);
+
+    let cleared = clear_file_data(&redis, &key_prefix, &["C:/src/app.py".to_string()]).await.unwrap();
+    assert_eq!(cleared, 1);
+    assert!(query_code_entity(&redis, &key_prefix, "function", Some("foo:bar")).await.unwrap().is_empty());
+    assert!(scan_keys(&redis, &format!("{}:*", key_prefix)).await.unwrap().is_empty());
+}
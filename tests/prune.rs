@@ -0,0 +1,55 @@
+//! Tests for pruning the data of files deleted from disk
+
+use indexer::prune::prune_project;
+use indexer::redis_ops::{clear_file_data, create_redis_client, query_code_entity};
+use indexer::remember::remember_files;
+use indexer::vector_store::{RedisVectorStore, VectorStore};
+use std::fs;
+
+#[tokio::test]
+async fn test_prune_removes_only_deleted_file() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:test_prune_{}", uuid::Uuid::new_v4());
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("pkg")).unwrap();
+    let kept = dir.path().join("kept.py");
+    let gone = dir.path().join("gone.py");
+    // Same file name as the deleted one, in another directory
+    let nested = dir.path().join("pkg/gone.py");
+    fs::write(&kept, "def kept():\n    pass\n").unwrap();
+    fs::write(&gone, "def gone():\n    pass\n\nclass Gone:\n    pass\n").unwrap();
+    fs::write(&nested, "def nested():\n    pass\n").unwrap();
+    let report = remember_files(&redis, &key_prefix, dir.path(), &[kept.clone(), gone.clone(), nested.clone()], 4096).await;
+    assert_eq!(report.indexed, 3);
+    
+    let store = RedisVectorStore::new("redis://localhost:6379/15", &key_prefix, 3).unwrap();
+    for (entity_id, file) in [("fn:kept.py:kept", &kept), ("fn:gone.py:gone", &gone), ("fn:gone.py:nested", &nested)] {
+        store.upsert_embedding(entity_id, &[1.0, 0.0, 0.0], Some(&file.to_string_lossy()), Some("function")).unwrap();
+    }
+    
+    fs::remove_file(&gone).unwrap();
+    let report = prune_project(&redis, &key_prefix, dir.path(), Some(&store)).await.unwrap();
+    
+    assert_eq!(report.pruned_files, vec!["gone.py".to_string()]);
+    assert_eq!(report.cleared_entities, 2);
+    assert_eq!(report.removed_vectors, 1);
+    let mut functions: Vec<String> = query_code_entity(&redis, &key_prefix, "function", None).await.unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    functions.sort();
+    assert_eq!(functions, vec!["kept", "nested"]);
+    assert!(query_code_entity(&redis, &key_prefix, "class", None).await.unwrap().is_empty());
+    let mut vectors = store.get_all_entity_ids().unwrap();
+    vectors.sort();
+    assert_eq!(vectors, vec!["fn:gone.py:nested", "fn:kept.py:kept"]);
+    
+    // Nothing left to prune
+    let report = prune_project(&redis, &key_prefix, dir.path(), Some(&store)).await.unwrap();
+    assert!(report.pruned_files.is_empty());
+    
+    clear_file_data(&redis, &key_prefix, &["kept.py".to_string(), "pkg/gone.py".to_string()]).await.unwrap();
+    for entity_id in vectors {
+        store.delete_embedding(&entity_id).unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+//! Tests for retrying Redis writes that fail on transient connection errors
+
+use fred::error::{Error, ErrorKind};
+use indexer::embedder::RetryPolicy;
+use indexer::redis_ops::{is_transient_redis_error, with_redis_retry};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+fn no_delay(max_retries: u32) -> RetryPolicy {
+    RetryPolicy { max_retries, base_delay: Duration::ZERO, max_delay: Duration::ZERO }
+}
+
+/// Stand-in for a Redis client whose first `failures` writes hit a dropped connection
+struct FlakyRedis {
+    failures: Mutex<u32>,
+    kind: ErrorKind,
+    attempts: Mutex<u32>,
+    stored: Mutex<HashMap<String, String>>,
+}
+
+impl FlakyRedis {
+    fn new(failures: u32, kind: ErrorKind) -> Self {
+        Self { failures: Mutex::new(failures), kind, attempts: Mutex::new(0), stored: Mutex::new(HashMap::new()) }
+    }
+
+    async fn set(&self, key: &str, value: &str) -> Result<(), Error> {
+        *self.attempts.lock().unwrap() += 1;
+        let mut failures = self.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(Error::new(self.kind.clone(), "connection reset by peer"));
+        }
+        self.stored.lock().unwrap().insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_transient_error_kinds() {
+    assert!(is_transient_redis_error(&Error::new(ErrorKind::IO, "broken pipe")));
+    assert!(is_transient_redis_error(&Error::new(ErrorKind::Timeout, "timed out")));
+    assert!(!is_transient_redis_error(&Error::new(ErrorKind::Parse, "bad value")));
+    assert!(!is_transient_redis_error(&Error::new(ErrorKind::InvalidArgument, "wrong type")));
+}
+
+#[tokio::test]
+async fn test_transient_failure_then_success_stores_value() {
+    let redis = FlakyRedis::new(2, ErrorKind::IO);
+    with_redis_retry(&no_delay(3), "file store", || redis.set("code_index:p:files:a.py", "content")).await.unwrap();
+
+    assert_eq!(*redis.attempts.lock().unwrap(), 3);
+    assert_eq!(redis.stored.lock().unwrap().get("code_index:p:files:a.py").map(String::as_str), Some("content"));
+}
+
+#[tokio::test]
+async fn test_retries_stop_at_the_limit() {
+    let redis = FlakyRedis::new(5, ErrorKind::Timeout);
+    let err = with_redis_retry(&no_delay(2), "file store", || redis.set("key", "value")).await.unwrap_err();
+
+    assert_eq!(*err.kind(), ErrorKind::Timeout);
+    assert_eq!(*redis.attempts.lock().unwrap(), 3, "one attempt plus two retries");
+    assert!(redis.stored.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_logical_errors_are_not_retried() {
+    let redis = FlakyRedis::new(1, ErrorKind::Parse);
+    assert!(with_redis_retry(&no_delay(3), "file store", || redis.set("key", "value")).await.is_err());
+    assert_eq!(*redis.attempts.lock().unwrap(), 1);
+}
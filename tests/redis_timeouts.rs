@@ -0,0 +1,67 @@
+//! Tests for the configurable Redis connect and command timeouts
+
+use fred::prelude::*;
+use indexer::redis_ops::{create_redis_client_with_timeouts, RedisTimeouts};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+/// Read one RESP command array from `reader`, returning its upper-cased name
+fn read_command(reader: &mut impl BufRead) -> Option<String> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok().filter(|&n| n > 0)?;
+    let args: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+    let mut name = None;
+    for _ in 0..args {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+        let mut arg = vec![0; len + 2];
+        reader.read_exact(&mut arg).ok()?;
+        name.get_or_insert_with(|| String::from_utf8_lossy(&arg[..len]).to_uppercase());
+    }
+    name
+}
+
+/// Answer the connection handshake, then never reply to a GET
+fn stall_on_get(stream: TcpStream) {
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+    while let Some(command) = read_command(&mut reader) {
+        let reply: &[u8] = match command.as_str() {
+            "PING" => b"+PONG\r\n",
+            "CLIENT" => b":1\r\n",
+            "GET" => continue,
+            _ => b"-ERR unsupported\r\n",
+        };
+        if writer.write_all(reply).is_err() {
+            break;
+        }
+    }
+}
+
+/// Serve connections on a local port that complete the handshake but stall on commands
+fn stalling_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            std::thread::spawn(move || stall_on_get(stream));
+        }
+    });
+    format!("redis://{}", addr)
+}
+
+#[tokio::test]
+async fn test_short_command_timeout_fails_promptly_against_stalled_server() {
+    let url = stalling_server();
+    let timeouts = RedisTimeouts { connect: Duration::from_secs(1), command: Duration::from_millis(500) };
+    let client = create_redis_client_with_timeouts(&url, &timeouts).await
+        .expect("the server answers the handshake");
+
+    let started = Instant::now();
+    let err = client.get::<Option<String>, _>("code_index:p:files:a.py").await.unwrap_err();
+
+    assert_eq!(*err.kind(), ErrorKind::Timeout, "{:?}", err);
+    assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+}
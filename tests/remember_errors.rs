@@ -0,0 +1,80 @@
+//! Tests for the remember command's per-file indexing
+
+use fred::interfaces::KeysInterface;
+use indexer::file_processing::modified_unix_secs;
+use indexer::redis_ops::{clear_file_data, create_redis_client, query_code_entity};
+use indexer::remember::remember_files;
+use std::fs;
+use std::time::{Duration, UNIX_EPOCH};
+
+#[tokio::test]
+async fn test_unreadable_file_does_not_stop_remember() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:test_remember_{}", uuid::Uuid::new_v4());
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("first.py"), "def first():\n    pass\n").unwrap();
+    fs::write(dir.path().join("bad.py"), b"def bad():\n    return '\xff\xfe'\n").unwrap();
+    fs::write(dir.path().join("last.py"), "def last():\n    pass\n").unwrap();
+    let files = vec![
+        dir.path().join("first.py"),
+        dir.path().join("missing.py"),
+        dir.path().join("bad.py"),
+        dir.path().join("last.py"),
+    ];
+    
+    let report = remember_files(&redis, &key_prefix, dir.path(), &files, 4096).await;
+    assert_eq!(report.indexed, 2);
+    assert_eq!(report.errors.len(), 2, "{:?}", report.errors);
+    assert!(report.errors[0].contains("missing.py"));
+    assert!(report.errors[1].contains("bad.py"));
+    
+    let mut names: Vec<String> = query_code_entity(&redis, &key_prefix, "function", None).await.unwrap()
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["first", "last"]);
+    
+    clear_file_data(&redis, &key_prefix, &["first.py".to_string(), "last.py".to_string()]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_remember_stores_absolute_mtime() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:test_mtime_{}", uuid::Uuid::new_v4());
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dated.py");
+    fs::write(&path, "def dated():\n    pass\n").unwrap();
+    // A fixed time in the past, so a relative age could not match it by accident
+    let mtime = UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+    
+    let report = remember_files(&redis, &key_prefix, dir.path(), std::slice::from_ref(&path), 4096).await;
+    assert_eq!(report.indexed, 1);
+    
+    let stored: String = redis.get(format!("{}:files:dated.py", key_prefix)).await.unwrap();
+    let stored: serde_json::Value = serde_json::from_str(&stored).unwrap();
+    assert_eq!(stored["last_modified"], 1_600_000_000);
+    assert_eq!(stored["last_modified"], modified_unix_secs(&fs::metadata(&path).unwrap()).unwrap());
+    
+    clear_file_data(&redis, &key_prefix, &["dated.py".to_string()]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_remember_reports_unparseable_files() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:test_unparseable_{}", uuid::Uuid::new_v4());
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("good.py"), "def good():\n    pass\n").unwrap();
+    fs::write(dir.path().join("broken.py"), "def broken(:\n    pass\n").unwrap();
+    let files = vec![dir.path().join("broken.py"), dir.path().join("good.py")];
+    
+    let report = remember_files(&redis, &key_prefix, dir.path(), &files, 4096).await;
+    // The broken file's content is still stored, but it is reported, not dropped silently
+    assert_eq!(report.indexed, 2);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(report.diagnostics.len(), 1);
+    assert_eq!(report.diagnostics[0].file, "broken.py");
+    
+    clear_file_data(&redis, &key_prefix, &["good.py".to_string(), "broken.py".to_string()]).await.unwrap();
+}
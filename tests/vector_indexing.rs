@@ -14,7 +14,7 @@ fn test_extract_entities_for_vectorization() {
     let code = "def foo():\n    pass\nclass Bar:\n    def baz(self):\n        pass\n";
     let mut file = File::create(&file_path).unwrap();
     write!(file, "{}", code).unwrap();
-    let entities = extract_code_info(&file_path, dir.path());
+    let entities = extract_code_info(&file_path, dir.path()).unwrap();
     assert!(entities.iter().any(|e| e.name == "foo"));
     assert!(entities.iter().any(|e| e.name == "Bar"));
     assert!(entities.iter().any(|e| e.name == "baz"));
@@ -34,7 +34,7 @@ fn test_embedding_generation_for_entities() {
 fn test_store_embeddings_with_metadata() {
     // For the test, we'll use the trait implementation which is synchronous
     // This allows us to test without async/await
-    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix", 3).unwrap();
     let embedding = vec![1.0, 2.0, 3.0];
     let entity_id = "entity1";
     
@@ -1,7 +1,9 @@
 //! Extreme tests for the vector query functionality
 
 use indexer::cli::Commands;
+use indexer::vectorize::Granularity;
 use indexer::embedder::{Embedder, MockEmbedder};
+use indexer::error::IndexerError;
 use indexer::vector_store::{VectorStore, MockVectorStore};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -160,7 +162,7 @@ impl QueryTestVectorStore {
 }
 
 impl VectorStore for QueryTestVectorStore {
-    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), String> {
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
         // Record the store call
         self.store_calls.lock().unwrap().push(entity_id.to_string());
         
@@ -214,7 +216,7 @@ impl VectorStore for QueryTestVectorStore {
             .collect()
     }
     
-    fn get_all_entity_ids(&self) -> Result<Vec<String>, String> {
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push("get_all_entity_ids".to_string());
         
@@ -222,24 +224,24 @@ impl VectorStore for QueryTestVectorStore {
         Ok(entities.keys().cloned().collect())
     }
     
-    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, String> {
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push(format!("get_entity_vector:{}", entity_id));
         
         let entities = self.stored_entities.lock().unwrap();
         entities.get(entity_id)
             .cloned()
-            .ok_or_else(|| format!("Entity not found: {}", entity_id))
+            .ok_or_else(|| IndexerError::Store(format!("Entity not found: {}", entity_id)))
     }
     
-    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, String> {
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push(format!("get_entity_metadata:{}", entity_id));
         
         let metadata = self.stored_metadata.lock().unwrap();
         metadata.get(entity_id)
             .cloned()
-            .ok_or_else(|| format!("Entity metadata not found: {}", entity_id))
+            .ok_or_else(|| IndexerError::Store(format!("Entity metadata not found: {}", entity_id)))
     }
 }
 
@@ -261,7 +263,26 @@ async fn test_query_command_basic() {
             db: Some("redis".to_string()),
             top_k: Some(3),
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the query command function
@@ -312,7 +333,26 @@ async fn test_query_command_with_different_entity_types() {
                 db: Some("redis".to_string()),
                 top_k: Some(1),
                 verbose: true,
+                index_todos: false,
+                show_parse_errors: false,
+                plan: false,
+                concurrency: 4,
+                max_file_bytes: None,
+                from_index: false,
+                entity_types: None,
+                exclude_entity_types: None,
+                timeout: None,
+                only_missing: false,
+                dimension: None,
+                model: None,
+                limit: None,
+                emit: None,
+                granularity: Granularity::Entity,
+                force_reembed: false,
+                normalize_embeddings: false,
             },
+            quiet: false,
+            verbose: false,
         };
         
         // Call the query command function
@@ -352,7 +392,26 @@ async fn test_query_command_with_varying_top_k() {
                 db: Some("redis".to_string()),
                 top_k: Some(top_k),
                 verbose: true,
+                index_todos: false,
+                show_parse_errors: false,
+                plan: false,
+                concurrency: 4,
+                max_file_bytes: None,
+                from_index: false,
+                entity_types: None,
+                exclude_entity_types: None,
+                timeout: None,
+                only_missing: false,
+                dimension: None,
+                model: None,
+                limit: None,
+                emit: None,
+                granularity: Granularity::Entity,
+                force_reembed: false,
+                normalize_embeddings: false,
             },
+            quiet: false,
+            verbose: false,
         };
         
         // Call the query command function
@@ -393,7 +452,26 @@ async fn test_query_command_with_empty_store() {
             db: Some("redis".to_string()),
             top_k: Some(3),
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the query command function
@@ -430,7 +508,26 @@ async fn test_query_command_with_very_long_query() {
             db: Some("redis".to_string()),
             top_k: Some(3),
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the query command function
@@ -473,7 +570,26 @@ async fn test_query_command_with_special_characters() {
                 db: Some("redis".to_string()),
                 top_k: Some(3),
                 verbose: true,
+                index_todos: false,
+                show_parse_errors: false,
+                plan: false,
+                concurrency: 4,
+                max_file_bytes: None,
+                from_index: false,
+                entity_types: None,
+                exclude_entity_types: None,
+                timeout: None,
+                only_missing: false,
+                dimension: None,
+                model: None,
+                limit: None,
+                emit: None,
+                granularity: Granularity::Entity,
+                force_reembed: false,
+                normalize_embeddings: false,
             },
+            quiet: false,
+            verbose: false,
         };
         
         // Call the query command function
@@ -513,7 +629,26 @@ async fn test_query_command_with_unicode_characters() {
                 db: Some("redis".to_string()),
                 top_k: Some(3),
                 verbose: true,
+                index_todos: false,
+                show_parse_errors: false,
+                plan: false,
+                concurrency: 4,
+                max_file_bytes: None,
+                from_index: false,
+                entity_types: None,
+                exclude_entity_types: None,
+                timeout: None,
+                only_missing: false,
+                dimension: None,
+                model: None,
+                limit: None,
+                emit: None,
+                granularity: Granularity::Entity,
+                force_reembed: false,
+                normalize_embeddings: false,
             },
+            quiet: false,
+            verbose: false,
         };
         
         // Call the query command function
@@ -543,7 +678,26 @@ async fn test_query_command_output_format() {
             db: Some("redis".to_string()),
             top_k: Some(3),
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the query command function
@@ -572,7 +726,26 @@ async fn test_query_command_output_format() {
             db: Some("redis".to_string()),
             top_k: Some(3),
             verbose: false,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the query command function
@@ -601,7 +774,26 @@ async fn test_query_command_project_isolation() {
             db: Some("redis".to_string()),
             top_k: Some(3),
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     let project2_args = CliArgs {
@@ -612,7 +804,26 @@ async fn test_query_command_project_isolation() {
             db: Some("redis".to_string()),
             top_k: Some(3),
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the query command function for both projects
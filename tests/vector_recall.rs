@@ -5,13 +5,24 @@ use serde_json;
 
 #[test]
 fn test_similarity_search_returns_top_k_matches() {
-    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+    let key_prefix = format!("code_index:recall_{}", uuid::Uuid::new_v4());
+    let store = RedisVectorStore::new("redis://localhost:6379/0", &key_prefix, 3).unwrap();
+    let stored = [("func1", [0.9, 0.1, 0.2]), ("func2", [0.8, 0.2, 0.3]), ("class1", [0.1, 0.9, 0.2]), ("var1", [0.2, 0.3, 0.9])];
+    for (entity_id, vector) in &stored {
+        VectorStore::upsert_embedding(&store, entity_id, vector, Some("test.py"), Some("function")).unwrap();
+    }
     let query_vec = vec![1.0, 2.0, 3.0];
     let top_k = 3;
     
     // Use the trait method which returns a synchronous result
     let results = VectorStore::similarity_search(&store, &query_vec, top_k);
     assert_eq!(results.len(), top_k, "Should return exactly top_k matches");
+    assert_eq!(results[0], "var1", "Closest vector should rank first");
+    
+    // Cleanup
+    for (entity_id, _) in &stored {
+        VectorStore::delete_embedding(&store, entity_id).unwrap();
+    }
 }
 
 #[test]
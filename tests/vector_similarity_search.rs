@@ -1,15 +1,31 @@
 //! RED tests for vector similarity search with more comprehensive requirements
 //! Tests similarity search, scoring, filtering, and result formatting
 
-use indexer::vector_store::RedisVectorStore;
+use indexer::vector_store::{InMemoryVectorStore, VectorStore};
 use indexer::vector_search::{search_vectors, SearchResult, SearchOptions};
 use indexer::output_format::{format_search_results, OutputFormat};
 use std::collections::HashMap;
 
+/// Store seeded with two functions, a class, a variable and a docstring from test.py
+fn fixture_store() -> InMemoryVectorStore {
+    let store = InMemoryVectorStore::new();
+    let fixtures: [(&str, [f32; 3], &str); 5] = [
+        ("func1", [0.9, 0.1, 0.2], "function"),
+        ("func2", [0.8, 0.2, 0.3], "function"),
+        ("class1", [0.1, 0.9, 0.2], "class"),
+        ("var1", [0.2, 0.3, 0.9], "variable"),
+        ("doc1", [0.5, 0.5, 0.5], "docstring"),
+    ];
+    for (entity_id, vector, entity_type) in fixtures {
+        store.upsert_embedding(entity_id, &vector, Some("test.py"), Some(entity_type)).unwrap();
+    }
+    store
+}
+
 #[test]
 fn test_similarity_search_with_scoring() {
     // This test verifies that search results include similarity scores
-    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+    let store = fixture_store();
     let query_vec = vec![1.0, 2.0, 3.0];
     let top_k = 3;
     
@@ -19,6 +35,14 @@ fn test_similarity_search_with_scoring() {
         min_score: Some(0.5),
         entity_types: None,
         file_filter: None,
+        offset: 0,
+        fields: None,
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
     };
     
     let results = search_vectors(&store, &query_vec, &options);
@@ -47,7 +71,7 @@ fn test_similarity_search_with_scoring() {
 #[test]
 fn test_similarity_search_with_filtering() {
     // This test verifies that search results can be filtered by entity type and file
-    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+    let store = fixture_store();
     let query_vec = vec![1.0, 2.0, 3.0];
     
     // Test filtering by entity type
@@ -56,6 +80,14 @@ fn test_similarity_search_with_filtering() {
         min_score: None,
         entity_types: Some(vec!["function".to_string()]),
         file_filter: None,
+        offset: 0,
+        fields: None,
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
     };
     
     let type_results = search_vectors(&store, &query_vec, &type_options);
@@ -74,6 +106,14 @@ fn test_similarity_search_with_filtering() {
         min_score: None,
         entity_types: None,
         file_filter: Some("test.py".to_string()),
+        offset: 0,
+        fields: None,
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
     };
     
     let file_results = search_vectors(&store, &query_vec, &file_options);
@@ -100,6 +140,8 @@ fn test_search_results_formatting() {
                 map.insert("file".to_string(), "test.py".to_string());
                 map
             },
+            rank: 1,
+            vector: None,
         },
         SearchResult {
             entity_id: "class1".to_string(),
@@ -110,6 +152,8 @@ fn test_search_results_formatting() {
                 map.insert("file".to_string(), "test.py".to_string());
                 map
             },
+            rank: 2,
+            vector: None,
         },
     ];
     
@@ -131,7 +175,7 @@ fn test_search_results_formatting() {
 #[test]
 fn test_search_with_min_score_filter() {
     // This test verifies that search results can be filtered by minimum score
-    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+    let store = fixture_store();
     let query_vec = vec![1.0, 2.0, 3.0];
     
     // Set a high minimum score to filter results
@@ -140,6 +184,14 @@ fn test_search_with_min_score_filter() {
         min_score: Some(0.9),
         entity_types: None,
         file_filter: None,
+        offset: 0,
+        fields: None,
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
     };
     
     let results = search_vectors(&store, &query_vec, &options);
@@ -156,7 +208,7 @@ fn test_search_with_min_score_filter() {
 #[test]
 fn test_search_with_combined_filters() {
     // This test verifies that search results can be filtered by multiple criteria
-    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+    let store = fixture_store();
     let query_vec = vec![1.0, 2.0, 3.0];
     
     // Combine multiple filters
@@ -165,6 +217,14 @@ fn test_search_with_combined_filters() {
         min_score: Some(0.7),
         entity_types: Some(vec!["function".to_string()]),
         file_filter: Some("test.py".to_string()),
+        offset: 0,
+        fields: None,
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
     };
     
     let results = search_vectors(&store, &query_vec, &options);
@@ -181,3 +241,242 @@ fn test_search_with_combined_filters() {
                   "All results should be from file 'test.py'");
     }
 }
+
+#[test]
+fn test_search_with_offset_pagination() {
+    // This test verifies that offset skips ranked results and ranks stay absolute
+    let store = fixture_store();
+    let query_vec = vec![1.0, 2.0, 3.0];
+    
+    let all_options = SearchOptions {
+        top_k: 5,
+        min_score: None,
+        entity_types: None,
+        file_filter: None,
+        offset: 0,
+        fields: None,
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
+    };
+    let all_results = search_vectors(&store, &query_vec, &all_options).unwrap();
+    assert_eq!(all_results.len(), 5, "Should return the full five-result set");
+    
+    let page_options = SearchOptions {
+        top_k: 2,
+        offset: 2,
+        ..all_options
+    };
+    let page_results = search_vectors(&store, &query_vec, &page_options).unwrap();
+    
+    assert_eq!(page_results.len(), 2, "Should return exactly top_k results from the page");
+    assert_eq!(page_results[0].entity_id, all_results[2].entity_id);
+    assert_eq!(page_results[1].entity_id, all_results[3].entity_id);
+    assert_eq!(page_results[0].rank, 3);
+    assert_eq!(page_results[1].rank, 4);
+    
+    let human_output = format_search_results(&page_results, OutputFormat::Human);
+    assert!(human_output.starts_with("Results:\n3. "), "Human output should show absolute rank 3");
+    assert!(human_output.contains("\n4. "), "Human output should show absolute rank 4");
+    
+    let json_output = format_search_results(&page_results, OutputFormat::Json);
+    assert!(json_output.contains("\"rank\":3"), "JSON output should include absolute rank 3");
+    assert!(json_output.contains("\"rank\":4"), "JSON output should include absolute rank 4");
+}
+
+#[test]
+fn test_search_with_metadata_field_projection() {
+    // This test verifies that only the requested metadata fields (plus id) are returned
+    let store = fixture_store();
+    let query_vec = vec![1.0, 2.0, 3.0];
+    
+    let options = SearchOptions {
+        top_k: 5,
+        min_score: None,
+        entity_types: None,
+        file_filter: None,
+        offset: 0,
+        fields: Some(vec!["type".to_string()]),
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
+    };
+    
+    let results = search_vectors(&store, &query_vec, &options).unwrap();
+    assert!(!results.is_empty(), "Search should return results");
+    
+    for result in &results {
+        assert!(result.metadata.contains_key("id"), "Metadata should always include id");
+        assert!(result.metadata.contains_key("type"), "Metadata should include requested field");
+        assert_eq!(result.metadata.len(), 2, "Metadata should only contain id and type, got {:?}", result.metadata);
+    }
+}
+
+#[test]
+fn test_search_with_include_only_type_filter_is_case_insensitive() {
+    // This test verifies that inclusion matches stored lowercase types regardless of case
+    let store = fixture_store();
+    let query_vec = vec![1.0, 2.0, 3.0];
+    
+    let options = SearchOptions {
+        top_k: 5,
+        min_score: None,
+        entity_types: Some(vec!["Function".to_string(), "CLASS".to_string()]),
+        file_filter: None,
+        offset: 0,
+        fields: None,
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
+    };
+    
+    let results = search_vectors(&store, &query_vec, &options).unwrap();
+    assert_eq!(results.len(), 3, "Should return both functions and the class");
+    for result in &results {
+        let entity_type = result.metadata.get("type").unwrap();
+        assert!(entity_type == "function" || entity_type == "class",
+                "Unexpected entity type {}", entity_type);
+    }
+}
+
+#[test]
+fn test_search_with_exclude_only_type_filter() {
+    // This test verifies that excluded entity types are dropped from the results
+    let store = fixture_store();
+    let query_vec = vec![1.0, 2.0, 3.0];
+    
+    let options = SearchOptions {
+        top_k: 5,
+        min_score: None,
+        entity_types: None,
+        file_filter: None,
+        offset: 0,
+        fields: None,
+        exclude_entity_types: Some(vec!["Variable".to_string()]),
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
+    };
+    
+    let results = search_vectors(&store, &query_vec, &options).unwrap();
+    assert_eq!(results.len(), 4, "Should return everything except the variable");
+    assert!(results.iter().all(|r| r.metadata.get("type").unwrap() != "variable"),
+            "No result should be a variable");
+}
+
+#[test]
+fn test_search_with_combined_include_and_exclude_type_filters() {
+    // This test verifies that exclusion is applied after inclusion
+    let store = fixture_store();
+    let query_vec = vec![1.0, 2.0, 3.0];
+    
+    let options = SearchOptions {
+        top_k: 5,
+        min_score: None,
+        entity_types: Some(vec!["function".to_string(), "class".to_string()]),
+        file_filter: None,
+        offset: 0,
+        fields: None,
+        exclude_entity_types: Some(vec!["class".to_string()]),
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: false,
+        alpha: None,
+        mmr: None,
+    };
+    
+    let results = search_vectors(&store, &query_vec, &options).unwrap();
+    assert_eq!(results.len(), 2, "Should return only the two functions");
+    assert!(results.iter().all(|r| r.metadata.get("type").unwrap() == "function"),
+            "All results should be functions");
+}
+
+#[test]
+fn test_search_results_are_identical_across_chunk_sizes() {
+    // This test verifies that the parallel scan is deterministic regardless of chunking
+    let store = InMemoryVectorStore::new();
+    for i in 0..250 {
+        let x = i as f32;
+        let vector = vec![(x * 0.37).sin(), (x * 0.11).cos(), (x % 7.0) / 7.0 + 0.01];
+        store.upsert_embedding(&format!("entity{}", i), &vector, Some("test.py"), Some("function")).unwrap();
+    }
+    let query_vec = vec![0.3, 0.6, 0.9];
+    
+    let run = |search_threads: Option<usize>, chunk_size: Option<usize>| {
+        let options = SearchOptions {
+            top_k: 25,
+            min_score: None,
+            entity_types: None,
+            file_filter: None,
+            offset: 0,
+            fields: None,
+            exclude_entity_types: None,
+            search_threads,
+            chunk_size,
+            include_vectors: false,
+            alpha: None,
+            mmr: None,
+        };
+        search_vectors(&store, &query_vec, &options)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.entity_id, r.score, r.rank))
+            .collect::<Vec<_>>()
+    };
+    
+    let baseline = run(Some(1), Some(usize::MAX));
+    assert_eq!(baseline.len(), 25);
+    for threads in [Some(1), Some(4), None] {
+        assert_eq!(run(threads, Some(1)), baseline, "chunk size 1 should match");
+        assert_eq!(run(threads, Some(100)), baseline, "chunk size 100 should match");
+        assert_eq!(run(threads, Some(usize::MAX)), baseline, "a single chunk should match");
+        assert_eq!(run(threads, None), baseline, "default chunking should match");
+    }
+}
+
+#[test]
+fn test_search_with_include_vectors() {
+    // This test verifies that vectors are returned only when requested
+    let store = fixture_store();
+    let query_vec = vec![1.0, 2.0, 3.0];
+    
+    let options = SearchOptions {
+        top_k: 5,
+        min_score: None,
+        entity_types: None,
+        file_filter: None,
+        offset: 0,
+        fields: None,
+        exclude_entity_types: None,
+        search_threads: None,
+        chunk_size: None,
+        include_vectors: true,
+        alpha: None,
+        mmr: None,
+    };
+    let with_vectors = search_vectors(&store, &query_vec, &options).unwrap();
+    assert!(!with_vectors.is_empty());
+    for result in &with_vectors {
+        let vector = result.vector.as_ref().expect("Vector should be present when requested");
+        assert_eq!(vector.len(), 3, "Vector should match the stored dimension");
+    }
+    let json_output = format_search_results(&with_vectors, OutputFormat::Json);
+    assert!(json_output.contains("\"vector\":["), "JSON output should include vectors");
+    
+    let options = SearchOptions { include_vectors: false, ..options };
+    let without_vectors = search_vectors(&store, &query_vec, &options).unwrap();
+    assert!(without_vectors.iter().all(|r| r.vector.is_none()), "Vectors should be absent by default");
+    let json_output = format_search_results(&without_vectors, OutputFormat::Json);
+    assert!(!json_output.contains("\"vector\""), "JSON output should omit vectors when not requested");
+}
@@ -19,11 +19,10 @@ fn cleanup_redis_keys(prefix: &str) {
     let client = Client::open("redis://127.0.0.1/").expect("Failed to connect to Redis");
     let mut con = client.get_connection().expect("Failed to get Redis connection");
     
-    // Find all keys with the prefix
-    let keys: Vec<String> = redis::cmd("KEYS")
-        .arg(format!("{}:*", prefix))
-        .query(&mut con)
-        .expect("Failed to query Redis keys");
+    // Find all keys with the prefix; SCAN, unlike KEYS, does not block a shared server
+    let keys: Vec<String> = con.scan_match(format!("{}:*", prefix))
+        .expect("Failed to scan Redis keys")
+        .collect();
     
     // Delete all found keys
     if !keys.is_empty() {
@@ -35,7 +35,7 @@ fn test_redis_vector_store_logs_upsert() {
     init_logger(logs.clone()).unwrap();
     let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
     let key_prefix = "code:testproject";
-    let store = RedisVectorStore::new(&redis_url, key_prefix);
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap();
     
     // Use the trait method which logs operations
     let entity_id = "foo";
@@ -1,5 +1,6 @@
 //! Tests for vector store functionality using a mock implementation
 
+use indexer::error::IndexerError;
 use indexer::vector_store::VectorStore;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -67,13 +68,13 @@ impl MockVectorStore {
 }
 
 impl VectorStore for MockVectorStore {
-    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), String> {
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
         // Record the store call
         self.store_calls.lock().unwrap().push(entity_id.to_string());
         
         // Validate embedding
         if embedding.is_empty() {
-            return Err("Empty embedding is not allowed".to_string());
+            return Err(IndexerError::Store("Empty embedding is not allowed".to_string()));
         }
         
         // Store the embedding
@@ -176,7 +177,7 @@ impl VectorStore for MockVectorStore {
             .collect()
     }
     
-    fn get_all_entity_ids(&self) -> Result<Vec<String>, String> {
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push("get_all_entity_ids".to_string());
         
@@ -190,7 +191,7 @@ impl VectorStore for MockVectorStore {
         Ok(ids)
     }
     
-    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, String> {
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push(format!("get_entity_vector:{}", entity_id));
         
@@ -199,10 +200,10 @@ impl VectorStore for MockVectorStore {
         
         entities.get(&prefixed_id)
             .cloned()
-            .ok_or_else(|| format!("Entity not found: {}", entity_id))
+            .ok_or_else(|| IndexerError::Store(format!("Entity not found: {}", entity_id)))
     }
     
-    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, String> {
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push(format!("get_entity_metadata:{}", entity_id));
         
@@ -211,7 +212,7 @@ impl VectorStore for MockVectorStore {
         
         metadata.get(&prefixed_id)
             .cloned()
-            .ok_or_else(|| format!("Entity metadata not found: {}", entity_id))
+            .ok_or_else(|| IndexerError::Store(format!("Entity metadata not found: {}", entity_id)))
     }
 }
 
@@ -1,8 +1,10 @@
 //! RED test for Redis backend implementation (upsert/query, key prefixing, entity typing)
 
-use indexer::vector_store::RedisVectorStore;
+use indexer::vector_store::{runtimes_built_on_this_thread, RedisVectorStore};
 use std::env;
-use indexer::vector_store::VectorStore;
+use indexer::embedder::{Embedder, MockEmbedder};
+use indexer::vectorize::{process_directory, ProgressEvent, VectorizeOptions};
+use indexer::vector_store::{text_hash, EntityDetails, UpsertItem, VectorStore};
 
 #[test]
 fn test_redis_vector_store_upsert_and_query() {
@@ -10,7 +12,7 @@ fn test_redis_vector_store_upsert_and_query() {
     // This allows us to test without async/await
     let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
     let key_prefix = "code:testproject";
-    let store = RedisVectorStore::new(&redis_url, key_prefix);
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap();
     
     // For testing, we'll use the VectorStore trait methods which are synchronous
     let entity_id = "foo";
@@ -29,7 +31,278 @@ fn test_redis_vector_store_upsert_and_query() {
 fn test_redis_vector_store_key_prefixing() {
     let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
     let key_prefix = "code:testproject";
-    let store = RedisVectorStore::new(&redis_url, key_prefix);
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap();
     let full_key = store.make_key("doc", "foo");
     assert_eq!(full_key, "code:testproject:doc:foo");
 }
+
+#[test]
+fn test_redis_vector_store_normalizes_embeddings() {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = "code:testproject:normalized";
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap().with_normalize(true);
+    assert!(store.normalizes());
+    
+    VectorStore::upsert_embedding(&store, "bar", &[3.0, 4.0, 0.0], Some("test.py"), Some("function")).unwrap();
+    let stored = VectorStore::get_entity_vector(&store, "bar").unwrap();
+    let magnitude = stored.iter().map(|x| x * x).sum::<f32>().sqrt();
+    assert!((magnitude - 1.0).abs() < 1e-6, "Stored vector should have unit length, got {}", magnitude);
+    
+    let metadata = VectorStore::get_entity_metadata(&store, "bar").unwrap();
+    assert_eq!(metadata.get("normalized").map(String::as_str), Some("true"));
+    VectorStore::delete_embedding(&store, "bar").unwrap();
+}
+
+#[test]
+fn test_redis_vector_store_upsert_batch() {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = "code:testproject:batch";
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap();
+    
+    let items: Vec<UpsertItem> = (0..100)
+        .map(|i| (format!("fn:batch.py:f{}", i), vec![1.0, i as f32, 0.5], Some("batch.py".to_string()), Some("function".to_string()), EntityDetails { line_start: Some(i), line_end: Some(i + 1), ..EntityDetails::default() }))
+        .collect();
+    VectorStore::upsert_batch(&store, &items).unwrap();
+    
+    for (entity_id, embedding, _, _, details) in &items {
+        assert_eq!(&VectorStore::get_entity_vector(&store, entity_id).unwrap(), embedding);
+        let metadata = VectorStore::get_entity_metadata(&store, entity_id).unwrap();
+        assert_eq!(metadata.get("file").map(String::as_str), Some("batch.py"));
+        assert_eq!(metadata.get("line_start"), details.line_start.map(|line| line.to_string()).as_ref());
+    }
+    let ids = VectorStore::filtered_entity_ids(&store, Some(&["function".to_string()]), Some("batch.py")).unwrap();
+    assert_eq!(ids.len(), 100);
+    
+    for (entity_id, _, _, _, _) in &items {
+        VectorStore::delete_embedding(&store, entity_id).unwrap();
+    }
+}
+
+#[test]
+fn test_redis_vector_store_concurrent_upserts_share_pool() {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = "code:testproject:pooled";
+    let store = RedisVectorStore::with_pool_size(&redis_url, key_prefix, 3, 2).unwrap();
+    assert!(store.pool().is_some());
+    
+    // Eight threads write through the same two connections
+    std::thread::scope(|scope| {
+        for t in 0..8 {
+            let store = &store;
+            scope.spawn(move || {
+                for i in 0..25 {
+                    let entity_id = format!("fn:pooled.py:t{}_{}", t, i);
+                    VectorStore::upsert_embedding(store, &entity_id, &[1.0, t as f32, i as f32], Some("pooled.py"), Some("function"))
+                        .unwrap_or_else(|e| panic!("upsert of {} failed: {}", entity_id, e));
+                }
+            });
+        }
+    });
+    
+    let ids = VectorStore::filtered_entity_ids(&store, Some(&["function".to_string()]), Some("pooled.py")).unwrap();
+    assert_eq!(ids.len(), 200);
+    for t in 0..8 {
+        for i in 0..25 {
+            let entity_id = format!("fn:pooled.py:t{}_{}", t, i);
+            assert_eq!(VectorStore::get_entity_vector(&store, &entity_id).unwrap(), vec![1.0, t as f32, i as f32]);
+            VectorStore::delete_embedding(&store, &entity_id).unwrap();
+        }
+    }
+}
+
+// Inside a runtime, where building or blocking on a fresh runtime per call would panic
+#[tokio::test(flavor = "multi_thread")]
+async fn test_redis_vector_store_reuses_its_runtime_across_searches() {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = format!("code:runtime_{}", uuid::Uuid::new_v4());
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("a.py"), "def foo():\n    return 1\n\ndef bar():\n    return 2\n").unwrap();
+    std::fs::write(temp_dir.path().join("b.py"), "def baz():\n    return 3\n").unwrap();
+
+    let embedder = MockEmbedder::new();
+    let before = runtimes_built_on_this_thread();
+    let store = RedisVectorStore::new(&redis_url, &key_prefix, embedder.dimension()).unwrap();
+    assert_eq!(runtimes_built_on_this_thread(), before + 1);
+
+    let (mut files, mut reported) = (0, 0);
+    let processed = process_directory(temp_dir.path(), &embedder, &store, &VectorizeOptions::default(), &mut |event| {
+        if let ProgressEvent::FileFinished { entities, .. } = event {
+            files += 1;
+            reported += entities;
+        }
+    }).unwrap();
+    assert_eq!(files, 2);
+    assert_eq!(processed, 3);
+    assert_eq!(processed, reported);
+    let mut stored = VectorStore::get_all_entity_ids(&store).unwrap();
+    stored.sort();
+    assert_eq!(stored, vec!["fn:a.py:bar", "fn:a.py:foo", "fn:b.py:baz"]);
+
+    let query = embedder.embed("def foo():\n    return 1");
+    let first = VectorStore::similarity_search(&store, &query, processed);
+    let second = VectorStore::similarity_search(&store, &query, processed);
+    assert_eq!(first.len(), processed);
+    assert_eq!(second, first);
+    assert_eq!(runtimes_built_on_this_thread(), before + 1, "the run and its searches should reuse the store's runtime");
+
+    for id in stored {
+        VectorStore::delete_embedding(&store, &id).unwrap();
+    }
+}
+
+#[test]
+fn test_redis_vector_store_dedup_shares_identical_vectors() {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = "code:testproject:dedup";
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap().with_dedup(true);
+    assert!(store.dedups());
+    
+    // Two trivial constructors with the same embedding text
+    let hash = text_hash("method __init__\ndef __init__(self)");
+    let details = |line| EntityDetails { line_start: Some(line), text_hash: Some(hash.clone()), ..EntityDetails::default() };
+    let items: Vec<UpsertItem> = vec![
+        ("method:a.py:A.__init__".to_string(), vec![0.5, 0.5, 0.0], Some("a.py".to_string()), Some("method".to_string()), details(2)),
+        ("method:b.py:B.__init__".to_string(), vec![0.5, 0.5, 0.0], Some("b.py".to_string()), Some("method".to_string()), details(7)),
+    ];
+    VectorStore::upsert_batch(&store, &items).unwrap();
+    
+    // One vector is stored, and both entities reference it
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let shared: Vec<String> = runtime.block_on(async {
+        let client = indexer::redis_ops::create_redis_client(&redis_url).await.unwrap();
+        indexer::redis_ops::scan_keys(&client, &format!("{}:shared_vector:*", key_prefix)).await.unwrap()
+    });
+    assert_eq!(shared, vec![store.shared_vector_key(&hash)]);
+    for (entity_id, _, file, _, _) in &items {
+        assert_eq!(VectorStore::get_entity_vector(&store, entity_id).unwrap(), vec![0.5, 0.5, 0.0]);
+        let metadata = VectorStore::get_entity_metadata(&store, entity_id).unwrap();
+        assert_eq!(metadata.get("file"), file.as_ref());
+        assert_eq!(metadata.get("shared_vector"), Some(&hash));
+    }
+    assert_eq!(VectorStore::similarity_search(&store, &[0.5, 0.5, 0.0], 2).len(), 2);
+    
+    for (entity_id, ..) in &items {
+        VectorStore::delete_embedding(&store, entity_id).unwrap();
+    }
+}
+
+#[test]
+fn test_redis_vector_store_looks_up_entity_types_in_one_hash() {
+    use fred::prelude::*;
+    
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = "code:testproject:entity_type";
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap();
+    VectorStore::upsert_embedding(&store, "fn:a.py:load", &[1.0, 0.0, 0.0], Some("a.py"), Some("function")).unwrap();
+    
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let client = runtime.block_on(indexer::redis_ops::create_redis_client(&redis_url)).unwrap();
+    let recorded: Option<String> = runtime.block_on(client.hget(store.entity_type_key(), "fn:a.py:load")).unwrap();
+    assert_eq!(recorded.as_deref(), Some("function"));
+    
+    // An entity stored before the hash existed is found through the type indexes and backfilled
+    let _: u64 = runtime.block_on(client.hdel(store.entity_type_key(), "fn:a.py:load")).unwrap();
+    assert_eq!(VectorStore::get_entity_vector(&store, "fn:a.py:load").unwrap(), vec![1.0, 0.0, 0.0]);
+    let recorded: Option<String> = runtime.block_on(client.hget(store.entity_type_key(), "fn:a.py:load")).unwrap();
+    assert_eq!(recorded.as_deref(), Some("function"));
+    
+    assert!(VectorStore::delete_embedding(&store, "fn:a.py:load").unwrap());
+    let recorded: Option<String> = runtime.block_on(client.hget(store.entity_type_key(), "fn:a.py:load")).unwrap();
+    assert_eq!(recorded, None);
+    assert!(!VectorStore::delete_embedding(&store, "fn:a.py:load").unwrap());
+}
+
+#[test]
+fn test_redis_vector_store_replace_all_swaps_entities_and_meta() {
+    use indexer::vector_store::EmbeddingMeta;
+    
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = "code:testproject:replace_all";
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap();
+    for entity_id in VectorStore::get_all_entity_ids(&store).unwrap() {
+        VectorStore::delete_embedding(&store, &entity_id).unwrap();
+    }
+    VectorStore::upsert_embedding(&store, "fn:old.py:gone", &[1.0, 0.0, 0.0], Some("old.py"), Some("function")).unwrap();
+    
+    let details = EntityDetails { line_start: Some(3), line_end: Some(9), signature: Some("def load(path)".to_string()), text_hash: None };
+    let items: Vec<UpsertItem> = vec![
+        ("fn:a.py:load".to_string(), vec![0.0, 1.0, 0.0], Some("a.py".to_string()), Some("function".to_string()), details.clone()),
+    ];
+    let meta = EmbeddingMeta { provider: "openai".to_string(), model: "text-embedding-3-small".to_string(), dimension: 3, normalized: false };
+    
+    assert_eq!(VectorStore::replace_all(&store, &items, &meta).unwrap(), 1);
+    assert_eq!(VectorStore::get_all_entity_ids(&store).unwrap(), vec!["fn:a.py:load".to_string()]);
+    assert_eq!(VectorStore::filtered_entity_ids(&store, None, Some("old.py")).unwrap(), Vec::<String>::new());
+    let metadata = VectorStore::get_entity_metadata(&store, "fn:a.py:load").unwrap();
+    assert_eq!(EntityDetails::from_metadata(&metadata), details);
+    assert_eq!(VectorStore::get_embedding_meta(&store).unwrap(), Some(meta));
+    
+    VectorStore::delete_embedding(&store, "fn:a.py:load").unwrap();
+}
+
+#[test]
+fn test_redis_vector_store_sweeps_unreferenced_shared_vectors() {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = "code:testproject:sweep";
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap().with_dedup(true);
+    let hash = text_hash("method __init__\ndef __init__(self)");
+    let details = EntityDetails { text_hash: Some(hash.clone()), ..EntityDetails::default() };
+    let items: Vec<UpsertItem> = vec![
+        ("method:a.py:A.__init__".to_string(), vec![0.5, 0.5, 0.0], Some("a.py".to_string()), Some("method".to_string()), details.clone()),
+        ("method:b.py:B.__init__".to_string(), vec![0.5, 0.5, 0.0], Some("b.py".to_string()), Some("method".to_string()), details),
+    ];
+    VectorStore::upsert_batch(&store, &items).unwrap();
+    
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let shared_keys = || runtime.block_on(async {
+        let client = indexer::redis_ops::create_redis_client(&redis_url).await.unwrap();
+        indexer::redis_ops::scan_keys(&client, &format!("{}:shared_vector:*", key_prefix)).await.unwrap()
+    });
+    
+    // Still referenced by B.__init__
+    VectorStore::delete_embedding(&store, "method:a.py:A.__init__").unwrap();
+    assert_eq!(runtime.block_on(store.sweep_shared_vectors_async()).unwrap(), 0);
+    assert_eq!(shared_keys(), vec![store.shared_vector_key(&hash)]);
+    
+    VectorStore::delete_embedding(&store, "method:b.py:B.__init__").unwrap();
+    assert_eq!(runtime.block_on(store.sweep_shared_vectors_async()).unwrap(), 1);
+    assert!(shared_keys().is_empty());
+}
+
+#[test]
+fn test_redis_vector_store_get_vectors_batch_reads_plain_and_shared_vectors() {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let key_prefix = "code:testproject:vectors_batch";
+    let store = RedisVectorStore::new(&redis_url, key_prefix, 3).unwrap().with_dedup(true);
+    
+    let hash = text_hash("function shared\ndef shared()");
+    let items: Vec<UpsertItem> = vec![
+        ("fn:a.py:plain".to_string(), vec![1.0, 0.0, 0.0], Some("a.py".to_string()), Some("function".to_string()), EntityDetails::default()),
+        ("fn:b.py:shared".to_string(), vec![0.0, 1.0, 0.0], Some("b.py".to_string()), Some("function".to_string()),
+            EntityDetails { text_hash: Some(hash.clone()), ..EntityDetails::default() }),
+    ];
+    VectorStore::upsert_batch(&store, &items).unwrap();
+    
+    let ids = vec!["fn:b.py:shared".to_string(), "fn:a.py:missing".to_string(), "fn:a.py:plain".to_string()];
+    let fetched = VectorStore::get_vectors_batch(&store, &ids).unwrap();
+    assert_eq!(fetched.len(), 3);
+    let (vector, metadata) = fetched[0].as_ref().unwrap();
+    assert_eq!((vector.as_slice(), metadata.get("file").map(String::as_str)), (&[0.0, 1.0, 0.0][..], Some("b.py")));
+    assert!(fetched[1].is_err(), "a missing entity gets its own error");
+    let (vector, metadata) = fetched[2].as_ref().unwrap();
+    assert_eq!((vector.as_slice(), metadata.get("file").map(String::as_str)), (&[1.0, 0.0, 0.0][..], Some("a.py")));
+    
+    for (entity_id, ..) in &items {
+        VectorStore::delete_embedding(&store, entity_id).unwrap();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_redis_vector_store_sync_api_inside_multi_thread_runtime() {
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379/0".to_string());
+    let store = RedisVectorStore::new(&redis_url, "code:testproject:multi_thread", 3).unwrap();
+    
+    VectorStore::upsert_embedding(&store, "fn:a.py:f", &[1.0, 0.0, 0.0], Some("a.py"), Some("function")).unwrap();
+    assert_eq!(VectorStore::get_entity_vector(&store, "fn:a.py:f").unwrap(), vec![1.0, 0.0, 0.0]);
+    assert!(VectorStore::delete_embedding(&store, "fn:a.py:f").unwrap());
+}
@@ -1,8 +1,10 @@
 //! Tests for vectorize command implementation (TDD: Phase 2 - RED)
 
 use indexer::cli::{CliArgs, Commands};
-use indexer::embedder::MockEmbedder;
-use indexer::vector_store::{RedisVectorStore, VectorStore};
+use indexer::vectorize::{Granularity, VectorizeOptions};
+use indexer::embedder::{Embedder, MockEmbedder};
+use indexer::error::IndexerError;
+use indexer::vector_store::{InMemoryVectorStore, RedisVectorStore, VectorStore};
 use tempfile;
 
 #[tokio::test]
@@ -27,7 +29,7 @@ async fn test_vectorize_command_execution() {
     
     // Setup mock components
     let mock_embedder = MockEmbedder::new();
-    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix", 3).unwrap();
     
     // Create test CLI args
     let args = CliArgs {
@@ -39,7 +41,26 @@ async fn test_vectorize_command_execution() {
             batch_size: Some(10),
             dry_run: false,
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function
@@ -71,7 +92,7 @@ impl DryRunMockStore {
 }
 
 impl VectorStore for DryRunMockStore {
-    fn upsert_embedding(&self, entity_id: &str, _embedding: &[f32], _file: Option<&str>, _entity_type: Option<&str>) -> Result<(), String> {
+    fn upsert_embedding(&self, entity_id: &str, _embedding: &[f32], _file: Option<&str>, _entity_type: Option<&str>) -> Result<(), IndexerError> {
         // Only store if not in dry run mode, which we'll handle in the vectorize_command function
         log::info!("DryRunMockStore: Would store entity {}", entity_id);
         Ok(())
@@ -81,16 +102,16 @@ impl VectorStore for DryRunMockStore {
         vec![]
     }
     
-    fn get_all_entity_ids(&self) -> Result<Vec<String>, String> {
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
         // Return empty list for dry run test
         Ok(self.stored_entities.borrow().clone())
     }
     
-    fn get_entity_vector(&self, _entity_id: &str) -> Result<Vec<f32>, String> {
+    fn get_entity_vector(&self, _entity_id: &str) -> Result<Vec<f32>, IndexerError> {
         Ok(vec![0.0, 0.0, 0.0])
     }
     
-    fn get_entity_metadata(&self, _entity_id: &str) -> Result<std::collections::HashMap<String, String>, String> {
+    fn get_entity_metadata(&self, _entity_id: &str) -> Result<std::collections::HashMap<String, String>, IndexerError> {
         Ok(std::collections::HashMap::new())
     }
 }
@@ -125,7 +146,26 @@ async fn test_vectorize_command_dry_run() {
             batch_size: Some(10),
             dry_run: true,
             verbose: false,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function
@@ -161,7 +201,7 @@ async fn test_vectorize_command_with_config_fallback() {
     
     // Setup mock components
     let mock_embedder = MockEmbedder::new();
-    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix");
+    let store = RedisVectorStore::new("redis://localhost:6379/0", "test_prefix", 3).unwrap();
     
     // Create test CLI args with minimal arguments
     let args = CliArgs {
@@ -173,7 +213,26 @@ async fn test_vectorize_command_with_config_fallback() {
             batch_size: None,
             dry_run: false,
             verbose: false,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Mock config would be loaded here in a real test
@@ -189,3 +248,249 @@ async fn test_vectorize_command_with_config_fallback() {
     let entity_ids = store.get_all_entity_ids().unwrap();
     assert!(!entity_ids.is_empty(), "Should have extracted and stored entities");
 }
+
+#[tokio::test]
+async fn test_vectorize_dry_run_leaves_populated_store_unchanged() {
+    // Dry-run must walk and extract as usual without writing, even when the
+    // store already holds entities
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("app.py"), "def handler():\n    pass\n\nclass Model:\n    pass\n").unwrap();
+    
+    let store = InMemoryVectorStore::new();
+    store.upsert_embedding("fn:existing.py:old", &[1.0, 0.0, 0.0], Some("existing.py"), Some("function")).unwrap();
+    let ids_before = store.get_all_entity_ids().unwrap();
+    
+    let args = CliArgs {
+        command: Commands::Vectorize {
+            name: "test_project".to_string(),
+            path: temp_dir.path().to_string_lossy().to_string(),
+            provider: Some("mock".to_string()),
+            db: Some("memory".to_string()),
+            batch_size: Some(10),
+            dry_run: true,
+            verbose: false,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
+        },
+        quiet: false,
+        verbose: false,
+    };
+    let result = indexer::vectorize_command(&args, &MockEmbedder::new(), &store).await;
+    assert!(result.is_ok(), "Dry run should succeed: {:?}", result);
+    assert_eq!(store.get_all_entity_ids().unwrap(), ids_before, "Dry run must not mutate the store");
+    
+    // The would-be entity count is still reported
+    let would_store = indexer::vectorize::process_directory(temp_dir.path(), &MockEmbedder::new(), &store, &VectorizeOptions { dry_run: true, ..VectorizeOptions::default() }, &mut |_| {}).unwrap();
+    assert_eq!(would_store, 2, "Dry run should report handler and Model");
+    assert_eq!(store.count().unwrap(), 1);
+}
+
+/// Embedder that takes a while per entity, standing in for a slow provider
+struct SlowEmbedder(std::time::Duration);
+
+impl Embedder for SlowEmbedder {
+    fn embed(&self, input: &str) -> Vec<f32> {
+        std::thread::sleep(self.0);
+        MockEmbedder::new().embed(input)
+    }
+}
+
+#[tokio::test]
+async fn test_vectorize_timeout_keeps_partial_progress() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    for i in 0..8 {
+        std::fs::write(temp_dir.path().join(format!("mod_{}.py", i)), format!("def handler_{}():\n    pass\n", i)).unwrap();
+    }
+    
+    let store = InMemoryVectorStore::new();
+    let args = CliArgs {
+        command: Commands::Vectorize {
+            name: "test_project".to_string(),
+            path: temp_dir.path().to_string_lossy().to_string(),
+            provider: Some("mock".to_string()),
+            db: Some("memory".to_string()),
+            batch_size: Some(1),
+            dry_run: false,
+            verbose: false,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 1,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: Some(1),
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
+        },
+        quiet: false,
+        verbose: false,
+    };
+    let embedder = SlowEmbedder(std::time::Duration::from_millis(400));
+    let result = indexer::vectorize_command(&args, &embedder, &store).await;
+    
+    match result {
+        Err(IndexerError::TimedOut { secs, stored }) => {
+            assert_eq!(secs, 1);
+            assert!(stored > 0 && stored < 8, "Expected a partial run, got {} entities", stored);
+            assert_eq!(store.count().unwrap(), stored, "Entities embedded before the deadline stay stored");
+        }
+        other => panic!("Expected a timeout error, got {:?}", other),
+    }
+}
+
+/// Embedder that records how many embedding calls were in flight at once
+#[derive(Default)]
+struct OverlapEmbedder {
+    active: std::sync::atomic::AtomicUsize,
+    peak: std::sync::atomic::AtomicUsize,
+}
+
+impl Embedder for OverlapEmbedder {
+    fn embed(&self, input: &str) -> Vec<f32> {
+        use std::sync::atomic::Ordering;
+        let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(active, Ordering::SeqCst);
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        self.active.fetch_sub(1, Ordering::SeqCst);
+        MockEmbedder::new().embed(input)
+    }
+}
+
+#[tokio::test]
+async fn test_vectorize_command_embeds_files_concurrently() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    for i in 0..8 {
+        std::fs::write(temp_dir.path().join(format!("mod_{}.py", i)), format!("def handler_{}():\n    pass\n", i)).unwrap();
+    }
+    
+    let store = InMemoryVectorStore::new();
+    let args = CliArgs {
+        command: Commands::Vectorize {
+            name: "test_project".to_string(),
+            path: temp_dir.path().to_string_lossy().to_string(),
+            provider: Some("mock".to_string()),
+            db: Some("memory".to_string()),
+            batch_size: Some(2),
+            dry_run: false,
+            verbose: false,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
+        },
+        quiet: false,
+        verbose: false,
+    };
+    let embedder = OverlapEmbedder::default();
+    indexer::vectorize_command(&args, &embedder, &store).await.unwrap();
+    
+    assert_eq!(store.count().unwrap(), 8);
+    let peak = embedder.peak.load(std::sync::atomic::Ordering::SeqCst);
+    assert!(peak > 1, "--concurrency 4 should embed several files at once, peak was {}", peak);
+}
+
+/// Embedder for a model the crate does not know, claiming the wrong dimension
+struct UnknownModelEmbedder;
+
+impl Embedder for UnknownModelEmbedder {
+    fn embed(&self, _input: &str) -> Vec<f32> {
+        vec![0.2, 0.4, 0.6, 0.8, 1.0]
+    }
+    
+    fn dimension(&self) -> usize {
+        3
+    }
+}
+
+#[tokio::test]
+async fn test_vectorize_dimension_override_builds_store() {
+    let dimension = indexer::vectorize::resolve_dimension(&UnknownModelEmbedder, Some(5)).unwrap();
+    assert_eq!(dimension, 5);
+    assert_eq!(indexer::vectorize::resolve_dimension(&UnknownModelEmbedder, None).unwrap(), 3);
+    
+    let key_prefix = format!("code_index:dimension_{}", uuid::Uuid::new_v4());
+    let store = RedisVectorStore::new("redis://localhost:6379/15", &key_prefix, dimension).unwrap();
+    assert_eq!(store.dimension(), 5);
+    store.upsert_embedding("fn:a.py:fetch", &UnknownModelEmbedder.embed("def fetch(): pass"), Some("a.py"), Some("function")).unwrap();
+    assert_eq!(store.get_entity_vector("fn:a.py:fetch").unwrap().len(), 5);
+    store.delete_embedding("fn:a.py:fetch").unwrap();
+}
+
+#[tokio::test]
+async fn test_vectorize_dimension_mismatch_fails_fast() {
+    let err = indexer::vectorize::resolve_dimension(&UnknownModelEmbedder, Some(1536)).unwrap_err();
+    assert!(err.to_string().contains("--dimension 1536"), "unexpected error: {}", err);
+    
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join("app.py"), "def handler():\n    pass\n").unwrap();
+    let store = InMemoryVectorStore::new();
+    let args = CliArgs {
+        command: Commands::Vectorize {
+            name: "test_project".to_string(),
+            path: temp_dir.path().to_string_lossy().to_string(),
+            provider: Some("mock".to_string()),
+            db: Some("memory".to_string()),
+            batch_size: Some(10),
+            dry_run: false,
+            verbose: false,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 1,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: Some(1536),
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
+        },
+        quiet: false,
+        verbose: false,
+    };
+    let result = indexer::vectorize_command(&args, &UnknownModelEmbedder, &store).await;
+    assert!(matches!(result, Err(IndexerError::Config(_))), "expected a config error, got {:?}", result);
+    assert_eq!(store.count().unwrap(), 0, "nothing should be embedded with a wrong --dimension");
+}
@@ -1,17 +1,20 @@
 //! Tests for edge cases and error handling in the vectorize command
 
 use indexer::cli::{CliArgs, Commands};
+use indexer::vectorize::Granularity;
 use indexer::embedder::Embedder;
+use indexer::error::IndexerError;
 use indexer::vector_store::VectorStore;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::sync::Mutex;
 use tempfile;
 
 // Mock embedder that can be configured to fail
 struct FailingEmbedder {
     should_fail: bool,
     fail_on_input: Option<String>,
-    embed_calls: RefCell<Vec<String>>,
+    embed_calls: Mutex<Vec<String>>,
 }
 
 impl FailingEmbedder {
@@ -19,7 +22,7 @@ impl FailingEmbedder {
         FailingEmbedder {
             should_fail,
             fail_on_input: None,
-            embed_calls: RefCell::new(Vec::new()),
+            embed_calls: Mutex::new(Vec::new()),
         }
     }
     
@@ -29,13 +32,13 @@ impl FailingEmbedder {
     }
     
     fn get_embed_calls(&self) -> Vec<String> {
-        self.embed_calls.borrow().clone()
+        self.embed_calls.lock().unwrap().clone()
     }
 }
 
 impl Embedder for FailingEmbedder {
     fn embed(&self, input: &str) -> Vec<f32> {
-        self.embed_calls.borrow_mut().push(input.to_string());
+        self.embed_calls.lock().unwrap().push(input.to_string());
         
         // Fail if configured to do so
         if self.should_fail {
@@ -78,9 +81,9 @@ impl FailingVectorStore {
 }
 
 impl VectorStore for FailingVectorStore {
-    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), String> {
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
         if self.should_fail_upsert {
-            return Err("VectorStore upsert failure (simulated)".to_string());
+            return Err(IndexerError::Store("VectorStore upsert failure (simulated)".to_string()));
         }
         
         // Store the embedding
@@ -109,32 +112,32 @@ impl VectorStore for FailingVectorStore {
         self.stored_entities.borrow().keys().cloned().collect()
     }
     
-    fn get_all_entity_ids(&self) -> Result<Vec<String>, String> {
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
         if self.should_fail_query {
-            return Err("VectorStore query failure (simulated)".to_string());
+            return Err(IndexerError::Store("VectorStore query failure (simulated)".to_string()));
         }
         
         Ok(self.stored_entities.borrow().keys().cloned().collect())
     }
     
-    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, String> {
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
         if self.should_fail_query {
-            return Err("VectorStore query failure (simulated)".to_string());
+            return Err(IndexerError::Store("VectorStore query failure (simulated)".to_string()));
         }
         
         self.stored_entities.borrow().get(entity_id)
             .cloned()
-            .ok_or_else(|| format!("Entity not found: {}", entity_id))
+            .ok_or_else(|| IndexerError::Store(format!("Entity not found: {}", entity_id)))
     }
     
-    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, String> {
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
         if self.should_fail_query {
-            return Err("VectorStore query failure (simulated)".to_string());
+            return Err(IndexerError::Store("VectorStore query failure (simulated)".to_string()));
         }
         
         self.stored_metadata.borrow().get(entity_id)
             .cloned()
-            .ok_or_else(|| format!("Entity metadata not found: {}", entity_id))
+            .ok_or_else(|| IndexerError::Store(format!("Entity metadata not found: {}", entity_id)))
     }
 }
 
@@ -153,7 +156,26 @@ async fn test_vectorize_command_invalid_path() {
             batch_size: Some(10),
             dry_run: false,
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function
@@ -161,7 +183,7 @@ async fn test_vectorize_command_invalid_path() {
     
     // Verify that the command fails with a proper error message
     assert!(result.is_err(), "Command should fail with invalid path");
-    let error = result.unwrap_err();
+    let error = result.unwrap_err().to_string();
     assert!(error.contains("path does not exist"), "Error should mention path does not exist");
 }
 
@@ -203,7 +225,26 @@ async fn test_vectorize_command_mixed_file_types() {
             batch_size: Some(10),
             dry_run: false,
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function
@@ -225,6 +266,55 @@ async fn test_vectorize_command_mixed_file_types() {
     // We're not asserting on the count because our implementation might handle this differently
 }
 
+#[tokio::test]
+async fn test_vectorize_command_rs_only_project_skips_python_files() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    std::fs::write(temp_dir.path().join(".indexer.yaml"), "extensions: [rs]\n").unwrap();
+    std::fs::write(temp_dir.path().join("lib.rs"), "fn rust_function() {\n    println!(\"rust\");\n}\n").unwrap();
+    std::fs::write(temp_dir.path().join("app.py"), "def python_function():\n    pass\n").unwrap();
+
+    let embedder = FailingEmbedder::new(false);
+    let store = FailingVectorStore::new(false, false);
+    let args = CliArgs {
+        command: Commands::Vectorize {
+            name: "test_project".to_string(),
+            path: temp_dir.path().to_string_lossy().to_string(),
+            provider: Some("mock".to_string()),
+            db: Some("memory".to_string()),
+            batch_size: Some(10),
+            dry_run: false,
+            verbose: false,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 1,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
+        },
+        quiet: false,
+        verbose: false,
+    };
+
+    indexer::vectorize_command(&args, &embedder, &store).await.unwrap();
+
+    let files: Vec<String> = store.stored_metadata.borrow().values()
+        .filter_map(|meta| meta.get("file").cloned())
+        .collect();
+    assert!(!files.is_empty(), "the .rs file should be embedded");
+    assert!(files.iter().all(|f| f.ends_with("lib.rs")), "only .rs files should be embedded: {:?}", files);
+}
+
 #[tokio::test]
 async fn test_vectorize_command_embedder_failure() {
     // Create a temporary directory
@@ -256,7 +346,26 @@ async fn test_vectorize_command_embedder_failure() {
             batch_size: Some(10),
             dry_run: false,
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function
@@ -296,7 +405,26 @@ async fn test_vectorize_command_store_failure() {
             batch_size: Some(10),
             dry_run: false,
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function
@@ -311,6 +439,7 @@ async fn test_vectorize_command_store_failure() {
         },
         Err(e) => {
             // Command failed with an error - verify it mentions the failure
+            let e = e.to_string();
             assert!(e.contains("failure") || e.contains("error") || e.contains("Error"), 
                     "Error should mention the failure: {}", e);
         }
@@ -1,7 +1,9 @@
 //! Extreme tests for the vectorize command with pathological cases
 
 use indexer::cli::{CliArgs, Commands};
+use indexer::vectorize::Granularity;
 use indexer::embedder::Embedder;
+use indexer::error::IndexerError;
 use indexer::vector_store::VectorStore;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
@@ -192,7 +194,7 @@ impl UnreliableVectorStore {
 }
 
 impl VectorStore for UnreliableVectorStore {
-    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), String> {
+    fn upsert_embedding(&self, entity_id: &str, embedding: &[f32], file: Option<&str>, entity_type: Option<&str>) -> Result<(), IndexerError> {
         // Record the store call
         self.store_calls.lock().unwrap().push(entity_id.to_string());
         
@@ -203,7 +205,7 @@ if should_partition {
             // Record failure
             self.failures.lock().unwrap().push(format!("Network partition during store of {}", entity_id));
             
-            return Err("Network partition (simulated)".to_string());
+            return Err(IndexerError::Store("Network partition (simulated)".to_string()));
         }
         
         // Check for race condition
@@ -219,7 +221,7 @@ if should_partition {
                     // Record failure
                     self.failures.lock().unwrap().push(format!("Race condition during store of {}", entity_id));
                     
-                    return Err("Race condition detected (simulated)".to_string());
+                    return Err(IndexerError::Store("Race condition detected (simulated)".to_string()));
                 }
             }
             
@@ -234,7 +236,7 @@ if should_partition {
             Err(_) => {
                 // RwLock poisoned
                 self.failures.lock().unwrap().push(format!("RwLock poisoned during store of {}", entity_id));
-                return Err("RwLock poisoned (simulated)".to_string());
+                return Err(IndexerError::Store("RwLock poisoned (simulated)".to_string()));
             }
         }
         
@@ -254,7 +256,7 @@ if should_partition {
             Err(_) => {
                 // RwLock poisoned
                 self.failures.lock().unwrap().push(format!("RwLock poisoned during metadata store of {}", entity_id));
-                return Err("RwLock poisoned (simulated)".to_string());
+                return Err(IndexerError::Store("RwLock poisoned (simulated)".to_string()));
             }
         }
         
@@ -286,7 +288,7 @@ if should_partition {
         }
     }
     
-    fn get_all_entity_ids(&self) -> Result<Vec<String>, String> {
+    fn get_all_entity_ids(&self) -> Result<Vec<String>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push("get_all_entity_ids".to_string());
         
@@ -297,7 +299,7 @@ if should_partition {
             // Record failure
             self.failures.lock().unwrap().push("Network partition during get_all_entity_ids".to_string());
             
-            return Err("Network partition (simulated)".to_string());
+            return Err(IndexerError::Store("Network partition (simulated)".to_string()));
         }
         
         match self.stored_entities.read() {
@@ -305,12 +307,12 @@ if should_partition {
             Err(_) => {
                 // RwLock poisoned
                 self.failures.lock().unwrap().push("RwLock poisoned during get_all_entity_ids".to_string());
-                Err("RwLock poisoned (simulated)".to_string())
+                Err(IndexerError::Store("RwLock poisoned (simulated)".to_string()))
             }
         }
     }
     
-    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, String> {
+    fn get_entity_vector(&self, entity_id: &str) -> Result<Vec<f32>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push(format!("get_entity_vector:{}", entity_id));
         
@@ -321,22 +323,22 @@ if should_partition {
             // Record failure
             self.failures.lock().unwrap().push(format!("Network partition during get_entity_vector of {}", entity_id));
             
-            return Err("Network partition (simulated)".to_string());
+            return Err(IndexerError::Store("Network partition (simulated)".to_string()));
         }
         
         match self.stored_entities.read() {
             Ok(entities) => entities.get(entity_id)
                 .cloned()
-                .ok_or_else(|| format!("Entity not found: {}", entity_id)),
+                .ok_or_else(|| IndexerError::Store(format!("Entity not found: {}", entity_id))),
             Err(_) => {
                 // RwLock poisoned
                 self.failures.lock().unwrap().push(format!("RwLock poisoned during get_entity_vector of {}", entity_id));
-                Err("RwLock poisoned (simulated)".to_string())
+                Err(IndexerError::Store("RwLock poisoned (simulated)".to_string()))
             }
         }
     }
     
-    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, String> {
+    fn get_entity_metadata(&self, entity_id: &str) -> Result<HashMap<String, String>, IndexerError> {
         // Record the query call
         self.query_calls.lock().unwrap().push(format!("get_entity_metadata:{}", entity_id));
         
@@ -347,17 +349,17 @@ if should_partition {
             // Record failure
             self.failures.lock().unwrap().push(format!("Network partition during get_entity_metadata of {}", entity_id));
             
-            return Err("Network partition (simulated)".to_string());
+            return Err(IndexerError::Store("Network partition (simulated)".to_string()));
         }
         
         match self.stored_metadata.read() {
             Ok(metadata) => metadata.get(entity_id)
                 .cloned()
-                .ok_or_else(|| format!("Entity metadata not found: {}", entity_id)),
+                .ok_or_else(|| IndexerError::Store(format!("Entity metadata not found: {}", entity_id))),
             Err(_) => {
                 // RwLock poisoned
                 self.failures.lock().unwrap().push(format!("RwLock poisoned during get_entity_metadata of {}", entity_id));
-                Err("RwLock poisoned (simulated)".to_string())
+                Err(IndexerError::Store("RwLock poisoned (simulated)".to_string()))
             }
         }
     }
@@ -482,7 +484,26 @@ fn function_{0}_2() {{
             batch_size: Some(10),
             dry_run: false,
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function
@@ -518,7 +539,7 @@ fn function_{0}_2() {{
             println!("Command failed with error: {}", e);
             
             // Error should be meaningful
-            assert!(!e.is_empty(), "Error message should not be empty");
+            assert!(!e.to_string().is_empty(), "Error message should not be empty");
         }
     }
     
@@ -531,6 +552,8 @@ fn function_{0}_2() {{
 async fn test_vectorize_command_with_extreme_batch_sizes() {
     // Create a temporary directory
     let temp_dir = tempfile::tempdir().unwrap();
+    // vectorize only walks .rs files when the project config lists them
+    std::fs::write(temp_dir.path().join(".indexer.yaml"), "extensions: [rs]\n").unwrap();
     
     // Create 100 small Rust files
     for i in 0..100 {
@@ -566,7 +589,26 @@ fn small_function_{0}() {{
             batch_size: Some(1), // Tiny batch size
             dry_run: false,
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function with tiny batch size
@@ -582,7 +624,26 @@ fn small_function_{0}() {{
             batch_size: Some(1000), // Huge batch size
             dry_run: false,
             verbose: true,
+            index_todos: false,
+            show_parse_errors: false,
+            plan: false,
+            concurrency: 4,
+            max_file_bytes: None,
+            from_index: false,
+            entity_types: None,
+            exclude_entity_types: None,
+            timeout: None,
+            only_missing: false,
+            dimension: None,
+            model: None,
+            limit: None,
+            emit: None,
+            granularity: Granularity::Entity,
+            force_reembed: false,
+            normalize_embeddings: false,
         },
+        quiet: false,
+        verbose: false,
     };
     
     // Call the vectorize command function with huge batch size
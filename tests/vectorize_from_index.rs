@@ -0,0 +1,81 @@
+//! Tests for vectorizing the entities already stored by `remember`
+
+use indexer::ast_parser::CodeEntity;
+use indexer::embedder::MockEmbedder;
+use indexer::redis_ops::{clear_file_data, create_redis_client, store_code_entities};
+use indexer::vector_store::{InMemoryVectorStore, VectorStore};
+use indexer::vectorize::{vectorize_from_index, EntityTypeFilter, VectorizeOptions};
+
+fn entity(entity_type: &str, name: &str, parent_class: Option<&str>) -> CodeEntity {
+    CodeEntity {
+        entity_type: entity_type.to_string(),
+        file_path: "pkg/models.py".to_string(),
+        name: name.to_string(),
+        signature: None,
+        docstring: Some(format!("Docs for {}", name)),
+        line_start: 1,
+        line_end: 2,
+        parent_class: parent_class.map(str::to_string),
+        bases: None,
+        value_repr: None,
+        source: None,
+    }
+}
+
+#[tokio::test]
+async fn test_from_index_embeds_entities_only_in_redis() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:from_index_{}", uuid::Uuid::new_v4());
+    // No file on disk backs these entities
+    let entities = vec![
+        entity("class", "Widget", None),
+        entity("method", "save", Some("Widget")),
+        entity("function", "load_widgets", None),
+    ];
+    store_code_entities(&redis, &key_prefix, &entities).await.unwrap();
+    
+    let store = InMemoryVectorStore::new();
+    let report = vectorize_from_index(&redis, &key_prefix, &MockEmbedder, &store, &VectorizeOptions { concurrency: 2, ..VectorizeOptions::default() }).await.unwrap();
+    
+    assert_eq!(report.processed, 3);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    let mut ids = store.get_all_entity_ids().unwrap();
+    ids.sort();
+    assert_eq!(ids, vec!["class:models.py:Widget", "fn:models.py:load_widgets", "method:models.py:Widget.save"]);
+    let metadata = store.get_entity_metadata("method:models.py:Widget.save").unwrap();
+    assert_eq!(metadata.get("file").map(String::as_str), Some("pkg/models.py"));
+    
+    // A dry run embeds but stores nothing
+    let empty = InMemoryVectorStore::new();
+    let report = vectorize_from_index(&redis, &key_prefix, &MockEmbedder, &empty, &VectorizeOptions { concurrency: 2, dry_run: true, ..VectorizeOptions::default() }).await.unwrap();
+    assert_eq!(report.processed, 3);
+    assert!(empty.is_empty());
+    
+    clear_file_data(&redis, &key_prefix, &["pkg/models.py".to_string()]).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_from_index_embeds_only_selected_types() {
+    let redis = create_redis_client("redis://localhost:6379/15").await.unwrap();
+    let key_prefix = format!("code_index:from_index_types_{}", uuid::Uuid::new_v4());
+    let entities = vec![
+        entity("function", "load_widgets", None),
+        entity("variable", "MAX_WIDGETS", None),
+        entity("variable", "registry", None),
+    ];
+    store_code_entities(&redis, &key_prefix, &entities).await.unwrap();
+    
+    let store = InMemoryVectorStore::new();
+    let types = EntityTypeFilter::new(Some(vec!["function".to_string()]), None);
+    let report = vectorize_from_index(&redis, &key_prefix, &MockEmbedder, &store, &VectorizeOptions { concurrency: 2, types, ..VectorizeOptions::default() }).await.unwrap();
+    assert_eq!(report.processed, 1);
+    assert_eq!(store.get_all_entity_ids().unwrap(), vec!["fn:models.py:load_widgets"]);
+    
+    // Excluding variables has the same effect here
+    let store = InMemoryVectorStore::new();
+    let types = EntityTypeFilter::new(None, Some(vec!["variable".to_string()]));
+    vectorize_from_index(&redis, &key_prefix, &MockEmbedder, &store, &VectorizeOptions { concurrency: 2, types, ..VectorizeOptions::default() }).await.unwrap();
+    assert_eq!(store.get_all_entity_ids().unwrap(), vec!["fn:models.py:load_widgets"]);
+    
+    clear_file_data(&redis, &key_prefix, &["pkg/models.py".to_string()]).await.unwrap();
+}